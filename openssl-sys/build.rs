@@ -13,16 +13,21 @@ use std::process::Command;
 
 // The set of `OPENSSL_NO_<FOO>`s that we care about.
 const DEFINES: &'static [&'static str] = &[
+    "OPENSSL_NO_ARIA",
     "OPENSSL_NO_BUF_FREELISTS",
+    "OPENSSL_NO_CAMELLIA",
     "OPENSSL_NO_COMP",
     "OPENSSL_NO_EC",
     "OPENSSL_NO_EC2M",
     "OPENSSL_NO_ENGINE",
     "OPENSSL_NO_KRB5",
     "OPENSSL_NO_NEXTPROTONEG",
+    "OPENSSL_NO_OCB",
     "OPENSSL_NO_PSK",
     "OPENSSL_NO_RFC3779",
     "OPENSSL_NO_SHA",
+    "OPENSSL_NO_SM2",
+    "OPENSSL_NO_SM3",
     "OPENSSL_NO_SRP",
     "OPENSSL_NO_SSL3_METHOD",
     "OPENSSL_NO_TLSEXT",
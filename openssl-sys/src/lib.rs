@@ -4,7 +4,7 @@
 
 extern crate libc;
 
-use libc::{c_char, c_int, c_long, c_uchar, c_uint, c_ulong, c_void, size_t, FILE};
+use libc::{c_char, c_double, c_int, c_long, c_uchar, c_uint, c_ulong, c_void, size_t, time_t, FILE};
 use std::mem;
 use std::ptr;
 
@@ -27,7 +27,10 @@ pub enum ASN1_TYPE {}
 pub enum ASN1_OBJECT {}
 pub enum BN_CTX {}
 pub enum BN_GENCB {}
+pub enum CMAC_CTX {}
 pub enum CMS_ContentInfo {}
+pub enum CMS_RecipientInfo {}
+pub enum CMS_SignerInfo {}
 pub enum CONF {}
 pub enum CONF_METHOD {}
 pub enum COMP_METHOD {}
@@ -44,12 +47,16 @@ pub enum OCSP_CERTID {}
 pub enum OCSP_RESPONSE {}
 pub enum OCSP_REQUEST {}
 pub enum OCSP_ONEREQ {}
+pub enum PKCS7 {}
+pub enum PKCS8_PRIV_KEY_INFO {}
 pub enum SSL_CIPHER {}
 pub enum SSL_METHOD {}
 pub enum X509_CRL {}
+pub enum X509_ATTRIBUTE {}
 pub enum X509_EXTENSION {}
 pub enum X509_NAME {}
 pub enum X509_NAME_ENTRY {}
+pub enum X509_PUBKEY {}
 pub enum X509_STORE {}
 pub enum X509_STORE_CTX {}
 pub enum bio_st {}
@@ -98,6 +105,18 @@ pub struct GENERAL_NAME {
     pub d: *mut c_void,
 }
 
+#[repr(C)]
+pub struct BASIC_CONSTRAINTS {
+    pub ca: c_int,
+    pub pathlen: *mut ASN1_INTEGER,
+}
+
+#[repr(C)]
+pub struct ACCESS_DESCRIPTION {
+    pub method: *mut ASN1_OBJECT,
+    pub location: *mut GENERAL_NAME,
+}
+
 #[repr(C)]
 pub struct X509V3_CTX {
     flags: c_int,
@@ -200,14 +219,43 @@ pub const BIO_FLAGS_IO_SPECIAL: c_int = 0x04;
 pub const BIO_FLAGS_RWS: c_int = BIO_FLAGS_READ | BIO_FLAGS_WRITE | BIO_FLAGS_IO_SPECIAL;
 pub const BIO_FLAGS_SHOULD_RETRY: c_int = 0x08;
 
+pub const BN_FLG_CONSTTIME: c_int = 0x04;
+
 pub const CRYPTO_LOCK: c_int = 1;
 
+pub const EC_FLAG_NON_FIPS_ALLOW: c_int = 0x1;
+pub const EC_FLAG_FIPS_CHECKED: c_int = 0x2;
+#[cfg(not(ossl101))]
+pub const EC_FLAG_COFACTOR_ECDH: c_int = 0x1000;
+
 pub const ERR_TXT_MALLOCED: c_int = 0x01;
 pub const ERR_TXT_STRING: c_int = 0x02;
 
+pub const ERR_LIB_EVP: c_int = 6;
 pub const ERR_LIB_PEM: c_int = 9;
+pub const ERR_LIB_SSL: c_int = 20;
+pub const ERR_LIB_PKCS12: c_int = 35;
+pub const ERR_LIB_USER: c_int = 128;
+
+pub const EVP_R_BAD_DECRYPT: c_int = 100;
+pub const EVP_R_UNSUPPORTED_CIPHER: c_int = 107;
+pub const EVP_R_UNSUPPORTED_PRIVATE_KEY_ALGORITHM: c_int = 118;
+pub const EVP_R_UNSUPPORTED_ALGORITHM: c_int = 156;
+pub const EVP_R_UNKNOWN_CIPHER: c_int = 160;
+pub const EVP_R_UNKNOWN_DIGEST: c_int = 161;
+
+pub const PEM_R_BAD_DECRYPT: c_int = 101;
+pub const PEM_R_BAD_PASSWORD_READ: c_int = 104;
 pub const PEM_R_NO_START_LINE: c_int = 108;
 
+pub const PKCS12_R_MAC_VERIFY_FAILURE: c_int = 113;
+
+pub const SSL_R_CERTIFICATE_VERIFY_FAILED: c_int = 134;
+pub const SSL_R_SSLV3_ALERT_HANDSHAKE_FAILURE: c_int = 1040;
+pub const SSL_R_SSLV3_ALERT_BAD_CERTIFICATE: c_int = 1042;
+pub const SSL_R_SSLV3_ALERT_CERTIFICATE_EXPIRED: c_int = 1045;
+pub const SSL_R_TLSV1_ALERT_UNKNOWN_CA: c_int = 1048;
+
 pub const EVP_MAX_MD_SIZE: c_uint = 64;
 pub const EVP_PKEY_RSA: c_int = NID_rsaEncryption;
 pub const EVP_PKEY_HMAC: c_int = NID_hmac;
@@ -223,6 +271,7 @@ pub const EVP_PKEY_CTRL_RSA_PSS_SALTLEN: c_int = EVP_PKEY_ALG_CTRL + 2;
 
 pub const EVP_PKEY_CTRL_RSA_MGF1_MD: c_int = EVP_PKEY_ALG_CTRL + 5;
 pub const EVP_PKEY_CTRL_GET_RSA_PADDING: c_int = EVP_PKEY_ALG_CTRL + 6;
+pub const EVP_PKEY_CTRL_RSA_OAEP_MD: c_int = EVP_PKEY_ALG_CTRL + 9;
 
 pub const EVP_PKEY_CTRL_SET_MAC_KEY: c_int = 6;
 pub const EVP_PKEY_CTRL_CIPHER: c_int = 12;
@@ -235,6 +284,7 @@ pub const EVP_PKEY_OP_SIGNCTX: c_int = 1 << 6;
 pub const EVP_PKEY_OP_VERIFYCTX: c_int = 1 << 7;
 pub const EVP_PKEY_OP_ENCRYPT: c_int = 1 << 8;
 pub const EVP_PKEY_OP_DECRYPT: c_int = 1 << 9;
+pub const EVP_PKEY_OP_DERIVE: c_int = 1 << 10;
 
 pub const EVP_PKEY_OP_TYPE_SIG: c_int = EVP_PKEY_OP_SIGN | EVP_PKEY_OP_VERIFY
     | EVP_PKEY_OP_VERIFYRECOVER | EVP_PKEY_OP_SIGNCTX
@@ -1204,6 +1254,23 @@ pub const OPENSSL_EC_NAMED_CURVE: c_int = 1;
 pub const PKCS5_SALT_LEN: c_int = 8;
 pub const PKCS12_DEFAULT_ITER: c_int = 2048;
 
+pub const PKCS7_TEXT: c_int = 0x1;
+pub const PKCS7_NOCERTS: c_int = 0x2;
+pub const PKCS7_NOSIGS: c_int = 0x4;
+pub const PKCS7_NOCHAIN: c_int = 0x8;
+pub const PKCS7_NOINTERN: c_int = 0x10;
+pub const PKCS7_NOVERIFY: c_int = 0x20;
+pub const PKCS7_DETACHED: c_int = 0x40;
+pub const PKCS7_BINARY: c_int = 0x80;
+pub const PKCS7_NOATTR: c_int = 0x100;
+pub const PKCS7_NOSMIMECAP: c_int = 0x200;
+pub const PKCS7_NOOLDMIMETYPE: c_int = 0x400;
+pub const PKCS7_CRLFEOL: c_int = 0x800;
+pub const PKCS7_STREAM: c_int = 0x1000;
+pub const PKCS7_NOCRL: c_int = 0x2000;
+pub const PKCS7_PARTIAL: c_int = 0x4000;
+pub const PKCS7_REUSE_DIGEST: c_int = 0x8000;
+
 pub const RSA_F4: c_long = 0x10001;
 
 pub const RSA_PKCS1_PADDING: c_int = 1;
@@ -1238,6 +1305,7 @@ pub const SSL_CTRL_SET_TLSEXT_STATUS_REQ_CB_ARG: c_int = 64;
 pub const SSL_CTRL_SET_TLSEXT_STATUS_REQ_TYPE: c_int = 65;
 pub const SSL_CTRL_GET_TLSEXT_STATUS_REQ_OCSP_RESP: c_int = 70;
 pub const SSL_CTRL_SET_TLSEXT_STATUS_REQ_OCSP_RESP: c_int = 71;
+pub const SSL_CTRL_CLEAR_MODE: c_int = 78;
 pub const SSL_CTRL_GET_EXTRA_CHAIN_CERTS: c_int = 82;
 
 pub const SSL_MODE_ENABLE_PARTIAL_WRITE: c_long = 0x1;
@@ -1246,6 +1314,11 @@ pub const SSL_MODE_AUTO_RETRY: c_long = 0x4;
 pub const SSL_MODE_NO_AUTO_CHAIN: c_long = 0x8;
 pub const SSL_MODE_RELEASE_BUFFERS: c_long = 0x10;
 
+pub const SSL_CB_HANDSHAKE_DONE: c_int = 0x20;
+
+pub const SSL_SENT_SHUTDOWN: c_int = 1;
+pub const SSL_RECEIVED_SHUTDOWN: c_int = 2;
+
 pub const SSL_ERROR_NONE: c_int = 0;
 pub const SSL_ERROR_SSL: c_int = 1;
 pub const SSL_ERROR_SYSCALL: c_int = 5;
@@ -1324,6 +1397,8 @@ pub const OPENSSL_NPN_UNSUPPORTED: c_int = 0;
 pub const OPENSSL_NPN_NEGOTIATED: c_int = 1;
 pub const OPENSSL_NPN_NO_OVERLAP: c_int = 2;
 
+pub const V_ASN1_OCTET_STRING: c_int = 4;
+pub const V_ASN1_OBJECT: c_int = 6;
 pub const V_ASN1_GENERALIZEDTIME: c_int = 24;
 pub const V_ASN1_UTCTIME: c_int = 23;
 
@@ -1427,6 +1502,16 @@ pub const GEN_URI: c_int = 6;
 pub const GEN_IPADD: c_int = 7;
 pub const GEN_RID: c_int = 8;
 
+pub const X509v3_KU_DIGITAL_SIGNATURE: c_uint = 0x0080;
+pub const X509v3_KU_NON_REPUDIATION: c_uint = 0x0040;
+pub const X509v3_KU_KEY_ENCIPHERMENT: c_uint = 0x0020;
+pub const X509v3_KU_DATA_ENCIPHERMENT: c_uint = 0x0010;
+pub const X509v3_KU_KEY_AGREEMENT: c_uint = 0x0008;
+pub const X509v3_KU_KEY_CERT_SIGN: c_uint = 0x0004;
+pub const X509v3_KU_CRL_SIGN: c_uint = 0x0002;
+pub const X509v3_KU_ENCIPHER_ONLY: c_uint = 0x0001;
+pub const X509v3_KU_DECIPHER_ONLY: c_uint = 0x8000;
+
 pub const DTLS1_COOKIE_LENGTH: c_uint = 256;
 
 // macros
@@ -1491,10 +1576,26 @@ pub unsafe fn EVP_PKEY_CTX_set_rsa_mgf1_md(ctx: *mut EVP_PKEY_CTX, md: *mut EVP_
     )
 }
 
+#[cfg(any(ossl102, ossl110))]
+pub unsafe fn EVP_PKEY_CTX_set_rsa_oaep_md(ctx: *mut EVP_PKEY_CTX, md: *mut EVP_MD) -> c_int {
+    EVP_PKEY_CTX_ctrl(
+        ctx,
+        EVP_PKEY_RSA,
+        EVP_PKEY_OP_TYPE_CRYPT,
+        EVP_PKEY_CTRL_RSA_OAEP_MD,
+        0,
+        md as *mut c_void,
+    )
+}
+
 pub unsafe fn SSL_CTX_set_mode(ctx: *mut SSL_CTX, op: c_long) -> c_long {
     SSL_CTX_ctrl(ctx, SSL_CTRL_MODE, op, ptr::null_mut())
 }
 
+pub unsafe fn SSL_clear_mode(ssl: *mut SSL, op: c_long) -> c_long {
+    SSL_ctrl(ssl, SSL_CTRL_CLEAR_MODE, op, ptr::null_mut())
+}
+
 pub unsafe fn SSL_CTX_set_read_ahead(ctx: *mut SSL_CTX, m: c_long) -> c_long {
     SSL_CTX_ctrl(ctx, SSL_CTRL_SET_READ_AHEAD, m, ptr::null_mut())
 }
@@ -1524,6 +1625,11 @@ pub unsafe fn SSL_CTX_set0_verify_cert_store(ctx: *mut SSL_CTX, st: *mut X509_ST
     SSL_CTX_ctrl(ctx, SSL_CTRL_SET_VERIFY_CERT_STORE, 0, st as *mut c_void)
 }
 
+#[cfg(not(any(ossl101, libressl)))]
+pub unsafe fn SSL_set1_verify_cert_store(ssl: *mut SSL, st: *mut X509_STORE) -> c_long {
+    SSL_ctrl(ssl, SSL_CTRL_SET_VERIFY_CERT_STORE, 1, st as *mut c_void)
+}
+
 pub unsafe fn SSL_CTX_set_tlsext_servername_callback(
     ctx: *mut SSL_CTX,
     cb: Option<extern "C" fn()>,
@@ -1616,19 +1722,46 @@ extern "C" {
         ivec: *mut c_uchar,
         enc: c_int,
     );
+    pub fn AES_wrap_key(
+        key: *mut AES_KEY,
+        iv: *const c_uchar,
+        out: *mut c_uchar,
+        in_: *const c_uchar,
+        inlen: c_uint,
+    ) -> c_int;
+    pub fn AES_unwrap_key(
+        key: *mut AES_KEY,
+        iv: *const c_uchar,
+        out: *mut c_uchar,
+        in_: *const c_uchar,
+        inlen: c_uint,
+    ) -> c_int;
 
+    pub fn ASN1_INTEGER_dup(x: *const ASN1_INTEGER) -> *mut ASN1_INTEGER;
+    pub fn ASN1_INTEGER_cmp(x: *const ASN1_INTEGER, y: *const ASN1_INTEGER) -> c_int;
     pub fn ASN1_INTEGER_get(dest: *const ASN1_INTEGER) -> c_long;
     pub fn ASN1_INTEGER_set(dest: *mut ASN1_INTEGER, value: c_long) -> c_int;
     pub fn ASN1_INTEGER_to_BN(ai: *const ASN1_INTEGER, bn: *mut BIGNUM) -> *mut BIGNUM;
     pub fn ASN1_GENERALIZEDTIME_free(tm: *mut ASN1_GENERALIZEDTIME);
     pub fn ASN1_GENERALIZEDTIME_print(b: *mut BIO, tm: *const ASN1_GENERALIZEDTIME) -> c_int;
     pub fn ASN1_STRING_type_new(ty: c_int) -> *mut ASN1_STRING;
+    pub fn ASN1_TIME_new() -> *mut ASN1_TIME;
     pub fn ASN1_TIME_free(tm: *mut ASN1_TIME);
     pub fn ASN1_TIME_print(b: *mut BIO, tm: *const ASN1_TIME) -> c_int;
+    pub fn ASN1_TIME_set(tm: *mut ASN1_TIME, t: time_t) -> *mut ASN1_TIME;
+    pub fn ASN1_TIME_set_string(s: *mut ASN1_TIME, str: *const c_char) -> c_int;
+    #[cfg(any(ossl102, ossl110))]
+    pub fn ASN1_TIME_diff(
+        pday: *mut c_int,
+        psec: *mut c_int,
+        from: *const ASN1_TIME,
+        to: *const ASN1_TIME,
+    ) -> c_int;
     pub fn ASN1_BIT_STRING_free(x: *mut ASN1_BIT_STRING);
     pub fn ASN1_OBJECT_free(x: *mut ASN1_OBJECT);
 
     pub fn BIO_ctrl(b: *mut BIO, cmd: c_int, larg: c_long, parg: *mut c_void) -> c_long;
+    pub fn BIO_ctrl_pending(b: *mut BIO) -> size_t;
     pub fn BIO_free_all(b: *mut BIO);
     pub fn BIO_new_fp(stream: *mut FILE, close_flag: c_int) -> *mut BIO;
     pub fn BIO_new_socket(sock: c_int, close_flag: c_int) -> *mut BIO;
@@ -1676,6 +1809,14 @@ extern "C" {
         m: *const BIGNUM,
         ctx: *mut BN_CTX,
     ) -> c_int;
+    pub fn BN_mod_exp_mont_consttime(
+        r: *mut BIGNUM,
+        a: *const BIGNUM,
+        p: *const BIGNUM,
+        m: *const BIGNUM,
+        ctx: *mut BN_CTX,
+        m_ctx: *mut BN_MONT_CTX,
+    ) -> c_int;
     pub fn BN_mod_inverse(
         r: *mut BIGNUM,
         a: *const BIGNUM,
@@ -1702,6 +1843,12 @@ extern "C" {
         m: *const BIGNUM,
         ctx: *mut BN_CTX,
     ) -> c_int;
+    pub fn BN_mod_sqrt(
+        r: *mut BIGNUM,
+        a: *const BIGNUM,
+        p: *const BIGNUM,
+        ctx: *mut BN_CTX,
+    ) -> *mut BIGNUM;
     pub fn BN_mul(r: *mut BIGNUM, a: *const BIGNUM, b: *const BIGNUM, ctx: *mut BN_CTX) -> c_int;
     pub fn BN_nnmod(
         rem: *mut BIGNUM,
@@ -1764,6 +1911,9 @@ extern "C" {
     pub fn NCONF_free(conf: *mut CONF);
 
     pub fn CRYPTO_memcmp(a: *const c_void, b: *const c_void, len: size_t) -> c_int;
+    pub fn OPENSSL_cleanse(ptr: *mut c_void, len: size_t);
+    #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(libressl)))]
+    pub fn OPENSSL_ia32cap_loc() -> *mut c_uint;
 
     pub fn DH_new() -> *mut DH;
     pub fn DH_free(dh: *mut DH);
@@ -1773,6 +1923,9 @@ extern "C" {
     pub fn DH_get_2048_224() -> *mut DH;
     #[cfg(not(any(ossl101, libressl)))]
     pub fn DH_get_2048_256() -> *mut DH;
+    pub fn DH_generate_key(dh: *mut DH) -> c_int;
+    pub fn DH_compute_key(key: *mut c_uchar, pub_key: *const BIGNUM, dh: *mut DH) -> c_int;
+    pub fn DH_size(dh: *const DH) -> c_int;
 
     pub fn EC_KEY_new() -> *mut EC_KEY;
     pub fn EC_KEY_new_by_curve_name(nid: c_int) -> *mut EC_KEY;
@@ -1786,6 +1939,9 @@ extern "C" {
     pub fn EC_KEY_get0_private_key(key: *const EC_KEY) -> *const BIGNUM;
     pub fn EC_KEY_generate_key(key: *mut EC_KEY) -> c_int;
     pub fn EC_KEY_check_key(key: *const EC_KEY) -> c_int;
+    pub fn EC_KEY_get_flags(key: *const EC_KEY) -> c_int;
+    pub fn EC_KEY_set_flags(key: *mut EC_KEY, flags: c_int);
+    pub fn EC_KEY_clear_flags(key: *mut EC_KEY, flags: c_int);
     pub fn EC_KEY_free(key: *mut EC_KEY);
     pub fn EC_KEY_set_public_key_affine_coordinates(
         key: *mut EC_KEY,
@@ -1827,12 +1983,15 @@ extern "C" {
         ctx: *mut BN_CTX,
     ) -> c_int;
     pub fn EC_GROUP_get_degree(group: *const EC_GROUP) -> c_int;
+    pub fn EC_GROUP_get_curve_name(group: *const EC_GROUP) -> c_int;
     pub fn EC_GROUP_get_order(
         group: *const EC_GROUP,
         order: *mut BIGNUM,
         ctx: *mut BN_CTX,
     ) -> c_int;
     pub fn EC_GROUP_set_asn1_flag(key: *mut EC_GROUP, flag: c_int);
+    pub fn EC_GROUP_precompute_mult(group: *mut EC_GROUP, ctx: *mut BN_CTX) -> c_int;
+    pub fn EC_GROUP_have_precompute_mult(group: *const EC_GROUP) -> c_int;
 
     pub fn EC_GROUP_free(group: *mut EC_GROUP);
 
@@ -1904,6 +2063,12 @@ extern "C" {
         dgst_len: c_int,
         eckey: *mut EC_KEY,
     ) -> *mut ECDSA_SIG;
+    pub fn i2d_ECDSA_SIG(sig: *const ECDSA_SIG, out: *mut *mut c_uchar) -> c_int;
+    pub fn d2i_ECDSA_SIG(
+        sig: *mut *mut ECDSA_SIG,
+        inp: *mut *const c_uchar,
+        length: c_long,
+    ) -> *mut ECDSA_SIG;
 
     pub fn ERR_peek_last_error() -> c_ulong;
     pub fn ERR_get_error() -> c_ulong;
@@ -1920,6 +2085,9 @@ extern "C" {
     pub fn ERR_put_error(lib: c_int, func: c_int, reason: c_int, file: *const c_char, line: c_int);
     pub fn ERR_set_error_data(data: *mut c_char, flags: c_int);
 
+    pub fn EVP_EncodeBlock(t: *mut c_uchar, f: *const c_uchar, n: c_int) -> c_int;
+    pub fn EVP_DecodeBlock(t: *mut c_uchar, f: *const c_uchar, n: c_int) -> c_int;
+
     pub fn EVP_md5() -> *const EVP_MD;
     pub fn EVP_ripemd160() -> *const EVP_MD;
     pub fn EVP_sha1() -> *const EVP_MD;
@@ -1951,6 +2119,12 @@ extern "C" {
     pub fn EVP_bf_cfb64() -> *const EVP_CIPHER;
     pub fn EVP_bf_ofb() -> *const EVP_CIPHER;
     pub fn EVP_rc4() -> *const EVP_CIPHER;
+    #[cfg(not(osslconf = "OPENSSL_NO_CAMELLIA"))]
+    pub fn EVP_camellia_128_cbc() -> *const EVP_CIPHER;
+    #[cfg(not(osslconf = "OPENSSL_NO_CAMELLIA"))]
+    pub fn EVP_camellia_192_cbc() -> *const EVP_CIPHER;
+    #[cfg(not(osslconf = "OPENSSL_NO_CAMELLIA"))]
+    pub fn EVP_camellia_256_cbc() -> *const EVP_CIPHER;
 
     pub fn EVP_des_cbc() -> *const EVP_CIPHER;
     pub fn EVP_des_ecb() -> *const EVP_CIPHER;
@@ -2002,6 +2176,25 @@ extern "C" {
         inlen: c_int,
     ) -> c_int;
     pub fn EVP_CipherFinal(ctx: *mut EVP_CIPHER_CTX, res: *mut u8, len: *mut c_int) -> c_int;
+    pub fn EVP_SealInit(
+        ctx: *mut EVP_CIPHER_CTX,
+        type_: *const EVP_CIPHER,
+        ek: *mut *mut c_uchar,
+        ekl: *mut c_int,
+        iv: *mut c_uchar,
+        pubk: *mut *mut EVP_PKEY,
+        npubk: c_int,
+    ) -> c_int;
+    pub fn EVP_SealFinal(ctx: *mut EVP_CIPHER_CTX, out: *mut c_uchar, outl: *mut c_int) -> c_int;
+    pub fn EVP_OpenInit(
+        ctx: *mut EVP_CIPHER_CTX,
+        type_: *const EVP_CIPHER,
+        ek: *const c_uchar,
+        ekl: c_int,
+        iv: *const c_uchar,
+        priv_: *mut EVP_PKEY,
+    ) -> c_int;
+    pub fn EVP_OpenFinal(ctx: *mut EVP_CIPHER_CTX, out: *mut c_uchar, outl: *mut c_int) -> c_int;
 
     pub fn EVP_DigestInit(ctx: *mut EVP_MD_CTX, typ: *const EVP_MD) -> c_int;
     pub fn EVP_DigestInit_ex(ctx: *mut EVP_MD_CTX, typ: *const EVP_MD, imple: *mut ENGINE)
@@ -2054,7 +2247,9 @@ extern "C" {
     pub fn EVP_PKEY_get1_DH(k: *mut EVP_PKEY) -> *mut DH;
     pub fn EVP_PKEY_get1_EC_KEY(k: *mut EVP_PKEY) -> *mut EC_KEY;
     pub fn EVP_PKEY_cmp(a: *const EVP_PKEY, b: *const EVP_PKEY) -> c_int;
+    pub fn EVP_PKEY_cmp_parameters(a: *const EVP_PKEY, b: *const EVP_PKEY) -> c_int;
     pub fn EVP_PKEY_id(pkey: *const EVP_PKEY) -> c_int;
+    pub fn EVP_PKEY_size(pkey: *mut EVP_PKEY) -> c_int;
     pub fn EVP_PKEY_new_mac_key(
         type_: c_int,
         e: *mut ENGINE,
@@ -2064,12 +2259,44 @@ extern "C" {
     pub fn EVP_PKEY_derive_init(ctx: *mut EVP_PKEY_CTX) -> c_int;
     pub fn EVP_PKEY_derive_set_peer(ctx: *mut EVP_PKEY_CTX, peer: *mut EVP_PKEY) -> c_int;
     pub fn EVP_PKEY_derive(ctx: *mut EVP_PKEY_CTX, key: *mut c_uchar, size: *mut size_t) -> c_int;
+    pub fn EVP_PKEY_encrypt_init(ctx: *mut EVP_PKEY_CTX) -> c_int;
+    pub fn EVP_PKEY_encrypt(
+        ctx: *mut EVP_PKEY_CTX,
+        pout: *mut c_uchar,
+        poutlen: *mut size_t,
+        pin: *const c_uchar,
+        pinlen: size_t,
+    ) -> c_int;
+    pub fn EVP_PKEY_decrypt_init(ctx: *mut EVP_PKEY_CTX) -> c_int;
+    pub fn EVP_PKEY_decrypt(
+        ctx: *mut EVP_PKEY_CTX,
+        pout: *mut c_uchar,
+        poutlen: *mut size_t,
+        pin: *const c_uchar,
+        pinlen: size_t,
+    ) -> c_int;
     pub fn d2i_PKCS8PrivateKey_bio(
         bp: *mut BIO,
         x: *mut *mut EVP_PKEY,
         cb: Option<PasswordCallback>,
         u: *mut c_void,
     ) -> *mut EVP_PKEY;
+    pub fn i2d_PKCS8PrivateKey_bio(
+        bp: *mut BIO,
+        x: *mut EVP_PKEY,
+        enc: *const EVP_CIPHER,
+        kstr: *mut c_char,
+        klen: c_int,
+        cb: Option<PasswordCallback>,
+        u: *mut c_void,
+    ) -> c_int;
+    pub fn d2i_PKCS8_PRIV_KEY_INFO(
+        a: *mut *mut PKCS8_PRIV_KEY_INFO,
+        pp: *mut *const c_uchar,
+        length: c_long,
+    ) -> *mut PKCS8_PRIV_KEY_INFO;
+    pub fn PKCS8_PRIV_KEY_INFO_free(p8: *mut PKCS8_PRIV_KEY_INFO);
+    pub fn EVP_PKCS82PKEY(p8: *const PKCS8_PRIV_KEY_INFO) -> *mut EVP_PKEY;
 
     pub fn EVP_PKEY_CTX_new(k: *mut EVP_PKEY, e: *mut ENGINE) -> *mut EVP_PKEY_CTX;
     pub fn EVP_PKEY_CTX_new_id(id: c_int, e: *mut ENGINE) -> *mut EVP_PKEY_CTX;
@@ -2089,6 +2316,7 @@ extern "C" {
     pub fn HMAC_CTX_copy(dst: *mut HMAC_CTX, src: *mut HMAC_CTX) -> c_int;
 
     pub fn OBJ_obj2nid(o: *const ASN1_OBJECT) -> c_int;
+    pub fn OBJ_cmp(a: *const ASN1_OBJECT, b: *const ASN1_OBJECT) -> c_int;
     pub fn OBJ_obj2txt(
         buf: *mut c_char,
         buf_len: c_int,
@@ -2096,6 +2324,11 @@ extern "C" {
         no_name: c_int,
     ) -> c_int;
     pub fn OBJ_nid2sn(nid: c_int) -> *const c_char;
+    pub fn OBJ_nid2ln(nid: c_int) -> *const c_char;
+    pub fn OBJ_sn2nid(sn: *const c_char) -> c_int;
+    pub fn OBJ_ln2nid(ln: *const c_char) -> c_int;
+    pub fn OBJ_txt2obj(s: *const c_char, no_name: c_int) -> *mut ASN1_OBJECT;
+    pub fn OBJ_find_sigid_algs(signid: c_int, pdig_nid: *mut c_int, ppkey_nid: *mut c_int) -> c_int;
 
     pub fn OCSP_BASICRESP_new() -> *mut OCSP_BASICRESP;
     pub fn OCSP_BASICRESP_free(r: *mut OCSP_BASICRESP);
@@ -2159,6 +2392,12 @@ extern "C" {
         callback: Option<PasswordCallback>,
         user_data: *mut c_void,
     ) -> *mut X509;
+    pub fn PEM_read_bio_PKCS7(
+        bio: *mut BIO,
+        out: *mut *mut PKCS7,
+        callback: Option<PasswordCallback>,
+        user_data: *mut c_void,
+    ) -> *mut PKCS7;
     pub fn PEM_read_bio_X509_REQ(
         bio: *mut BIO,
         out: *mut *mut X509_REQ,
@@ -2253,8 +2492,36 @@ extern "C" {
         user_data: *mut c_void,
     ) -> c_int;
     pub fn PEM_write_bio_DSA_PUBKEY(bp: *mut BIO, dsa: *mut DSA) -> c_int;
+    pub fn PEM_read_bio_EC_PUBKEY(
+        bp: *mut BIO,
+        ec: *mut *mut EC_KEY,
+        callback: Option<PasswordCallback>,
+        user_data: *mut c_void,
+    ) -> *mut EC_KEY;
+    pub fn PEM_write_bio_EC_PUBKEY(bp: *mut BIO, ec: *mut EC_KEY) -> c_int;
+
+    pub fn PEM_ASN1_read_bio(
+        d2i: unsafe extern "C" fn(*mut *mut c_void, *mut *const c_uchar, c_long) -> *mut c_void,
+        name: *const c_char,
+        bp: *mut BIO,
+        x: *mut *mut c_void,
+        callback: Option<PasswordCallback>,
+        user_data: *mut c_void,
+    ) -> *mut c_void;
+    pub fn PEM_ASN1_write_bio(
+        i2d: unsafe extern "C" fn(*const c_void, *mut *mut c_uchar) -> c_int,
+        name: *const c_char,
+        bp: *mut BIO,
+        x: *mut c_void,
+        cipher: *const EVP_CIPHER,
+        kstr: *mut c_uchar,
+        klen: c_int,
+        callback: Option<PasswordCallback>,
+        user_data: *mut c_void,
+    ) -> c_int;
 
     pub fn PEM_write_bio_X509(bio: *mut BIO, x509: *mut X509) -> c_int;
+    pub fn PEM_write_bio_PKCS7(bio: *mut BIO, p7: *mut PKCS7) -> c_int;
     pub fn PEM_write_bio_X509_REQ(bio: *mut BIO, x509: *mut X509_REQ) -> c_int;
 
     pub fn PEM_write_bio_ECPrivateKey(
@@ -2295,6 +2562,9 @@ extern "C" {
 
     pub fn RAND_bytes(buf: *mut u8, num: c_int) -> c_int;
     pub fn RAND_status() -> c_int;
+    pub fn RAND_add(buf: *const c_void, num: c_int, randomness: c_double);
+    pub fn RAND_seed(buf: *const c_void, num: c_int);
+    pub fn RAND_poll() -> c_int;
 
     pub fn RSA_new() -> *mut RSA;
     pub fn RSA_free(rsa: *mut RSA);
@@ -2412,12 +2682,18 @@ extern "C" {
     pub fn SSL_accept(ssl: *mut SSL) -> c_int;
     pub fn SSL_connect(ssl: *mut SSL) -> c_int;
     pub fn SSL_do_handshake(ssl: *mut SSL) -> c_int;
+    pub fn SSL_set_connect_state(ssl: *mut SSL);
+    pub fn SSL_set_accept_state(ssl: *mut SSL);
     pub fn SSL_ctrl(ssl: *mut SSL, cmd: c_int, larg: c_long, parg: *mut c_void) -> c_long;
     pub fn SSL_get_error(ssl: *const SSL, ret: c_int) -> c_int;
     pub fn SSL_read(ssl: *mut SSL, buf: *mut c_void, num: c_int) -> c_int;
+    pub fn SSL_peek(ssl: *mut SSL, buf: *mut c_void, num: c_int) -> c_int;
     pub fn SSL_write(ssl: *mut SSL, buf: *const c_void, num: c_int) -> c_int;
     pub fn SSL_get_ex_data_X509_STORE_CTX_idx() -> c_int;
     pub fn SSL_get_SSL_CTX(ssl: *const SSL) -> *mut SSL_CTX;
+    pub fn SSL_get_ciphers(ssl: *const SSL) -> *mut stack_st_SSL_CIPHER;
+    #[cfg(any(ossl102, ossl110))]
+    pub fn SSL_get1_supported_ciphers(ssl: *mut SSL) -> *mut stack_st_SSL_CIPHER;
     pub fn SSL_set_SSL_CTX(ssl: *mut SSL, ctx: *mut SSL_CTX) -> *mut SSL_CTX;
     #[cfg(not(any(osslconf = "OPENSSL_NO_COMP", libressl)))]
     pub fn SSL_get_current_compression(ssl: *mut SSL) -> *const COMP_METHOD;
@@ -2443,12 +2719,18 @@ extern "C" {
     pub fn SSL_get0_param(ssl: *mut SSL) -> *mut X509_VERIFY_PARAM;
     pub fn SSL_get_verify_result(ssl: *const SSL) -> c_long;
     pub fn SSL_shutdown(ssl: *mut SSL) -> c_int;
+    pub fn SSL_get_shutdown(ssl: *const SSL) -> c_int;
+    pub fn SSL_set_shutdown(ssl: *mut SSL, mode: c_int);
+    pub fn SSL_renegotiate(ssl: *mut SSL) -> c_int;
+    pub fn SSL_renegotiate_abbreviated(ssl: *mut SSL) -> c_int;
+    pub fn SSL_renegotiate_pending(ssl: *mut SSL) -> c_int;
     pub fn SSL_get_certificate(ssl: *const SSL) -> *mut X509;
     #[cfg(any(ossl101, libressl))]
     pub fn SSL_get_privatekey(ssl: *mut SSL) -> *mut EVP_PKEY;
     #[cfg(not(any(ossl101, libressl)))]
     pub fn SSL_get_privatekey(ssl: *const SSL) -> *mut EVP_PKEY;
     pub fn SSL_load_client_CA_file(file: *const c_char) -> *mut stack_st_X509_NAME;
+    pub fn SSL_get_client_CA_list(ssl: *const SSL) -> *mut stack_st_X509_NAME;
     pub fn SSL_set_tmp_dh_callback(
         ctx: *mut SSL,
         dh: unsafe extern "C" fn(ssl: *mut SSL, is_export: c_int, keylength: c_int) -> *mut DH,
@@ -2491,6 +2773,10 @@ extern "C" {
         verify_callback: Option<extern "C" fn(c_int, *mut X509_STORE_CTX) -> c_int>,
     );
     pub fn SSL_CTX_set_verify_depth(ctx: *mut SSL_CTX, depth: c_int);
+    pub fn SSL_CTX_set_info_callback(
+        ctx: *mut SSL_CTX,
+        cb: Option<unsafe extern "C" fn(*const SSL, c_int, c_int)>,
+    );
     pub fn SSL_CTX_load_verify_locations(
         ctx: *mut SSL_CTX,
         CAfile: *const c_char,
@@ -2524,6 +2810,7 @@ extern "C" {
     pub fn SSL_CTX_use_PrivateKey(ctx: *mut SSL_CTX, key: *mut EVP_PKEY) -> c_int;
     pub fn SSL_CTX_check_private_key(ctx: *const SSL_CTX) -> c_int;
     pub fn SSL_CTX_set_client_CA_list(ctx: *mut SSL_CTX, list: *mut stack_st_X509_NAME);
+    pub fn SSL_CTX_add_client_CA(ctx: *mut SSL_CTX, cacert: *mut X509) -> c_int;
     pub fn SSL_CTX_get_cert_store(ctx: *const SSL_CTX) -> *mut X509_STORE;
     // FIXME should take an option
     pub fn SSL_CTX_set_tmp_dh_callback(
@@ -2576,6 +2863,13 @@ extern "C" {
                 -> c_uint,
         >,
     );
+    #[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+    pub fn SSL_CTX_set_psk_server_callback(
+        ssl: *mut SSL_CTX,
+        psk_server_cb: Option<extern "C" fn(*mut SSL, *const c_char, *mut c_uchar, c_uint) -> c_uint>,
+    );
+    #[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+    pub fn SSL_CTX_use_psk_identity_hint(ctx: *mut SSL_CTX, hint: *const c_char) -> c_int;
 
     pub fn SSL_select_next_proto(
         out: *mut *mut c_uchar,
@@ -2597,8 +2891,12 @@ extern "C" {
     #[cfg(any(ossl110f, ossl111))]
     pub fn SSL_is_server(s: *const SSL) -> c_int;
 
+    pub fn SSL_SESSION_new() -> *mut SSL_SESSION;
     pub fn SSL_SESSION_free(s: *mut SSL_SESSION);
     pub fn SSL_SESSION_get_id(s: *const SSL_SESSION, len: *mut c_uint) -> *const c_uchar;
+    pub fn SSL_SESSION_get_time(s: *const SSL_SESSION) -> c_long;
+    pub fn SSL_SESSION_get_timeout(s: *const SSL_SESSION) -> c_long;
+    pub fn SSL_SESSION_set_timeout(s: *mut SSL_SESSION, t: c_long) -> c_long;
 
     pub fn d2i_SSL_SESSION(
         a: *mut *mut SSL_SESSION,
@@ -2631,6 +2929,15 @@ extern "C" {
     pub fn SSL_get0_alpn_selected(s: *const SSL, data: *mut *const c_uchar, len: *mut c_uint);
 
     pub fn X509_add_ext(x: *mut X509, ext: *mut X509_EXTENSION, loc: c_int) -> c_int;
+    pub fn X509_EXTENSION_get_object(ex: *mut X509_EXTENSION) -> *mut ASN1_OBJECT;
+    pub fn X509_ATTRIBUTE_get0_data(
+        attr: *mut X509_ATTRIBUTE,
+        idx: c_int,
+        atrtype: c_int,
+        data: *mut c_void,
+    ) -> *mut c_void;
+    pub fn X509_supported_extension(ex: *mut X509_EXTENSION) -> c_int;
+    pub fn X509V3_EXT_d2i(ext: *mut X509_EXTENSION) -> *mut c_void;
     pub fn X509_digest(
         x: *const X509,
         digest: *const EVP_MD,
@@ -2649,6 +2956,7 @@ extern "C" {
     pub fn X509_set_pubkey(x: *mut X509, pkey: *mut EVP_PKEY) -> c_int;
     pub fn X509_sign(x: *mut X509, pkey: *mut EVP_PKEY, md: *const EVP_MD) -> c_int;
     pub fn X509_get_pubkey(x: *mut X509) -> *mut EVP_PKEY;
+    pub fn X509_verify(x: *mut X509, pkey: *mut EVP_PKEY) -> c_int;
     pub fn X509_to_X509_REQ(x: *mut X509, pkey: *mut EVP_PKEY, md: *const EVP_MD) -> *mut X509_REQ;
     pub fn X509_verify_cert(ctx: *mut X509_STORE_CTX) -> c_int;
     pub fn X509_verify_cert_error_string(n: c_long) -> *const c_char;
@@ -2671,6 +2979,7 @@ extern "C" {
         set: c_int,
     ) -> c_int;
     pub fn X509_NAME_get_index_by_NID(n: *mut X509_NAME, nid: c_int, last_pos: c_int) -> c_int;
+    pub fn X509_NAME_cmp(a: *const X509_NAME, b: *const X509_NAME) -> c_int;
 
     pub fn X509_NAME_ENTRY_free(x: *mut X509_NAME_ENTRY);
 
@@ -2718,6 +3027,7 @@ extern "C" {
         -> c_int;
     pub fn X509_REQ_get_extensions(req: *mut X509_REQ) -> *mut stack_st_X509_EXTENSION;
     pub fn X509_REQ_sign(x: *mut X509_REQ, pkey: *mut EVP_PKEY, md: *const EVP_MD) -> c_int;
+    pub fn X509_REQ_verify(req: *mut X509_REQ, pkey: *mut EVP_PKEY) -> c_int;
 
     #[cfg(not(ossl101))]
     pub fn X509_VERIFY_PARAM_free(param: *mut X509_VERIFY_PARAM);
@@ -2735,6 +3045,31 @@ extern "C" {
         ip: *const c_uchar,
         iplen: size_t,
     ) -> c_int;
+    #[cfg(not(ossl101))]
+    pub fn X509_STORE_CTX_get0_param(ctx: *mut X509_STORE_CTX) -> *mut X509_VERIFY_PARAM;
+
+    #[cfg(not(any(ossl101, libressl)))]
+    pub fn X509_check_host(
+        x: *mut X509,
+        chk: *const c_char,
+        chklen: size_t,
+        flags: c_uint,
+        peername: *mut *mut c_char,
+    ) -> c_int;
+    #[cfg(not(any(ossl101, libressl)))]
+    pub fn X509_check_email(
+        x: *mut X509,
+        chk: *const c_char,
+        chklen: size_t,
+        flags: c_uint,
+    ) -> c_int;
+    #[cfg(not(any(ossl101, libressl)))]
+    pub fn X509_check_ip(
+        x: *mut X509,
+        chk: *const c_uchar,
+        chklen: size_t,
+        flags: c_uint,
+    ) -> c_int;
 
     pub fn d2i_DHparams(k: *mut *mut DH, pp: *mut *const c_uchar, length: c_long) -> *mut DH;
     pub fn i2d_DHparams(dh: *const DH, pp: *mut *mut c_uchar) -> c_int;
@@ -2753,6 +3088,9 @@ extern "C" {
         length: c_long,
     ) -> *mut EC_KEY;
     pub fn i2d_ECPrivateKey(ec_key: *mut EC_KEY, pp: *mut *mut c_uchar) -> c_int;
+    pub fn d2i_EC_PUBKEY(k: *mut *mut EC_KEY, pp: *mut *const c_uchar, length: c_long)
+        -> *mut EC_KEY;
+    pub fn i2d_EC_PUBKEY(a: *mut EC_KEY, pp: *mut *mut c_uchar) -> c_int;
 
     pub fn d2i_X509(a: *mut *mut X509, pp: *mut *const c_uchar, length: c_long) -> *mut X509;
     pub fn d2i_X509_REQ(
@@ -2764,6 +3102,17 @@ extern "C" {
     pub fn i2d_X509(x: *mut X509, buf: *mut *mut u8) -> c_int;
     pub fn i2d_X509_REQ_bio(b: *mut BIO, x: *mut X509_REQ) -> c_int;
     pub fn i2d_X509_REQ(x: *mut X509_REQ, buf: *mut *mut u8) -> c_int;
+    pub fn d2i_X509_NAME(
+        a: *mut *mut X509_NAME,
+        pp: *mut *const c_uchar,
+        length: c_long,
+    ) -> *mut X509_NAME;
+    pub fn i2d_X509_NAME(x: *mut X509_NAME, buf: *mut *mut u8) -> c_int;
+    pub fn i2d_X509_PUBKEY(x: *mut X509_PUBKEY, buf: *mut *mut u8) -> c_int;
+
+    pub fn X509_print(bio: *mut BIO, x: *mut X509) -> c_int;
+    pub fn X509_REQ_print(bio: *mut BIO, req: *mut X509_REQ) -> c_int;
+    pub fn X509_NAME_print(bio: *mut BIO, name: *mut X509_NAME, obase: c_int) -> c_int;
 
     pub fn d2i_AutoPrivateKey(
         a: *mut *mut EVP_PKEY,
@@ -2794,8 +3143,46 @@ extern "C" {
         ca: *mut *mut stack_st_X509,
     ) -> c_int;
     pub fn PKCS12_free(p12: *mut PKCS12);
+    pub fn PKCS12_verify_mac(p12: *mut PKCS12, pass: *const c_char, passlen: c_int) -> c_int;
+
+    pub fn PKCS7_encrypt(
+        certs: *mut stack_st_X509,
+        b: *mut BIO,
+        cipher: *const EVP_CIPHER,
+        flags: c_int,
+    ) -> *mut PKCS7;
+    pub fn PKCS7_decrypt(
+        p7: *mut PKCS7,
+        pkey: *mut EVP_PKEY,
+        cert: *mut X509,
+        data: *mut BIO,
+        flags: c_int,
+    ) -> c_int;
+    pub fn PKCS7_sign(
+        signcert: *mut X509,
+        pkey: *mut EVP_PKEY,
+        certs: *mut stack_st_X509,
+        data: *mut BIO,
+        flags: c_int,
+    ) -> *mut PKCS7;
+    pub fn PKCS7_verify(
+        p7: *mut PKCS7,
+        certs: *mut stack_st_X509,
+        store: *mut X509_STORE,
+        indata: *mut BIO,
+        out: *mut BIO,
+        flags: c_int,
+    ) -> c_int;
+    pub fn PKCS7_free(p7: *mut PKCS7);
+    pub fn i2d_PKCS7(a: *mut PKCS7, buf: *mut *mut u8) -> c_int;
+    pub fn d2i_PKCS7(a: *mut *mut PKCS7, pp: *mut *const u8, length: c_long) -> *mut PKCS7;
+    pub fn SMIME_write_PKCS7(out: *mut BIO, p7: *mut PKCS7, data: *mut BIO, flags: c_int)
+        -> c_int;
+    pub fn SMIME_read_PKCS7(bio: *mut BIO, bcont: *mut *mut BIO) -> *mut PKCS7;
 
     pub fn GENERAL_NAME_free(name: *mut GENERAL_NAME);
+    pub fn BASIC_CONSTRAINTS_free(bc: *mut BASIC_CONSTRAINTS);
+    pub fn ACCESS_DESCRIPTION_free(ad: *mut ACCESS_DESCRIPTION);
 
     pub fn HMAC_Init_ex(
         ctx: *mut HMAC_CTX,
@@ -2807,6 +3194,18 @@ extern "C" {
     pub fn HMAC_Update(ctx: *mut HMAC_CTX, data: *const c_uchar, len: size_t) -> c_int;
     pub fn HMAC_Final(ctx: *mut HMAC_CTX, md: *mut c_uchar, len: *mut c_uint) -> c_int;
 
+    pub fn CMAC_CTX_new() -> *mut CMAC_CTX;
+    pub fn CMAC_CTX_free(ctx: *mut CMAC_CTX);
+    pub fn CMAC_Init(
+        ctx: *mut CMAC_CTX,
+        key: *const c_void,
+        keylen: size_t,
+        cipher: *const EVP_CIPHER,
+        impl_: *mut ENGINE,
+    ) -> c_int;
+    pub fn CMAC_Update(ctx: *mut CMAC_CTX, data: *const c_void, dlen: size_t) -> c_int;
+    pub fn CMAC_Final(ctx: *mut CMAC_CTX, out: *mut c_uchar, poutlen: *mut size_t) -> c_int;
+
     // FIXME change to unsafe extern "C" fn
     pub fn SSL_CTX_set_cookie_generate_cb(
         s: *mut SSL_CTX,
@@ -2832,4 +3231,5 @@ extern "C" {
 
     pub fn EVP_MD_size(md: *const EVP_MD) -> c_int;
     pub fn EVP_get_cipherbyname(name: *const c_char) -> *const EVP_CIPHER;
+    pub fn EVP_get_digestbyname(name: *const c_char) -> *const EVP_MD;
 }
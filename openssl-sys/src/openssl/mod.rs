@@ -58,6 +58,13 @@ pub const CMS_DEBUG_DECRYPT: c_uint = 0x20000;
 #[cfg(not(ossl101))]
 pub const CMS_KEY_PARAM: c_uint = 0x40000;
 
+pub const CMS_RECIPINFO_NONE: c_int = -1;
+pub const CMS_RECIPINFO_TRANS: c_int = 0;
+pub const CMS_RECIPINFO_AGREE: c_int = 1;
+pub const CMS_RECIPINFO_KEK: c_int = 2;
+pub const CMS_RECIPINFO_PASS: c_int = 3;
+pub const CMS_RECIPINFO_OTHER: c_int = 4;
+
 extern "C" {
     pub fn CMS_decrypt(
         cms: *mut ::CMS_ContentInfo,
@@ -76,7 +83,75 @@ extern "C" {
         data: *mut ::BIO,
         flags: c_uint,
     ) -> *mut ::CMS_ContentInfo;
+    pub fn CMS_verify(
+        cms: *mut ::CMS_ContentInfo,
+        certs: *mut ::stack_st_X509,
+        store: *mut ::X509_STORE,
+        dcont: *mut ::BIO,
+        out: *mut ::BIO,
+        flags: c_uint,
+    ) -> c_int;
+    pub fn CMS_final(
+        cms: *mut ::CMS_ContentInfo,
+        data: *mut ::BIO,
+        dcont: *mut ::BIO,
+        flags: c_uint,
+    ) -> c_int;
+    pub fn CMS_encrypt(
+        certs: *mut ::stack_st_X509,
+        data: *mut ::BIO,
+        cipher: *const ::EVP_CIPHER,
+        flags: c_uint,
+    ) -> *mut ::CMS_ContentInfo;
     pub fn i2d_CMS_ContentInfo(a: *mut ::CMS_ContentInfo, pp: *mut *mut c_uchar) -> c_int;
+    pub fn i2d_CMS_bio_stream(
+        out: *mut ::BIO,
+        cms: *mut ::CMS_ContentInfo,
+        in_: *mut ::BIO,
+        flags: c_int,
+    ) -> c_int;
+    pub fn d2i_CMS_ContentInfo(
+        a: *mut *mut ::CMS_ContentInfo,
+        pp: *mut *const c_uchar,
+        length: c_long,
+    ) -> *mut ::CMS_ContentInfo;
+    pub fn CMS_get0_type(cms: *mut ::CMS_ContentInfo) -> *const ::ASN1_OBJECT;
+    pub fn CMS_get0_content(cms: *mut ::CMS_ContentInfo) -> *mut *mut ::ASN1_STRING;
+    pub fn CMS_get0_eContentType(cms: *mut ::CMS_ContentInfo) -> *const ::ASN1_OBJECT;
+    pub fn CMS_get0_SignerInfos(cms: *mut ::CMS_ContentInfo) -> *mut ::stack_st_CMS_SignerInfo;
+    pub fn CMS_SignerInfo_get0_algs(
+        si: *mut ::CMS_SignerInfo,
+        pk: *mut *mut ::EVP_PKEY,
+        signer: *mut *mut ::X509,
+        pdig: *mut *mut ::X509_ALGOR,
+        psig: *mut *mut ::X509_ALGOR,
+    );
+    pub fn CMS_signed_get_attr_count(si: *const ::CMS_SignerInfo) -> c_int;
+    pub fn CMS_signed_get_attr_by_NID(
+        si: *const ::CMS_SignerInfo,
+        nid: c_int,
+        lastpos: c_int,
+    ) -> c_int;
+    pub fn CMS_signed_get_attr(si: *const ::CMS_SignerInfo, loc: c_int) -> *mut ::X509_ATTRIBUTE;
+    pub fn CMS_set1_signer_certs(
+        cms: *mut ::CMS_ContentInfo,
+        certs: *mut ::stack_st_X509,
+        flags: c_uint,
+    ) -> c_int;
+    pub fn CMS_get0_signers(cms: *mut ::CMS_ContentInfo) -> *mut ::stack_st_X509;
+    pub fn CMS_get1_certs(cms: *mut ::CMS_ContentInfo) -> *mut ::stack_st_X509;
+    pub fn CMS_get0_RecipientInfos(
+        cms: *mut ::CMS_ContentInfo,
+    ) -> *mut ::stack_st_CMS_RecipientInfo;
+    pub fn CMS_RecipientInfo_type(ri: *mut ::CMS_RecipientInfo) -> c_int;
+    pub fn CMS_RecipientInfo_ktri_get0_signer_id(
+        ri: *mut ::CMS_RecipientInfo,
+        keyid: *mut *mut ::ASN1_STRING,
+        issuer: *mut *mut ::X509_NAME,
+        sno: *mut *mut ::ASN1_INTEGER,
+    ) -> c_int;
+    pub fn CMS_RecipientInfo_ktri_cert_cmp(ri: *mut ::CMS_RecipientInfo, cert: *mut ::X509)
+        -> c_int;
 
     pub fn FIPS_mode_set(onoff: c_int) -> c_int;
     pub fn FIPS_mode() -> c_int;
@@ -29,6 +29,16 @@ pub struct stack_st_X509_ATTRIBUTE {
     pub stack: _STACK,
 }
 
+#[repr(C)]
+pub struct stack_st_CMS_SignerInfo {
+    pub stack: _STACK,
+}
+
+#[repr(C)]
+pub struct stack_st_CMS_RecipientInfo {
+    pub stack: _STACK,
+}
+
 #[repr(C)]
 pub struct stack_st_X509_EXTENSION {
     pub stack: _STACK,
@@ -39,6 +49,11 @@ pub struct stack_st_GENERAL_NAME {
     pub stack: _STACK,
 }
 
+#[repr(C)]
+pub struct stack_st_ACCESS_DESCRIPTION {
+    pub stack: _STACK,
+}
+
 #[repr(C)]
 pub struct stack_st_void {
     pub stack: _STACK,
@@ -280,15 +295,15 @@ pub struct X509 {
 
 #[repr(C)]
 pub struct X509_CINF {
-    version: *mut c_void,
+    pub version: *mut ::ASN1_INTEGER,
     serialNumber: *mut c_void,
     signature: *mut c_void,
     issuer: *mut c_void,
     pub validity: *mut X509_VAL,
     subject: *mut c_void,
-    key: *mut c_void,
-    issuerUID: *mut c_void,
-    subjectUID: *mut c_void,
+    pub key: *mut ::X509_PUBKEY,
+    pub issuerUID: *mut ::ASN1_BIT_STRING,
+    pub subjectUID: *mut ::ASN1_BIT_STRING,
     pub extensions: *mut stack_st_X509_EXTENSION,
     enc: ASN1_ENCODING,
 }
@@ -317,7 +332,7 @@ pub struct X509_REQ_INFO {
     pub enc: ASN1_ENCODING,
     pub version: *mut ::ASN1_INTEGER,
     pub subject: *mut ::X509_NAME,
-    pubkey: *mut c_void,
+    pub pubkey: *mut ::X509_PUBKEY,
     pub attributes: *mut stack_st_X509_ATTRIBUTE,
 }
 
@@ -940,8 +955,13 @@ extern "C" {
         ppval: *mut *mut c_void,
         alg: *mut ::X509_ALGOR,
     );
+    pub fn X509_get_ext_count(x: *mut ::X509) -> c_int;
+    pub fn X509_get_ext(x: *mut ::X509, loc: c_int) -> *mut ::X509_EXTENSION;
+    pub fn X509_EXTENSION_get_critical(ex: *mut ::X509_EXTENSION) -> c_int;
     pub fn X509_NAME_get_entry(n: *mut ::X509_NAME, loc: c_int) -> *mut ::X509_NAME_ENTRY;
     pub fn X509_NAME_ENTRY_get_data(ne: *mut ::X509_NAME_ENTRY) -> *mut ::ASN1_STRING;
+    pub fn X509_NAME_ENTRY_get_object(ne: *mut ::X509_NAME_ENTRY) -> *mut ::ASN1_OBJECT;
+    pub fn X509_NAME_entry_count(n: *mut ::X509_NAME) -> c_int;
     pub fn X509_STORE_CTX_get_chain(ctx: *mut ::X509_STORE_CTX) -> *mut stack_st_X509;
     pub fn X509V3_EXT_nconf_nid(
         conf: *mut ::CONF,
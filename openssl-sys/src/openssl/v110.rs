@@ -19,6 +19,7 @@ pub enum RSA {}
 pub enum SSL {}
 pub enum SSL_CTX {}
 pub enum SSL_SESSION {}
+pub enum stack_st_ACCESS_DESCRIPTION {}
 pub enum stack_st_ASN1_OBJECT {}
 pub enum stack_st_GENERAL_NAME {}
 pub enum stack_st_OPENSSL_STRING {}
@@ -26,6 +27,8 @@ pub enum stack_st_void {}
 pub enum stack_st_X509 {}
 pub enum stack_st_X509_NAME {}
 pub enum stack_st_X509_ATTRIBUTE {}
+pub enum stack_st_CMS_SignerInfo {}
+pub enum stack_st_CMS_RecipientInfo {}
 pub enum stack_st_X509_EXTENSION {}
 pub enum stack_st_SSL_CIPHER {}
 pub enum OPENSSL_INIT_SETTINGS {}
@@ -36,6 +39,14 @@ pub enum X509_REQ {}
 
 pub const CMS_ASCIICRLF: c_uint = 0x80000;
 
+pub const NID_hkdf: c_int = 1036;
+pub const EVP_PKEY_HKDF: c_int = NID_hkdf;
+
+pub const EVP_PKEY_CTRL_HKDF_MD: c_int = ::EVP_PKEY_ALG_CTRL + 3;
+pub const EVP_PKEY_CTRL_HKDF_SALT: c_int = ::EVP_PKEY_ALG_CTRL + 4;
+pub const EVP_PKEY_CTRL_HKDF_KEY: c_int = ::EVP_PKEY_ALG_CTRL + 5;
+pub const EVP_PKEY_CTRL_HKDF_INFO: c_int = ::EVP_PKEY_ALG_CTRL + 6;
+
 pub const SSL_CTRL_SET_MIN_PROTO_VERSION: c_int = 123;
 pub const SSL_CTRL_SET_MAX_PROTO_VERSION: c_int = 124;
 #[cfg(ossl110g)]
@@ -159,6 +170,10 @@ extern "C" {
 
     pub fn EVP_chacha20() -> *const ::EVP_CIPHER;
     pub fn EVP_chacha20_poly1305() -> *const ::EVP_CIPHER;
+    #[cfg(not(osslconf = "OPENSSL_NO_OCB"))]
+    pub fn EVP_aes_128_ocb() -> *const ::EVP_CIPHER;
+    #[cfg(not(osslconf = "OPENSSL_NO_OCB"))]
+    pub fn EVP_aes_256_ocb() -> *const ::EVP_CIPHER;
 
     pub fn HMAC_CTX_new() -> *mut HMAC_CTX;
     pub fn HMAC_CTX_free(ctx: *mut HMAC_CTX);
@@ -198,8 +213,13 @@ extern "C" {
         ppval: *mut *const c_void,
         alg: *const ::X509_ALGOR,
     );
+    pub fn X509_get_ext_count(x: *const ::X509) -> c_int;
+    pub fn X509_get_ext(x: *const ::X509, loc: c_int) -> *mut ::X509_EXTENSION;
+    pub fn X509_EXTENSION_get_critical(ex: *const ::X509_EXTENSION) -> c_int;
     pub fn X509_NAME_get_entry(n: *const ::X509_NAME, loc: c_int) -> *mut ::X509_NAME_ENTRY;
     pub fn X509_NAME_ENTRY_get_data(ne: *const ::X509_NAME_ENTRY) -> *mut ::ASN1_STRING;
+    pub fn X509_NAME_ENTRY_get_object(ne: *const ::X509_NAME_ENTRY) -> *mut ::ASN1_OBJECT;
+    pub fn X509_NAME_entry_count(n: *const ::X509_NAME) -> c_int;
     pub fn X509V3_EXT_nconf_nid(
         conf: *mut ::CONF,
         ctx: *mut ::X509V3_CTX,
@@ -214,9 +234,21 @@ extern "C" {
     ) -> *mut ::X509_EXTENSION;
     pub fn ASN1_STRING_to_UTF8(out: *mut *mut c_uchar, s: *const ::ASN1_STRING) -> c_int;
     pub fn BN_is_negative(b: *const ::BIGNUM) -> c_int;
+    pub fn BN_secure_new() -> *mut ::BIGNUM;
+    pub fn BN_set_flags(b: *mut ::BIGNUM, n: c_int);
+    pub fn BN_get_flags(b: *const ::BIGNUM, n: c_int) -> c_int;
+    pub fn BN_GENCB_new() -> *mut ::BN_GENCB;
+    pub fn BN_GENCB_free(cb: *mut ::BN_GENCB);
+    pub fn BN_GENCB_set(
+        gencb: *mut ::BN_GENCB,
+        callback: Option<unsafe extern "C" fn(c_int, c_int, *mut ::BN_GENCB) -> c_int>,
+        cb_arg: *mut c_void,
+    );
+    pub fn BN_GENCB_get_arg(cb: *mut ::BN_GENCB) -> *mut c_void;
     pub fn EVP_CIPHER_key_length(cipher: *const EVP_CIPHER) -> c_int;
     pub fn EVP_CIPHER_block_size(cipher: *const EVP_CIPHER) -> c_int;
     pub fn EVP_CIPHER_iv_length(cipher: *const EVP_CIPHER) -> c_int;
+    pub fn EVP_CIPHER_nid(cipher: *const EVP_CIPHER) -> c_int;
     pub fn EVP_PBE_scrypt(
         pass: *const c_char,
         passlen: size_t,
@@ -269,6 +301,7 @@ extern "C" {
     pub fn ASN1_STRING_get0_data(x: *const ::ASN1_STRING) -> *const c_uchar;
     pub fn OPENSSL_sk_num(stack: *const ::OPENSSL_STACK) -> c_int;
     pub fn OPENSSL_sk_value(stack: *const ::OPENSSL_STACK, idx: c_int) -> *mut c_void;
+    pub fn SSL_has_pending(ssl: *const ::SSL) -> c_int;
     pub fn SSL_CTX_get_options(ctx: *const ::SSL_CTX) -> c_ulong;
     pub fn SSL_CTX_set_options(ctx: *mut ::SSL_CTX, op: c_ulong) -> c_ulong;
     pub fn SSL_CTX_clear_options(ctx: *mut ::SSL_CTX, op: c_ulong) -> c_ulong;
@@ -287,12 +320,22 @@ extern "C" {
         palg: *mut *const ::X509_ALGOR,
         x: *const ::X509,
     );
+    pub fn X509_get0_uids(
+        x: *const ::X509,
+        piuid: *mut *const ::ASN1_BIT_STRING,
+        psuid: *mut *const ::ASN1_BIT_STRING,
+    );
     pub fn DH_set0_pqg(
         dh: *mut ::DH,
         p: *mut ::BIGNUM,
         q: *mut ::BIGNUM,
         g: *mut ::BIGNUM,
     ) -> c_int;
+    pub fn DH_get0_key(
+        dh: *const ::DH,
+        pub_key: *mut *const ::BIGNUM,
+        priv_key: *mut *const ::BIGNUM,
+    );
     pub fn BIO_set_init(a: *mut ::BIO, init: c_int);
     pub fn BIO_set_data(a: *mut ::BIO, data: *mut c_void);
     pub fn BIO_get_data(a: *mut ::BIO) -> *mut c_void;
@@ -340,6 +383,12 @@ extern "C" {
         outlen: size_t,
     ) -> size_t;
     pub fn SSL_SESSION_up_ref(ses: *mut SSL_SESSION) -> c_int;
+    pub fn SSL_SESSION_get_protocol_version(s: *const SSL_SESSION) -> c_int;
+    pub fn SSL_CTX_get_ciphers(ctx: *const ::SSL_CTX) -> *mut ::stack_st_SSL_CIPHER;
+    pub fn SSL_CTX_set_security_level(ctx: *mut ::SSL_CTX, level: c_int);
+    pub fn SSL_CTX_get_security_level(ctx: *const ::SSL_CTX) -> c_int;
+    pub fn SSL_set_security_level(s: *mut ::SSL, level: c_int);
+    pub fn SSL_get_security_level(s: *const ::SSL) -> c_int;
     pub fn X509_get0_extensions(req: *const ::X509) -> *const stack_st_X509_EXTENSION;
     pub fn X509_STORE_CTX_get0_chain(ctx: *mut ::X509_STORE_CTX) -> *mut stack_st_X509;
     pub fn EVP_MD_CTX_new() -> *mut EVP_MD_CTX;
@@ -370,12 +419,16 @@ extern "C" {
         mac_iter: c_int,
         keytype: c_int,
     ) -> *mut PKCS12;
+    pub fn X509_get_version(x: *const X509) -> c_long;
     pub fn X509_REQ_get_version(req: *const X509_REQ) -> c_long;
     pub fn X509_REQ_get_subject_name(req: *const X509_REQ) -> *mut ::X509_NAME;
+    pub fn X509_get_X509_PUBKEY(x: *const X509) -> *mut ::X509_PUBKEY;
+    pub fn X509_REQ_get_X509_PUBKEY(req: *mut X509_REQ) -> *mut ::X509_PUBKEY;
     pub fn SSL_extension_supported(ext_type: c_uint) -> c_int;
     pub fn ECDSA_SIG_get0(sig: *const ECDSA_SIG, pr: *mut *const BIGNUM, ps: *mut *const BIGNUM);
     pub fn ECDSA_SIG_set0(sig: *mut ECDSA_SIG, pr: *mut BIGNUM, ps: *mut BIGNUM) -> c_int;
 
     pub fn SSL_CIPHER_get_cipher_nid(c: *const ::SSL_CIPHER) -> c_int;
     pub fn SSL_CIPHER_get_digest_nid(c: *const ::SSL_CIPHER) -> c_int;
+    pub fn SSL_get0_verified_chain(ssl: *const ::SSL) -> *mut ::stack_st_X509;
 }
@@ -1,4 +1,4 @@
-use libc::{c_char, c_uchar, c_int, c_uint, c_ulong, size_t, c_void};
+use libc::{c_char, c_uchar, c_int, c_long, c_uint, c_ulong, size_t, c_void};
 
 pub type SSL_CTX_keylog_cb_func =
     Option<unsafe extern "C" fn(ssl: *const ::SSL, line: *const c_char)>;
@@ -25,13 +25,69 @@ pub type SSL_custom_ext_parse_cb_ex =
                                 chainidx: size_t, al: *mut c_int,
                                 parse_arg: *mut c_void) -> c_int>;
 
+pub type SSL_psk_use_session_cb_func =
+    Option<unsafe extern "C" fn(ssl: *mut ::SSL, md: *const ::EVP_MD,
+                                id: *mut *const c_uchar, idlen: *mut size_t,
+                                sess: *mut *mut ::SSL_SESSION) -> c_int>;
+
+pub type SSL_client_hello_cb_fn =
+    Option<unsafe extern "C" fn(s: *mut ::SSL, al: *mut c_int, arg: *mut c_void) -> c_int>;
+
+pub type SSL_psk_find_session_cb_func =
+    Option<unsafe extern "C" fn(ssl: *mut ::SSL, identity: *const c_uchar,
+                                identity_len: size_t,
+                                sess: *mut *mut ::SSL_SESSION) -> c_int>;
+
 pub const SSL_COOKIE_LENGTH: c_int = 4096;
 
+pub const SSL_CLIENT_HELLO_SUCCESS: c_int = 1;
+pub const SSL_CLIENT_HELLO_ERROR: c_int = 0;
+pub const SSL_CLIENT_HELLO_RETRY: c_int = -1;
+
+pub const SSL_ERROR_WANT_CLIENT_HELLO_CB: c_int = 11;
+
 pub const SSL_OP_ENABLE_MIDDLEBOX_COMPAT: c_ulong = 0x00100000;
 pub const SSL_OP_NO_TLSv1_3: c_ulong = 0x20000000;
+pub const SSL_OP_NO_RENEGOTIATION: c_ulong = 0x40000000;
 
 pub const TLS1_3_VERSION: c_int = 0x304;
 
+pub const SSL_CTRL_SET_GROUPS_LIST: c_int = 92;
+
+pub const SSL_READ_EARLY_DATA_ERROR: c_int = 0;
+pub const SSL_READ_EARLY_DATA_SUCCESS: c_int = 1;
+pub const SSL_READ_EARLY_DATA_FINISH: c_int = 2;
+
+pub const SSL_EARLY_DATA_NOT_SENT: c_int = 0;
+pub const SSL_EARLY_DATA_REJECTED: c_int = 1;
+pub const SSL_EARLY_DATA_ACCEPTED: c_int = 2;
+
+pub const SSL_KEY_UPDATE_NONE: c_int = -1;
+pub const SSL_KEY_UPDATE_NOT_REQUESTED: c_int = 0;
+pub const SSL_KEY_UPDATE_REQUESTED: c_int = 1;
+
+pub const NID_sm2: c_int = 1172;
+pub const NID_sm3: c_int = 1143;
+pub const NID_ffdhe2048: c_int = 1126;
+pub const NID_ffdhe3072: c_int = 1127;
+pub const NID_ffdhe4096: c_int = 1128;
+pub const NID_ffdhe6144: c_int = 1129;
+pub const NID_ffdhe8192: c_int = 1130;
+
+pub const EVP_PKEY_SM2: c_int = NID_sm2;
+
+pub const NID_X25519: c_int = 1034;
+pub const NID_ED25519: c_int = 1087;
+
+pub const EVP_PKEY_X25519: c_int = NID_X25519;
+pub const EVP_PKEY_ED25519: c_int = NID_ED25519;
+
+pub const EVP_PKEY_CTRL_HKDF_MODE: c_int = ::EVP_PKEY_ALG_CTRL + 7;
+
+pub const EVP_PKEY_HKDEF_MODE_EXTRACT_AND_EXPAND: c_int = 0;
+pub const EVP_PKEY_HKDEF_MODE_EXTRACT_ONLY: c_int = 1;
+pub const EVP_PKEY_HKDEF_MODE_EXPAND_ONLY: c_int = 2;
+
 pub const SSL_EXT_TLS_ONLY: c_uint = 0x0001;
 /* This extension is only allowed in DTLS */
 pub const SSL_EXT_DTLS_ONLY: c_uint = 0x0002;
@@ -57,6 +113,53 @@ pub const SSL_EXT_TLS1_3_CERTIFICATE_REQUEST: c_uint = 0x4000;
 
 
 extern "C" {
+    pub fn DH_new_by_nid(nid: c_int) -> *mut ::DH;
+    pub fn EVP_DigestSign(
+        ctx: *mut ::EVP_MD_CTX,
+        sigret: *mut c_uchar,
+        siglen: *mut size_t,
+        tbs: *const c_uchar,
+        tbslen: size_t,
+    ) -> c_int;
+    pub fn EVP_DigestVerify(
+        ctx: *mut ::EVP_MD_CTX,
+        sigret: *const c_uchar,
+        siglen: size_t,
+        tbs: *const c_uchar,
+        tbslen: size_t,
+    ) -> c_int;
+    #[cfg(not(osslconf = "OPENSSL_NO_ARIA"))]
+    pub fn EVP_aria_128_cbc() -> *const ::EVP_CIPHER;
+    #[cfg(not(osslconf = "OPENSSL_NO_ARIA"))]
+    pub fn EVP_aria_128_gcm() -> *const ::EVP_CIPHER;
+    #[cfg(not(osslconf = "OPENSSL_NO_ARIA"))]
+    pub fn EVP_aria_256_cbc() -> *const ::EVP_CIPHER;
+    #[cfg(not(osslconf = "OPENSSL_NO_ARIA"))]
+    pub fn EVP_aria_256_gcm() -> *const ::EVP_CIPHER;
+    pub fn SSL_key_update(s: *mut ::SSL, updatetype: c_int) -> c_int;
+    pub fn SSL_get_key_update_type(s: *const ::SSL) -> c_int;
+    pub fn EVP_PKEY_new_raw_private_key(
+        ty: c_int,
+        e: *mut ::ENGINE,
+        key: *const c_uchar,
+        keylen: size_t,
+    ) -> *mut ::EVP_PKEY;
+    pub fn EVP_PKEY_new_raw_public_key(
+        ty: c_int,
+        e: *mut ::ENGINE,
+        key: *const c_uchar,
+        keylen: size_t,
+    ) -> *mut ::EVP_PKEY;
+    pub fn EVP_PKEY_get_raw_private_key(
+        pkey: *const ::EVP_PKEY,
+        key: *mut c_uchar,
+        len: *mut size_t,
+    ) -> c_int;
+    pub fn EVP_PKEY_get_raw_public_key(
+        pkey: *const ::EVP_PKEY,
+        key: *mut c_uchar,
+        len: *mut size_t,
+    ) -> c_int;
     pub fn SSL_CTX_set_keylog_callback(ctx: *mut ::SSL_CTX, cb: SSL_CTX_keylog_cb_func);
     pub fn SSL_CTX_add_custom_ext(ctx: *mut ::SSL_CTX, ext_type: c_uint, context: c_uint,
                                   add_cb: SSL_custom_ext_add_cb_ex,
@@ -65,7 +168,59 @@ extern "C" {
                                   parse_cb: SSL_custom_ext_parse_cb_ex,
                                   parse_arg: *mut c_void) -> c_int;
     pub fn SSL_stateless(s: *mut ::SSL) -> c_int;
+    pub fn SSL_CTX_set_psk_use_session_callback(
+        ctx: *mut ::SSL_CTX,
+        cb: SSL_psk_use_session_cb_func,
+    );
+    pub fn SSL_CTX_set_psk_find_session_callback(
+        ctx: *mut ::SSL_CTX,
+        cb: SSL_psk_find_session_cb_func,
+    );
+    pub fn SSL_CTX_set_client_hello_cb(
+        c: *mut ::SSL_CTX,
+        cb: SSL_client_hello_cb_fn,
+        arg: *mut c_void,
+    );
+    pub fn SSL_client_hello_isv2(s: *mut ::SSL) -> c_int;
+    pub fn SSL_client_hello_get0_legacy_version(s: *mut ::SSL) -> c_uint;
+    pub fn SSL_client_hello_get0_random(s: *mut ::SSL, out: *mut *const c_uchar) -> size_t;
+    pub fn SSL_client_hello_get0_session_id(s: *mut ::SSL, out: *mut *const c_uchar) -> size_t;
+    pub fn SSL_client_hello_get0_ciphers(s: *mut ::SSL, out: *mut *const c_uchar) -> size_t;
+    pub fn SSL_client_hello_get0_compression_methods(
+        s: *mut ::SSL,
+        out: *mut *const c_uchar,
+    ) -> size_t;
+    pub fn SSL_client_hello_get1_extensions_present(
+        s: *mut ::SSL,
+        out: *mut *mut c_int,
+        outlen: *mut size_t,
+    ) -> c_int;
+    pub fn SSL_client_hello_get0_ext(
+        s: *mut ::SSL,
+        type_: c_uint,
+        out: *mut *const c_uchar,
+        outlen: *mut size_t,
+    ) -> c_int;
+    pub fn SSL_CIPHER_find(ssl: *mut ::SSL, ptr: *const c_uchar) -> *const ::SSL_CIPHER;
+    pub fn SSL_SESSION_set1_master_key(
+        sess: *mut ::SSL_SESSION,
+        in_: *const c_uchar,
+        len: size_t,
+    ) -> c_int;
+    pub fn SSL_SESSION_set_cipher(s: *mut ::SSL_SESSION, cipher: *const ::SSL_CIPHER) -> c_int;
+    pub fn SSL_SESSION_set_protocol_version(s: *mut ::SSL_SESSION, version: c_int) -> c_int;
+    #[cfg(not(osslconf = "OPENSSL_NO_SM3"))]
+    pub fn EVP_sm3() -> *const ::EVP_MD;
+    pub fn EVP_sha3_224() -> *const ::EVP_MD;
+    pub fn EVP_sha3_256() -> *const ::EVP_MD;
+    pub fn EVP_sha3_384() -> *const ::EVP_MD;
+    pub fn EVP_sha3_512() -> *const ::EVP_MD;
+    pub fn EVP_shake128() -> *const ::EVP_MD;
+    pub fn EVP_shake256() -> *const ::EVP_MD;
+    pub fn EVP_DigestFinalXOF(ctx: *mut ::EVP_MD_CTX, md: *mut c_uchar, len: size_t) -> c_int;
+    pub fn EVP_PKEY_set_alias_type(pkey: *mut ::EVP_PKEY, type_: c_int) -> c_int;
     pub fn SSL_CIPHER_get_handshake_digest(cipher: *const ::SSL_CIPHER) -> *const ::EVP_MD;
+    pub fn SSL_CIPHER_standard_name(cipher: *const ::SSL_CIPHER) -> *const c_char;
     pub fn SSL_CTX_set_stateless_cookie_generate_cb(
         s: *mut ::SSL_CTX,
         cb: Option<unsafe extern "C" fn(
@@ -82,4 +237,29 @@ extern "C" {
             cookie_len: size_t
         ) -> c_int>
     );
+    pub fn SSL_CTX_set_ciphersuites(ctx: *mut ::SSL_CTX, str: *const c_char) -> c_int;
+    pub fn SSL_set_ciphersuites(s: *mut ::SSL, str: *const c_char) -> c_int;
+    pub fn SSL_CTX_set_max_early_data(ctx: *mut ::SSL_CTX, max_early_data: u32) -> c_int;
+    pub fn SSL_CTX_get_max_early_data(ctx: *const ::SSL_CTX) -> u32;
+    pub fn SSL_set_max_early_data(s: *mut ::SSL, max_early_data: u32) -> c_int;
+    pub fn SSL_get_max_early_data(s: *const ::SSL) -> u32;
+    pub fn SSL_read_early_data(
+        s: *mut ::SSL,
+        buf: *mut c_void,
+        num: size_t,
+        readbytes: *mut size_t,
+    ) -> c_int;
+    pub fn SSL_write_early_data(
+        s: *mut ::SSL,
+        buf: *const c_void,
+        num: size_t,
+        written: *mut size_t,
+    ) -> c_int;
+    pub fn SSL_get_early_data_status(s: *const ::SSL) -> c_int;
+    pub fn RAND_priv_bytes(buf: *mut c_uchar, num: c_int) -> c_int;
+    pub fn RAND_keep_random_devices_open(keep: c_int);
+}
+
+pub unsafe fn SSL_CTX_set1_groups_list(ctx: *mut ::SSL_CTX, list: *const c_char) -> c_long {
+    ::SSL_CTX_ctrl(ctx, SSL_CTRL_SET_GROUPS_LIST, 0, list as *mut c_void)
 }
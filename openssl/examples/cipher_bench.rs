@@ -0,0 +1,67 @@
+//! A micro-benchmark that measures bulk encryption throughput for a handful
+//! of ciphers and reports whether OpenSSL is using AES-NI for them.
+//!
+//! Usage: `cargo run --example cipher_bench [megabytes]`
+
+extern crate openssl;
+
+use std::env;
+use std::time::{Duration, Instant};
+
+use openssl::error::ErrorStack;
+use openssl::symm::{Cipher, Crypter, Mode};
+
+fn ciphers() -> Vec<(&'static str, Cipher)> {
+    vec![
+        ("aes-128-cbc", Cipher::aes_128_cbc()),
+        ("aes-256-cbc", Cipher::aes_256_cbc()),
+        ("aes-128-gcm", Cipher::aes_128_gcm()),
+        ("aes-256-gcm", Cipher::aes_256_gcm()),
+        ("aes-256-ctr", Cipher::aes_256_ctr()),
+        ("rc4", Cipher::rc4()),
+    ]
+}
+
+/// Encrypts `total` bytes in 16 KiB chunks, returning the elapsed time.
+fn bench(cipher: Cipher, total: usize) -> Result<Duration, ErrorStack> {
+    let key = vec![0x42; cipher.key_len()];
+    let iv = cipher.iv_len().map(|len| vec![0x24; len]);
+    let input = vec![0; 16 * 1024];
+    let mut output = vec![0; input.len() + cipher.block_size()];
+
+    let mut crypter = Crypter::new(cipher, Mode::Encrypt, &key, iv.as_ref().map(|v| &v[..]))?;
+    crypter.pad(false);
+
+    let start = Instant::now();
+    let mut done = 0;
+    while done < total {
+        crypter.update(&input, &mut output)?;
+        done += input.len();
+    }
+    crypter.finalize(&mut output)?;
+    Ok(start.elapsed())
+}
+
+fn main() {
+    let megabytes = env::args()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(64usize);
+    let total = megabytes * 1024 * 1024;
+
+    println!("{:<12} {:>10} {:>8}", "cipher", "MB/s", "aes-ni");
+    for (name, cipher) in ciphers() {
+        match bench(cipher, total) {
+            Ok(elapsed) => {
+                let secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9;
+                println!(
+                    "{:<12} {:>10.1} {:>8}",
+                    name,
+                    megabytes as f64 / secs,
+                    cipher.is_hardware_accelerated()
+                );
+            }
+            Err(e) => println!("{:<12} error: {}", name, e),
+        }
+    }
+}
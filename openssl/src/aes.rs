@@ -1,7 +1,8 @@
-//! Low level AES IGE functionality
+//! Low level AES IGE and key wrap functionality
 //!
 //! AES ECB, CBC, XTS, CTR, CFB, GCM and other conventional symmetric encryption
-//! modes are found in [`symm`].  This is the implementation of AES IGE.
+//! modes are found in [`symm`].  This is the implementation of AES IGE, along with the
+//! AES key wrap algorithm of [RFC 3394] used by formats such as JWE and CMS.
 //!
 //! Advanced Encryption Standard (AES) provides symmetric key cipher that
 //! the same key is used to encrypt and decrypt data.  This implementation
@@ -17,6 +18,7 @@
 //! encryption methods are found in the [`Crypter`] and [`Cipher`] structs.
 //!
 //! [`symm`]: ../symm/index.html
+//! [RFC 3394]: https://tools.ietf.org/html/rfc3394
 //! [`Crypter`]: ../symm/struct.Crypter.html
 //! [`Cipher`]: ../symm/struct.Cipher.html
 //!
@@ -48,7 +50,8 @@
 //! # }
 use ffi;
 use std::mem;
-use libc::c_int;
+use std::ptr;
+use libc::{c_int, c_uint};
 
 use symm::Mode;
 
@@ -147,6 +150,87 @@ pub fn aes_ige(in_: &[u8], out: &mut [u8], key: &AesKey, iv: &mut [u8], mode: Mo
     }
 }
 
+/// Wraps a key with the AES key wrap algorithm of RFC 3394, returning the number of bytes
+/// written to `out`.
+///
+/// `key` must have been created with `AesKey::new_encrypt`. `iv` defaults to the standard
+/// initial value `A6A6A6A6A6A6A6A6` if `None`. `in_` must be a multiple of 8 bytes long and at
+/// least 16 bytes long.
+///
+/// This corresponds to [`AES_wrap_key`].
+///
+/// # Panics
+///
+/// Panics if `out` is shorter than `in_` plus 8 bytes.
+///
+/// [`AES_wrap_key`]: https://www.openssl.org/docs/man1.1.1/man3/AES_wrap_key.html
+pub fn wrap_key(
+    key: &AesKey,
+    iv: Option<[u8; 8]>,
+    out: &mut [u8],
+    in_: &[u8],
+) -> Result<usize, KeyError> {
+    unsafe {
+        // the ciphertext is one 64 bit block longer than the plaintext
+        assert!(out.len() >= in_.len() + 8);
+        assert!(in_.len() <= c_uint::max_value() as usize);
+
+        // OpenSSL only reads from the key
+        let written = ffi::AES_wrap_key(
+            &key.0 as *const _ as *mut _,
+            iv.as_ref().map_or(ptr::null(), |iv| iv.as_ptr()),
+            out.as_mut_ptr(),
+            in_.as_ptr(),
+            in_.len() as c_uint,
+        );
+        if written <= 0 {
+            Err(KeyError(()))
+        } else {
+            Ok(written as usize)
+        }
+    }
+}
+
+/// Unwraps a key wrapped with the AES key wrap algorithm of RFC 3394, returning the number of
+/// bytes written to `out`.
+///
+/// `key` must have been created with `AesKey::new_decrypt`. `iv` defaults to the standard
+/// initial value `A6A6A6A6A6A6A6A6` if `None`. An error is returned if the integrity check
+/// fails, or if `in_` is not a multiple of 8 bytes long and at least 24 bytes long.
+///
+/// This corresponds to [`AES_unwrap_key`].
+///
+/// # Panics
+///
+/// Panics if `out` is shorter than `in_` minus 8 bytes.
+///
+/// [`AES_unwrap_key`]: https://www.openssl.org/docs/man1.1.1/man3/AES_unwrap_key.html
+pub fn unwrap_key(
+    key: &AesKey,
+    iv: Option<[u8; 8]>,
+    out: &mut [u8],
+    in_: &[u8],
+) -> Result<usize, KeyError> {
+    unsafe {
+        assert!(out.len() + 8 >= in_.len());
+        assert!(in_.len() <= c_uint::max_value() as usize);
+
+        // OpenSSL only reads from the key
+        let written = ffi::AES_unwrap_key(
+            &key.0 as *const _ as *mut _,
+            iv.as_ref().map_or(ptr::null(), |iv| iv.as_ptr()),
+            out.as_mut_ptr(),
+            in_.as_ptr(),
+            in_.len() as c_uint,
+        );
+        if written <= 0 {
+            Err(KeyError(()))
+        } else {
+            Ok(written as usize)
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use hex::FromHex;
@@ -177,4 +261,31 @@ mod test {
         aes_ige(&ct, &mut pt_actual, &key, &mut iv, Mode::Decrypt);
         assert_eq!(pt_actual, pt);
     }
+
+    // RFC 3394 section 4.6: 256 bits of key data with a 256 bit KEK
+    #[test]
+    fn key_wrap_rfc3394() {
+        let kek = Vec::from_hex("000102030405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F")
+            .unwrap();
+        let key_data =
+            Vec::from_hex("00112233445566778899AABBCCDDEEFF000102030405060708090A0B0C0D0E0F")
+                .unwrap();
+        let wrapped = Vec::from_hex(
+            "28C9F404C4B810F4CBCCB35CFB87F8263F5786E2D80ED326CBC7F0E71A99F43BFB988B9B7A02DD21",
+        ).unwrap();
+
+        let mut out = [0; 40];
+        let key = AesKey::new_encrypt(&kek).unwrap();
+        assert_eq!(wrap_key(&key, None, &mut out, &key_data).unwrap(), 40);
+        assert_eq!(&out[..], &wrapped[..]);
+
+        let mut out = [0; 32];
+        let key = AesKey::new_decrypt(&kek).unwrap();
+        assert_eq!(unwrap_key(&key, None, &mut out, &wrapped).unwrap(), 32);
+        assert_eq!(&out[..], &key_data[..]);
+
+        let mut corrupted = wrapped.clone();
+        corrupted[0] ^= 1;
+        assert!(unwrap_key(&key, None, &mut out, &corrupted).is_err());
+    }
 }
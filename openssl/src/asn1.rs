@@ -26,17 +26,21 @@
 //! ```
 use ffi;
 use foreign_types::{ForeignType, ForeignTypeRef};
-use libc::{c_char, c_int, c_long};
+use libc::{c_char, c_int, c_long, time_t};
+use std::cmp::Ordering;
+use std::ffi::CString;
 use std::fmt;
 use std::ptr;
 use std::slice;
 use std::str;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use {cvt, cvt_p};
 use bio::MemBio;
-use bn::BigNum;
+use bn::{BigNum, BigNumRef};
 use error::ErrorStack;
 use nid::Nid;
+use stack::Stackable;
 use string::OpensslString;
 
 foreign_type_and_impl_send_sync! {
@@ -94,6 +98,123 @@ foreign_type_and_impl_send_sync! {
     pub struct Asn1TimeRef;
 }
 
+/// The difference between two ASN.1 times.
+///
+/// The two components have the same sign.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimeDiff {
+    /// Difference in days
+    pub days: c_int,
+    /// Difference in seconds.
+    ///
+    /// This is always less than the number of seconds in a day.
+    pub secs: c_int,
+}
+
+impl TimeDiff {
+    /// Returns the total difference in seconds.
+    pub fn total_secs(&self) -> i64 {
+        self.days as i64 * 60 * 60 * 24 + self.secs as i64
+    }
+}
+
+impl Asn1TimeRef {
+    /// Find difference between two times
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    ///
+    /// This corresponds to [`ASN1_TIME_diff`].
+    ///
+    /// [`ASN1_TIME_diff`]: https://www.openssl.org/docs/man1.1.0/crypto/ASN1_TIME_diff.html
+    #[cfg(any(ossl102, ossl110))]
+    pub fn diff(&self, compare: &Self) -> Result<TimeDiff, ErrorStack> {
+        let mut days = 0;
+        let mut secs = 0;
+        let other = compare.as_ptr();
+
+        let err = unsafe { ffi::ASN1_TIME_diff(&mut days, &mut secs, self.as_ptr(), other) };
+
+        match err {
+            0 => Err(ErrorStack::get()),
+            _ => Ok(TimeDiff { days, secs }),
+        }
+    }
+
+    /// Compares two times.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    #[cfg(any(ossl102, ossl110))]
+    pub fn compare(&self, other: &Self) -> Result<Ordering, ErrorStack> {
+        let d = self.diff(other)?;
+        if d.days > 0 || d.secs > 0 {
+            Ok(Ordering::Less)
+        } else if d.days < 0 || d.secs < 0 {
+            Ok(Ordering::Greater)
+        } else {
+            Ok(Ordering::Equal)
+        }
+    }
+}
+
+#[cfg(any(ossl102, ossl110))]
+impl PartialEq for Asn1TimeRef {
+    fn eq(&self, other: &Asn1TimeRef) -> bool {
+        self.compare(other).ok() == Some(Ordering::Equal)
+    }
+}
+
+#[cfg(any(ossl102, ossl110))]
+impl PartialOrd for Asn1TimeRef {
+    fn partial_cmp(&self, other: &Asn1TimeRef) -> Option<Ordering> {
+        self.compare(other).ok()
+    }
+}
+
+#[cfg(any(ossl102, ossl110))]
+impl PartialEq<SystemTime> for Asn1TimeRef {
+    fn eq(&self, other: &SystemTime) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+#[cfg(any(ossl102, ossl110))]
+impl PartialOrd<SystemTime> for Asn1TimeRef {
+    fn partial_cmp(&self, other: &SystemTime) -> Option<Ordering> {
+        match Asn1Time::from_system_time(*other) {
+            Ok(other) => self.partial_cmp(&*other),
+            Err(_) => None,
+        }
+    }
+}
+
+#[cfg(any(ossl102, ossl110))]
+impl PartialEq for Asn1Time {
+    fn eq(&self, other: &Asn1Time) -> bool {
+        **self == **other
+    }
+}
+
+#[cfg(any(ossl102, ossl110))]
+impl PartialOrd for Asn1Time {
+    fn partial_cmp(&self, other: &Asn1Time) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+#[cfg(any(ossl102, ossl110))]
+impl PartialEq<SystemTime> for Asn1Time {
+    fn eq(&self, other: &SystemTime) -> bool {
+        **self == *other
+    }
+}
+
+#[cfg(any(ossl102, ossl110))]
+impl PartialOrd<SystemTime> for Asn1Time {
+    fn partial_cmp(&self, other: &SystemTime) -> Option<Ordering> {
+        (**self).partial_cmp(other)
+    }
+}
+
 impl fmt::Display for Asn1TimeRef {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         unsafe {
@@ -114,10 +235,64 @@ impl Asn1Time {
         }
     }
 
+    /// Creates a new time corresponding to the current time.
+    pub fn now() -> Result<Asn1Time, ErrorStack> {
+        Asn1Time::from_period(0)
+    }
+
     /// Creates a new time on specified interval in days from now
     pub fn days_from_now(days: u32) -> Result<Asn1Time, ErrorStack> {
         Asn1Time::from_period(days as c_long * 60 * 60 * 24)
     }
+
+    /// Creates a new time from the specified number of seconds since the Unix epoch.
+    ///
+    /// This corresponds to [`ASN1_TIME_set`].
+    ///
+    /// [`ASN1_TIME_set`]: https://www.openssl.org/docs/man1.1.0/crypto/ASN1_TIME_set.html
+    pub fn from_unix(time: time_t) -> Result<Asn1Time, ErrorStack> {
+        ffi::init();
+
+        unsafe {
+            let handle = cvt_p(ffi::ASN1_TIME_set(ptr::null_mut(), time))?;
+            Ok(Asn1Time::from_ptr(handle))
+        }
+    }
+
+    /// Creates a new time corresponding to a `SystemTime`.
+    pub fn from_system_time(time: SystemTime) -> Result<Asn1Time, ErrorStack> {
+        let secs = match time.duration_since(UNIX_EPOCH) {
+            Ok(d) => d.as_secs() as time_t,
+            Err(e) => {
+                let d = e.duration();
+                // round towards negative infinity for times before the epoch
+                -(d.as_secs() as time_t) - (d.subsec_nanos() > 0) as time_t
+            }
+        };
+        Asn1Time::from_unix(secs)
+    }
+
+    /// Creates a new time from a string in the UTCTime (`YYMMDDHHMMSSZ`) or GeneralizedTime
+    /// (`YYYYMMDDHHMMSSZ`) format.
+    ///
+    /// This corresponds to [`ASN1_TIME_set_string`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s` contains a null byte.
+    ///
+    /// [`ASN1_TIME_set_string`]: https://www.openssl.org/docs/man1.1.0/crypto/ASN1_TIME_set_string.html
+    #[cfg_attr(feature = "cargo-clippy", allow(should_implement_trait))]
+    pub fn from_str(s: &str) -> Result<Asn1Time, ErrorStack> {
+        ffi::init();
+
+        unsafe {
+            let s = CString::new(s).unwrap();
+            let time = Asn1Time::from_ptr(cvt_p(ffi::ASN1_TIME_new())?);
+            cvt(ffi::ASN1_TIME_set_string(time.as_ptr(), s.as_ptr()))?;
+            Ok(time)
+        }
+    }
 }
 
 foreign_type_and_impl_send_sync! {
@@ -191,6 +366,17 @@ foreign_type_and_impl_send_sync! {
     pub struct Asn1IntegerRef;
 }
 
+impl Asn1Integer {
+    /// Converts a `BigNum` to an `Asn1Integer`.
+    ///
+    /// This is equivalent to [`BigNumRef::to_asn1_integer`].
+    ///
+    /// [`BigNumRef::to_asn1_integer`]: ../bn/struct.BigNumRef.html#method.to_asn1_integer
+    pub fn from_bn(bn: &BigNumRef) -> Result<Asn1Integer, ErrorStack> {
+        bn.to_asn1_integer()
+    }
+}
+
 impl Asn1IntegerRef {
     #[allow(missing_docs)]
     #[deprecated(since = "0.10.6", note = "use to_bn instead")]
@@ -220,6 +406,55 @@ impl Asn1IntegerRef {
     pub fn set(&mut self, value: i32) -> Result<(), ErrorStack> {
         unsafe { cvt(::ffi::ASN1_INTEGER_set(self.as_ptr(), value as c_long)).map(|_| ()) }
     }
+
+    /// Returns a copy of the integer.
+    ///
+    /// This corresponds to [`ASN1_INTEGER_dup`].
+    ///
+    /// [`ASN1_INTEGER_dup`]: https://www.openssl.org/docs/man1.1.0/crypto/ASN1_INTEGER_get.html
+    pub fn to_owned(&self) -> Result<Asn1Integer, ErrorStack> {
+        unsafe { cvt_p(ffi::ASN1_INTEGER_dup(self.as_ptr())).map(|p| Asn1Integer::from_ptr(p)) }
+    }
+}
+
+impl PartialEq for Asn1IntegerRef {
+    fn eq(&self, other: &Asn1IntegerRef) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Asn1IntegerRef {}
+
+impl PartialOrd for Asn1IntegerRef {
+    fn partial_cmp(&self, other: &Asn1IntegerRef) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Asn1IntegerRef {
+    fn cmp(&self, other: &Asn1IntegerRef) -> Ordering {
+        unsafe { ffi::ASN1_INTEGER_cmp(self.as_ptr(), other.as_ptr()).cmp(&0) }
+    }
+}
+
+impl PartialEq for Asn1Integer {
+    fn eq(&self, other: &Asn1Integer) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for Asn1Integer {}
+
+impl PartialOrd for Asn1Integer {
+    fn partial_cmp(&self, other: &Asn1Integer) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Asn1Integer {
+    fn cmp(&self, other: &Asn1Integer) -> Ordering {
+        (**self).cmp(&**other)
+    }
 }
 
 foreign_type_and_impl_send_sync! {
@@ -273,29 +508,85 @@ foreign_type_and_impl_send_sync! {
     pub struct Asn1ObjectRef;
 }
 
+impl Stackable for Asn1Object {
+    type StackType = ffi::stack_st_ASN1_OBJECT;
+}
+
+impl Asn1Object {
+    /// Creates an object from its textual representation.
+    ///
+    /// `s` may either be a short or long name known to OpenSSL, such as `"CN"` or `"commonName"`,
+    /// or a dotted numerical OID such as `"1.2.840.113549.1.1.11"`. OIDs which OpenSSL does not
+    /// recognize are supported, and will have a `nid` of `Nid::UNDEF`.
+    ///
+    /// This corresponds to [`OBJ_txt2obj`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s` contains a null byte.
+    ///
+    /// [`OBJ_txt2obj`]: https://www.openssl.org/docs/man1.1.0/crypto/OBJ_txt2obj.html
+    #[cfg_attr(feature = "cargo-clippy", allow(should_implement_trait))]
+    pub fn from_str(s: &str) -> Result<Asn1Object, ErrorStack> {
+        ffi::init();
+
+        unsafe {
+            let s = CString::new(s).unwrap();
+            let obj = cvt_p(ffi::OBJ_txt2obj(s.as_ptr(), 0))?;
+            Ok(Asn1Object::from_ptr(obj))
+        }
+    }
+}
+
 impl Asn1ObjectRef {
     /// Returns the NID associated with this OID.
     pub fn nid(&self) -> Nid {
         unsafe { Nid::from_raw(ffi::OBJ_obj2nid(self.as_ptr())) }
     }
-}
 
-impl fmt::Display for Asn1ObjectRef {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+    /// Returns the dotted numerical form of the OID, such as `"2.5.4.3"`.
+    ///
+    /// Unlike the `Display` implementation, this never substitutes the object's name.
+    ///
+    /// This corresponds to [`OBJ_obj2txt`] with `no_name` set.
+    ///
+    /// [`OBJ_obj2txt`]: https://www.openssl.org/docs/man1.1.0/crypto/OBJ_obj2txt.html
+    pub fn text(&self) -> String {
+        self.to_text(1)
+    }
+
+    fn to_text(&self, no_name: c_int) -> String {
         unsafe {
-            let mut buf = [0; 80];
+            let mut buf = vec![0u8; 80];
             let len = ffi::OBJ_obj2txt(
                 buf.as_mut_ptr() as *mut _,
                 buf.len() as c_int,
                 self.as_ptr(),
-                0,
+                no_name,
             );
-            let s = str::from_utf8(&buf[..len as usize]).map_err(|_| fmt::Error)?;
-            fmt.write_str(s)
+            let len = if len < 0 { 0 } else { len as usize };
+            // the return value is the full length, even if the output was truncated
+            if len >= buf.len() {
+                buf = vec![0; len + 1];
+                ffi::OBJ_obj2txt(
+                    buf.as_mut_ptr() as *mut _,
+                    buf.len() as c_int,
+                    self.as_ptr(),
+                    no_name,
+                );
+            }
+            buf.truncate(len);
+            String::from_utf8_lossy(&buf).into_owned()
         }
     }
 }
 
+impl fmt::Display for Asn1ObjectRef {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.to_text(0))
+    }
+}
+
 #[cfg(any(ossl101, ossl102))]
 use ffi::ASN1_STRING_data;
 
@@ -0,0 +1,175 @@
+//! Base64 encoding and decoding.
+//!
+//! Both the standard alphabet and the URL and filename safe alphabet of [RFC 4648] are supported.
+//! The URL safe variants omit padding, as required by JOSE formats such as JWS and JWE.
+//!
+//! # Examples
+//!
+//! ```
+//! use openssl::base64;
+//!
+//! assert_eq!(base64::encode_block(b"\xfb\xff"), "+/8=");
+//! assert_eq!(base64::encode_block_url(b"\xfb\xff"), "-_8");
+//! assert_eq!(base64::decode_block_url("-_8").unwrap(), b"\xfb\xff");
+//! ```
+//!
+//! [RFC 4648]: https://tools.ietf.org/html/rfc4648
+use ffi;
+use libc::c_int;
+
+use cvt_n;
+use error::ErrorStack;
+
+/// Encodes a slice of bytes with the standard base64 alphabet and padding.
+///
+/// This corresponds to [`EVP_EncodeBlock`].
+///
+/// # Panics
+///
+/// Panics if the input length or computed output length overflow a signed C integer.
+///
+/// [`EVP_EncodeBlock`]: https://www.openssl.org/docs/man1.1.1/man3/EVP_DecodeBlock.html
+pub fn encode_block(src: &[u8]) -> String {
+    assert!(src.len() <= c_int::max_value() as usize / 4 * 3 - 2);
+    let len = (src.len() + 2) / 3 * 4;
+
+    let mut out = vec![0; len + 1];
+    unsafe {
+        let written = ffi::EVP_EncodeBlock(out.as_mut_ptr(), src.as_ptr(), src.len() as c_int);
+        out.truncate(written as usize);
+        String::from_utf8_unchecked(out)
+    }
+}
+
+/// Decodes a string encoded with the standard base64 alphabet.
+///
+/// Leading and trailing whitespace is ignored. Padding is required.
+///
+/// This corresponds to [`EVP_DecodeBlock`].
+///
+/// # Panics
+///
+/// Panics if the input length overflows a signed C integer.
+///
+/// [`EVP_DecodeBlock`]: https://www.openssl.org/docs/man1.1.1/man3/EVP_DecodeBlock.html
+pub fn decode_block(src: &str) -> Result<Vec<u8>, ErrorStack> {
+    let src = src.trim();
+    if src.is_empty() {
+        return Ok(vec![]);
+    }
+    assert!(src.len() <= c_int::max_value() as usize);
+
+    let mut out = vec![0; src.len() / 4 * 3 + 3];
+    unsafe {
+        let len = cvt_n(ffi::EVP_DecodeBlock(
+            out.as_mut_ptr(),
+            src.as_ptr(),
+            src.len() as c_int,
+        ))?;
+        out.truncate(len as usize);
+    }
+
+    // OpenSSL decodes padding characters as zero bytes
+    if src.ends_with('=') {
+        out.pop();
+        if src.ends_with("==") {
+            out.pop();
+        }
+    }
+    Ok(out)
+}
+
+/// Encodes a slice of bytes with the URL and filename safe base64 alphabet, without padding.
+///
+/// # Panics
+///
+/// Panics if the input length or computed output length overflow a signed C integer.
+pub fn encode_block_url(src: &[u8]) -> String {
+    encode_block(src)
+        .chars()
+        .filter(|&c| c != '=')
+        .map(|c| match c {
+            '+' => '-',
+            '/' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Decodes a string encoded with the URL and filename safe base64 alphabet, without padding.
+///
+/// Unlike `decode_block`, whitespace, padding, and characters of the standard alphabet are
+/// rejected.
+///
+/// # Panics
+///
+/// Panics if the input length overflows a signed C integer.
+pub fn decode_block_url(src: &str) -> Result<Vec<u8>, ErrorStack> {
+    let mut standard = src.bytes()
+        .map(|b| match b {
+            b'-' => b'+',
+            b'_' => b'/',
+            b if is_alphanumeric(b) => b,
+            // rejected by OpenSSL
+            _ => b'*',
+        })
+        .collect::<Vec<_>>();
+
+    // a single character left over cannot encode a byte
+    if standard.len() % 4 == 1 {
+        standard.push(b'*');
+    }
+    while standard.len() % 4 != 0 {
+        standard.push(b'=');
+    }
+
+    // the translated input is ASCII
+    decode_block(&String::from_utf8(standard).unwrap())
+}
+
+fn is_alphanumeric(b: u8) -> bool {
+    (b >= b'A' && b <= b'Z') || (b >= b'a' && b <= b'z') || (b >= b'0' && b <= b'9')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 4648 test vectors
+    const VECTORS: &'static [(&'static [u8], &'static str)] = &[
+        (b"", ""),
+        (b"f", "Zg=="),
+        (b"fo", "Zm8="),
+        (b"foo", "Zm9v"),
+        (b"foob", "Zm9vYg=="),
+        (b"fooba", "Zm9vYmE="),
+        (b"foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn standard() {
+        for &(raw, encoded) in VECTORS {
+            assert_eq!(encode_block(raw), encoded);
+            assert_eq!(decode_block(encoded).unwrap(), raw);
+        }
+        assert_eq!(decode_block(" Zm9v\n").unwrap(), b"foo");
+        assert!(decode_block("Zm9").is_err());
+        assert!(decode_block("Zm9*").is_err());
+    }
+
+    #[test]
+    fn url() {
+        for &(raw, encoded) in VECTORS {
+            let encoded = encoded.trim_right_matches('=');
+            assert_eq!(encode_block_url(raw), encoded);
+            assert_eq!(decode_block_url(encoded).unwrap(), raw);
+        }
+
+        assert_eq!(encode_block_url(b"\xfb\xff\xbf"), "-_-_");
+        assert_eq!(decode_block_url("-_-_").unwrap(), b"\xfb\xff\xbf");
+        assert!(decode_block_url("+/+/").is_err());
+        assert!(decode_block_url("Zg==").is_err());
+        assert!(decode_block_url("Zm9vY").is_err());
+        assert!(decode_block_url(" Zm9v").is_err());
+    }
+}
@@ -28,6 +28,7 @@
 use ffi;
 use foreign_types::{ForeignType, ForeignTypeRef};
 use libc::c_int;
+use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::ffi::CString;
 use std::{fmt, ptr};
@@ -37,6 +38,8 @@ use {cvt, cvt_n, cvt_p};
 use asn1::Asn1Integer;
 use error::ErrorStack;
 use string::OpensslString;
+#[cfg(ossl110)]
+use util::{invoke_bn_gencb, CallbackState};
 
 #[cfg(ossl10x)]
 use ffi::{get_rfc2409_prime_1024 as BN_get_rfc2409_prime_1024,
@@ -339,6 +342,45 @@ impl BigNumRef {
         unsafe { ffi::BN_set_negative(self.as_ptr(), negative as c_int) }
     }
 
+    /// Marks `self` as secret, so that operations on it use constant time algorithms where
+    /// OpenSSL provides them.
+    ///
+    /// This should be set on private exponents and other values whose timing must not leak.
+    ///
+    /// OpenSSL documentation at [`BN_set_flags`]
+    ///
+    /// [`BN_set_flags`]: https://www.openssl.org/docs/man1.1.0/crypto/BN_set_flags.html
+    pub fn set_const_time(&mut self) {
+        self._set_flags(ffi::BN_FLG_CONSTTIME)
+    }
+
+    /// Returns `true` if `self` has been marked as secret with [`set_const_time`].
+    ///
+    /// [`set_const_time`]: #method.set_const_time
+    pub fn is_const_time(&self) -> bool {
+        self._get_flags(ffi::BN_FLG_CONSTTIME) == ffi::BN_FLG_CONSTTIME
+    }
+
+    #[cfg(ossl10x)]
+    fn _set_flags(&mut self, flags: c_int) {
+        unsafe { (*self.as_ptr()).flags |= flags }
+    }
+
+    #[cfg(ossl110)]
+    fn _set_flags(&mut self, flags: c_int) {
+        unsafe { ffi::BN_set_flags(self.as_ptr(), flags) }
+    }
+
+    #[cfg(ossl10x)]
+    fn _get_flags(&self, flags: c_int) -> c_int {
+        unsafe { (*self.as_ptr()).flags & flags }
+    }
+
+    #[cfg(ossl110)]
+    fn _get_flags(&self, flags: c_int) -> c_int {
+        unsafe { ffi::BN_get_flags(self.as_ptr(), flags) }
+    }
+
     /// Compare the absolute values of `self` and `oth`.
     ///
     /// OpenSSL documentation at [`BN_ucmp`]
@@ -488,6 +530,54 @@ impl BigNumRef {
         }
     }
 
+    /// Like [`generate_prime`], but reports progress to `callback`.
+    ///
+    /// The callback is invoked with the `p` and `n` arguments of OpenSSL's [`BN_GENCB`] callback,
+    /// describing the stage of the search. Returning `false` from it aborts the generation, in
+    /// which case an error is returned.
+    ///
+    /// Requires OpenSSL 1.1.0 or newer.
+    ///
+    /// OpenSSL documentation at [`BN_generate_prime_ex`]
+    ///
+    /// [`generate_prime`]: #method.generate_prime
+    /// [`BN_GENCB`]: https://www.openssl.org/docs/man1.1.0/crypto/BN_GENCB_new.html
+    /// [`BN_generate_prime_ex`]: https://www.openssl.org/docs/man1.1.0/crypto/BN_generate_prime_ex.html
+    #[cfg(ossl110)]
+    pub fn generate_prime_with_callback<F>(
+        &mut self,
+        bits: i32,
+        safe: bool,
+        add: Option<&BigNumRef>,
+        rem: Option<&BigNumRef>,
+        callback: F,
+    ) -> Result<(), ErrorStack>
+    where
+        F: FnMut(i32, i32) -> bool,
+    {
+        unsafe {
+            let mut cb = CallbackState::new(callback);
+            let gencb = cvt_p(ffi::BN_GENCB_new())?;
+            ffi::BN_GENCB_set(
+                gencb,
+                Some(invoke_bn_gencb::<F>),
+                &mut cb as *mut _ as *mut _,
+            );
+
+            let r = cvt(ffi::BN_generate_prime_ex(
+                self.as_ptr(),
+                bits as c_int,
+                safe as c_int,
+                add.map(|n| n.as_ptr()).unwrap_or(ptr::null_mut()),
+                rem.map(|n| n.as_ptr()).unwrap_or(ptr::null_mut()),
+                gencb,
+            )).map(|_| ());
+
+            ffi::BN_GENCB_free(gencb);
+            r
+        }
+    }
+
     /// Places the result of `a * b` in `self`.
     /// [`core::ops::Mul`] is also implemented for `BigNumRef`.
     ///
@@ -745,6 +835,55 @@ impl BigNumRef {
         }
     }
 
+    /// Places the result of `a^p mod m` in `self`, using a constant time algorithm.
+    ///
+    /// This should be used when `p` is secret. `m` must be odd.
+    ///
+    /// OpenSSL documentation at [`BN_mod_exp_mont_consttime`]
+    ///
+    /// [`BN_mod_exp_mont_consttime`]: https://www.openssl.org/docs/man1.1.0/crypto/BN_mod_exp.html
+    pub fn mod_exp_consttime(
+        &mut self,
+        a: &BigNumRef,
+        p: &BigNumRef,
+        m: &BigNumRef,
+        ctx: &mut BigNumContextRef,
+    ) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::BN_mod_exp_mont_consttime(
+                self.as_ptr(),
+                a.as_ptr(),
+                p.as_ptr(),
+                m.as_ptr(),
+                ctx.as_ptr(),
+                ptr::null_mut(),
+            )).map(|_| ())
+        }
+    }
+
+    /// Places a square root of `a` modulo the prime `p` in `self`.
+    ///
+    /// An error is returned if `a` is not a quadratic residue modulo `p`.
+    ///
+    /// OpenSSL documentation at [`BN_mod_sqrt`]
+    ///
+    /// [`BN_mod_sqrt`]: https://www.openssl.org/docs/man1.1.0/crypto/BN_mod_sqrt.html
+    pub fn mod_sqrt(
+        &mut self,
+        a: &BigNumRef,
+        p: &BigNumRef,
+        ctx: &mut BigNumContextRef,
+    ) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt_p(ffi::BN_mod_sqrt(
+                self.as_ptr(),
+                a.as_ptr(),
+                p.as_ptr(),
+                ctx.as_ptr(),
+            )).map(|_| ())
+        }
+    }
+
     /// Places the inverse of `a` modulo `n` in `self`.
     pub fn mod_inverse(
         &mut self,
@@ -785,7 +924,15 @@ impl BigNumRef {
 
     /// Checks whether `self` is prime.
     ///
-    /// Performs a Miller-Rabin probabilistic primality test with `checks` iterations.
+    /// Performs a Miller-Rabin probabilistic primality test with `checks` iterations, using
+    /// randomly chosen bases. OpenSSL does not support a fixed, deterministic set of bases.
+    ///
+    /// If `checks` is 0, the number of iterations is chosen based on the size of `self` such that
+    /// the probability of a randomly generated composite number being reported as prime is less
+    /// than `2 ^ -80`. This is appropriate for numbers generated locally, but not for values
+    /// received from an untrusted peer, such as DH parameters or RSA key factors, which may have
+    /// been constructed to pass a small number of iterations. Those should be tested with an
+    /// explicit, larger number of checks, such as 64.
     ///
     /// OpenSSL documentation at [`BN_is_prime_ex`]
     ///
@@ -793,8 +940,9 @@ impl BigNumRef {
     ///
     /// # Return Value
     ///
-    /// Returns `true` if `self` is prime with an error probability of less than `0.25 ^ checks`.
-
+    /// Returns `false` if `self` is definitely composite. Returns `true` if `self` is probably
+    /// prime. For any composite input, including adversarially chosen ones, the probability of
+    /// this is less than `0.25 ^ checks`.
     pub fn is_prime(&self, checks: i32, ctx: &mut BigNumContextRef) -> Result<bool, ErrorStack> {
         unsafe {
             cvt_n(ffi::BN_is_prime_ex(
@@ -810,15 +958,18 @@ impl BigNumRef {
     ///
     /// If `do_trial_division` is `true`, first performs trial division by a number of small primes.
     /// Then, like `is_prime`, performs a Miller-Rabin probabilistic primality test with `checks`
-    /// iterations.
+    /// iterations. Trial division quickly rejects most composite numbers, but does not change the
+    /// error bounds. See [`is_prime`] for the meaning of `checks`, including a value of 0.
     ///
     /// OpenSSL documentation at [`BN_is_prime_fasttest_ex`]
     ///
+    /// [`is_prime`]: #method.is_prime
     /// [`BN_is_prime_fasttest_ex`]: https://www.openssl.org/docs/man1.1.0/crypto/BN_is_prime_fasttest_ex.html
     ///
     /// # Return Value
     ///
-    /// Returns `true` if `self` is prime with an error probability of less than `0.25 ^ checks`.
+    /// Returns `false` if `self` is definitely composite. Returns `true` if `self` is probably
+    /// prime, with an error probability of less than `0.25 ^ checks`.
     pub fn is_prime_fasttest(
         &self,
         checks: i32,
@@ -907,6 +1058,25 @@ impl BigNum {
         }
     }
 
+    /// Creates a new `BigNum` with the value 0, allocated on OpenSSL's secure heap.
+    ///
+    /// If the secure heap has not been initialized, this behaves like [`new`].
+    ///
+    /// Requires OpenSSL 1.1.0 or newer.
+    ///
+    /// OpenSSL documentation at [`BN_secure_new`]
+    ///
+    /// [`new`]: #method.new
+    /// [`BN_secure_new`]: https://www.openssl.org/docs/man1.1.0/crypto/BN_secure_new.html
+    #[cfg(ossl110)]
+    pub fn new_secure() -> Result<BigNum, ErrorStack> {
+        unsafe {
+            ffi::init();
+            let v = cvt_p(ffi::BN_secure_new())?;
+            Ok(BigNum::from_ptr(v))
+        }
+    }
+
     /// Creates a new `BigNum` with the given value.
     ///
     /// OpenSSL documentation at [`BN_set_word`]
@@ -1221,6 +1391,28 @@ macro_rules! delegate {
     }
 }
 
+thread_local! {
+    static OP_CTX: RefCell<Option<BigNumContext>> = RefCell::new(None);
+}
+
+/// Runs `f` with a `BigNumContext` cached for the current thread.
+///
+/// The context is taken out of the cache for the duration of the call so that nested uses simply
+/// allocate a fresh one rather than aliasing it.
+fn with_op_ctx<F, T>(f: F) -> T
+where
+    F: FnOnce(&mut BigNumContextRef) -> T,
+{
+    let ctx = OP_CTX.with(|c| c.borrow_mut().take());
+    let mut ctx = match ctx {
+        Some(ctx) => ctx,
+        None => BigNumContext::new().unwrap(),
+    };
+    let r = f(&mut *ctx);
+    OP_CTX.with(|c| *c.borrow_mut() = Some(ctx));
+    r
+}
+
 impl<'a, 'b> Add<&'b BigNumRef> for &'a BigNumRef {
     type Output = BigNum;
 
@@ -1249,9 +1441,8 @@ impl<'a, 'b> Mul<&'b BigNumRef> for &'a BigNumRef {
     type Output = BigNum;
 
     fn mul(self, oth: &BigNumRef) -> BigNum {
-        let mut ctx = BigNumContext::new().unwrap();
         let mut r = BigNum::new().unwrap();
-        r.checked_mul(self, oth, &mut ctx).unwrap();
+        with_op_ctx(|ctx| r.checked_mul(self, oth, ctx)).unwrap();
         r
     }
 }
@@ -1262,9 +1453,8 @@ impl<'a, 'b> Div<&'b BigNumRef> for &'a BigNumRef {
     type Output = BigNum;
 
     fn div(self, oth: &'b BigNumRef) -> BigNum {
-        let mut ctx = BigNumContext::new().unwrap();
         let mut r = BigNum::new().unwrap();
-        r.checked_div(self, oth, &mut ctx).unwrap();
+        with_op_ctx(|ctx| r.checked_div(self, oth, ctx)).unwrap();
         r
     }
 }
@@ -1275,9 +1465,8 @@ impl<'a, 'b> Rem<&'b BigNumRef> for &'a BigNumRef {
     type Output = BigNum;
 
     fn rem(self, oth: &'b BigNumRef) -> BigNum {
-        let mut ctx = BigNumContext::new().unwrap();
         let mut r = BigNum::new().unwrap();
-        r.checked_rem(self, oth, &mut ctx).unwrap();
+        with_op_ctx(|ctx| r.checked_rem(self, oth, ctx)).unwrap();
         r
     }
 }
@@ -1367,6 +1556,24 @@ mod tests {
         assert!((-a).is_negative());
     }
 
+    #[test]
+    fn test_operators() {
+        let a = BigNum::from_u32(1000).unwrap();
+        let b = BigNum::from_u32(7).unwrap();
+
+        assert_eq!(&a + &b, BigNum::from_u32(1007).unwrap());
+        assert_eq!(&a - &b, BigNum::from_u32(993).unwrap());
+        assert_eq!(&a / &b, BigNum::from_u32(142).unwrap());
+        assert_eq!(&a % &b, BigNum::from_u32(6).unwrap());
+
+        // Repeated operations reuse the cached context.
+        let mut acc = BigNum::from_u32(1).unwrap();
+        for _ in 0..10 {
+            acc = &acc * &b;
+        }
+        assert_eq!(acc, BigNum::from_u32(282475249).unwrap());
+    }
+
     #[test]
     fn test_shift() {
         let a = BigNum::from_u32(909829283).unwrap();
@@ -1401,4 +1608,94 @@ mod tests {
         assert!(p.is_prime(100, &mut ctx).unwrap());
         assert!(p.is_prime_fasttest(100, &mut ctx, true).unwrap());
     }
+
+    #[test]
+    fn test_is_prime() {
+        let mut ctx = BigNumContext::new().unwrap();
+
+        // 2^127 - 1
+        let mersenne = BigNum::from_hex_str("7fffffffffffffffffffffffffffffff").unwrap();
+        assert!(mersenne.is_prime(0, &mut ctx).unwrap());
+        assert!(mersenne.is_prime_fasttest(64, &mut ctx, false).unwrap());
+
+        // Carmichael numbers pass the Fermat test for all coprime bases
+        for &n in &[561, 41041, 825265] {
+            let n = BigNum::from_u32(n).unwrap();
+            assert!(!n.is_prime(64, &mut ctx).unwrap());
+            assert!(!n.is_prime_fasttest(0, &mut ctx, true).unwrap());
+        }
+
+        for &n in &[0, 1, 4] {
+            assert!(!BigNum::from_u32(n).unwrap().is_prime(0, &mut ctx).unwrap());
+        }
+        assert!(BigNum::from_u32(2).unwrap().is_prime(0, &mut ctx).unwrap());
+    }
+
+    #[test]
+    fn test_const_time() {
+        let mut a = BigNum::from_u32(1000).unwrap();
+        assert!(!a.is_const_time());
+        a.set_const_time();
+        assert!(a.is_const_time());
+    }
+
+    #[test]
+    fn test_mod_exp_consttime() {
+        let a = BigNum::from_u32(4).unwrap();
+        let mut p = BigNum::from_u32(13).unwrap();
+        p.set_const_time();
+        let m = BigNum::from_u32(497).unwrap();
+        let mut ctx = BigNumContext::new().unwrap();
+
+        let mut r = BigNum::new().unwrap();
+        r.mod_exp_consttime(&a, &p, &m, &mut ctx).unwrap();
+        assert_eq!(r, BigNum::from_u32(445).unwrap());
+
+        let mut expected = BigNum::new().unwrap();
+        expected.mod_exp(&a, &p, &m, &mut ctx).unwrap();
+        assert_eq!(r, expected);
+    }
+
+    #[test]
+    fn test_mod_sqrt() {
+        let a = BigNum::from_u32(10).unwrap();
+        let p = BigNum::from_u32(13).unwrap();
+        let mut ctx = BigNumContext::new().unwrap();
+
+        let mut r = BigNum::new().unwrap();
+        r.mod_sqrt(&a, &p, &mut ctx).unwrap();
+        assert!(r == BigNum::from_u32(6).unwrap() || r == BigNum::from_u32(7).unwrap());
+
+        // 5 is not a quadratic residue modulo 13
+        let a = BigNum::from_u32(5).unwrap();
+        assert!(r.mod_sqrt(&a, &p, &mut ctx).is_err());
+    }
+
+    #[test]
+    #[cfg(ossl110)]
+    fn test_generate_prime_with_callback() {
+        let mut calls = 0;
+        let mut p = BigNum::new().unwrap();
+        p.generate_prime_with_callback(128, false, None, None, |_, _| {
+            calls += 1;
+            true
+        }).unwrap();
+        assert!(calls > 0);
+
+        let mut ctx = BigNumContext::new().unwrap();
+        assert!(p.is_prime(100, &mut ctx).unwrap());
+
+        assert!(
+            p.generate_prime_with_callback(128, false, None, None, |_, _| false)
+                .is_err()
+        );
+    }
+
+    #[test]
+    #[cfg(ossl110)]
+    fn test_new_secure() {
+        let mut a = BigNum::new_secure().unwrap();
+        a.add_word(7).unwrap();
+        assert_eq!(a, BigNum::from_u32(7).unwrap());
+    }
 }
@@ -0,0 +1,204 @@
+//! Cipher-based message authentication codes.
+//!
+//! CMAC computes a MAC with a block cipher, as described in [RFC 4493] for AES. On OpenSSL 1.1.0
+//! and newer, CMAC keys can also be created with [`PKey::cmac`] and used with the `sign` module.
+//! The `Cmac` type provided here works on all supported OpenSSL versions.
+//!
+//! # Example
+//!
+//! ```rust
+//! extern crate hex;
+//! extern crate openssl;
+//!
+//! use hex::FromHex;
+//! use openssl::cmac::cmac;
+//! use openssl::symm::Cipher;
+//!
+//! # fn main() {
+//! let key = Vec::from_hex("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+//! let data = Vec::from_hex("6bc1bee22e409f96e93d7e117393172a").unwrap();
+//!
+//! let tag = cmac(Cipher::aes_128_cbc(), &key, &data).unwrap();
+//! assert_eq!(tag, Vec::from_hex("070a16b46b4d4144f79bdd9dd04a287c").unwrap());
+//! # }
+//! ```
+//!
+//! [RFC 4493]: https://tools.ietf.org/html/rfc4493
+//! [`PKey::cmac`]: ../pkey/struct.PKey.html#method.cmac
+use ffi;
+use std::io::prelude::*;
+use std::io;
+use std::ptr;
+
+use {cvt, cvt_p};
+use error::ErrorStack;
+use symm::Cipher;
+
+/// A CMAC context.
+///
+/// The cipher should be a CBC mode cipher, such as `Cipher::aes_128_cbc`.
+pub struct Cmac {
+    ctx: *mut ffi::CMAC_CTX,
+    block_size: usize,
+}
+
+unsafe impl Sync for Cmac {}
+unsafe impl Send for Cmac {}
+
+impl Cmac {
+    /// Creates a new `Cmac` keyed with `key`.
+    ///
+    /// This corresponds to [`CMAC_Init`].
+    ///
+    /// [`CMAC_Init`]: https://www.openssl.org/docs/man1.1.0/crypto/CMAC_Init.html
+    pub fn new(cipher: Cipher, key: &[u8]) -> Result<Cmac, ErrorStack> {
+        ffi::init();
+
+        unsafe {
+            let ctx = cvt_p(ffi::CMAC_CTX_new())?;
+            let cmac = Cmac {
+                ctx,
+                block_size: cipher.block_size(),
+            };
+            cvt(ffi::CMAC_Init(
+                cmac.ctx,
+                key.as_ptr() as *const _,
+                key.len(),
+                cipher.as_ptr(),
+                ptr::null_mut(),
+            ))?;
+            Ok(cmac)
+        }
+    }
+
+    /// Feeds data into the MAC.
+    ///
+    /// This corresponds to [`CMAC_Update`].
+    ///
+    /// [`CMAC_Update`]: https://www.openssl.org/docs/man1.1.0/crypto/CMAC_Update.html
+    pub fn update(&mut self, data: &[u8]) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::CMAC_Update(
+                self.ctx,
+                data.as_ptr() as *const _,
+                data.len(),
+            )).map(|_| ())
+        }
+    }
+
+    /// Returns the MAC of the data fed in so far.
+    ///
+    /// The context is reset afterwards, and can be reused to compute the MAC of another message
+    /// with the same key.
+    ///
+    /// This corresponds to [`CMAC_Final`].
+    ///
+    /// [`CMAC_Final`]: https://www.openssl.org/docs/man1.1.0/crypto/CMAC_Final.html
+    pub fn finish(&mut self) -> Result<Vec<u8>, ErrorStack> {
+        unsafe {
+            let mut buf = vec![0; self.block_size];
+            let mut len = buf.len();
+            cvt(ffi::CMAC_Final(self.ctx, buf.as_mut_ptr(), &mut len))?;
+            buf.truncate(len);
+
+            cvt(ffi::CMAC_Init(
+                self.ctx,
+                ptr::null(),
+                0,
+                ptr::null(),
+                ptr::null_mut(),
+            ))?;
+            Ok(buf)
+        }
+    }
+}
+
+impl Write for Cmac {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Cmac {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::CMAC_CTX_free(self.ctx);
+        }
+    }
+}
+
+/// Computes the CMAC of `data` with `key`.
+pub fn cmac(cipher: Cipher, key: &[u8], data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let mut c = Cmac::new(cipher, key)?;
+    c.update(data)?;
+    c.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use hex::FromHex;
+
+    use super::*;
+
+    const KEY: &'static str = "2b7e151628aed2a6abf7158809cf4f3c";
+    const DATA: &'static str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+                                30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    // RFC 4493 section 4
+    #[test]
+    fn rfc4493() {
+        let key = Vec::from_hex(KEY).unwrap();
+        let data = Vec::from_hex(DATA).unwrap();
+        let tests = [
+            (0, "bb1d6929e95937287fa37d129b756746"),
+            (16, "070a16b46b4d4144f79bdd9dd04a287c"),
+            (40, "dfa66747de9ae63030ca32611497c827"),
+            (64, "51f0bebf7e3b9d92fc49741779363cfe"),
+        ];
+
+        for &(len, tag) in &tests {
+            let out = cmac(Cipher::aes_128_cbc(), &key, &data[..len]).unwrap();
+            assert_eq!(out, Vec::from_hex(tag).unwrap());
+        }
+    }
+
+    #[test]
+    fn reuse() {
+        let key = Vec::from_hex(KEY).unwrap();
+        let data = Vec::from_hex(DATA).unwrap();
+
+        let mut c = Cmac::new(Cipher::aes_128_cbc(), &key).unwrap();
+        for chunk in data[..40].chunks(7) {
+            c.write_all(chunk).unwrap();
+        }
+        let tag = Vec::from_hex("dfa66747de9ae63030ca32611497c827").unwrap();
+        assert_eq!(c.finish().unwrap(), tag);
+
+        c.update(&data[..16]).unwrap();
+        let tag = Vec::from_hex("070a16b46b4d4144f79bdd9dd04a287c").unwrap();
+        assert_eq!(c.finish().unwrap(), tag);
+    }
+
+    #[test]
+    #[cfg(ossl110)]
+    fn matches_pkey() {
+        use pkey::PKey;
+        use sign::Signer;
+
+        let key = Vec::from_hex(KEY).unwrap();
+        let data = Vec::from_hex(DATA).unwrap();
+
+        let pkey = PKey::cmac(&Cipher::aes_128_cbc(), &key).unwrap();
+        let mut signer = Signer::new_without_digest(&pkey).unwrap();
+        signer.update(&data).unwrap();
+
+        let out = cmac(Cipher::aes_128_cbc(), &key, &data).unwrap();
+        assert_eq!(signer.sign_to_vec().unwrap(), out);
+    }
+}
@@ -6,18 +6,43 @@
 //! Data accepted by this module will be smime type `enveloped-data`.
 
 use ffi;
-use foreign_types::{ForeignType, ForeignTypeRef};
+use foreign_types::{ForeignType, ForeignTypeRef, Opaque};
+#[cfg(any(ossl102, ossl110))]
+use std::cmp::Ordering;
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::panic;
 use std::ptr;
 
+#[cfg(any(ossl102, ossl110))]
+use asn1::Asn1Time;
+use asn1::{Asn1IntegerRef, Asn1StringRef, Asn1TimeRef};
 use bio::{MemBio, MemBioSlice};
 use error::ErrorStack;
-use libc::c_uint;
+use libc::{c_int, c_uint};
+use nid::Nid;
 use pkey::{HasPrivate, PKeyRef};
-use stack::Stack;
-use x509::X509;
-use {cvt, cvt_p};
+use pkcs7::{Pkcs7, Pkcs7Ref};
+use ssl::bio;
+use stack::{Stack, StackRef, Stackable};
+use symm::Cipher;
+use x509::{X509, X509NameRef, X509Ref};
+use x509::store::X509StoreRef;
+use {cvt, cvt_n, cvt_p};
 
 bitflags! {
+    /// Options controlling the creation and processing of CMS structures.
+    ///
+    /// Verification performs every check required by RFC 5652 by default: the signer
+    /// certificates are validated against the trust store, the signed attributes are checked, and
+    /// the content digest is compared. Flags such as `NO_SIGNER_CERT_VERIFY`, `NO_ATTR_VERIFY`,
+    /// `NO_CONTENT_VERIFY` and `NOSIGS` relax individual checks, and should only be used when
+    /// interoperating with an implementation known to produce nonconforming structures. They are
+    /// rejected by [`CmsContentInfoRef::verify_strict`].
+    ///
+    /// [`CmsContentInfoRef::verify_strict`]: struct.CmsContentInfoRef.html#method.verify_strict
     pub struct CMSOptions : c_uint {
         const TEXT = ffi::CMS_TEXT;
         const CMS_NOCERTS = ffi::CMS_NOCERTS;
@@ -46,6 +71,24 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Additional requirements enforced by [`CmsContentInfoRef::verify_strict`].
+    ///
+    /// RFC 5652 makes these attributes optional, but some profiles, such as S/MIME, require them.
+    ///
+    /// [`CmsContentInfoRef::verify_strict`]: struct.CmsContentInfoRef.html#method.verify_strict
+    pub struct ConformanceOptions: u32 {
+        /// Requires every signer to include signed attributes.
+        const REQUIRE_SIGNED_ATTRIBUTES = 0x1;
+        /// Requires every signer to include a signing-time attribute which was checked
+        /// successfully.
+        ///
+        /// The check requires OpenSSL 1.0.2 or newer, so verification always fails on older
+        /// versions if this is set.
+        const REQUIRE_SIGNING_TIME = 0x2;
+    }
+}
+
 foreign_type_and_impl_send_sync! {
     type CType = ffi::CMS_ContentInfo;
     fn drop = ffi::CMS_ContentInfo_free;
@@ -65,7 +108,355 @@ foreign_type_and_impl_send_sync! {
     pub struct CmsContentInfoRef;
 }
 
+/// The outcome of checking a signed attribute of a `SignerInfo`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AttributeStatus {
+    /// The attribute is not present.
+    Absent,
+    /// The attribute is present and was checked successfully.
+    Valid,
+    /// The attribute is present but failed its check.
+    Invalid,
+    /// The attribute is present but was not checked, because the options disabled the check.
+    NotChecked,
+}
+
+/// The signed attributes of a single signer, and the checks performed on them.
+#[derive(Debug, Clone)]
+pub struct SignerReport {
+    signed_attributes: bool,
+    content_type: AttributeStatus,
+    message_digest: AttributeStatus,
+    signing_time: AttributeStatus,
+}
+
+impl SignerReport {
+    /// Determines if the signer included any signed attributes.
+    ///
+    /// Without signed attributes, the signature covers the content directly and none of the
+    /// attribute checks apply.
+    pub fn has_signed_attributes(&self) -> bool {
+        self.signed_attributes
+    }
+
+    /// Returns the status of the content-type attribute.
+    ///
+    /// It is valid if it matches the type of the encapsulated content.
+    pub fn content_type(&self) -> AttributeStatus {
+        self.content_type
+    }
+
+    /// Returns the status of the message-digest attribute.
+    ///
+    /// It is valid if it matches the digest of the content. This check is disabled by
+    /// `CMSOptions::NO_CONTENT_VERIFY`.
+    pub fn message_digest(&self) -> AttributeStatus {
+        self.message_digest
+    }
+
+    /// Returns the status of the signing-time attribute.
+    ///
+    /// It is valid if the time is not in the future and falls within the validity period of the
+    /// signer's certificate. This check requires OpenSSL 1.0.2 or newer, and is reported as not
+    /// checked otherwise.
+    pub fn signing_time(&self) -> AttributeStatus {
+        self.signing_time
+    }
+
+    fn is_valid(&self) -> bool {
+        if !self.signed_attributes {
+            return true;
+        }
+
+        // RFC 5652 requires the content-type and message-digest attributes if any are present
+        self.content_type != AttributeStatus::Absent
+            && self.message_digest != AttributeStatus::Absent
+            && self.content_type != AttributeStatus::Invalid
+            && self.message_digest != AttributeStatus::Invalid
+            && self.signing_time != AttributeStatus::Invalid
+    }
+}
+
+/// The signed attribute checks performed while verifying a signedData structure.
+///
+/// This is returned by [`CmsContentInfoRef::verify_with_report`].
+///
+/// [`CmsContentInfoRef::verify_with_report`]: struct.CmsContentInfoRef.html#method.verify_with_report
+#[derive(Debug, Clone)]
+pub struct VerifyReport {
+    signers: Vec<SignerReport>,
+}
+
+impl VerifyReport {
+    /// Returns a report for each signer, in the order they appear in the structure.
+    pub fn signers(&self) -> &[SignerReport] {
+        &self.signers
+    }
+
+    /// Determines if no signed attribute is invalid or missing when required.
+    pub fn is_valid(&self) -> bool {
+        self.signers.iter().all(SignerReport::is_valid)
+    }
+}
+
+/// The type of a `CmsRecipientInfo`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RecipientInfoType(c_int);
+
+impl RecipientInfoType {
+    /// The content encryption key is encrypted with the recipient's public key.
+    pub const KEY_TRANSPORT: RecipientInfoType = RecipientInfoType(ffi::CMS_RECIPINFO_TRANS);
+
+    /// The content encryption key is encrypted with a key agreed with the recipient.
+    pub const KEY_AGREEMENT: RecipientInfoType = RecipientInfoType(ffi::CMS_RECIPINFO_AGREE);
+
+    /// The content encryption key is encrypted with a previously distributed symmetric key.
+    pub const KEK: RecipientInfoType = RecipientInfoType(ffi::CMS_RECIPINFO_KEK);
+
+    /// The content encryption key is encrypted with a key derived from a password.
+    pub const PASSWORD: RecipientInfoType = RecipientInfoType(ffi::CMS_RECIPINFO_PASS);
+
+    /// The content encryption key is encrypted by some other method.
+    pub const OTHER: RecipientInfoType = RecipientInfoType(ffi::CMS_RECIPINFO_OTHER);
+
+    /// Constructs a `RecipientInfoType` from a raw OpenSSL value.
+    pub fn from_raw(raw: c_int) -> RecipientInfoType {
+        RecipientInfoType(raw)
+    }
+
+    /// Returns the raw OpenSSL value represented by this type.
+    pub fn as_raw(&self) -> c_int {
+        self.0
+    }
+}
+
+/// A recipient of an envelopedData structure.
+///
+/// Recipient infos are owned by their content info, and are obtained with
+/// [`CmsContentInfoRef::recipient_infos`].
+///
+/// [`CmsContentInfoRef::recipient_infos`]: struct.CmsContentInfoRef.html#method.recipient_infos
+pub struct CmsRecipientInfo(*mut ffi::CMS_RecipientInfo);
+
+impl ForeignType for CmsRecipientInfo {
+    type CType = ffi::CMS_RecipientInfo;
+    type Ref = CmsRecipientInfoRef;
+
+    #[inline]
+    unsafe fn from_ptr(ptr: *mut ffi::CMS_RecipientInfo) -> CmsRecipientInfo {
+        CmsRecipientInfo(ptr)
+    }
+
+    #[inline]
+    fn as_ptr(&self) -> *mut ffi::CMS_RecipientInfo {
+        self.0
+    }
+}
+
+impl Deref for CmsRecipientInfo {
+    type Target = CmsRecipientInfoRef;
+
+    fn deref(&self) -> &CmsRecipientInfoRef {
+        unsafe { CmsRecipientInfoRef::from_ptr(self.0) }
+    }
+}
+
+impl DerefMut for CmsRecipientInfo {
+    fn deref_mut(&mut self) -> &mut CmsRecipientInfoRef {
+        unsafe { CmsRecipientInfoRef::from_ptr_mut(self.0) }
+    }
+}
+
+impl Stackable for CmsRecipientInfo {
+    type StackType = ffi::stack_st_CMS_RecipientInfo;
+}
+
+/// Reference to a [`CmsRecipientInfo`].
+///
+/// [`CmsRecipientInfo`]: struct.CmsRecipientInfo.html
+pub struct CmsRecipientInfoRef(Opaque);
+
+impl ForeignTypeRef for CmsRecipientInfoRef {
+    type CType = ffi::CMS_RecipientInfo;
+}
+
+impl CmsRecipientInfoRef {
+    /// Returns the type of the recipient info.
+    ///
+    /// This corresponds to [`CMS_RecipientInfo_type`].
+    ///
+    /// [`CMS_RecipientInfo_type`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_RecipientInfo_type.html
+    pub fn recipient_type(&self) -> RecipientInfoType {
+        unsafe { RecipientInfoType(ffi::CMS_RecipientInfo_type(self.as_ptr())) }
+    }
+
+    /// Returns the subject key identifier of the recipient's certificate, if the recipient is
+    /// identified by one.
+    ///
+    /// This is only present for key transport recipients encrypted with
+    /// `CMSOptions::USE_KEYID`.
+    ///
+    /// This corresponds to [`CMS_RecipientInfo_ktri_get0_signer_id`].
+    ///
+    /// [`CMS_RecipientInfo_ktri_get0_signer_id`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_RecipientInfo_ktri_get0_signer_id.html
+    pub fn key_id(&self) -> Option<&[u8]> {
+        unsafe {
+            match self.signer_id() {
+                Some((key_id, _, _)) if !key_id.is_null() => {
+                    Some(Asn1StringRef::from_ptr(key_id).as_slice())
+                }
+                _ => None,
+            }
+        }
+    }
+
+    /// Returns the issuer name of the recipient's certificate, if the recipient is identified by
+    /// its issuer and serial number.
+    ///
+    /// This is only present for key transport recipients.
+    ///
+    /// This corresponds to [`CMS_RecipientInfo_ktri_get0_signer_id`].
+    ///
+    /// [`CMS_RecipientInfo_ktri_get0_signer_id`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_RecipientInfo_ktri_get0_signer_id.html
+    pub fn issuer_name(&self) -> Option<&X509NameRef> {
+        unsafe {
+            match self.signer_id() {
+                Some((_, issuer, _)) if !issuer.is_null() => Some(X509NameRef::from_ptr(issuer)),
+                _ => None,
+            }
+        }
+    }
+
+    /// Returns the serial number of the recipient's certificate, if the recipient is identified
+    /// by its issuer and serial number.
+    ///
+    /// This is only present for key transport recipients.
+    ///
+    /// This corresponds to [`CMS_RecipientInfo_ktri_get0_signer_id`].
+    ///
+    /// [`CMS_RecipientInfo_ktri_get0_signer_id`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_RecipientInfo_ktri_get0_signer_id.html
+    pub fn serial_number(&self) -> Option<&Asn1IntegerRef> {
+        unsafe {
+            match self.signer_id() {
+                Some((_, _, serial)) if !serial.is_null() => Some(Asn1IntegerRef::from_ptr(serial)),
+                _ => None,
+            }
+        }
+    }
+
+    /// Determines if `cert` is the certificate of a key transport recipient.
+    ///
+    /// This corresponds to [`CMS_RecipientInfo_ktri_cert_cmp`].
+    ///
+    /// [`CMS_RecipientInfo_ktri_cert_cmp`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_RecipientInfo_ktri_cert_cmp.html
+    pub fn matches(&self, cert: &X509Ref) -> bool {
+        if self.recipient_type() != RecipientInfoType::KEY_TRANSPORT {
+            return false;
+        }
+        unsafe { ffi::CMS_RecipientInfo_ktri_cert_cmp(self.as_ptr(), cert.as_ptr()) == 0 }
+    }
+
+    // Returns the key identifier, issuer, and serial number of a key transport recipient, of
+    // which either the key identifier or the other two are set.
+    unsafe fn signer_id(
+        &self,
+    ) -> Option<(
+        *mut ffi::ASN1_STRING,
+        *mut ffi::X509_NAME,
+        *mut ffi::ASN1_INTEGER,
+    )> {
+        if self.recipient_type() != RecipientInfoType::KEY_TRANSPORT {
+            return None;
+        }
+
+        let mut key_id = ptr::null_mut();
+        let mut issuer = ptr::null_mut();
+        let mut serial = ptr::null_mut();
+        let r = ffi::CMS_RecipientInfo_ktri_get0_signer_id(
+            self.as_ptr(),
+            &mut key_id,
+            &mut issuer,
+            &mut serial,
+        );
+        match cvt(r) {
+            Ok(_) => Some((key_id, issuer, serial)),
+            Err(_) => None,
+        }
+    }
+}
+
 impl CmsContentInfoRef {
+    /// Returns the certificates of the signers of a signedData structure.
+    ///
+    /// Signer certificates are looked up in the structure itself, and additionally in `certs`
+    /// if provided. Signers whose certificate cannot be found are omitted. The certificates are
+    /// not verified, so this can be used to inspect who signed a message before, or instead of,
+    /// calling `verify`.
+    ///
+    /// This takes `self` mutably because the certificates found are cached in the structure.
+    ///
+    /// This corresponds to [`CMS_get0_signers`].
+    ///
+    /// [`CMS_get0_signers`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_get0_signers.html
+    pub fn signers(&mut self, certs: Option<&StackRef<X509>>) -> Result<Stack<X509>, ErrorStack> {
+        unsafe {
+            let certs = certs.map_or(ptr::null_mut(), |c| c.as_ptr());
+            cvt_n(ffi::CMS_set1_signer_certs(self.as_ptr(), certs, 0))?;
+
+            // no stack is allocated if no signer certificate was found
+            let signers = ffi::CMS_get0_signers(self.as_ptr());
+            if signers.is_null() {
+                return Stack::new();
+            }
+
+            // the stack does not own references to its certificates, so take one for each
+            let signers = Stack::<X509>::from_ptr(signers);
+            for cert in signers.iter() {
+                mem::forget(cert.to_owned());
+            }
+            Ok(signers)
+        }
+    }
+
+    /// Returns the certificates embedded in a signedData or envelopedData structure.
+    ///
+    /// This typically contains the signers' certificates along with their chains.
+    ///
+    /// This corresponds to [`CMS_get1_certs`].
+    ///
+    /// [`CMS_get1_certs`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_get1_certs.html
+    pub fn certificates(&self) -> Result<Stack<X509>, ErrorStack> {
+        unsafe {
+            let certs = ffi::CMS_get1_certs(self.as_ptr());
+            if certs.is_null() {
+                // a missing certificate set is not an error, but other content types are
+                let ret = ErrorStack::get();
+                if ret.errors().is_empty() {
+                    Stack::new()
+                } else {
+                    Err(ret)
+                }
+            } else {
+                Ok(Stack::from_ptr(certs))
+            }
+        }
+    }
+
+    /// Returns the recipients of an envelopedData structure.
+    ///
+    /// A holder of several keys can use this to select the key to decrypt with, rather than
+    /// attempting decryption with each of them.
+    ///
+    /// This corresponds to [`CMS_get0_RecipientInfos`].
+    ///
+    /// [`CMS_get0_RecipientInfos`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_get0_RecipientInfos.html
+    pub fn recipient_infos(&self) -> Result<&StackRef<CmsRecipientInfo>, ErrorStack> {
+        unsafe {
+            let infos = cvt_p(ffi::CMS_get0_RecipientInfos(self.as_ptr()))?;
+            Ok(StackRef::from_ptr(infos))
+        }
+    }
+
     /// Given the sender's private key, `pkey` and the recipient's certificiate, `cert`,
     /// decrypt the data in `self`.
     ///
@@ -76,22 +467,336 @@ impl CmsContentInfoRef {
     where
         T: HasPrivate,
     {
-        unsafe {
-            let pkey = pkey.as_ptr();
-            let cert = cert.as_ptr();
-            let out = MemBio::new()?;
-            let flags: u32 = 0;
+        let mut out = vec![];
+        self.decrypt_to(pkey, cert, &mut out)?;
+        Ok(out)
+    }
 
+    /// Like `decrypt`, but appends the decrypted data to `out` rather than allocating a new `Vec`.
+    ///
+    /// This allows a single buffer to be reused across many decryptions. The data is written
+    /// directly into `out`, which is grown by `decrypted_len_hint` up front. Returns the number
+    /// of bytes appended. `out` is left unmodified if decryption fails.
+    ///
+    /// OpenSSL documentation at [`CMS_decrypt`]
+    ///
+    /// [`CMS_decrypt`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_decrypt.html
+    pub fn decrypt_to<T>(
+        &self,
+        pkey: &PKeyRef<T>,
+        cert: &X509,
+        out: &mut Vec<u8>,
+    ) -> Result<usize, ErrorStack>
+    where
+        T: HasPrivate,
+    {
+        self.decrypt_inner(pkey, Some(&**cert), CMSOptions::empty(), out)
+    }
+
+    /// Decrypts the data in `self` with the recipient's private key, `pkey`, without requiring
+    /// the recipient's certificate.
+    ///
+    /// If `cert` is `None`, the key is tried against every recipient in turn. To avoid revealing
+    /// which recipient matched, OpenSSL then decrypts the content with a random key if none did,
+    /// so a wrong key usually fails while decrypting the content rather than the key. Setting
+    /// `CMSOptions::DEBUG_DECRYPT` in `flags` disables this, reporting a missing recipient
+    /// directly, but should only be used where such padding oracle attacks are not a concern.
+    ///
+    /// OpenSSL documentation at [`CMS_decrypt`]
+    ///
+    /// [`CMS_decrypt`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_decrypt.html
+    pub fn decrypt_without_cert_check<T>(
+        &self,
+        pkey: &PKeyRef<T>,
+        cert: Option<&X509Ref>,
+        flags: CMSOptions,
+    ) -> Result<Vec<u8>, ErrorStack>
+    where
+        T: HasPrivate,
+    {
+        let mut out = vec![];
+        self.decrypt_inner(pkey, cert, flags, &mut out)?;
+        Ok(out)
+    }
+
+    fn decrypt_inner<T>(
+        &self,
+        pkey: &PKeyRef<T>,
+        cert: Option<&X509Ref>,
+        flags: CMSOptions,
+        out: &mut Vec<u8>,
+    ) -> Result<usize, ErrorStack>
+    where
+        T: HasPrivate,
+    {
+        let len = out.len();
+        if let Some(hint) = self.decrypted_len_hint() {
+            out.reserve(hint);
+        }
+
+        let r = unsafe {
+            let (bio, _method) = bio::new(WriteOnly(&mut *out))?;
+            let bio = StreamBio::<WriteOnly<&mut Vec<u8>>>::new(bio);
+
+            // writes to a `Vec` cannot fail, so there is no stream error to check
             cvt(ffi::CMS_decrypt(
                 self.as_ptr(),
-                pkey,
-                cert,
+                pkey.as_ptr(),
+                cert.map_or(ptr::null_mut(), |c| c.as_ptr()),
                 ptr::null_mut(),
-                out.as_ptr(),
-                flags.into(),
+                bio.as_ptr(),
+                flags.bits(),
+            ))
+        };
+
+        match r {
+            Ok(_) => Ok(out.len() - len),
+            Err(e) => {
+                out.truncate(len);
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns an upper bound on the length of the data `decrypt` returns.
+    ///
+    /// This is the length of the encrypted content of an envelopedData structure, and can be used
+    /// to size the buffer passed to `decrypt_to`. `None` is returned for other content types, or
+    /// if the content is detached.
+    pub fn decrypted_len_hint(&self) -> Option<usize> {
+        unsafe {
+            let nid = Nid::from_raw(ffi::OBJ_obj2nid(ffi::CMS_get0_type(self.as_ptr())));
+            if nid != Nid::PKCS7_ENVELOPED {
+                return None;
+            }
+
+            let content = ffi::CMS_get0_content(self.as_ptr());
+            if content.is_null() || (*content).is_null() {
+                return None;
+            }
+            Some(ffi::ASN1_STRING_length(*content) as usize)
+        }
+    }
+
+    /// Verifies a signedData structure.
+    ///
+    /// Signer certificates are looked up in the structure itself and in `certs`, and are validated
+    /// against `store`. For a detached signature, the signed content must be provided in
+    /// `detached_data`. If `output_data` is provided, the verified content is written to it.
+    ///
+    /// This takes `self` mutably because `CMS_verify` caches the signer certificates it finds in
+    /// the structure.
+    ///
+    /// OpenSSL documentation at [`CMS_verify`]
+    ///
+    /// [`CMS_verify`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_verify.html
+    pub fn verify(
+        &mut self,
+        certs: Option<&StackRef<X509>>,
+        store: &X509StoreRef,
+        detached_data: Option<&[u8]>,
+        output_data: Option<&mut Vec<u8>>,
+        flags: CMSOptions,
+    ) -> Result<(), ErrorStack> {
+        let out_bio = MemBio::new()?;
+
+        let detached_bio = match detached_data {
+            Some(data) => Some(MemBioSlice::new(data)?),
+            None => None,
+        };
+        let detached_bio_ptr = detached_bio
+            .as_ref()
+            .map_or(ptr::null_mut(), |p| p.as_ptr());
+
+        unsafe {
+            cvt(ffi::CMS_verify(
+                self.as_ptr(),
+                certs.map_or(ptr::null_mut(), |p| p.as_ptr()),
+                store.as_ptr(),
+                detached_bio_ptr,
+                out_bio.as_ptr(),
+                flags.bits(),
             ))?;
+        }
+
+        if let Some(data) = output_data {
+            data.clear();
+            data.extend_from_slice(out_bio.get_buf());
+        }
+
+        Ok(())
+    }
+
+    /// Like `verify`, but also reports on the signed attributes of each signer.
+    ///
+    /// The signature, signer certificate, and message-digest checks performed by `verify` still
+    /// cause an error to be returned when they fail. In addition, the content-type attribute is
+    /// compared to the type of the encapsulated content and the signing-time attribute is checked
+    /// for plausibility. The outcome of these checks is only reported, so the returned report must
+    /// be inspected with [`VerifyReport::is_valid`].
+    ///
+    /// If `CMSOptions::NO_ATTR_VERIFY` is set, the signed attributes are not authenticated, and
+    /// all present attributes are reported as not checked.
+    ///
+    /// This takes `self` mutably because `CMS_verify` caches the signer certificates it finds in
+    /// the structure.
+    ///
+    /// [`VerifyReport::is_valid`]: struct.VerifyReport.html#method.is_valid
+    pub fn verify_with_report(
+        &mut self,
+        certs: Option<&StackRef<X509>>,
+        store: &X509StoreRef,
+        detached_data: Option<&[u8]>,
+        output_data: Option<&mut Vec<u8>>,
+        flags: CMSOptions,
+    ) -> Result<VerifyReport, ErrorStack> {
+        self.verify(certs, store, detached_data, output_data, flags)?;
+
+        unsafe {
+            let content_type = ffi::CMS_get0_eContentType(self.as_ptr());
+            let infos = ffi::CMS_get0_SignerInfos(self.as_ptr());
+            let mut signers = vec![];
+            if !infos.is_null() {
+                for info in StackRef::<SignerInfo>::from_ptr(infos) {
+                    signers.push(signer_report(info, content_type, flags));
+                }
+            }
+
+            Ok(VerifyReport { signers })
+        }
+    }
+
+    /// Like `verify`, but also enforces the RFC 5652 requirements on signed attributes.
+    ///
+    /// An error is returned if `flags` disables any verification check, if a signed attribute
+    /// fails one of the checks described in `verify_with_report`, or if a requirement in
+    /// `conformance` is not met. `output_data` is only written to if verification succeeds.
+    pub fn verify_strict(
+        &mut self,
+        certs: Option<&StackRef<X509>>,
+        store: &X509StoreRef,
+        detached_data: Option<&[u8]>,
+        output_data: Option<&mut Vec<u8>>,
+        flags: CMSOptions,
+        conformance: ConformanceOptions,
+    ) -> Result<(), ErrorStack> {
+        let relaxed = CMSOptions::NO_SIGNER_CERT_VERIFY | CMSOptions::NO_ATTR_VERIFY
+            | CMSOptions::NO_CONTENT_VERIFY | CMSOptions::NOSIGS
+            | CMSOptions::NOVERIFY;
+        if flags.intersects(relaxed) {
+            return Err(ErrorStack::from_data(
+                "the options disable a verification check",
+            ));
+        }
+
+        let mut out = vec![];
+        let report = {
+            let out = if output_data.is_some() {
+                Some(&mut out)
+            } else {
+                None
+            };
+            self.verify_with_report(certs, store, detached_data, out, flags)?
+        };
+
+        for signer in report.signers() {
+            if !signer.is_valid() {
+                return Err(ErrorStack::from_data(
+                    "a signed attribute is invalid or missing",
+                ));
+            }
+            if conformance.contains(ConformanceOptions::REQUIRE_SIGNED_ATTRIBUTES)
+                && !signer.has_signed_attributes()
+            {
+                return Err(ErrorStack::from_data("a signer has no signed attributes"));
+            }
+            if conformance.contains(ConformanceOptions::REQUIRE_SIGNING_TIME)
+                && signer.signing_time() != AttributeStatus::Valid
+            {
+                return Err(ErrorStack::from_data(
+                    "a signer has no verified signing time",
+                ));
+            }
+        }
+
+        if let Some(data) = output_data {
+            *data = out;
+        }
+
+        Ok(())
+    }
+
+    /// Verifies a detached signedData structure, streaming the signed content from `data`.
+    ///
+    /// This behaves like `verify` with the content passed as `detached_data`, but the content is
+    /// only digested as it is read, so arbitrarily large content can be verified without
+    /// buffering it in memory. `flags` should match those the content was signed with, in
+    /// particular `CMSOptions::BINARY`.
+    ///
+    /// This takes `self` mutably because `CMS_verify` caches the signer certificates it finds in
+    /// the structure.
+    ///
+    /// OpenSSL documentation at [`CMS_verify`]
+    ///
+    /// [`CMS_verify`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_verify.html
+    pub fn verify_detached<R>(
+        &mut self,
+        certs: Option<&StackRef<X509>>,
+        store: &X509StoreRef,
+        data: R,
+        flags: CMSOptions,
+    ) -> io::Result<()>
+    where
+        R: Read,
+    {
+        unsafe {
+            let (in_bio, _in_method) = bio::new(ReadOnly(data))?;
+            let in_bio = StreamBio::<ReadOnly<R>>::new(in_bio);
 
-            Ok(out.get_buf().to_owned())
+            let r = cvt(ffi::CMS_verify(
+                self.as_ptr(),
+                certs.map_or(ptr::null_mut(), |p| p.as_ptr()),
+                store.as_ptr(),
+                in_bio.as_ptr(),
+                ptr::null_mut(),
+                flags.bits(),
+            ));
+
+            // a read error truncates the content, so takes precedence over the verification error
+            in_bio.check()?;
+            r?;
+            Ok(())
+        }
+    }
+
+    /// Completes a structure created with `CMSOptions::PARTIAL`, streaming its content from
+    /// `data`.
+    ///
+    /// For a signedData structure this computes the signatures over the content, which is only
+    /// embedded in the structure if it is not detached.
+    ///
+    /// OpenSSL documentation at [`CMS_final`]
+    ///
+    /// [`CMS_final`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_final.html
+    pub fn finalize<R>(&mut self, data: R, flags: CMSOptions) -> io::Result<()>
+    where
+        R: Read,
+    {
+        unsafe {
+            let (in_bio, _in_method) = bio::new(ReadOnly(data))?;
+            let in_bio = StreamBio::<ReadOnly<R>>::new(in_bio);
+
+            let r = cvt(ffi::CMS_final(
+                self.as_ptr(),
+                in_bio.as_ptr(),
+                ptr::null_mut(),
+                flags.bits(),
+            ));
+
+            // read errors look like the end of the input to OpenSSL
+            in_bio.check()?;
+            r?;
+            Ok(())
         }
     }
 
@@ -104,6 +809,27 @@ impl CmsContentInfoRef {
     to_der,
     ffi::i2d_CMS_ContentInfo
     }
+
+    /// Converts a signedData or envelopedData structure to PKCS #7.
+    ///
+    /// The two formats share an encoding for these content types, so the structure is re-encoded
+    /// and parsed as PKCS #7. An error is returned for other content types, and for structures
+    /// using features PKCS #7 lacks, such as signers identified by subject key identifier.
+    pub fn to_pkcs7(&self) -> Result<Pkcs7, ErrorStack> {
+        self.check_pkcs7_content_type()?;
+        Pkcs7::from_der(&self.to_der()?)
+    }
+
+    fn check_pkcs7_content_type(&self) -> Result<(), ErrorStack> {
+        let nid = unsafe { Nid::from_raw(ffi::OBJ_obj2nid(ffi::CMS_get0_type(self.as_ptr()))) };
+        if nid == Nid::PKCS7_SIGNED || nid == Nid::PKCS7_ENVELOPED {
+            Ok(())
+        } else {
+            Err(ErrorStack::from_data(
+                "only signedData and envelopedData can be converted",
+            ))
+        }
+    }
 }
 
 impl CmsContentInfo {
@@ -122,6 +848,41 @@ impl CmsContentInfo {
         }
     }
 
+    from_der! {
+        /// Deserializes a DER-encoded ContentInfo structure.
+        ///
+        /// The CMS `ContentInfo` encoding is a superset of the PKCS#7 one, so DER-encoded PKCS#7
+        /// `signedData` and `envelopedData` structures can be loaded directly with this method.
+        ///
+        /// This corresponds to [`d2i_CMS_ContentInfo`].
+        ///
+        /// [`d2i_CMS_ContentInfo`]: https://www.openssl.org/docs/manmaster/man3/d2i_X509.html
+        from_der,
+        CmsContentInfo,
+        ffi::d2i_CMS_ContentInfo
+    }
+
+    from_der_strict! {
+        /// Like `from_der`, but rejects input larger than `max_len` bytes or with trailing data.
+        ///
+        /// See the [`parse`] module for details.
+        ///
+        /// [`parse`]: ../parse/index.html
+        from_der_strict,
+        CmsContentInfo,
+        ffi::d2i_CMS_ContentInfo
+    }
+
+    /// Converts a PKCS #7 signedData or envelopedData structure to CMS.
+    ///
+    /// The two formats share an encoding for these content types, so the structure is re-encoded
+    /// and parsed as CMS. An error is returned for other content types.
+    pub fn from_pkcs7(pkcs7: &Pkcs7Ref) -> Result<CmsContentInfo, ErrorStack> {
+        let cms = CmsContentInfo::from_der(&pkcs7.to_der()?)?;
+        cms.check_pkcs7_content_type()?;
+        Ok(cms)
+    }
+
     /// Given a signing cert `signcert`, private key `pkey`, a certificate stack `certs`,
     /// data `data` and flags `flags`, create a CmsContentInfo struct.
     ///
@@ -146,10 +907,11 @@ impl CmsContentInfo {
                 Some(pkey) => pkey.as_ptr(),
                 None => ptr::null_mut(),
             };
-            let data_bio_ptr = match data {
-                Some(data) => MemBioSlice::new(data)?.as_ptr(),
-                None => ptr::null_mut(),
+            let data_bio = match data {
+                Some(data) => Some(MemBioSlice::new(data)?),
+                None => None,
             };
+            let data_bio_ptr = data_bio.as_ref().map_or(ptr::null_mut(), |p| p.as_ptr());
             let certs = match certs {
                 Some(certs) => certs.as_ptr(),
                 None => ptr::null_mut(),
@@ -166,4 +928,915 @@ impl CmsContentInfo {
             Ok(CmsContentInfo::from_ptr(cms))
         }
     }
+
+    /// Creates a detached signedData structure over the content read from `data`.
+    ///
+    /// The content is streamed through the digest rather than buffered, so arbitrarily large
+    /// inputs can be signed, and is not included in the returned structure. The signature can be
+    /// verified with [`verify_detached`].
+    ///
+    /// OpenSSL documentation at [`CMS_sign`]
+    ///
+    /// [`verify_detached`]: struct.CmsContentInfoRef.html#method.verify_detached
+    /// [`CMS_sign`]: https://www.openssl.org/docs/manmaster/man3/CMS_sign.html
+    pub fn sign_detached<T, R>(
+        signcert: &X509,
+        pkey: &PKeyRef<T>,
+        certs: Option<&Stack<X509>>,
+        data: R,
+        flags: CMSOptions,
+    ) -> io::Result<CmsContentInfo>
+    where
+        T: HasPrivate,
+        R: Read,
+    {
+        let flags = (flags | CMSOptions::DETACHED) - CMSOptions::STREAM;
+        let mut cms = CmsContentInfo::sign(
+            Some(signcert),
+            Some(pkey),
+            certs,
+            None,
+            flags | CMSOptions::PARTIAL,
+        )?;
+        cms.finalize(data, flags)?;
+        Ok(cms)
+    }
+
+    /// Creates an envelopedData structure containing `data`, encrypted with `cipher` to each of
+    /// the recipient certificates in `certs`.
+    ///
+    /// OpenSSL documentation at [`CMS_encrypt`]
+    ///
+    /// [`CMS_encrypt`]: https://www.openssl.org/docs/man1.1.0/crypto/CMS_encrypt.html
+    pub fn encrypt(
+        certs: &StackRef<X509>,
+        data: &[u8],
+        cipher: Cipher,
+        flags: CMSOptions,
+    ) -> Result<CmsContentInfo, ErrorStack> {
+        unsafe {
+            let data_bio = MemBioSlice::new(data)?;
+
+            let cms = cvt_p(ffi::CMS_encrypt(
+                certs.as_ptr(),
+                data_bio.as_ptr(),
+                cipher.as_ptr(),
+                flags.bits(),
+            ))?;
+
+            Ok(CmsContentInfo::from_ptr(cms))
+        }
+    }
+
+    /// Like `encrypt`, but streams the data from `data` and writes the BER-encoded envelopedData
+    /// structure to `out` as it is produced.
+    ///
+    /// Neither the plaintext nor the ciphertext is buffered in memory, allowing arbitrarily large
+    /// inputs to be encrypted. The output uses indefinite length encoding, and can be read back
+    /// with `from_der`.
+    ///
+    /// OpenSSL documentation at [`i2d_CMS_bio_stream`]
+    ///
+    /// [`i2d_CMS_bio_stream`]: https://www.openssl.org/docs/man1.1.0/crypto/i2d_CMS_bio_stream.html
+    pub fn encrypt_stream<R, W>(
+        certs: &StackRef<X509>,
+        data: R,
+        cipher: Cipher,
+        flags: CMSOptions,
+        out: W,
+    ) -> io::Result<()>
+    where
+        R: Read,
+        W: Write,
+    {
+        let flags = flags | CMSOptions::STREAM;
+
+        unsafe {
+            let (in_bio, _in_method) = bio::new(ReadOnly(data))?;
+            let in_bio = StreamBio::<ReadOnly<R>>::new(in_bio);
+            let (out_bio, _out_method) = bio::new(WriteOnly(out))?;
+            let out_bio = StreamBio::<WriteOnly<W>>::new(out_bio);
+
+            let cms = CmsContentInfo::from_ptr(cvt_p(ffi::CMS_encrypt(
+                certs.as_ptr(),
+                in_bio.as_ptr(),
+                cipher.as_ptr(),
+                flags.bits(),
+            ))?);
+            let r = cvt(ffi::i2d_CMS_bio_stream(
+                out_bio.as_ptr(),
+                cms.as_ptr(),
+                in_bio.as_ptr(),
+                flags.bits() as c_int,
+            ));
+
+            // read errors look like the end of the input to OpenSSL, so must be checked even if
+            // encoding succeeded
+            in_bio.check()?;
+            out_bio.check()?;
+            r?;
+            Ok(())
+        }
+    }
+}
+
+// Adapters for the stream BIO, which requires both `Read` and `Write`.
+struct ReadOnly<R>(R);
+
+impl<R: Read> Read for ReadOnly<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<R> Write for ReadOnly<R> {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::Other, "write to CMS input"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct WriteOnly<W>(W);
+
+impl<W> Read for WriteOnly<W> {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::Other, "read from CMS output"))
+    }
+}
+
+impl<W: Write> Write for WriteOnly<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // OpenSSL does not reliably retry short writes
+        self.0.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+struct StreamBio<S>(*mut ffi::BIO, PhantomData<S>);
+
+impl<S> StreamBio<S> {
+    fn new(bio: *mut ffi::BIO) -> StreamBio<S> {
+        StreamBio(bio, PhantomData)
+    }
+
+    fn as_ptr(&self) -> *mut ffi::BIO {
+        self.0
+    }
+
+    unsafe fn check(&self) -> io::Result<()> {
+        if let Some(err) = bio::take_panic::<S>(self.0) {
+            panic::resume_unwind(err);
+        }
+        match bio::take_error::<S>(self.0) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<S> Drop for StreamBio<S> {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::BIO_free_all(self.0);
+        }
+    }
+}
+
+// Signer infos are owned by their content info, so this type is only used to iterate over them.
+struct SignerInfo(*mut ffi::CMS_SignerInfo);
+
+impl ForeignType for SignerInfo {
+    type CType = ffi::CMS_SignerInfo;
+    type Ref = SignerInfoRef;
+
+    unsafe fn from_ptr(ptr: *mut ffi::CMS_SignerInfo) -> SignerInfo {
+        SignerInfo(ptr)
+    }
+
+    fn as_ptr(&self) -> *mut ffi::CMS_SignerInfo {
+        self.0
+    }
+}
+
+impl Stackable for SignerInfo {
+    type StackType = ffi::stack_st_CMS_SignerInfo;
+}
+
+struct SignerInfoRef(Opaque);
+
+impl ForeignTypeRef for SignerInfoRef {
+    type CType = ffi::CMS_SignerInfo;
+}
+
+unsafe fn signer_report(
+    info: &SignerInfoRef,
+    content_type: *const ffi::ASN1_OBJECT,
+    flags: CMSOptions,
+) -> SignerReport {
+    let signed_attributes = ffi::CMS_signed_get_attr_count(info.as_ptr()) > 0;
+    let unchecked = flags.contains(CMSOptions::NO_ATTR_VERIFY);
+
+    let status = |nid: Nid, check: &Fn(*mut ffi::X509_ATTRIBUTE) -> bool| {
+        let loc = ffi::CMS_signed_get_attr_by_NID(info.as_ptr(), nid.as_raw(), -1);
+        if loc < 0 {
+            AttributeStatus::Absent
+        } else if unchecked {
+            AttributeStatus::NotChecked
+        } else if check(ffi::CMS_signed_get_attr(info.as_ptr(), loc)) {
+            AttributeStatus::Valid
+        } else {
+            AttributeStatus::Invalid
+        }
+    };
+
+    let content_type = status(Nid::PKCS9_CONTENTTYPE, &|attr| {
+        let object = ffi::X509_ATTRIBUTE_get0_data(attr, 0, ffi::V_ASN1_OBJECT, ptr::null_mut());
+        !object.is_null() && !content_type.is_null()
+            && ffi::OBJ_cmp(object as *const _, content_type) == 0
+    });
+
+    // CMS_verify has already compared the digest, and failed if it did not match
+    let content_verified = !flags.contains(CMSOptions::NO_CONTENT_VERIFY);
+    let message_digest = match status(Nid::PKCS9_MESSAGEDIGEST, &|_| content_verified) {
+        AttributeStatus::Invalid => AttributeStatus::NotChecked,
+        status => status,
+    };
+
+    let mut signer = ptr::null_mut();
+    ffi::CMS_SignerInfo_get0_algs(
+        info.as_ptr(),
+        ptr::null_mut(),
+        &mut signer,
+        ptr::null_mut(),
+        ptr::null_mut(),
+    );
+    let signing_time = status(Nid::PKCS9_SIGNINGTIME, &|attr| {
+        let mut time =
+            ffi::X509_ATTRIBUTE_get0_data(attr, 0, ffi::V_ASN1_UTCTIME, ptr::null_mut());
+        if time.is_null() {
+            time = ffi::X509_ATTRIBUTE_get0_data(
+                attr,
+                0,
+                ffi::V_ASN1_GENERALIZEDTIME,
+                ptr::null_mut(),
+            );
+        }
+        if time.is_null() {
+            return false;
+        }
+
+        let signer = if signer.is_null() {
+            None
+        } else {
+            Some(X509Ref::from_ptr(signer))
+        };
+        signing_time_plausible(Asn1TimeRef::from_ptr(time as *mut _), signer).unwrap_or(false)
+    });
+    // times cannot be compared before OpenSSL 1.0.2
+    #[cfg(not(any(ossl102, ossl110)))]
+    let signing_time = match signing_time {
+        AttributeStatus::Valid => AttributeStatus::NotChecked,
+        status => status,
+    };
+
+    SignerReport {
+        signed_attributes,
+        content_type,
+        message_digest,
+        signing_time,
+    }
+}
+
+#[cfg(any(ossl102, ossl110))]
+fn signing_time_plausible(
+    time: &Asn1TimeRef,
+    signer: Option<&X509Ref>,
+) -> Result<bool, ErrorStack> {
+    if time.compare(&*Asn1Time::now()?)? == Ordering::Greater {
+        return Ok(false);
+    }
+
+    if let Some(signer) = signer {
+        if signer.not_before().compare(time)? == Ordering::Greater
+            || signer.not_after().compare(time)? == Ordering::Less
+        {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(not(any(ossl102, ossl110)))]
+fn signing_time_plausible(_: &Asn1TimeRef, _: Option<&X509Ref>) -> Result<bool, ErrorStack> {
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pkey::{PKey, Private};
+    use pkcs7::Pkcs7Flags;
+    use x509::store::{X509Store, X509StoreBuilder};
+
+    fn signer() -> (X509, PKey<Private>) {
+        let cert = X509::from_pem(include_bytes!("../test/cert.pem")).unwrap();
+        let key = PKey::private_key_from_pem(include_bytes!("../test/key.pem")).unwrap();
+        (cert, key)
+    }
+
+    fn trust_store() -> X509Store {
+        let root_ca = X509::from_pem(include_bytes!("../test/root-ca.pem")).unwrap();
+        let mut builder = X509StoreBuilder::new().unwrap();
+        builder.add_cert(root_ca).unwrap();
+        builder.build()
+    }
+
+    #[test]
+    fn sign_verify_attached() {
+        let (cert, key) = signer();
+        let cms = CmsContentInfo::sign(
+            Some(&cert),
+            Some(&key),
+            None,
+            Some(b"hello world"),
+            CMSOptions::BINARY,
+        ).unwrap();
+        let mut cms = CmsContentInfo::from_der(&cms.to_der().unwrap()).unwrap();
+
+        let mut out = vec![];
+        cms.verify(None, &trust_store(), None, Some(&mut out), CMSOptions::BINARY)
+            .unwrap();
+        assert_eq!(out, b"hello world");
+    }
+
+    #[test]
+    fn sign_verify_detached() {
+        let (cert, key) = signer();
+        let flags = CMSOptions::BINARY | CMSOptions::DETACHED;
+        let cms = CmsContentInfo::sign(
+            Some(&cert),
+            Some(&key),
+            None,
+            Some(b"hello world"),
+            flags,
+        ).unwrap();
+        let mut cms = CmsContentInfo::from_der(&cms.to_der().unwrap()).unwrap();
+        let store = trust_store();
+
+        cms.verify(None, &store, Some(b"hello world"), None, flags)
+            .unwrap();
+        assert!(
+            cms.verify(None, &store, Some(b"hello there"), None, flags)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn sign_verify_detached_stream() {
+        let (cert, key) = signer();
+        let flags = CMSOptions::BINARY;
+        let data = (0..1024 * 1024).map(|i| i as u8).collect::<Vec<_>>();
+
+        let cms = CmsContentInfo::sign_detached(&cert, &key, None, &data[..], flags).unwrap();
+        let mut cms = CmsContentInfo::from_der(&cms.to_der().unwrap()).unwrap();
+        let store = trust_store();
+
+        cms.verify_detached(None, &store, &data[..], flags).unwrap();
+        cms.verify(None, &store, Some(&data[..]), None, flags).unwrap();
+        assert!(cms.verify_detached(None, &store, &data[1..], flags).is_err());
+    }
+
+    #[test]
+    fn verify_untrusted_signer() {
+        let (cert, key) = signer();
+        let mut cms = CmsContentInfo::sign(
+            Some(&cert),
+            Some(&key),
+            None,
+            Some(b"hello world"),
+            CMSOptions::BINARY,
+        ).unwrap();
+        let empty = X509StoreBuilder::new().unwrap().build();
+
+        assert!(
+            cms.verify(None, &empty, None, None, CMSOptions::BINARY)
+                .is_err()
+        );
+        cms.verify(
+            None,
+            &empty,
+            None,
+            None,
+            CMSOptions::BINARY | CMSOptions::NO_SIGNER_CERT_VERIFY,
+        ).unwrap();
+    }
+
+    #[test]
+    fn verify_report() {
+        let (cert, key) = signer();
+        let cms = CmsContentInfo::sign(
+            Some(&cert),
+            Some(&key),
+            None,
+            Some(b"hello world"),
+            CMSOptions::BINARY,
+        ).unwrap();
+        let mut cms = CmsContentInfo::from_der(&cms.to_der().unwrap()).unwrap();
+        let store = trust_store();
+
+        let report = cms.verify_with_report(None, &store, None, None, CMSOptions::BINARY)
+            .unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.signers().len(), 1);
+        let signer = &report.signers()[0];
+        assert!(signer.has_signed_attributes());
+        assert_eq!(signer.content_type(), AttributeStatus::Valid);
+        assert_eq!(signer.message_digest(), AttributeStatus::Valid);
+        #[cfg(any(ossl102, ossl110))]
+        assert_eq!(signer.signing_time(), AttributeStatus::Valid);
+
+        let flags = CMSOptions::BINARY | CMSOptions::NO_ATTR_VERIFY;
+        let report = cms.verify_with_report(None, &store, None, None, flags)
+            .unwrap();
+        let signer = &report.signers()[0];
+        assert_eq!(signer.content_type(), AttributeStatus::NotChecked);
+        assert_eq!(signer.message_digest(), AttributeStatus::NotChecked);
+        assert_eq!(signer.signing_time(), AttributeStatus::NotChecked);
+    }
+
+    #[test]
+    fn verify_report_without_attributes() {
+        let (cert, key) = signer();
+        let flags = CMSOptions::BINARY | CMSOptions::NOATTR;
+        let mut cms = CmsContentInfo::sign(
+            Some(&cert),
+            Some(&key),
+            None,
+            Some(b"hello world"),
+            flags,
+        ).unwrap();
+
+        let report = cms.verify_with_report(None, &trust_store(), None, None, flags)
+            .unwrap();
+        assert!(report.is_valid());
+        let signer = &report.signers()[0];
+        assert!(!signer.has_signed_attributes());
+        assert_eq!(signer.content_type(), AttributeStatus::Absent);
+        assert_eq!(signer.message_digest(), AttributeStatus::Absent);
+        assert_eq!(signer.signing_time(), AttributeStatus::Absent);
+    }
+
+    #[test]
+    fn verify_strict() {
+        let (cert, key) = signer();
+        let mut cms = CmsContentInfo::sign(
+            Some(&cert),
+            Some(&key),
+            None,
+            Some(b"hello world"),
+            CMSOptions::BINARY,
+        ).unwrap();
+        let store = trust_store();
+
+        let mut out = vec![];
+        cms.verify_strict(
+            None,
+            &store,
+            None,
+            Some(&mut out),
+            CMSOptions::BINARY,
+            ConformanceOptions::REQUIRE_SIGNED_ATTRIBUTES,
+        ).unwrap();
+        assert_eq!(out, b"hello world");
+
+        let relaxed = CMSOptions::BINARY | CMSOptions::NO_ATTR_VERIFY;
+        assert!(
+            cms.verify_strict(None, &store, None, None, relaxed, ConformanceOptions::empty())
+                .is_err()
+        );
+
+        let flags = CMSOptions::BINARY | CMSOptions::NOATTR;
+        let mut cms = CmsContentInfo::sign(
+            Some(&cert),
+            Some(&key),
+            None,
+            Some(b"hello world"),
+            flags,
+        ).unwrap();
+        cms.verify_strict(None, &store, None, None, flags, ConformanceOptions::empty())
+            .unwrap();
+        let mut out = vec![];
+        assert!(
+            cms.verify_strict(
+                None,
+                &store,
+                None,
+                Some(&mut out),
+                flags,
+                ConformanceOptions::REQUIRE_SIGNED_ATTRIBUTES,
+            ).is_err()
+        );
+        assert!(out.is_empty());
+    }
+
+    // The vectors are encoded the way other implementations commonly encode them, rather than
+    // the way OpenSSL does, and are signed by a certificate valid until 2118.
+    fn interop_store() -> X509Store {
+        let ca = X509::from_pem(include_bytes!("../test/cms-interop-ca.pem")).unwrap();
+        let mut builder = X509StoreBuilder::new().unwrap();
+        builder.add_cert(ca).unwrap();
+        builder.build()
+    }
+
+    // Attached content in indefinite-length BER with a chunked octet string, and a signer
+    // identified by subject key identifier, as produced by BouncyCastle's streaming generator.
+    #[test]
+    fn interop_signed_ber() {
+        let content = include_bytes!("../test/cms-content.txt");
+        let der = include_bytes!("../test/cms-signed-ber.der");
+        let mut cms = CmsContentInfo::from_der(der).unwrap();
+
+        let mut out = vec![];
+        cms.verify_strict(
+            None,
+            &interop_store(),
+            None,
+            Some(&mut out),
+            CMSOptions::BINARY,
+            ConformanceOptions::REQUIRE_SIGNED_ATTRIBUTES,
+        ).unwrap();
+        assert_eq!(&out[..], &content[..]);
+
+        let cert = X509::from_pem(include_bytes!("../test/cms-interop-cert.pem")).unwrap();
+        let signers = cms.signers(None).unwrap();
+        assert_eq!(signers.len(), 1);
+        assert_eq!(signers[0].to_der().unwrap(), cert.to_der().unwrap());
+    }
+
+    // Detached DER with issuer and serial number signer identification, NULL algorithm
+    // parameters, and an S/MIME capabilities attribute, as produced by Windows' SignedCms.
+    #[test]
+    fn interop_signed_detached() {
+        let content = include_bytes!("../test/cms-content.txt");
+        let der = include_bytes!("../test/cms-signed-detached.der");
+        let mut cms = CmsContentInfo::from_der(der).unwrap();
+        let store = interop_store();
+
+        let report = cms.verify_with_report(
+            None,
+            &store,
+            Some(content),
+            None,
+            CMSOptions::BINARY,
+        ).unwrap();
+        assert!(report.is_valid());
+        #[cfg(any(ossl102, ossl110))]
+        cms.verify_strict(
+            None,
+            &store,
+            Some(content),
+            None,
+            CMSOptions::BINARY,
+            ConformanceOptions::REQUIRE_SIGNING_TIME,
+        ).unwrap();
+        assert!(
+            cms.verify(None, &store, Some(&content[1..]), None, CMSOptions::BINARY)
+                .is_err()
+        );
+    }
+
+    // Indefinite-length BER envelopedData with one recipient identified by subject key
+    // identifier and another by issuer and serial number.
+    #[test]
+    fn interop_enveloped_ber() {
+        let content = include_bytes!("../test/cms-content.txt");
+        let der = include_bytes!("../test/cms-enveloped-ber.der");
+        let cms = CmsContentInfo::from_der(der).unwrap();
+
+        let cert = X509::from_pem(include_bytes!("../test/cms-interop-cert.pem")).unwrap();
+        let key = PKey::private_key_from_pem(include_bytes!("../test/cms-interop-key.pem"))
+            .unwrap();
+        assert_eq!(&cms.decrypt(&key, &cert).unwrap()[..], &content[..]);
+
+        let (cert, key) = signer();
+        assert_eq!(&cms.decrypt(&key, &cert).unwrap()[..], &content[..]);
+
+        let infos = cms.recipient_infos().unwrap();
+        assert_eq!(infos.len(), 2);
+        assert_eq!(infos.iter().filter(|i| i.key_id().is_some()).count(), 1);
+        assert_eq!(infos.iter().filter(|i| i.matches(&cert)).count(), 1);
+    }
+
+    #[test]
+    fn encrypt_multiple_recipients() {
+        let (cert, key) = signer();
+        let ca_cert = X509::from_pem(include_bytes!("../test/root-ca.pem")).unwrap();
+        let ca_key = PKey::private_key_from_pem(include_bytes!("../test/root-ca.key")).unwrap();
+
+        let mut certs = Stack::new().unwrap();
+        certs.push(cert.clone()).unwrap();
+        certs.push(ca_cert.clone()).unwrap();
+
+        let cms = CmsContentInfo::encrypt(
+            &certs,
+            b"hello world",
+            Cipher::aes_256_cbc(),
+            CMSOptions::BINARY,
+        ).unwrap();
+        let cms = CmsContentInfo::from_der(&cms.to_der().unwrap()).unwrap();
+
+        assert_eq!(cms.decrypt(&key, &cert).unwrap(), b"hello world");
+        assert_eq!(cms.decrypt(&ca_key, &ca_cert).unwrap(), b"hello world");
+    }
+
+    #[test]
+    fn decrypt_without_cert() {
+        let (cert, key) = signer();
+        let ca_key = PKey::private_key_from_pem(include_bytes!("../test/root-ca.key")).unwrap();
+
+        let mut certs = Stack::new().unwrap();
+        certs.push(cert.clone()).unwrap();
+
+        let cms = CmsContentInfo::encrypt(
+            &certs,
+            b"hello world",
+            Cipher::aes_256_cbc(),
+            CMSOptions::BINARY,
+        ).unwrap();
+
+        let out = cms.decrypt_without_cert_check(&key, None, CMSOptions::empty())
+            .unwrap();
+        assert_eq!(out, b"hello world");
+        let out = cms.decrypt_without_cert_check(&key, Some(&*cert), CMSOptions::empty())
+            .unwrap();
+        assert_eq!(out, b"hello world");
+
+        assert!(cms.decrypt_without_cert_check(&ca_key, None, CMSOptions::DEBUG_DECRYPT)
+            .is_err());
+    }
+
+    #[test]
+    fn signers_and_certificates() {
+        let (cert, key) = signer();
+        let der = cert.to_der().unwrap();
+
+        let cms = CmsContentInfo::sign(
+            Some(&cert),
+            Some(&key),
+            None,
+            Some(b"hello world"),
+            CMSOptions::BINARY,
+        ).unwrap();
+        let mut cms = CmsContentInfo::from_der(&cms.to_der().unwrap()).unwrap();
+
+        let signers = cms.signers(None).unwrap();
+        assert_eq!(signers.len(), 1);
+        assert_eq!(signers.iter().next().unwrap().to_der().unwrap(), der);
+        let certs = cms.certificates().unwrap();
+        assert_eq!(certs.len(), 1);
+        assert_eq!(certs.iter().next().unwrap().to_der().unwrap(), der);
+
+        let cms = CmsContentInfo::sign(
+            Some(&cert),
+            Some(&key),
+            None,
+            Some(b"hello world"),
+            CMSOptions::BINARY | CMSOptions::CMS_NOCERTS,
+        ).unwrap();
+        let mut cms = CmsContentInfo::from_der(&cms.to_der().unwrap()).unwrap();
+
+        assert_eq!(cms.signers(None).unwrap().len(), 0);
+        assert_eq!(cms.certificates().unwrap().len(), 0);
+        let mut certs = Stack::new().unwrap();
+        certs.push(cert.clone()).unwrap();
+        let signers = cms.signers(Some(&certs)).unwrap();
+        assert_eq!(signers.len(), 1);
+        assert_eq!(signers.iter().next().unwrap().to_der().unwrap(), der);
+
+        let mut cms = CmsContentInfo::encrypt(
+            &certs,
+            b"hello world",
+            Cipher::aes_256_cbc(),
+            CMSOptions::BINARY,
+        ).unwrap();
+        assert!(cms.signers(None).is_err());
+    }
+
+    #[test]
+    fn recipient_infos() {
+        let (cert, _) = signer();
+        let ca_cert = X509::from_pem(include_bytes!("../test/root-ca.pem")).unwrap();
+
+        let mut certs = Stack::new().unwrap();
+        certs.push(cert.clone()).unwrap();
+
+        let cms = CmsContentInfo::encrypt(
+            &certs,
+            b"hello world",
+            Cipher::aes_256_cbc(),
+            CMSOptions::BINARY,
+        ).unwrap();
+        let cms = CmsContentInfo::from_der(&cms.to_der().unwrap()).unwrap();
+
+        let infos = cms.recipient_infos().unwrap();
+        assert_eq!(infos.len(), 1);
+        let info = infos.iter().next().unwrap();
+        assert_eq!(info.recipient_type(), RecipientInfoType::KEY_TRANSPORT);
+        assert!(info.key_id().is_none());
+        assert_eq!(
+            info.issuer_name().unwrap().to_der().unwrap(),
+            cert.issuer_name().to_der().unwrap()
+        );
+        assert_eq!(
+            info.serial_number().unwrap().to_bn().unwrap(),
+            cert.serial_number().to_bn().unwrap()
+        );
+        assert!(info.matches(&cert));
+        assert!(!info.matches(&ca_cert));
+
+        let (cert, key) = signer();
+        let signed = CmsContentInfo::sign(
+            Some(&cert),
+            Some(&key),
+            None,
+            Some(b"hello world"),
+            CMSOptions::BINARY,
+        ).unwrap();
+        assert!(signed.recipient_infos().is_err());
+    }
+
+    #[test]
+    fn encrypt_stream() {
+        let (cert, key) = signer();
+        let mut certs = Stack::new().unwrap();
+        certs.push(cert.clone()).unwrap();
+
+        let data = (0..1024 * 1024).map(|i| i as u8).collect::<Vec<_>>();
+        let mut out = vec![];
+        CmsContentInfo::encrypt_stream(
+            &certs,
+            &data[..],
+            Cipher::aes_256_cbc(),
+            CMSOptions::BINARY,
+            &mut out,
+        ).unwrap();
+
+        let cms = CmsContentInfo::from_der(&out).unwrap();
+        assert!(cms.decrypt(&key, &cert).unwrap() == data);
+    }
+
+    #[test]
+    fn encrypt_stream_read_error() {
+        struct Failing;
+
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::Other, "boom"))
+            }
+        }
+
+        let (cert, _) = signer();
+        let mut certs = Stack::new().unwrap();
+        certs.push(cert).unwrap();
+
+        let err = CmsContentInfo::encrypt_stream(
+            &certs,
+            Failing,
+            Cipher::aes_256_cbc(),
+            CMSOptions::BINARY,
+            vec![],
+        ).unwrap_err();
+        assert_eq!(err.to_string(), "boom");
+    }
+
+    #[test]
+    fn pkcs7_conversion() {
+        let (cert, key) = signer();
+        let store = trust_store();
+        let mut certs = Stack::new().unwrap();
+        certs.push(cert.clone()).unwrap();
+
+        let pkcs7 = Pkcs7::sign(&cert, &key, &certs, b"hello world", Pkcs7Flags::BINARY).unwrap();
+        let mut cms = CmsContentInfo::from_pkcs7(&pkcs7).unwrap();
+        let mut out = vec![];
+        cms.verify(None, &store, None, Some(&mut out), CMSOptions::BINARY)
+            .unwrap();
+        assert_eq!(out, b"hello world");
+
+        let cms = CmsContentInfo::sign(
+            Some(&cert),
+            Some(&key),
+            None,
+            Some(b"hello world"),
+            CMSOptions::BINARY,
+        ).unwrap();
+        let pkcs7 = cms.to_pkcs7().unwrap();
+        let mut out = vec![];
+        pkcs7
+            .verify(&certs, &store, None, Some(&mut out), Pkcs7Flags::BINARY)
+            .unwrap();
+        assert_eq!(out, b"hello world");
+
+        let cms = CmsContentInfo::encrypt(
+            &certs,
+            b"hello world",
+            Cipher::aes_256_cbc(),
+            CMSOptions::BINARY,
+        ).unwrap();
+        let pkcs7 = cms.to_pkcs7().unwrap();
+        let out = pkcs7.decrypt(&key, &cert, Pkcs7Flags::BINARY).unwrap();
+        assert_eq!(out, b"hello world");
+
+        let pkcs7 = Pkcs7::encrypt(
+            &certs,
+            b"hello world",
+            Cipher::aes_256_cbc(),
+            Pkcs7Flags::BINARY,
+        ).unwrap();
+        let cms = CmsContentInfo::from_pkcs7(&pkcs7).unwrap();
+        assert_eq!(cms.decrypt(&key, &cert).unwrap(), b"hello world");
+
+        // signers identified by subject key identifier cannot be represented in PKCS #7
+        let cms = CmsContentInfo::from_der(include_bytes!("../test/cms-signed-ber.der")).unwrap();
+        assert!(cms.to_pkcs7().is_err());
+    }
+
+    #[test]
+    fn der_round_trip() {
+        let cert = include_bytes!("../test/cert.pem");
+        let cert = X509::from_pem(cert).unwrap();
+        let key = include_bytes!("../test/key.pem");
+        let key = PKey::private_key_from_pem(key).unwrap();
+
+        let cms = CmsContentInfo::sign(
+            Some(&cert),
+            Some(&key),
+            None,
+            Some(b"hello world"),
+            CMSOptions::BINARY,
+        ).unwrap();
+        let der = cms.to_der().unwrap();
+
+        let cms = CmsContentInfo::from_der(&der).unwrap();
+        assert_eq!(cms.to_der().unwrap(), der);
+    }
+
+    #[test]
+    fn decrypt_to() {
+        let (cert, key) = signer();
+        let mut certs = Stack::new().unwrap();
+        certs.push(cert.clone()).unwrap();
+
+        let data = (0..100 * 1024).map(|i| i as u8).collect::<Vec<_>>();
+        let cms = CmsContentInfo::encrypt(
+            &certs,
+            &data,
+            Cipher::aes_256_cbc(),
+            CMSOptions::BINARY,
+        ).unwrap();
+        let cms = CmsContentInfo::from_der(&cms.to_der().unwrap()).unwrap();
+
+        let hint = cms.decrypted_len_hint().unwrap();
+        assert!(hint >= data.len());
+
+        let mut out = b"prefix".to_vec();
+        assert_eq!(cms.decrypt_to(&key, &cert, &mut out).unwrap(), data.len());
+        assert_eq!(&out[..6], b"prefix");
+        assert!(out[6..] == data[..]);
+
+        // the buffer is reused without growing
+        out.clear();
+        let capacity = out.capacity();
+        assert_eq!(cms.decrypt_to(&key, &cert, &mut out).unwrap(), data.len());
+        assert!(out == data);
+        assert_eq!(out.capacity(), capacity);
+    }
+
+    #[test]
+    fn decrypt_to_preserves_buffer_on_error() {
+        let cert = include_bytes!("../test/cert.pem");
+        let cert = X509::from_pem(cert).unwrap();
+        let key = include_bytes!("../test/key.pem");
+        let key = PKey::private_key_from_pem(key).unwrap();
+
+        let cms = CmsContentInfo::sign(
+            Some(&cert),
+            Some(&key),
+            None,
+            Some(b"hello world"),
+            CMSOptions::BINARY,
+        ).unwrap();
+
+        let mut out = b"prefix".to_vec();
+        assert!(cms.decrypt_to(&key, &cert, &mut out).is_err());
+        assert_eq!(out, b"prefix");
+    }
 }
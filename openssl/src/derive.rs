@@ -14,6 +14,14 @@ pub struct Deriver<'a>(*mut ffi::EVP_PKEY_CTX, PhantomData<&'a ()>);
 unsafe impl<'a> Sync for Deriver<'a> {}
 unsafe impl<'a> Send for Deriver<'a> {}
 
+impl<'a> Drop for Deriver<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::EVP_PKEY_CTX_free(self.0);
+        }
+    }
+}
+
 impl<'a> Deriver<'a> {
     /// Creates a new `Deriver` using the provided private key.
     ///
@@ -95,6 +103,7 @@ impl<'a> Deriver<'a> {
 mod test {
     use super::*;
 
+    use dh::Dh;
     use ec::{EcGroup, EcKey};
     use nid::Nid;
     use pkey::PKey;
@@ -119,5 +128,23 @@ mod test {
         deriver.set_peer(&pkey2).unwrap();
         let shared = deriver.derive_to_vec().unwrap();
         assert!(!shared.is_empty());
+
+        let mut deriver2 = Deriver::new(&pkey2).unwrap();
+        deriver2.set_peer(&pkey).unwrap();
+        assert_eq!(shared, deriver2.derive_to_vec().unwrap());
+    }
+
+    #[test]
+    fn test_dh_key_derive() {
+        let params = include_bytes!("../test/dhparams.pem");
+        let dh = Dh::params_from_pem(params).unwrap().generate_key().unwrap();
+        let dh2 = Dh::params_from_pem(params).unwrap().generate_key().unwrap();
+        let expected = dh.compute_key(dh2.public_key()).unwrap();
+
+        let pkey = PKey::from_dh(dh).unwrap();
+        let pkey2 = PKey::from_dh(dh2).unwrap();
+        let mut deriver = Deriver::new(&pkey).unwrap();
+        deriver.set_peer(&pkey2).unwrap();
+        assert_eq!(expected, deriver.derive_to_vec().unwrap());
     }
 }
@@ -4,9 +4,11 @@ use foreign_types::{ForeignType, ForeignTypeRef};
 use std::mem;
 use std::ptr;
 
-use {cvt, cvt_p};
-use bn::BigNum;
-use pkey::{HasParams, Params};
+use {cvt, cvt_n, cvt_p};
+use bn::{BigNum, BigNumRef};
+#[cfg(ossl111)]
+use nid::Nid;
+use pkey::{HasParams, HasPrivate, HasPublic, Params, Private};
 
 generic_foreign_type_and_impl_send_sync! {
     type CType = ffi::DH;
@@ -42,6 +44,69 @@ where
         params_to_der,
         ffi::i2d_DHparams
     }
+
+    /// Returns the size of the prime modulus in bytes.
+    ///
+    /// This is also the maximum length of a shared secret computed with this key.
+    ///
+    /// This corresponds to [`DH_size`].
+    ///
+    /// [`DH_size`]: https://www.openssl.org/docs/man1.1.0/crypto/DH_size.html
+    pub fn size(&self) -> u32 {
+        unsafe { ffi::DH_size(self.as_ptr()) as u32 }
+    }
+}
+
+impl<T> DhRef<T>
+where
+    T: HasPublic,
+{
+    /// Returns a reference to the public key component of `self`.
+    ///
+    /// This corresponds to [`DH_get0_key`].
+    ///
+    /// [`DH_get0_key`]: https://www.openssl.org/docs/man1.1.0/crypto/DH_get0_key.html
+    pub fn public_key(&self) -> &BigNumRef {
+        unsafe {
+            let (pub_key, _) = compat::DH_get0_key(self.as_ptr());
+            BigNumRef::from_ptr(pub_key as *mut _)
+        }
+    }
+}
+
+impl<T> DhRef<T>
+where
+    T: HasPrivate,
+{
+    /// Returns a reference to the private key component of `self`.
+    ///
+    /// This corresponds to [`DH_get0_key`].
+    ///
+    /// [`DH_get0_key`]: https://www.openssl.org/docs/man1.1.0/crypto/DH_get0_key.html
+    pub fn private_key(&self) -> &BigNumRef {
+        unsafe {
+            let (_, priv_key) = compat::DH_get0_key(self.as_ptr());
+            BigNumRef::from_ptr(priv_key as *mut _)
+        }
+    }
+
+    /// Computes the shared secret between this key and a peer's public key.
+    ///
+    /// This corresponds to [`DH_compute_key`].
+    ///
+    /// [`DH_compute_key`]: https://www.openssl.org/docs/man1.1.0/crypto/DH_compute_key.html
+    pub fn compute_key(&self, public_key: &BigNumRef) -> Result<Vec<u8>, ErrorStack> {
+        unsafe {
+            let mut key = vec![0; self.size() as usize];
+            let len = cvt_n(ffi::DH_compute_key(
+                key.as_mut_ptr(),
+                public_key.as_ptr(),
+                self.as_ptr(),
+            ))?;
+            key.truncate(len as usize);
+            Ok(key)
+        }
+    }
 }
 
 impl Dh<Params> {
@@ -59,6 +124,20 @@ impl Dh<Params> {
         }
     }
 
+    /// Generates a new public/private key pair from these parameters.
+    ///
+    /// This corresponds to [`DH_generate_key`].
+    ///
+    /// [`DH_generate_key`]: https://www.openssl.org/docs/man1.1.0/crypto/DH_generate_key.html
+    pub fn generate_key(self) -> Result<Dh<Private>, ErrorStack> {
+        unsafe {
+            cvt(ffi::DH_generate_key(self.as_ptr()))?;
+            let ptr = self.as_ptr();
+            mem::forget(self);
+            Ok(Dh::from_ptr(ptr))
+        }
+    }
+
     from_pem! {
         /// Deserializes a PEM-encoded PKCS#3 DHpararameters structure.
         ///
@@ -109,11 +188,39 @@ impl Dh<Params> {
             cvt_p(ffi::DH_get_2048_256()).map(|p| Dh::from_ptr(p))
         }
     }
+
+    /// Returns the parameters of one of the RFC 7919 finite field groups.
+    ///
+    /// `nid` should be one of `Nid::FFDHE2048`, `Nid::FFDHE3072`, `Nid::FFDHE4096`,
+    /// `Nid::FFDHE6144` or `Nid::FFDHE8192`.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`DH_new_by_nid`].
+    ///
+    /// [`DH_new_by_nid`]: https://www.openssl.org/docs/man1.1.1/man3/DH_new_by_nid.html
+    #[cfg(ossl111)]
+    pub fn from_nid(nid: Nid) -> Result<Dh<Params>, ErrorStack> {
+        unsafe {
+            ffi::init();
+            cvt_p(ffi::DH_new_by_nid(nid.as_raw())).map(|p| Dh::from_ptr(p))
+        }
+    }
 }
 
 #[cfg(ossl110)]
+#[allow(bad_style)]
 mod compat {
+    use std::ptr;
+    use ffi;
+
     pub use ffi::DH_set0_pqg;
+
+    pub unsafe fn DH_get0_key(dh: *const ffi::DH) -> (*const ffi::BIGNUM, *const ffi::BIGNUM) {
+        let (mut pub_key, mut priv_key) = (ptr::null(), ptr::null());
+        ffi::DH_get0_key(dh, &mut pub_key, &mut priv_key);
+        (pub_key, priv_key)
+    }
 }
 
 #[cfg(ossl10x)]
@@ -133,12 +240,18 @@ mod compat {
         (*dh).g = g;
         1
     }
+
+    pub unsafe fn DH_get0_key(dh: *const ffi::DH) -> (*const ffi::BIGNUM, *const ffi::BIGNUM) {
+        ((*dh).pub_key, (*dh).priv_key)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use dh::Dh;
     use bn::BigNum;
+    #[cfg(ossl111)]
+    use nid::Nid;
     use ssl::{SslContext, SslMethod};
 
     #[test]
@@ -194,4 +307,25 @@ mod tests {
         let der = dh.params_to_der().unwrap();
         Dh::params_from_der(&der).unwrap();
     }
+
+    #[test]
+    fn test_dh_compute_key() {
+        let params = include_bytes!("../test/dhparams.pem");
+        let alice = Dh::params_from_pem(params).unwrap().generate_key().unwrap();
+        let bob = Dh::params_from_pem(params).unwrap().generate_key().unwrap();
+
+        let alice_secret = alice.compute_key(bob.public_key()).unwrap();
+        let bob_secret = bob.compute_key(alice.public_key()).unwrap();
+        assert_eq!(alice_secret, bob_secret);
+        assert!(alice.private_key() != bob.private_key());
+    }
+
+    #[test]
+    #[cfg(ossl111)]
+    fn test_dh_from_nid() {
+        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        let dh = Dh::from_nid(Nid::FFDHE2048).unwrap();
+        assert_eq!(dh.size(), 256);
+        ctx.set_tmp_dh(&dh).unwrap();
+    }
 }
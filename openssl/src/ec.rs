@@ -16,6 +16,17 @@
 //! [`Nid`]: ../nid/struct.Nid.html
 //! [Eliptic Curve Cryptography]: https://wiki.openssl.org/index.php/Elliptic_Curve_Cryptography
 //!
+//! # Side-channel resistance
+//!
+//! OpenSSL 1.1.1 and newer perform scalar multiplication by a private key in constant time, and
+//! mark private keys with `BN_FLG_CONSTTIME` as they are set, which can be checked with
+//! [`BigNumRef::is_const_time`]. Precomputed multiples of the generator, added by
+//! [`EcGroupRef::precompute_mult`], speed up operations with public scalars but are not used for
+//! private ones.
+//!
+//! [`BigNumRef::is_const_time`]: ../bn/struct.BigNumRef.html#method.is_const_time
+//! [`EcGroupRef::precompute_mult`]: struct.EcGroupRef.html#method.precompute_mult
+//!
 //! # Examples
 //!
 //! ```
@@ -101,6 +112,23 @@ impl Asn1Flag {
     pub const NAMED_CURVE: Asn1Flag = Asn1Flag(ffi::OPENSSL_EC_NAMED_CURVE);
 }
 
+bitflags! {
+    /// Flags controlling the behavior of an `EcKey`.
+    pub struct EcKeyFlags: c_int {
+        /// Allows the key to be used by algorithms not approved in FIPS mode.
+        const NON_FIPS_ALLOW = ffi::EC_FLAG_NON_FIPS_ALLOW;
+        /// Set once the key has passed the FIPS pairwise consistency test.
+        const FIPS_CHECKED = ffi::EC_FLAG_FIPS_CHECKED;
+        /// Multiplies by the cofactor of the curve when deriving shared secrets.
+        ///
+        /// This prevents small subgroup attacks on curves with a cofactor other than 1.
+        ///
+        /// Requires OpenSSL 1.0.2 or newer.
+        #[cfg(not(ossl101))]
+        const COFACTOR_ECDH = ffi::EC_FLAG_COFACTOR_ECDH;
+    }
+}
+
 foreign_type_and_impl_send_sync! {
     type CType = ffi::EC_GROUP;
     fn drop = ffi::EC_GROUP_free;
@@ -196,6 +224,20 @@ impl EcGroupRef {
         }
     }
 
+    /// Returns the name of the curve, if a name is associated.
+    ///
+    /// OpenSSL documentation at [`EC_GROUP_get_curve_name`]
+    ///
+    /// [`EC_GROUP_get_curve_name`]: https://www.openssl.org/docs/man1.1.0/crypto/EC_GROUP_get_curve_name.html
+    pub fn curve_name(&self) -> Option<Nid> {
+        let nid = unsafe { ffi::EC_GROUP_get_curve_name(self.as_ptr()) };
+        if nid > 0 {
+            Some(Nid::from_raw(nid))
+        } else {
+            None
+        }
+    }
+
     /// Returns the degree of the curve.
     ///
     /// OpenSSL documentation at [`EC_GROUP_get_degree`]
@@ -234,6 +276,27 @@ impl EcGroupRef {
             ffi::EC_GROUP_set_asn1_flag(self.as_ptr(), flag.0);
         }
     }
+
+    /// Precomputes multiples of the generator to speed up later multiplications by it.
+    ///
+    /// The precomputed values are only used for multiplication by public scalars, such as during
+    /// signature verification. Multiplication by private scalars is unaffected.
+    ///
+    /// This corresponds to [`EC_GROUP_precompute_mult`].
+    ///
+    /// [`EC_GROUP_precompute_mult`]: https://www.openssl.org/docs/man1.1.0/crypto/EC_GROUP_precompute_mult.html
+    pub fn precompute_mult(&mut self, ctx: &mut BigNumContextRef) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::EC_GROUP_precompute_mult(self.as_ptr(), ctx.as_ptr())).map(|_| ()) }
+    }
+
+    /// Determines if multiples of the generator have been precomputed.
+    ///
+    /// This corresponds to [`EC_GROUP_have_precompute_mult`].
+    ///
+    /// [`EC_GROUP_have_precompute_mult`]: https://www.openssl.org/docs/man1.1.0/crypto/EC_GROUP_have_precompute_mult.html
+    pub fn has_precompute_mult(&self) -> bool {
+        unsafe { ffi::EC_GROUP_have_precompute_mult(self.as_ptr()) == 1 }
+    }
 }
 
 foreign_type_and_impl_send_sync! {
@@ -569,6 +632,28 @@ impl<T> EcKeyRef<T>
 where
     T: HasPublic,
 {
+    to_pem! {
+        /// Serializes the public key into a PEM-encoded SubjectPublicKeyInfo structure.
+        ///
+        /// The output will have a header of `-----BEGIN PUBLIC KEY-----`.
+        ///
+        /// This corresponds to [`PEM_write_bio_EC_PUBKEY`].
+        ///
+        /// [`PEM_write_bio_EC_PUBKEY`]: https://www.openssl.org/docs/man1.1.0/crypto/PEM_write_bio_EC_PUBKEY.html
+        public_key_to_pem,
+        ffi::PEM_write_bio_EC_PUBKEY
+    }
+
+    to_der! {
+        /// Serializes the public key into a DER-encoded SubjectPublicKeyInfo structure.
+        ///
+        /// This corresponds to [`i2d_EC_PUBKEY`].
+        ///
+        /// [`i2d_EC_PUBKEY`]: https://www.openssl.org/docs/man1.1.0/crypto/i2d_EC_PUBKEY.html
+        public_key_to_der,
+        ffi::i2d_EC_PUBKEY
+    }
+
     /// Returns the public key.
     ///
     /// OpenSSL documentation at [`EC_KEY_get0_pubic_key`]
@@ -606,6 +691,33 @@ where
     pub fn check_key(&self) -> Result<(), ErrorStack> {
         unsafe { cvt(ffi::EC_KEY_check_key(self.as_ptr())).map(|_| ()) }
     }
+
+    /// Returns the flags set on the key.
+    ///
+    /// This corresponds to [`EC_KEY_get_flags`].
+    ///
+    /// [`EC_KEY_get_flags`]: https://www.openssl.org/docs/man1.1.0/crypto/EC_KEY_get_flags.html
+    pub fn flags(&self) -> EcKeyFlags {
+        unsafe { EcKeyFlags::from_bits_truncate(ffi::EC_KEY_get_flags(self.as_ptr())) }
+    }
+
+    /// Sets flags on the key, in addition to those already set.
+    ///
+    /// This corresponds to [`EC_KEY_set_flags`].
+    ///
+    /// [`EC_KEY_set_flags`]: https://www.openssl.org/docs/man1.1.0/crypto/EC_KEY_set_flags.html
+    pub fn set_flags(&mut self, flags: EcKeyFlags) {
+        unsafe { ffi::EC_KEY_set_flags(self.as_ptr(), flags.bits()) }
+    }
+
+    /// Clears flags on the key.
+    ///
+    /// This corresponds to [`EC_KEY_clear_flags`].
+    ///
+    /// [`EC_KEY_clear_flags`]: https://www.openssl.org/docs/man1.1.0/crypto/EC_KEY_clear_flags.html
+    pub fn clear_flags(&mut self, flags: EcKeyFlags) {
+        unsafe { ffi::EC_KEY_clear_flags(self.as_ptr(), flags.bits()) }
+    }
 }
 
 impl<T> ToOwned for EcKeyRef<T> {
@@ -693,6 +805,30 @@ impl EcKey<Public> {
         }
     }
 
+    from_pem! {
+        /// Decodes a PEM-encoded SubjectPublicKeyInfo structure containing an EC key.
+        ///
+        /// The input should have a header of `-----BEGIN PUBLIC KEY-----`.
+        ///
+        /// This corresponds to [`PEM_read_bio_EC_PUBKEY`].
+        ///
+        /// [`PEM_read_bio_EC_PUBKEY`]: https://www.openssl.org/docs/man1.1.0/crypto/PEM_read_bio_EC_PUBKEY.html
+        public_key_from_pem,
+        EcKey<Public>,
+        ffi::PEM_read_bio_EC_PUBKEY
+    }
+
+    from_der! {
+        /// Decodes a DER-encoded SubjectPublicKeyInfo structure containing an EC key.
+        ///
+        /// This corresponds to [`d2i_EC_PUBKEY`].
+        ///
+        /// [`d2i_EC_PUBKEY`]: https://www.openssl.org/docs/man1.1.0/crypto/d2i_EC_PUBKEY.html
+        public_key_from_der,
+        EcKey<Public>,
+        ffi::d2i_EC_PUBKEY
+    }
+
     /// Constructs a public key from its affine coordinates.
     pub fn from_public_key_affine_coordinates(
         group: &EcGroupRef,
@@ -813,12 +949,35 @@ mod test {
         EcKey::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
     }
 
+    #[test]
+    fn curve_name() {
+        let group = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+        assert_eq!(group.curve_name(), Some(Nid::SECP384R1));
+    }
+
     #[test]
     fn generate() {
         let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
         EcKey::generate(&group).unwrap();
     }
 
+    #[test]
+    fn public_key_round_trip() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = EcKey::generate(&group).unwrap();
+        let mut ctx = BigNumContext::new().unwrap();
+
+        let pem = key.public_key_to_pem().unwrap();
+        assert!(pem.starts_with(b"-----BEGIN PUBLIC KEY-----"));
+        let public = EcKey::public_key_from_pem(&pem).unwrap();
+        assert!(public.public_key().eq(&group, key.public_key(), &mut ctx).unwrap());
+
+        let der = key.public_key_to_der().unwrap();
+        let public = EcKey::public_key_from_der(&der).unwrap();
+        assert!(public.public_key().eq(&group, key.public_key(), &mut ctx).unwrap());
+        assert_eq!(public.public_key_to_der().unwrap(), der);
+    }
+
     #[test]
     fn dup() {
         let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
@@ -926,4 +1085,36 @@ mod test {
         assert_eq!(xbn2, xbn);
         assert_eq!(ybn2, ybn);
     }
+
+    #[test]
+    fn precompute_mult() {
+        let mut group = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+        let mut ctx = BigNumContext::new().unwrap();
+        group.precompute_mult(&mut ctx).unwrap();
+        assert!(group.has_precompute_mult());
+
+        let key = EcKey::generate(&group).unwrap();
+        key.check_key().unwrap();
+    }
+
+    #[test]
+    fn flags() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let mut key = EcKey::generate(&group).unwrap();
+        assert!(!key.flags().contains(EcKeyFlags::NON_FIPS_ALLOW));
+
+        key.set_flags(EcKeyFlags::NON_FIPS_ALLOW);
+        assert!(key.flags().contains(EcKeyFlags::NON_FIPS_ALLOW));
+
+        key.clear_flags(EcKeyFlags::NON_FIPS_ALLOW);
+        assert!(!key.flags().contains(EcKeyFlags::NON_FIPS_ALLOW));
+    }
+
+    #[test]
+    #[cfg(ossl111)]
+    fn private_key_const_time() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = EcKey::generate(&group).unwrap();
+        assert!(key.private_key().is_const_time());
+    }
 }
@@ -3,10 +3,11 @@ use ffi;
 use foreign_types::{ForeignType, ForeignTypeRef};
 use libc::c_int;
 use std::mem;
+use std::ptr;
 
 use bn::{BigNum, BigNumRef};
 use {cvt, cvt_n, cvt_p};
-use ec::EcKeyRef;
+use ec::{EcGroupRef, EcKeyRef};
 use error::ErrorStack;
 use pkey::{Private, Public};
 
@@ -59,6 +60,47 @@ impl EcdsaSig {
         }
     }
 
+    /// Decodes a signature in the fixed-size `r || s` format used by JWS and WebCrypto.
+    ///
+    /// Each component must be encoded in big-endian form, left-padded with zeros to the byte length
+    /// of the group's degree. `None` is returned if `sig` is not exactly twice that length.
+    pub fn from_fixed_bytes(
+        group: &EcGroupRef,
+        sig: &[u8],
+    ) -> Result<Option<EcdsaSig>, ErrorStack> {
+        let len = component_len(group);
+        if sig.len() != len * 2 {
+            return Ok(None);
+        }
+
+        let r = BigNum::from_slice(&sig[..len])?;
+        let s = BigNum::from_slice(&sig[len..])?;
+        EcdsaSig::from_private_components(r, s).map(Some)
+    }
+
+    from_der! {
+        /// Decodes a DER-encoded ECDSA signature.
+        ///
+        /// This corresponds to [`d2i_ECDSA_SIG`].
+        ///
+        /// [`d2i_ECDSA_SIG`]: https://www.openssl.org/docs/man1.1.0/crypto/d2i_ECDSA_SIG.html
+        from_der,
+        EcdsaSig,
+        ffi::d2i_ECDSA_SIG
+    }
+}
+
+impl EcdsaSigRef {
+    to_der! {
+        /// Serializes the ECDSA signature into a DER-encoded ECDSASignature structure.
+        ///
+        /// This corresponds to [`i2d_ECDSA_SIG`].
+        ///
+        /// [`i2d_ECDSA_SIG`]: https://www.openssl.org/docs/man1.1.0/crypto/i2d_ECDSA_SIG.html
+        to_der,
+        ffi::i2d_ECDSA_SIG
+    }
+
     /// Verifies if the signature is a valid ECDSA signature using the given public key.
     ///
     /// OpenSSL documentation at [`ECDSA_do_verify`]
@@ -76,6 +118,26 @@ impl EcdsaSig {
         }
     }
 
+    /// Encodes the signature in the fixed-size `r || s` format used by JWS and WebCrypto.
+    ///
+    /// Each component is encoded in big-endian form, left-padded with zeros to the byte length of
+    /// the group's degree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a component is too large for the group, which is never the case for signatures
+    /// produced with a key on that group.
+    pub fn to_fixed_bytes(&self, group: &EcGroupRef) -> Vec<u8> {
+        let len = component_len(group);
+        let mut out = vec![0; len * 2];
+        for (n, chunk) in [self.r(), self.s()].iter().zip(out.chunks_mut(len)) {
+            let n = n.to_vec();
+            assert!(n.len() <= len, "signature component too large for the group");
+            chunk[len - n.len()..].copy_from_slice(&n);
+        }
+        out
+    }
+
     /// Returns internal component: `r` of a `EcdsaSig`. (See X9.62 or FIPS 186-2)
     ///
     /// OpenSSL documentation at [`ECDSA_SIG_get0`]
@@ -101,6 +163,10 @@ impl EcdsaSig {
     }
 }
 
+fn component_len(group: &EcGroupRef) -> usize {
+    (group.degree() as usize + 7) / 8
+}
+
 #[cfg(ossl110)]
 mod compat {
     use std::ptr;
@@ -180,6 +246,21 @@ mod test {
         assert!(verification3 == false);
     }
 
+    #[test]
+    fn der_round_trip() {
+        let group = EcGroup::from_curve_name(CURVE_IDENTIFER).unwrap();
+        let private_key = EcKey::generate(&group).unwrap();
+        let public_key = get_public_key(&group, &private_key).unwrap();
+        let data = String::from("hello");
+        let res = EcdsaSig::sign(data.as_bytes(), &private_key).unwrap();
+
+        let der = res.to_der().unwrap();
+        let res2 = EcdsaSig::from_der(&der).unwrap();
+        assert_eq!(res.r(), res2.r());
+        assert_eq!(res.s(), res2.s());
+        assert!(res2.verify(data.as_bytes(), &public_key).unwrap());
+    }
+
     #[test]
     fn check_private_components() {
         let group = EcGroup::from_curve_name(CURVE_IDENTIFER).unwrap();
@@ -198,4 +279,34 @@ mod test {
         let verification2 = res2.verify(data.as_bytes(), &public_key).unwrap();
         assert!(verification2);
     }
+
+    #[test]
+    fn fixed_bytes_round_trip() {
+        for &(nid, len) in &[
+            (Nid::X9_62_PRIME256V1, 64),
+            (Nid::SECP384R1, 96),
+            (Nid::SECP521R1, 132),
+        ] {
+            let group = EcGroup::from_curve_name(nid).unwrap();
+            let private_key = EcKey::generate(&group).unwrap();
+            let public_key = get_public_key(&group, &private_key).unwrap();
+            let data = String::from("hello");
+            let res = EcdsaSig::sign(data.as_bytes(), &private_key).unwrap();
+
+            let fixed = res.to_fixed_bytes(&group);
+            assert_eq!(fixed.len(), len);
+            let res2 = EcdsaSig::from_fixed_bytes(&group, &fixed)
+                .unwrap()
+                .unwrap();
+            assert_eq!(res.r(), res2.r());
+            assert_eq!(res.s(), res2.s());
+            assert!(res2.verify(data.as_bytes(), &public_key).unwrap());
+
+            assert!(
+                EcdsaSig::from_fixed_bytes(&group, &fixed[1..])
+                    .unwrap()
+                    .is_none()
+            );
+        }
+    }
 }
@@ -0,0 +1,233 @@
+//! Hybrid public key encryption with elliptic curve keys.
+//!
+//! This is an ECIES-style construction built from the primitives in this crate:
+//!
+//! 1. A fresh ephemeral key is generated on the recipient's curve for every message.
+//! 2. An ECDH shared secret is computed between the ephemeral key and the recipient's key.
+//! 3. HKDF-SHA256 ([RFC 5869]) expands the shared secret into a 256 bit AES key and a 96 bit
+//!    nonce. The info parameter is `"openssl ecies v1"` followed by the ephemeral and recipient
+//!    public keys, binding the derived key to both. The salt is empty.
+//! 4. The message is encrypted with AES-256-GCM, authenticating the caller-provided associated
+//!    data.
+//!
+//! Since both the key and nonce are derived from a single-use ephemeral key, a nonce is never
+//! reused under the same key.
+//!
+//! The wire format is stable:
+//!
+//! ```text
+//! ephemeral public key (uncompressed SEC1 point) || ciphertext || tag (16 bytes)
+//! ```
+//!
+//! The length of the ephemeral public key is determined by the recipient's curve, for example
+//! 65 bytes for P-256.
+//!
+//! Requires OpenSSL 1.1.0 or newer.
+//!
+//! # Example
+//!
+//! ```rust
+//! use openssl::ec::{EcGroup, EcKey};
+//! use openssl::ecies;
+//! use openssl::nid::Nid;
+//!
+//! let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+//! let key = EcKey::generate(&group).unwrap();
+//!
+//! let encrypted = ecies::encrypt(&key, b"context", b"hello world").unwrap();
+//! let decrypted = ecies::decrypt(&key, b"context", &encrypted).unwrap();
+//! assert_eq!(&decrypted[..], b"hello world");
+//! ```
+//!
+//! [RFC 5869]: https://tools.ietf.org/html/rfc5869
+use std::cmp;
+
+use bn::BigNumContext;
+use derive::Deriver;
+use ec::{EcGroupRef, EcKey, EcKeyRef, EcPoint, EcPointRef, PointConversionForm};
+use error::ErrorStack;
+use hash::MessageDigest;
+use kdf;
+use pkey::{HasPrivate, HasPublic, PKey};
+use symm::{self, Cipher};
+
+const INFO: &'static [u8] = b"openssl ecies v1";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// Encrypts `data` to the holder of the private half of `recipient`.
+///
+/// `aad` is authenticated but not encrypted, and must be provided again to decrypt.
+pub fn encrypt<T>(recipient: &EcKeyRef<T>, aad: &[u8], data: &[u8]) -> Result<Vec<u8>, ErrorStack>
+where
+    T: HasPublic,
+{
+    let group = recipient.group();
+    let mut ctx = BigNumContext::new()?;
+
+    let ephemeral = EcKey::generate(group)?;
+    let mut out = point_to_bytes(group, ephemeral.public_key(), &mut ctx)?;
+    let recipient_bytes = point_to_bytes(group, recipient.public_key(), &mut ctx)?;
+
+    let ephemeral = PKey::from_ec_key(ephemeral)?;
+    let peer = PKey::from_ec_key(EcKey::from_public_key(group, recipient.public_key())?)?;
+    let mut deriver = Deriver::new(&ephemeral)?;
+    deriver.set_peer(&peer)?;
+    let secret = deriver.derive_to_vec()?;
+
+    let (key, nonce) = derive_key(&secret, &out, &recipient_bytes)?;
+    let mut tag = [0; TAG_LEN];
+    let ciphertext = symm::encrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key,
+        Some(&nonce),
+        aad,
+        data,
+        &mut tag,
+    )?;
+
+    out.extend_from_slice(&ciphertext);
+    out.extend_from_slice(&tag);
+    Ok(out)
+}
+
+/// Decrypts a message produced by [`encrypt`] with the private key of the recipient.
+///
+/// An error is returned if the message is malformed, was encrypted to a different key, or either
+/// it or `aad` has been modified.
+///
+/// [`encrypt`]: fn.encrypt.html
+pub fn decrypt<T>(recipient: &EcKeyRef<T>, aad: &[u8], data: &[u8]) -> Result<Vec<u8>, ErrorStack>
+where
+    T: HasPrivate,
+{
+    let group = recipient.group();
+    let mut ctx = BigNumContext::new()?;
+
+    // an uncompressed point is a tag byte followed by both coordinates
+    let point_len = 1 + 2 * ((group.degree() as usize + 7) / 8);
+    let (ephemeral_bytes, data) = data.split_at(cmp::min(point_len, data.len()));
+    let (ciphertext, tag) = if data.len() < TAG_LEN {
+        // an empty tag is rejected outright, while a short one would be checked as a prefix
+        (data, &[][..])
+    } else {
+        data.split_at(data.len() - TAG_LEN)
+    };
+
+    let point = EcPoint::from_bytes(group, ephemeral_bytes, &mut ctx)?;
+    let ephemeral = EcKey::from_public_key(group, &point)?;
+    ephemeral.check_key()?;
+    let recipient_bytes = point_to_bytes(group, recipient.public_key(), &mut ctx)?;
+
+    let ephemeral = PKey::from_ec_key(ephemeral)?;
+    let key = PKey::from_ec_key(recipient.to_owned())?;
+    let mut deriver = Deriver::new(&key)?;
+    deriver.set_peer(&ephemeral)?;
+    let secret = deriver.derive_to_vec()?;
+
+    let (key, nonce) = derive_key(&secret, ephemeral_bytes, &recipient_bytes)?;
+    symm::decrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key,
+        Some(&nonce),
+        aad,
+        ciphertext,
+        tag,
+    )
+}
+
+fn point_to_bytes(
+    group: &EcGroupRef,
+    point: &EcPointRef,
+    ctx: &mut BigNumContext,
+) -> Result<Vec<u8>, ErrorStack> {
+    point.to_bytes(group, PointConversionForm::UNCOMPRESSED, ctx)
+}
+
+fn derive_key(
+    secret: &[u8],
+    ephemeral: &[u8],
+    recipient: &[u8],
+) -> Result<([u8; KEY_LEN], [u8; NONCE_LEN]), ErrorStack> {
+    let mut info = INFO.to_vec();
+    info.extend_from_slice(ephemeral);
+    info.extend_from_slice(recipient);
+
+    let mut okm = [0; KEY_LEN + NONCE_LEN];
+    kdf::hkdf(MessageDigest::sha256(), secret, &[], &info, &mut okm)?;
+
+    let mut key = [0; KEY_LEN];
+    let mut nonce = [0; NONCE_LEN];
+    key.copy_from_slice(&okm[..KEY_LEN]);
+    nonce.copy_from_slice(&okm[KEY_LEN..]);
+    Ok((key, nonce))
+}
+
+#[cfg(test)]
+mod test {
+    use ec::EcGroup;
+    use nid::Nid;
+    use super::*;
+
+    fn generate(nid: Nid) -> EcKey<::pkey::Private> {
+        let group = EcGroup::from_curve_name(nid).unwrap();
+        EcKey::generate(&group).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        for &nid in &[Nid::X9_62_PRIME256V1, Nid::SECP384R1] {
+            let key = generate(nid);
+            let encrypted = encrypt(&key, b"aad", b"hello world").unwrap();
+            let point_len = 1 + 2 * ((key.group().degree() as usize + 7) / 8);
+            assert_eq!(encrypted.len(), point_len + b"hello world".len() + TAG_LEN);
+
+            let decrypted = decrypt(&key, b"aad", &encrypted).unwrap();
+            assert_eq!(&decrypted[..], b"hello world");
+        }
+    }
+
+    #[test]
+    fn public_key_encrypt() {
+        let key = generate(Nid::X9_62_PRIME256V1);
+        let public = EcKey::from_public_key(key.group(), key.public_key()).unwrap();
+
+        let encrypted = encrypt(&public, b"", b"").unwrap();
+        assert_eq!(encrypted.len(), 65 + TAG_LEN);
+        assert!(decrypt(&key, b"", &encrypted).unwrap().is_empty());
+    }
+
+    #[test]
+    fn fresh_ephemeral_key() {
+        let key = generate(Nid::X9_62_PRIME256V1);
+        let a = encrypt(&key, b"", b"hello world").unwrap();
+        let b = encrypt(&key, b"", b"hello world").unwrap();
+        assert!(a[..65] != b[..65]);
+        assert!(a[65..] != b[65..]);
+    }
+
+    #[test]
+    fn tampering() {
+        let key = generate(Nid::X9_62_PRIME256V1);
+        let encrypted = encrypt(&key, b"aad", b"hello world").unwrap();
+
+        assert!(decrypt(&key, b"other", &encrypted).is_err());
+        for &i in &[0, 10, 65, encrypted.len() - 1] {
+            let mut tampered = encrypted.clone();
+            tampered[i] ^= 1;
+            assert!(decrypt(&key, b"aad", &tampered).is_err());
+        }
+        for len in &[0, 10, 65, 80] {
+            assert!(decrypt(&key, b"aad", &encrypted[..*len]).is_err());
+        }
+    }
+
+    #[test]
+    fn wrong_key() {
+        let key = generate(Nid::X9_62_PRIME256V1);
+        let other = generate(Nid::X9_62_PRIME256V1);
+        let encrypted = encrypt(&key, b"", b"hello world").unwrap();
+        assert!(decrypt(&other, b"", &encrypted).is_err());
+    }
+}
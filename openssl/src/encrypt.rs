@@ -0,0 +1,457 @@
+//! Message encryption.
+//!
+//! The [`Encrypter`] allows for encryption of data given a public key. The [`Decrypter`] can be
+//! used with the corresponding private key to decrypt the data.
+//!
+//! Unlike the methods on `Rsa`, these types allow the padding and its parameters to be
+//! configured, such as the digests used by RSA-OAEP.
+//!
+//! # Examples
+//!
+//! Encrypt and decrypt data given an RSA keypair using OAEP with SHA-256:
+//!
+//! ```rust
+//! use openssl::encrypt::{Decrypter, Encrypter};
+//! use openssl::hash::MessageDigest;
+//! use openssl::pkey::PKey;
+//! use openssl::rsa::{Padding, Rsa};
+//!
+//! // Generate a keypair
+//! let keypair = Rsa::generate(2048).unwrap();
+//! let keypair = PKey::from_rsa(keypair).unwrap();
+//!
+//! let data = b"hello, world!";
+//!
+//! // Encrypt the data with RSA-OAEP
+//! let mut encrypter = Encrypter::new(&keypair).unwrap();
+//! encrypter.set_rsa_padding(Padding::PKCS1_OAEP).unwrap();
+//! # #[cfg(any(ossl102, ossl110))]
+//! encrypter.set_rsa_oaep_md(MessageDigest::sha256()).unwrap();
+//! # #[cfg(any(ossl102, ossl110))]
+//! encrypter.set_rsa_mgf1_md(MessageDigest::sha256()).unwrap();
+//! // Create an output buffer
+//! let buffer_len = encrypter.encrypt_len(data).unwrap();
+//! let mut encrypted = vec![0; buffer_len];
+//! // Encrypt and truncate the buffer
+//! let encrypted_len = encrypter.encrypt(data, &mut encrypted).unwrap();
+//! encrypted.truncate(encrypted_len);
+//!
+//! // Decrypt the data
+//! let mut decrypter = Decrypter::new(&keypair).unwrap();
+//! decrypter.set_rsa_padding(Padding::PKCS1_OAEP).unwrap();
+//! # #[cfg(any(ossl102, ossl110))]
+//! decrypter.set_rsa_oaep_md(MessageDigest::sha256()).unwrap();
+//! # #[cfg(any(ossl102, ossl110))]
+//! decrypter.set_rsa_mgf1_md(MessageDigest::sha256()).unwrap();
+//! // Create an output buffer
+//! let buffer_len = decrypter.decrypt_len(&encrypted).unwrap();
+//! let mut decrypted = vec![0; buffer_len];
+//! // Decrypt and truncate the buffer
+//! let decrypted_len = decrypter.decrypt(&encrypted, &mut decrypted).unwrap();
+//! decrypted.truncate(decrypted_len);
+//! assert_eq!(&*decrypted, data);
+//! ```
+//!
+//! [`Encrypter`]: struct.Encrypter.html
+//! [`Decrypter`]: struct.Decrypter.html
+use ffi;
+use foreign_types::ForeignTypeRef;
+use std::marker::PhantomData;
+use std::ptr;
+
+use {cvt, cvt_p};
+use error::ErrorStack;
+#[cfg(any(ossl102, ossl110))]
+use hash::MessageDigest;
+use pkey::{HasPrivate, HasPublic, PKeyRef};
+use rsa::Padding;
+
+/// A type which encrypts data.
+pub struct Encrypter<'a> {
+    pctx: *mut ffi::EVP_PKEY_CTX,
+    _p: PhantomData<&'a ()>,
+}
+
+unsafe impl<'a> Sync for Encrypter<'a> {}
+unsafe impl<'a> Send for Encrypter<'a> {}
+
+impl<'a> Drop for Encrypter<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::EVP_PKEY_CTX_free(self.pctx);
+        }
+    }
+}
+
+impl<'a> Encrypter<'a> {
+    /// Creates a new `Encrypter`.
+    ///
+    /// This corresponds to [`EVP_PKEY_encrypt_init`].
+    ///
+    /// [`EVP_PKEY_encrypt_init`]: https://www.openssl.org/docs/man1.0.2/crypto/EVP_PKEY_encrypt.html
+    pub fn new<T>(pkey: &'a PKeyRef<T>) -> Result<Encrypter<'a>, ErrorStack>
+    where
+        T: HasPublic,
+    {
+        unsafe {
+            ffi::init();
+
+            let pctx = cvt_p(ffi::EVP_PKEY_CTX_new(pkey.as_ptr(), ptr::null_mut()))?;
+            let encrypter = Encrypter {
+                pctx,
+                _p: PhantomData,
+            };
+            cvt(ffi::EVP_PKEY_encrypt_init(encrypter.pctx))?;
+            Ok(encrypter)
+        }
+    }
+
+    /// Returns the RSA padding mode in use.
+    ///
+    /// This is only useful for RSA keys.
+    ///
+    /// This corresponds to `EVP_PKEY_CTX_get_rsa_padding`.
+    pub fn rsa_padding(&self) -> Result<Padding, ErrorStack> {
+        unsafe { rsa_padding(self.pctx) }
+    }
+
+    /// Sets the RSA padding mode.
+    ///
+    /// This is only useful for RSA keys.
+    ///
+    /// This corresponds to [`EVP_PKEY_CTX_set_rsa_padding`].
+    ///
+    /// [`EVP_PKEY_CTX_set_rsa_padding`]: https://www.openssl.org/docs/man1.1.0/crypto/EVP_PKEY_CTX_set_rsa_padding.html
+    pub fn set_rsa_padding(&mut self, padding: Padding) -> Result<(), ErrorStack> {
+        unsafe { set_rsa_padding(self.pctx, padding) }
+    }
+
+    /// Sets the RSA MGF1 algorithm.
+    ///
+    /// This is only useful for RSA keys using OAEP padding.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    ///
+    /// This corresponds to [`EVP_PKEY_CTX_set_rsa_mgf1_md`].
+    ///
+    /// [`EVP_PKEY_CTX_set_rsa_mgf1_md`]: https://www.openssl.org/docs/manmaster/man7/RSA-PSS.html
+    #[cfg(any(ossl102, ossl110))]
+    pub fn set_rsa_mgf1_md(&mut self, md: MessageDigest) -> Result<(), ErrorStack> {
+        unsafe { set_rsa_mgf1_md(self.pctx, md) }
+    }
+
+    /// Sets the RSA OAEP digest.
+    ///
+    /// This is only useful for RSA keys using OAEP padding. It defaults to SHA-1.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    ///
+    /// This corresponds to [`EVP_PKEY_CTX_set_rsa_oaep_md`].
+    ///
+    /// [`EVP_PKEY_CTX_set_rsa_oaep_md`]: https://www.openssl.org/docs/man1.1.0/crypto/EVP_PKEY_CTX_set_rsa_oaep_md.html
+    #[cfg(any(ossl102, ossl110))]
+    pub fn set_rsa_oaep_md(&mut self, md: MessageDigest) -> Result<(), ErrorStack> {
+        unsafe { set_rsa_oaep_md(self.pctx, md) }
+    }
+
+    /// Returns an upper bound on the length of the ciphertext of `from`.
+    ///
+    /// It can be used to size the buffer passed to [`Encrypter::encrypt`].
+    ///
+    /// This corresponds to [`EVP_PKEY_encrypt`] called with a null output buffer.
+    ///
+    /// [`Encrypter::encrypt`]: #method.encrypt
+    /// [`EVP_PKEY_encrypt`]: https://www.openssl.org/docs/man1.0.2/crypto/EVP_PKEY_encrypt.html
+    pub fn encrypt_len(&self, from: &[u8]) -> Result<usize, ErrorStack> {
+        let mut written = 0;
+        unsafe {
+            cvt(ffi::EVP_PKEY_encrypt(
+                self.pctx,
+                ptr::null_mut(),
+                &mut written,
+                from.as_ptr(),
+                from.len(),
+            ))?;
+        }
+        Ok(written)
+    }
+
+    /// Encrypts `from`, writing the ciphertext into `to`.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// This corresponds to [`EVP_PKEY_encrypt`].
+    ///
+    /// [`EVP_PKEY_encrypt`]: https://www.openssl.org/docs/man1.0.2/crypto/EVP_PKEY_encrypt.html
+    pub fn encrypt(&self, from: &[u8], to: &mut [u8]) -> Result<usize, ErrorStack> {
+        let mut written = to.len();
+        unsafe {
+            cvt(ffi::EVP_PKEY_encrypt(
+                self.pctx,
+                to.as_mut_ptr(),
+                &mut written,
+                from.as_ptr(),
+                from.len(),
+            ))?;
+        }
+        Ok(written)
+    }
+}
+
+/// A type which decrypts data.
+pub struct Decrypter<'a> {
+    pctx: *mut ffi::EVP_PKEY_CTX,
+    _p: PhantomData<&'a ()>,
+}
+
+unsafe impl<'a> Sync for Decrypter<'a> {}
+unsafe impl<'a> Send for Decrypter<'a> {}
+
+impl<'a> Drop for Decrypter<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::EVP_PKEY_CTX_free(self.pctx);
+        }
+    }
+}
+
+impl<'a> Decrypter<'a> {
+    /// Creates a new `Decrypter`.
+    ///
+    /// This corresponds to [`EVP_PKEY_decrypt_init`].
+    ///
+    /// [`EVP_PKEY_decrypt_init`]: https://www.openssl.org/docs/man1.0.2/crypto/EVP_PKEY_decrypt.html
+    pub fn new<T>(pkey: &'a PKeyRef<T>) -> Result<Decrypter<'a>, ErrorStack>
+    where
+        T: HasPrivate,
+    {
+        unsafe {
+            ffi::init();
+
+            let pctx = cvt_p(ffi::EVP_PKEY_CTX_new(pkey.as_ptr(), ptr::null_mut()))?;
+            let decrypter = Decrypter {
+                pctx,
+                _p: PhantomData,
+            };
+            cvt(ffi::EVP_PKEY_decrypt_init(decrypter.pctx))?;
+            Ok(decrypter)
+        }
+    }
+
+    /// Returns the RSA padding mode in use.
+    ///
+    /// This is only useful for RSA keys.
+    ///
+    /// This corresponds to `EVP_PKEY_CTX_get_rsa_padding`.
+    pub fn rsa_padding(&self) -> Result<Padding, ErrorStack> {
+        unsafe { rsa_padding(self.pctx) }
+    }
+
+    /// Sets the RSA padding mode.
+    ///
+    /// This is only useful for RSA keys.
+    ///
+    /// This corresponds to [`EVP_PKEY_CTX_set_rsa_padding`].
+    ///
+    /// [`EVP_PKEY_CTX_set_rsa_padding`]: https://www.openssl.org/docs/man1.1.0/crypto/EVP_PKEY_CTX_set_rsa_padding.html
+    pub fn set_rsa_padding(&mut self, padding: Padding) -> Result<(), ErrorStack> {
+        unsafe { set_rsa_padding(self.pctx, padding) }
+    }
+
+    /// Sets the RSA MGF1 algorithm.
+    ///
+    /// This is only useful for RSA keys using OAEP padding.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    ///
+    /// This corresponds to [`EVP_PKEY_CTX_set_rsa_mgf1_md`].
+    ///
+    /// [`EVP_PKEY_CTX_set_rsa_mgf1_md`]: https://www.openssl.org/docs/manmaster/man7/RSA-PSS.html
+    #[cfg(any(ossl102, ossl110))]
+    pub fn set_rsa_mgf1_md(&mut self, md: MessageDigest) -> Result<(), ErrorStack> {
+        unsafe { set_rsa_mgf1_md(self.pctx, md) }
+    }
+
+    /// Sets the RSA OAEP digest.
+    ///
+    /// This is only useful for RSA keys using OAEP padding. It defaults to SHA-1.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    ///
+    /// This corresponds to [`EVP_PKEY_CTX_set_rsa_oaep_md`].
+    ///
+    /// [`EVP_PKEY_CTX_set_rsa_oaep_md`]: https://www.openssl.org/docs/man1.1.0/crypto/EVP_PKEY_CTX_set_rsa_oaep_md.html
+    #[cfg(any(ossl102, ossl110))]
+    pub fn set_rsa_oaep_md(&mut self, md: MessageDigest) -> Result<(), ErrorStack> {
+        unsafe { set_rsa_oaep_md(self.pctx, md) }
+    }
+
+    /// Returns an upper bound on the length of the plaintext of `from`.
+    ///
+    /// It can be used to size the buffer passed to [`Decrypter::decrypt`].
+    ///
+    /// This corresponds to [`EVP_PKEY_decrypt`] called with a null output buffer.
+    ///
+    /// [`Decrypter::decrypt`]: #method.decrypt
+    /// [`EVP_PKEY_decrypt`]: https://www.openssl.org/docs/man1.0.2/crypto/EVP_PKEY_decrypt.html
+    pub fn decrypt_len(&self, from: &[u8]) -> Result<usize, ErrorStack> {
+        let mut written = 0;
+        unsafe {
+            cvt(ffi::EVP_PKEY_decrypt(
+                self.pctx,
+                ptr::null_mut(),
+                &mut written,
+                from.as_ptr(),
+                from.len(),
+            ))?;
+        }
+        Ok(written)
+    }
+
+    /// Decrypts `from`, writing the plaintext into `to`.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// This corresponds to [`EVP_PKEY_decrypt`].
+    ///
+    /// [`EVP_PKEY_decrypt`]: https://www.openssl.org/docs/man1.0.2/crypto/EVP_PKEY_decrypt.html
+    pub fn decrypt(&self, from: &[u8], to: &mut [u8]) -> Result<usize, ErrorStack> {
+        let mut written = to.len();
+        unsafe {
+            cvt(ffi::EVP_PKEY_decrypt(
+                self.pctx,
+                to.as_mut_ptr(),
+                &mut written,
+                from.as_ptr(),
+                from.len(),
+            ))?;
+        }
+        Ok(written)
+    }
+}
+
+unsafe fn rsa_padding(pctx: *mut ffi::EVP_PKEY_CTX) -> Result<Padding, ErrorStack> {
+    let mut pad = 0;
+    cvt(ffi::EVP_PKEY_CTX_get_rsa_padding(pctx, &mut pad)).map(|_| Padding::from_raw(pad))
+}
+
+unsafe fn set_rsa_padding(
+    pctx: *mut ffi::EVP_PKEY_CTX,
+    padding: Padding,
+) -> Result<(), ErrorStack> {
+    cvt(ffi::EVP_PKEY_CTX_set_rsa_padding(pctx, padding.as_raw())).map(|_| ())
+}
+
+#[cfg(any(ossl102, ossl110))]
+unsafe fn set_rsa_mgf1_md(
+    pctx: *mut ffi::EVP_PKEY_CTX,
+    md: MessageDigest,
+) -> Result<(), ErrorStack> {
+    cvt(ffi::EVP_PKEY_CTX_set_rsa_mgf1_md(pctx, md.as_ptr() as *mut _)).map(|_| ())
+}
+
+#[cfg(any(ossl102, ossl110))]
+unsafe fn set_rsa_oaep_md(
+    pctx: *mut ffi::EVP_PKEY_CTX,
+    md: MessageDigest,
+) -> Result<(), ErrorStack> {
+    cvt(ffi::EVP_PKEY_CTX_set_rsa_oaep_md(pctx, md.as_ptr() as *mut _)).map(|_| ())
+}
+
+#[cfg(test)]
+mod test {
+    use hex::FromHex;
+
+    use super::*;
+    #[cfg(any(ossl102, ossl110))]
+    use hash::MessageDigest;
+    use pkey::PKey;
+    use rsa::{Padding, Rsa};
+
+    const INPUT: &'static str =
+        "65794a68624763694f694a53557a49314e694a392e65794a7063334d694f694a71623255694c41304b49434a6c\
+         654841694f6a457a4d4441344d546b7a4f44417344516f67496d6830644841364c79396c654746746347786c4c\
+         6d4e76625339706331397962323930496a7030636e566c6651";
+
+    #[test]
+    fn rsa_encrypt_decrypt() {
+        let key = include_bytes!("../test/rsa.pem");
+        let private_key = Rsa::private_key_from_pem(key).unwrap();
+        let pkey = PKey::from_rsa(private_key).unwrap();
+
+        let mut encrypter = Encrypter::new(&pkey).unwrap();
+        encrypter.set_rsa_padding(Padding::PKCS1).unwrap();
+        assert_eq!(encrypter.rsa_padding().unwrap(), Padding::PKCS1);
+        let input = Vec::from_hex(INPUT).unwrap();
+        let buffer_len = encrypter.encrypt_len(&input).unwrap();
+        let mut encoded = vec![0u8; buffer_len];
+        let encoded_len = encrypter.encrypt(&input, &mut encoded).unwrap();
+        let encoded = &encoded[..encoded_len];
+
+        let mut decrypter = Decrypter::new(&pkey).unwrap();
+        decrypter.set_rsa_padding(Padding::PKCS1).unwrap();
+        let buffer_len = decrypter.decrypt_len(&encoded).unwrap();
+        let mut decoded = vec![0u8; buffer_len];
+        let decoded_len = decrypter.decrypt(&encoded, &mut decoded).unwrap();
+        let decoded = &decoded[..decoded_len];
+
+        assert_eq!(decoded, &*input);
+    }
+
+    #[test]
+    #[cfg(any(ossl102, ossl110))]
+    fn rsa_encrypt_decrypt_oaep_sha256() {
+        let key = include_bytes!("../test/rsa.pem");
+        let private_key = Rsa::private_key_from_pem(key).unwrap();
+        let pkey = PKey::from_rsa(private_key).unwrap();
+        let public_key = PKey::public_key_from_pem(&pkey.public_key_to_pem().unwrap()).unwrap();
+
+        let mut encrypter = Encrypter::new(&public_key).unwrap();
+        encrypter.set_rsa_padding(Padding::PKCS1_OAEP).unwrap();
+        encrypter.set_rsa_oaep_md(MessageDigest::sha256()).unwrap();
+        encrypter.set_rsa_mgf1_md(MessageDigest::sha256()).unwrap();
+        let input = Vec::from_hex(INPUT).unwrap();
+        let buffer_len = encrypter.encrypt_len(&input).unwrap();
+        let mut encoded = vec![0u8; buffer_len];
+        let encoded_len = encrypter.encrypt(&input, &mut encoded).unwrap();
+        let encoded = &encoded[..encoded_len];
+
+        // the digests must match on both sides
+        let mut decrypter = Decrypter::new(&pkey).unwrap();
+        decrypter.set_rsa_padding(Padding::PKCS1_OAEP).unwrap();
+        let mut decoded = vec![0u8; decrypter.decrypt_len(&encoded).unwrap()];
+        assert!(decrypter.decrypt(&encoded, &mut decoded).is_err());
+
+        let mut decrypter = Decrypter::new(&pkey).unwrap();
+        decrypter.set_rsa_padding(Padding::PKCS1_OAEP).unwrap();
+        decrypter.set_rsa_oaep_md(MessageDigest::sha256()).unwrap();
+        decrypter.set_rsa_mgf1_md(MessageDigest::sha256()).unwrap();
+        let buffer_len = decrypter.decrypt_len(&encoded).unwrap();
+        let mut decoded = vec![0u8; buffer_len];
+        let decoded_len = decrypter.decrypt(&encoded, &mut decoded).unwrap();
+        let decoded = &decoded[..decoded_len];
+
+        assert_eq!(decoded, &*input);
+    }
+
+    #[test]
+    fn rsa_oaep_interop_with_rsa() {
+        let key = include_bytes!("../test/rsa.pem");
+        let private_key = Rsa::private_key_from_pem(key).unwrap();
+        let input = Vec::from_hex(INPUT).unwrap();
+
+        let mut encoded = vec![0u8; private_key.size() as usize];
+        let encoded_len = private_key
+            .public_encrypt(&input, &mut encoded, Padding::PKCS1_OAEP)
+            .unwrap();
+        let encoded = &encoded[..encoded_len];
+
+        let pkey = PKey::from_rsa(private_key).unwrap();
+        let mut decrypter = Decrypter::new(&pkey).unwrap();
+        decrypter.set_rsa_padding(Padding::PKCS1_OAEP).unwrap();
+        let mut decoded = vec![0u8; decrypter.decrypt_len(&encoded).unwrap()];
+        let decoded_len = decrypter.decrypt(&encoded, &mut decoded).unwrap();
+
+        assert_eq!(&decoded[..decoded_len], &*input);
+    }
+}
@@ -0,0 +1,368 @@
+//! Envelope encryption.
+//!
+//! Data is encrypted with a randomly generated symmetric key, which is in turn encrypted with the
+//! public key of each recipient. A recipient decrypts the symmetric key with their private key and
+//! uses it to decrypt the data.
+//!
+//! # Example
+//!
+//! ```rust
+//! use openssl::envelope::{Open, Seal};
+//! use openssl::pkey::PKey;
+//! use openssl::rsa::Rsa;
+//! use openssl::symm::Cipher;
+//!
+//! let rsa = Rsa::generate(2048).unwrap();
+//! let key = PKey::from_rsa(rsa).unwrap();
+//! let public_key = PKey::public_key_from_pem(&key.public_key_to_pem().unwrap()).unwrap();
+//!
+//! let cipher = Cipher::aes_256_cbc();
+//! let mut seal = Seal::new(cipher, &[public_key]).unwrap();
+//!
+//! let secret = b"My secret message";
+//! let mut encrypted = vec![0; secret.len() + cipher.block_size()];
+//! let mut enc_len = seal.update(secret, &mut encrypted).unwrap();
+//! enc_len += seal.finalize(&mut encrypted[enc_len..]).unwrap();
+//! encrypted.truncate(enc_len);
+//!
+//! let mut open = Open::new(cipher, &key, seal.iv(), &seal.encrypted_keys()[0]).unwrap();
+//! let mut decrypted = vec![0; enc_len + cipher.block_size()];
+//! let mut dec_len = open.update(&encrypted, &mut decrypted).unwrap();
+//! dec_len += open.finalize(&mut decrypted[dec_len..]).unwrap();
+//! decrypted.truncate(dec_len);
+//!
+//! assert_eq!(&decrypted[..], &secret[..]);
+//! ```
+use ffi;
+use foreign_types::ForeignTypeRef;
+use libc::c_int;
+use std::cmp;
+use std::ptr;
+
+use {cvt, cvt_p};
+use error::ErrorStack;
+use pkey::{HasPrivate, HasPublic, PKey, PKeyRef};
+use symm::Cipher;
+
+/// Represents an EVP_Seal context.
+pub struct Seal {
+    ctx: *mut ffi::EVP_CIPHER_CTX,
+    block_size: usize,
+    iv: Option<Vec<u8>>,
+    enc_keys: Vec<Vec<u8>>,
+}
+
+unsafe impl Sync for Seal {}
+unsafe impl Send for Seal {}
+
+impl Seal {
+    /// Creates a new `Seal`, generating a symmetric key encrypted to each of `pub_keys`.
+    ///
+    /// This corresponds to [`EVP_SealInit`].
+    ///
+    /// [`EVP_SealInit`]: https://www.openssl.org/docs/man1.1.0/crypto/EVP_SealInit.html
+    pub fn new<T>(cipher: Cipher, pub_keys: &[PKey<T>]) -> Result<Seal, ErrorStack>
+    where
+        T: HasPublic,
+    {
+        ffi::init();
+
+        unsafe {
+            assert!(pub_keys.len() <= c_int::max_value() as usize);
+
+            let ctx = cvt_p(ffi::EVP_CIPHER_CTX_new())?;
+            let mut seal = Seal {
+                ctx: ctx,
+                block_size: cipher.block_size(),
+                iv: cipher.iv_len().map(|len| vec![0; len]),
+                enc_keys: Vec::with_capacity(pub_keys.len()),
+            };
+
+            let mut pkeys = Vec::with_capacity(pub_keys.len());
+            let mut ek_ptrs = Vec::with_capacity(pub_keys.len());
+            for key in pub_keys {
+                let mut buf = vec![0; ffi::EVP_PKEY_size(key.as_ptr()) as usize];
+                ek_ptrs.push(buf.as_mut_ptr());
+                seal.enc_keys.push(buf);
+                pkeys.push(key.as_ptr());
+            }
+            let mut ek_lens = vec![0; pub_keys.len()];
+
+            let iv = seal
+                .iv
+                .as_mut()
+                .map_or(ptr::null_mut(), |iv| iv.as_mut_ptr());
+            cvt(ffi::EVP_SealInit(
+                seal.ctx,
+                cipher.as_ptr(),
+                ek_ptrs.as_mut_ptr(),
+                ek_lens.as_mut_ptr(),
+                iv,
+                pkeys.as_mut_ptr(),
+                pkeys.len() as c_int,
+            ))?;
+
+            for (buf, len) in seal.enc_keys.iter_mut().zip(ek_lens) {
+                buf.truncate(len as usize);
+            }
+
+            Ok(seal)
+        }
+    }
+
+    /// Returns the initialization vector, if the cipher uses one.
+    pub fn iv(&self) -> Option<&[u8]> {
+        self.iv.as_ref().map(|iv| &**iv)
+    }
+
+    /// Returns the encrypted symmetric keys, in the same order as the public keys passed to `new`.
+    pub fn encrypted_keys(&self) -> &[Vec<u8>] {
+        &self.enc_keys
+    }
+
+    /// Feeds data from `input` through the cipher, writing encrypted bytes into `output`.
+    ///
+    /// The number of bytes written to `output` is returned. Note that this may
+    /// not be equal to the length of `input`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output.len() < input.len() + block_size` where `block_size` is
+    /// the block size of the cipher (see `Cipher::block_size`), or if
+    /// `output.len() > c_int::max_value()`.
+    pub fn update(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, ErrorStack> {
+        unsafe {
+            assert!(output.len() >= input.len() + self.block_size);
+            assert!(output.len() <= c_int::max_value() as usize);
+            let mut outl = output.len() as c_int;
+            let inl = input.len() as c_int;
+
+            cvt(ffi::EVP_CipherUpdate(
+                self.ctx,
+                output.as_mut_ptr(),
+                &mut outl,
+                input.as_ptr(),
+                inl,
+            ))?;
+
+            Ok(outl as usize)
+        }
+    }
+
+    /// Finishes the encryption process, writing any remaining data to `output`.
+    ///
+    /// The number of bytes written to `output` is returned.
+    ///
+    /// `update` should not be called after this method.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output` is less than the cipher's block size.
+    ///
+    /// This corresponds to [`EVP_SealFinal`].
+    ///
+    /// [`EVP_SealFinal`]: https://www.openssl.org/docs/man1.1.0/crypto/EVP_SealFinal.html
+    pub fn finalize(&mut self, output: &mut [u8]) -> Result<usize, ErrorStack> {
+        unsafe {
+            assert!(output.len() >= self.block_size);
+            let mut outl = cmp::min(output.len(), c_int::max_value() as usize) as c_int;
+
+            cvt(ffi::EVP_SealFinal(self.ctx, output.as_mut_ptr(), &mut outl))?;
+
+            Ok(outl as usize)
+        }
+    }
+}
+
+impl Drop for Seal {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::EVP_CIPHER_CTX_free(self.ctx);
+        }
+    }
+}
+
+/// Represents an EVP_Open context.
+pub struct Open {
+    ctx: *mut ffi::EVP_CIPHER_CTX,
+    block_size: usize,
+}
+
+unsafe impl Sync for Open {}
+unsafe impl Send for Open {}
+
+impl Open {
+    /// Creates a new `Open`, decrypting `encrypted_key` with `priv_key`.
+    ///
+    /// `iv` must be the initialization vector produced by the `Seal`, if the cipher uses one. An
+    /// error is returned if `iv` is missing, has the wrong length, or is provided for a cipher
+    /// which does not use one.
+    ///
+    /// This corresponds to [`EVP_OpenInit`].
+    ///
+    /// [`EVP_OpenInit`]: https://www.openssl.org/docs/man1.1.0/crypto/EVP_OpenInit.html
+    pub fn new<T>(
+        cipher: Cipher,
+        priv_key: &PKeyRef<T>,
+        iv: Option<&[u8]>,
+        encrypted_key: &[u8],
+    ) -> Result<Open, ErrorStack>
+    where
+        T: HasPrivate,
+    {
+        ffi::init();
+
+        unsafe {
+            assert!(encrypted_key.len() <= c_int::max_value() as usize);
+            let iv_error = match (cipher.iv_len(), iv) {
+                (Some(len), Some(iv)) if len != iv.len() => Some("IV length mismatch"),
+                (Some(_), Some(_)) | (None, None) => None,
+                (Some(_), None) => Some("an IV is required for this cipher"),
+                (None, Some(_)) => Some("an IV is not used by this cipher"),
+            };
+            if let Some(e) = iv_error {
+                return Err(ErrorStack::from_data(e));
+            }
+
+            let ctx = cvt_p(ffi::EVP_CIPHER_CTX_new())?;
+            let open = Open {
+                ctx: ctx,
+                block_size: cipher.block_size(),
+            };
+
+            let iv = iv.map_or(ptr::null(), |iv| iv.as_ptr());
+            // EVP_OpenInit returns the length of the decrypted key, and 0 on error
+            cvt(ffi::EVP_OpenInit(
+                open.ctx,
+                cipher.as_ptr(),
+                encrypted_key.as_ptr(),
+                encrypted_key.len() as c_int,
+                iv,
+                priv_key.as_ptr(),
+            ))?;
+
+            Ok(open)
+        }
+    }
+
+    /// Feeds data from `input` through the cipher, writing decrypted bytes into `output`.
+    ///
+    /// The number of bytes written to `output` is returned. Note that this may
+    /// not be equal to the length of `input`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output.len() < input.len() + block_size` where
+    /// `block_size` is the block size of the cipher (see `Cipher::block_size`),
+    /// or if `output.len() > c_int::max_value()`.
+    pub fn update(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, ErrorStack> {
+        unsafe {
+            assert!(output.len() >= input.len() + self.block_size);
+            assert!(output.len() <= c_int::max_value() as usize);
+            let mut outl = output.len() as c_int;
+            let inl = input.len() as c_int;
+
+            cvt(ffi::EVP_CipherUpdate(
+                self.ctx,
+                output.as_mut_ptr(),
+                &mut outl,
+                input.as_ptr(),
+                inl,
+            ))?;
+
+            Ok(outl as usize)
+        }
+    }
+
+    /// Finishes the decryption process, writing any remaining data to `output`.
+    ///
+    /// The number of bytes written to `output` is returned.
+    ///
+    /// `update` should not be called after this method.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output` is less than the cipher's block size.
+    ///
+    /// This corresponds to [`EVP_OpenFinal`].
+    ///
+    /// [`EVP_OpenFinal`]: https://www.openssl.org/docs/man1.1.0/crypto/EVP_OpenFinal.html
+    pub fn finalize(&mut self, output: &mut [u8]) -> Result<usize, ErrorStack> {
+        unsafe {
+            assert!(output.len() >= self.block_size);
+            let mut outl = cmp::min(output.len(), c_int::max_value() as usize) as c_int;
+
+            cvt(ffi::EVP_OpenFinal(self.ctx, output.as_mut_ptr(), &mut outl))?;
+
+            Ok(outl as usize)
+        }
+    }
+}
+
+impl Drop for Open {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::EVP_CIPHER_CTX_free(self.ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pkey::PKey;
+    use rsa::Rsa;
+
+    fn seal_and_open(cipher: Cipher) {
+        let key1 = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let key2 = PKey::from_rsa(Rsa::generate(1024).unwrap()).unwrap();
+        let public1 = PKey::public_key_from_pem(&key1.public_key_to_pem().unwrap()).unwrap();
+        let public2 = PKey::public_key_from_pem(&key2.public_key_to_pem().unwrap()).unwrap();
+
+        let mut seal = Seal::new(cipher, &[public1, public2]).unwrap();
+        assert_eq!(seal.encrypted_keys().len(), 2);
+        assert_eq!(seal.encrypted_keys()[0].len(), 256);
+        assert_eq!(seal.encrypted_keys()[1].len(), 128);
+
+        let secret = b"My secret message";
+        let mut encrypted = vec![0; secret.len() + cipher.block_size()];
+        let mut enc_len = seal.update(secret, &mut encrypted).unwrap();
+        enc_len += seal.finalize(&mut encrypted[enc_len..]).unwrap();
+        encrypted.truncate(enc_len);
+
+        for (key, ek) in [key1, key2].iter().zip(seal.encrypted_keys()) {
+            let mut open = Open::new(cipher, key, seal.iv(), ek).unwrap();
+            let mut decrypted = vec![0; enc_len + cipher.block_size()];
+            let mut dec_len = open.update(&encrypted, &mut decrypted).unwrap();
+            dec_len += open.finalize(&mut decrypted[dec_len..]).unwrap();
+            decrypted.truncate(dec_len);
+            assert_eq!(&decrypted[..], &secret[..]);
+        }
+    }
+
+    #[test]
+    fn seal_open_cbc() {
+        seal_and_open(Cipher::aes_256_cbc());
+    }
+
+    #[test]
+    fn seal_open_ecb() {
+        seal_and_open(Cipher::aes_128_ecb());
+    }
+
+    #[test]
+    fn open_iv_mismatch() {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let public = PKey::public_key_from_pem(&key.public_key_to_pem().unwrap()).unwrap();
+
+        let cbc = Cipher::aes_256_cbc();
+        let seal = Seal::new(cbc, &[public]).unwrap();
+        let ek = &seal.encrypted_keys()[0];
+        let iv = seal.iv().unwrap();
+
+        assert!(Open::new(cbc, &key, Some(&iv[..8]), ek).is_err());
+        assert!(Open::new(cbc, &key, None, ek).is_err());
+        assert!(Open::new(Cipher::aes_256_ecb(), &key, Some(iv), ek).is_err());
+        assert!(Open::new(cbc, &key, Some(iv), ek).is_ok());
+    }
+}
@@ -48,6 +48,17 @@ impl ErrorStack {
             error.put();
         }
     }
+
+    // Creates a stack holding a single error for a failure detected by this crate rather than by
+    // OpenSSL, described by `data`.
+    pub(crate) fn from_data(data: &str) -> ErrorStack {
+        ErrorStack(vec![Error {
+            code: (ffi::ERR_LIB_USER as c_ulong) << 24,
+            file: concat!(file!(), "\0").as_ptr() as *const c_char,
+            line: line!() as c_int,
+            data: Some(Cow::Owned(data.to_string())),
+        }])
+    }
 }
 
 impl ErrorStack {
@@ -55,6 +66,20 @@ impl ErrorStack {
     pub fn errors(&self) -> &[Error] {
         &self.0
     }
+
+    /// Returns a classification of the errors in the stack.
+    ///
+    /// OpenSSL pushes errors from the innermost failure outwards, so the most recently pushed error
+    /// with a known classification is used, as it typically carries the most context. If no error
+    /// in the stack can be classified, `ErrorKind::Other` is returned.
+    pub fn kind(&self) -> ErrorKind {
+        self.0
+            .iter()
+            .rev()
+            .map(Error::kind)
+            .find(|kind| *kind != ErrorKind::Other)
+            .unwrap_or(ErrorKind::Other)
+    }
 }
 
 impl fmt::Display for ErrorStack {
@@ -93,6 +118,36 @@ impl From<ErrorStack> for fmt::Error {
     }
 }
 
+/// A classification of OpenSSL errors.
+///
+/// This is derived from the library and reason codes of an error rather than its description,
+/// which can vary between OpenSSL versions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The peer reported that a certificate has expired.
+    CertificateExpired,
+    /// Verification of the peer's certificate chain failed.
+    CertificateVerifyFailed,
+    /// The peer did not recognize the certificate authority of a certificate.
+    UnknownCa,
+    /// The peer rejected a certificate.
+    BadCertificate,
+    /// The peer was unable to negotiate an acceptable set of security parameters.
+    HandshakeFailure,
+    /// Decryption failed, typically due to an incorrect key or corrupted data.
+    BadDecrypt,
+    /// A password-protected structure could not be decrypted with the supplied password.
+    WrongPassword,
+    /// An algorithm, cipher, or digest is not supported.
+    UnsupportedAlgorithm,
+    /// The input did not contain PEM-encoded data.
+    NoPemData,
+    /// Any other error.
+    Other,
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
 /// An error reported from OpenSSL.
 #[derive(Clone)]
 pub struct Error {
@@ -182,6 +237,34 @@ impl Error {
         self.code
     }
 
+    /// Returns a classification of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match (ffi::ERR_GET_LIB(self.code), ffi::ERR_GET_REASON(self.code)) {
+            (ffi::ERR_LIB_SSL, ffi::SSL_R_SSLV3_ALERT_CERTIFICATE_EXPIRED) => {
+                ErrorKind::CertificateExpired
+            }
+            (ffi::ERR_LIB_SSL, ffi::SSL_R_CERTIFICATE_VERIFY_FAILED) => {
+                ErrorKind::CertificateVerifyFailed
+            }
+            (ffi::ERR_LIB_SSL, ffi::SSL_R_TLSV1_ALERT_UNKNOWN_CA) => ErrorKind::UnknownCa,
+            (ffi::ERR_LIB_SSL, ffi::SSL_R_SSLV3_ALERT_BAD_CERTIFICATE) => ErrorKind::BadCertificate,
+            (ffi::ERR_LIB_SSL, ffi::SSL_R_SSLV3_ALERT_HANDSHAKE_FAILURE) => {
+                ErrorKind::HandshakeFailure
+            }
+            (ffi::ERR_LIB_EVP, ffi::EVP_R_BAD_DECRYPT) => ErrorKind::BadDecrypt,
+            (ffi::ERR_LIB_PEM, ffi::PEM_R_BAD_DECRYPT)
+            | (ffi::ERR_LIB_PEM, ffi::PEM_R_BAD_PASSWORD_READ)
+            | (ffi::ERR_LIB_PKCS12, ffi::PKCS12_R_MAC_VERIFY_FAILURE) => ErrorKind::WrongPassword,
+            (ffi::ERR_LIB_EVP, ffi::EVP_R_UNSUPPORTED_ALGORITHM)
+            | (ffi::ERR_LIB_EVP, ffi::EVP_R_UNSUPPORTED_CIPHER)
+            | (ffi::ERR_LIB_EVP, ffi::EVP_R_UNSUPPORTED_PRIVATE_KEY_ALGORITHM)
+            | (ffi::ERR_LIB_EVP, ffi::EVP_R_UNKNOWN_CIPHER)
+            | (ffi::ERR_LIB_EVP, ffi::EVP_R_UNKNOWN_DIGEST) => ErrorKind::UnsupportedAlgorithm,
+            (ffi::ERR_LIB_PEM, ffi::PEM_R_NO_START_LINE) => ErrorKind::NoPemData,
+            _ => ErrorKind::Other,
+        }
+    }
+
     /// Returns the name of the library reporting the error, if available.
     pub fn library(&self) -> Option<&'static str> {
         unsafe {
@@ -290,3 +373,52 @@ impl error::Error for Error {
         "an OpenSSL error"
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pkcs12::Pkcs12;
+    use pkey::PKey;
+    use symm::{self, Cipher};
+    use x509::X509;
+
+    #[test]
+    fn no_pem_data() {
+        let err = X509::from_pem(b"not a certificate").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NoPemData);
+    }
+
+    #[test]
+    fn bad_decrypt() {
+        // the last byte of the decrypted block is 0x3a, which is not valid padding
+        let err =
+            symm::decrypt(Cipher::aes_128_cbc(), &[0; 16], Some(&[0; 16]), &[0; 16]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::BadDecrypt);
+    }
+
+    #[test]
+    fn wrong_password() {
+        let der = include_bytes!("../test/identity.p12");
+        let pkcs12 = Pkcs12::from_der(der).unwrap();
+        let err = pkcs12.parse("wrongpass").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WrongPassword);
+    }
+
+    #[test]
+    fn unsupported_algorithm() {
+        // a PKCS#8 structure with the unknown key algorithm 1.2.3.4
+        let der = [
+            0x30, 0x0d, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2a, 0x03, 0x04, 0x04, 0x01,
+            0x00,
+        ];
+        let err = PKey::private_key_from_pkcs8(&der).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnsupportedAlgorithm);
+    }
+
+    #[test]
+    fn other() {
+        let err = ErrorStack::from_data("some error");
+        assert_eq!(err.kind(), ErrorKind::Other);
+        assert_eq!(err.errors()[0].kind(), ErrorKind::Other);
+    }
+}
@@ -2,6 +2,10 @@ use std::io::prelude::*;
 use std::io;
 use std::ops::{Deref, DerefMut};
 use std::fmt;
+use std::cmp;
+use std::panic;
+use std::slice;
+use std::thread::{self, JoinHandle};
 use ffi;
 
 #[cfg(ossl110)]
@@ -11,6 +15,7 @@ use ffi::{EVP_MD_CTX_create as EVP_MD_CTX_new, EVP_MD_CTX_destroy as EVP_MD_CTX_
 
 use {cvt, cvt_p};
 use error::ErrorStack;
+use nid::Nid;
 
 #[derive(Copy, Clone)]
 pub struct MessageDigest(*const ffi::EVP_MD);
@@ -18,6 +23,23 @@ pub struct MessageDigest(*const ffi::EVP_MD);
 impl MessageDigest {
     pub unsafe fn from_ptr(x: *const ffi::EVP_MD) -> Self { MessageDigest(x) }
 
+    /// Looks up the digest for a certain nid.
+    ///
+    /// This can be used to access digests which are provided by an engine, such as GOST R 34.11.
+    ///
+    /// This corresponds to [`EVP_get_digestbyname`] with the short name of the nid, as
+    /// `EVP_get_digestbynid` is a macro.
+    ///
+    /// [`EVP_get_digestbyname`]: https://www.openssl.org/docs/man1.0.2/crypto/EVP_get_digestbyname.html
+    pub fn from_nid(nid: Nid) -> Option<MessageDigest> {
+        let ptr = unsafe { ffi::EVP_get_digestbyname(ffi::OBJ_nid2sn(nid.as_raw())) };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(MessageDigest(ptr))
+        }
+    }
+
     pub fn md5() -> MessageDigest {
         unsafe { MessageDigest(ffi::EVP_md5()) }
     }
@@ -46,6 +68,56 @@ impl MessageDigest {
         unsafe { MessageDigest(ffi::EVP_ripemd160()) }
     }
 
+    /// Requires OpenSSL 1.1.1 or newer, built with SM3 support.
+    #[cfg(all(ossl111, not(osslconf = "OPENSSL_NO_SM3")))]
+    pub fn sm3() -> MessageDigest {
+        unsafe { MessageDigest(ffi::EVP_sm3()) }
+    }
+
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub fn sha3_224() -> MessageDigest {
+        unsafe { MessageDigest(ffi::EVP_sha3_224()) }
+    }
+
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub fn sha3_256() -> MessageDigest {
+        unsafe { MessageDigest(ffi::EVP_sha3_256()) }
+    }
+
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub fn sha3_384() -> MessageDigest {
+        unsafe { MessageDigest(ffi::EVP_sha3_384()) }
+    }
+
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub fn sha3_512() -> MessageDigest {
+        unsafe { MessageDigest(ffi::EVP_sha3_512()) }
+    }
+
+    /// An extendable-output function.
+    ///
+    /// Use `Hasher::finish_xof` or `hash_xof` to produce output of an arbitrary length.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub fn shake_128() -> MessageDigest {
+        unsafe { MessageDigest(ffi::EVP_shake128()) }
+    }
+
+    /// An extendable-output function.
+    ///
+    /// Use `Hasher::finish_xof` or `hash_xof` to produce output of an arbitrary length.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub fn shake_256() -> MessageDigest {
+        unsafe { MessageDigest(ffi::EVP_shake256()) }
+    }
+
     pub fn as_ptr(&self) -> *const ffi::EVP_MD {
         self.0
     }
@@ -180,6 +252,32 @@ impl Hasher {
             })
         }
     }
+
+    /// Writes the hash of the data written into `buf` and resets the hasher.
+    ///
+    /// The digest must be an extendable-output function such as SHAKE128, in which case `buf` may
+    /// be of any length.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`EVP_DigestFinalXOF`].
+    ///
+    /// [`EVP_DigestFinalXOF`]: https://www.openssl.org/docs/man1.1.1/man3/EVP_DigestFinalXOF.html
+    #[cfg(ossl111)]
+    pub fn finish_xof(&mut self, buf: &mut [u8]) -> Result<(), ErrorStack> {
+        if self.state == Finalized {
+            self.init()?;
+        }
+        unsafe {
+            cvt(ffi::EVP_DigestFinalXOF(
+                self.ctx,
+                buf.as_mut_ptr(),
+                buf.len(),
+            ))?;
+            self.state = Finalized;
+            Ok(())
+        }
+    }
 }
 
 impl Write for Hasher {
@@ -229,8 +327,8 @@ impl Drop for Hasher {
 /// store the digest data.
 #[derive(Copy)]
 pub struct DigestBytes {
-    buf: [u8; ffi::EVP_MAX_MD_SIZE as usize],
-    len: usize,
+    pub(crate) buf: [u8; ffi::EVP_MAX_MD_SIZE as usize],
+    pub(crate) len: usize,
 }
 
 impl Clone for DigestBytes {
@@ -276,10 +374,138 @@ pub fn hash(t: MessageDigest, data: &[u8]) -> Result<DigestBytes, ErrorStack> {
     h.finish()
 }
 
+/// Computes the hash of the `data` with the extendable-output function `t`, filling `buf`.
+///
+/// Requires OpenSSL 1.1.1 or newer.
+#[cfg(ossl111)]
+pub fn hash_xof(t: MessageDigest, data: &[u8], buf: &mut [u8]) -> Result<(), ErrorStack> {
+    let mut h = Hasher::new(t)?;
+    h.update(data)?;
+    h.finish_xof(buf)
+}
+
+/// Computes the hash of each of the buffers in `data` with the hash `t`, returning the digests in
+/// the same order as the buffers.
+///
+/// The buffers are split into up to `threads` contiguous chunks which are hashed concurrently, one
+/// hashing context per thread. One chunk is hashed on the calling thread, so a `threads` of 0 or 1
+/// hashes everything on the calling thread without spawning. If a worker thread cannot be spawned,
+/// its chunk is hashed on the calling thread instead.
+///
+/// # Panics
+///
+/// Panics if the `AsRef` implementation of `T` panics on a worker thread.
+pub fn hash_each<T>(
+    t: MessageDigest,
+    data: &[T],
+    threads: usize,
+) -> Result<Vec<DigestBytes>, ErrorStack>
+where
+    T: AsRef<[u8]> + Sync,
+{
+    let threads = cmp::max(1, cmp::min(threads, data.len()));
+    if threads == 1 {
+        return hash_chunk(t, data);
+    }
+
+    let chunk_len = (data.len() + threads - 1) / threads;
+    let mut chunks = data.chunks(chunk_len);
+    let first = chunks.next().unwrap();
+
+    let mut workers = Vec::with_capacity(threads - 1);
+    for chunk in chunks {
+        let worker = match unsafe { spawn_chunk(t, chunk) } {
+            Ok(guard) => Worker::Spawned(guard),
+            Err(_) => Worker::Done(hash_chunk(t, chunk)),
+        };
+        workers.push(worker);
+    }
+
+    // returning early drops the remaining workers, which joins their threads before `data` can go
+    // out of scope
+    let mut digests = Vec::with_capacity(data.len());
+    digests.extend(hash_chunk(t, first)?);
+    for worker in workers {
+        let result = match worker {
+            Worker::Spawned(guard) => match guard.join() {
+                Ok(result) => result,
+                Err(e) => panic::resume_unwind(e),
+            },
+            Worker::Done(result) => result,
+        };
+        digests.extend(result?);
+    }
+    Ok(digests)
+}
+
+type ChunkResult = Result<Vec<DigestBytes>, ErrorStack>;
+
+fn hash_chunk<T>(t: MessageDigest, data: &[T]) -> ChunkResult
+where
+    T: AsRef<[u8]>,
+{
+    let mut digests = Vec::with_capacity(data.len());
+    let mut h = Hasher::new(t)?;
+    for buf in data {
+        h.update(buf.as_ref())?;
+        digests.push(h.finish()?);
+    }
+    Ok(digests)
+}
+
+unsafe fn hash_raw_chunk<T>(t: MessageDigest, ptr: usize, len: usize) -> ChunkResult
+where
+    T: AsRef<[u8]>,
+{
+    hash_chunk(t, slice::from_raw_parts(ptr as *const T, len))
+}
+
+// The thread borrows `data` without a `'static` bound, so the returned guard must be joined or
+// dropped before `data` goes out of scope. `T: Sync` makes sharing the slice with the thread safe.
+unsafe fn spawn_chunk<T>(t: MessageDigest, data: &[T]) -> io::Result<JoinGuard>
+where
+    T: AsRef<[u8]> + Sync,
+{
+    spawn_raw_chunk(t, data.as_ptr() as usize, data.len(), hash_raw_chunk::<T>)
+}
+
+fn spawn_raw_chunk(
+    t: MessageDigest,
+    ptr: usize,
+    len: usize,
+    f: unsafe fn(MessageDigest, usize, usize) -> ChunkResult,
+) -> io::Result<JoinGuard> {
+    thread::Builder::new()
+        .spawn(move || unsafe { f(t, ptr, len) })
+        .map(|handle| JoinGuard(Some(handle)))
+}
+
+enum Worker {
+    Spawned(JoinGuard),
+    Done(ChunkResult),
+}
+
+struct JoinGuard(Option<JoinHandle<ChunkResult>>);
+
+impl JoinGuard {
+    fn join(mut self) -> thread::Result<ChunkResult> {
+        self.0.take().unwrap().join()
+    }
+}
+
+impl Drop for JoinGuard {
+    fn drop(&mut self) {
+        if let Some(handle) = self.0.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use hex::{self, FromHex};
     use std::io::prelude::*;
+    use std::iter;
 
     use super::*;
 
@@ -380,6 +606,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_hash_each() {
+        let data = (0..100)
+            .map(|i| iter::repeat(i as u8).take(i * 37).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        for &threads in &[0, 1, 3, 8, 100, 1000] {
+            let digests = hash_each(MessageDigest::sha256(), &data, threads).unwrap();
+            assert_eq!(digests.len(), data.len());
+            for (digest, input) in digests.iter().zip(data.iter()) {
+                assert_eq!(&**digest, &*hash(MessageDigest::sha256(), input).unwrap());
+            }
+        }
+
+        let empty: Vec<Vec<u8>> = vec![];
+        assert!(hash_each(MessageDigest::sha256(), &empty, 4).unwrap().is_empty());
+    }
+
     #[test]
     fn test_sha256() {
         let tests = [
@@ -402,4 +646,71 @@ mod tests {
             hash_test(MessageDigest::ripemd160(), test);
         }
     }
+
+    #[test]
+    #[cfg(all(ossl111, not(osslconf = "OPENSSL_NO_SM3")))]
+    fn test_sm3() {
+        let tests = [(
+            "616263",
+            "66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0",
+        )];
+
+        for test in tests.iter() {
+            hash_test(MessageDigest::sm3(), test);
+        }
+    }
+
+    #[test]
+    #[cfg(ossl111)]
+    fn test_sha3() {
+        let tests = [(
+            "616263",
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+        )];
+        for test in tests.iter() {
+            hash_test(MessageDigest::sha3_256(), test);
+        }
+
+        let tests = [(
+            "616263",
+            "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e\
+             10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+        )];
+        for test in tests.iter() {
+            hash_test(MessageDigest::sha3_512(), test);
+        }
+    }
+
+    #[test]
+    #[cfg(ossl111)]
+    fn test_shake() {
+        let mut buf = [0; 32];
+        hash_xof(MessageDigest::shake_128(), &[], &mut buf).unwrap();
+        assert_eq!(
+            hex::encode(&buf[..]),
+            "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"
+        );
+
+        let mut buf = [0; 64];
+        let mut h = Hasher::new(MessageDigest::shake_256()).unwrap();
+        h.finish_xof(&mut buf).unwrap();
+        assert_eq!(
+            hex::encode(&buf[..]),
+            "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f\
+             d75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be"
+        );
+
+        // the hasher is reset, and shorter output is a prefix of longer output
+        let mut short = [0; 16];
+        h.finish_xof(&mut short).unwrap();
+        assert_eq!(&short[..], &buf[..16]);
+    }
+
+    #[test]
+    fn from_nid() {
+        assert_eq!(
+            MessageDigest::from_nid(Nid::SHA256).unwrap().as_ptr(),
+            MessageDigest::sha256().as_ptr()
+        );
+    }
 }
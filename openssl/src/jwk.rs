@@ -0,0 +1,394 @@
+//! Raw key components in the form used by JSON Web Keys.
+//!
+//! [RFC 7518] represents the components of RSA and elliptic curve keys as big-endian unsigned
+//! integers. RSA components use the minimum number of bytes, while the coordinates and private
+//! key of an elliptic curve key are padded to the size of the curve. This module converts keys to
+//! and from those representations. The components must still be base64url encoded to form a JWK,
+//! which can be done with [`base64::encode_block_url`].
+//!
+//! # Examples
+//!
+//! ```
+//! use openssl::base64;
+//! use openssl::ec::{EcGroup, EcKey};
+//! use openssl::jwk::EcComponents;
+//! use openssl::nid::Nid;
+//!
+//! let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+//! let key = EcKey::generate(&group).unwrap();
+//!
+//! let components = EcComponents::from_private_key(&key).unwrap();
+//! assert_eq!(components.curve_name(), Some("P-256"));
+//! assert_eq!(base64::encode_block_url(&components.x).len(), 43);
+//!
+//! let decoded = components.to_private_key().unwrap();
+//! assert_eq!(decoded.private_key(), key.private_key());
+//! ```
+//!
+//! [RFC 7518]: https://tools.ietf.org/html/rfc7518#section-6
+//! [`base64::encode_block_url`]: ../base64/fn.encode_block_url.html
+use bn::{BigNum, BigNumContext, BigNumRef};
+use ec::{EcGroup, EcKey, EcKeyRef};
+use error::ErrorStack;
+use nid::Nid;
+use pkey::{HasPrivate, HasPublic, Id, PKey, PKeyRef, Private, Public};
+use rsa::{Rsa, RsaPrivateKeyBuilder, RsaRef};
+
+// the curves registered for use in JWKs, and their names
+const CURVES: &'static [(Nid, &'static str)] = &[
+    (Nid::X9_62_PRIME256V1, "P-256"),
+    (Nid::SECP384R1, "P-384"),
+    (Nid::SECP521R1, "P-521"),
+];
+
+/// The components of an RSA key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RsaComponents {
+    /// The modulus.
+    pub n: Vec<u8>,
+    /// The public exponent.
+    pub e: Vec<u8>,
+    /// The private exponent.
+    pub d: Option<Vec<u8>>,
+    /// The first prime factor.
+    pub p: Option<Vec<u8>>,
+    /// The second prime factor.
+    pub q: Option<Vec<u8>>,
+    /// The first factor CRT exponent.
+    pub dp: Option<Vec<u8>>,
+    /// The second factor CRT exponent.
+    pub dq: Option<Vec<u8>>,
+    /// The first CRT coefficient.
+    pub qi: Option<Vec<u8>>,
+}
+
+impl RsaComponents {
+    /// Returns the public components of a key.
+    pub fn from_public_key<T>(rsa: &RsaRef<T>) -> RsaComponents
+    where
+        T: HasPublic,
+    {
+        RsaComponents {
+            n: rsa.n().to_vec(),
+            e: rsa.e().to_vec(),
+            d: None,
+            p: None,
+            q: None,
+            dp: None,
+            dq: None,
+            qi: None,
+        }
+    }
+
+    /// Returns the public and private components of a key.
+    ///
+    /// The prime factors and CRT parameters are omitted if the key does not contain them.
+    pub fn from_private_key<T>(rsa: &RsaRef<T>) -> RsaComponents
+    where
+        T: HasPrivate,
+    {
+        let mut components = RsaComponents::from_public_key(rsa);
+        components.d = Some(rsa.d().to_vec());
+        if let (Some(p), Some(q)) = (rsa.p(), rsa.q()) {
+            components.p = Some(p.to_vec());
+            components.q = Some(q.to_vec());
+            if let (Some(dp), Some(dq), Some(qi)) = (rsa.dmp1(), rsa.dmq1(), rsa.iqmp()) {
+                components.dp = Some(dp.to_vec());
+                components.dq = Some(dq.to_vec());
+                components.qi = Some(qi.to_vec());
+            }
+        }
+        components
+    }
+
+    /// Constructs a public key from the components.
+    pub fn to_public_key(&self) -> Result<Rsa<Public>, ErrorStack> {
+        Rsa::from_public_components(BigNum::from_slice(&self.n)?, BigNum::from_slice(&self.e)?)
+    }
+
+    /// Constructs a private key from the components.
+    ///
+    /// The prime factors are optional, but if present the CRT parameters may be as well.
+    ///
+    /// An error is returned if `d` is `None`, if only one of the prime factors is present, or if
+    /// some but not all of the CRT parameters are present.
+    pub fn to_private_key(&self) -> Result<Rsa<Private>, ErrorStack> {
+        let d = match self.d {
+            Some(ref d) => d,
+            None => return Err(ErrorStack::from_data("the private exponent is missing")),
+        };
+        let mut builder = RsaPrivateKeyBuilder::new(
+            BigNum::from_slice(&self.n)?,
+            BigNum::from_slice(&self.e)?,
+            BigNum::from_slice(d)?,
+        )?;
+
+        match (&self.p, &self.q) {
+            (&Some(ref p), &Some(ref q)) => {
+                builder = builder.set_factors(BigNum::from_slice(p)?, BigNum::from_slice(q)?)?;
+            }
+            (&None, &None) => {}
+            _ => return Err(ErrorStack::from_data("only one prime factor is present")),
+        }
+
+        match (&self.dp, &self.dq, &self.qi) {
+            (&Some(ref dp), &Some(ref dq), &Some(ref qi)) => {
+                builder = builder.set_crt_params(
+                    BigNum::from_slice(dp)?,
+                    BigNum::from_slice(dq)?,
+                    BigNum::from_slice(qi)?,
+                )?;
+            }
+            (&None, &None, &None) => {}
+            _ => {
+                return Err(ErrorStack::from_data(
+                    "only some of the CRT parameters are present",
+                ))
+            }
+        }
+
+        Ok(builder.build())
+    }
+}
+
+/// The components of an elliptic curve key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcComponents {
+    /// The curve.
+    pub curve: Nid,
+    /// The x coordinate of the public point, padded to the size of the curve's field.
+    pub x: Vec<u8>,
+    /// The y coordinate of the public point, padded to the size of the curve's field.
+    pub y: Vec<u8>,
+    /// The private key, padded to the size of the curve's order.
+    pub d: Option<Vec<u8>>,
+}
+
+impl EcComponents {
+    /// Returns the public components of a key.
+    ///
+    /// An error is returned if the key uses explicit curve parameters rather than a named curve.
+    pub fn from_public_key<T>(key: &EcKeyRef<T>) -> Result<EcComponents, ErrorStack>
+    where
+        T: HasPublic,
+    {
+        let group = key.group();
+        let curve = match group.curve_name() {
+            Some(curve) => curve,
+            None => return Err(ErrorStack::from_data("the key does not use a named curve")),
+        };
+
+        let mut ctx = BigNumContext::new()?;
+        let mut x = BigNum::new()?;
+        let mut y = BigNum::new()?;
+        key.public_key()
+            .affine_coordinates_gfp(group, &mut x, &mut y, &mut ctx)?;
+
+        let len = (group.degree() as usize + 7) / 8;
+        Ok(EcComponents {
+            curve,
+            x: pad(&x, len),
+            y: pad(&y, len),
+            d: None,
+        })
+    }
+
+    /// Returns the public and private components of a key.
+    ///
+    /// An error is returned if the key uses explicit curve parameters rather than a named curve.
+    pub fn from_private_key<T>(key: &EcKeyRef<T>) -> Result<EcComponents, ErrorStack>
+    where
+        T: HasPrivate,
+    {
+        let mut components = EcComponents::from_public_key(key)?;
+
+        let mut ctx = BigNumContext::new()?;
+        let mut order = BigNum::new()?;
+        key.group().order(&mut order, &mut ctx)?;
+        components.d = Some(pad(key.private_key(), order.num_bytes() as usize));
+
+        Ok(components)
+    }
+
+    /// Returns the JWK name of the curve, if it has one.
+    pub fn curve_name(&self) -> Option<&'static str> {
+        CURVES
+            .iter()
+            .find(|&&(nid, _)| nid == self.curve)
+            .map(|&(_, name)| name)
+    }
+
+    /// Returns the curve with the given JWK name, if it is known.
+    pub fn curve_from_name(name: &str) -> Option<Nid> {
+        CURVES
+            .iter()
+            .find(|&&(_, n)| n == name)
+            .map(|&(nid, _)| nid)
+    }
+
+    /// Constructs a public key from the components.
+    ///
+    /// The public point is checked to be on the curve.
+    pub fn to_public_key(&self) -> Result<EcKey<Public>, ErrorStack> {
+        let group = EcGroup::from_curve_name(self.curve)?;
+        let x = BigNum::from_slice(&self.x)?;
+        let y = BigNum::from_slice(&self.y)?;
+        EcKey::from_public_key_affine_coordinates(&group, &x, &y)
+    }
+
+    /// Constructs a private key from the components.
+    ///
+    /// The key is checked to be valid, including that the public point corresponds to the
+    /// private key.
+    ///
+    /// An error is returned if `d` is `None`.
+    pub fn to_private_key(&self) -> Result<EcKey<Private>, ErrorStack> {
+        let d = match self.d {
+            Some(ref d) => BigNum::from_slice(d)?,
+            None => return Err(ErrorStack::from_data("the private key is missing")),
+        };
+        let public = self.to_public_key()?;
+        let key = EcKey::from_private_components(public.group(), &d, public.public_key())?;
+        key.check_key()?;
+        Ok(key)
+    }
+}
+
+/// The components of an RSA or elliptic curve key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyComponents {
+    /// The components of an RSA key.
+    Rsa(RsaComponents),
+    /// The components of an elliptic curve key.
+    Ec(EcComponents),
+}
+
+impl KeyComponents {
+    /// Returns the public components of a key.
+    ///
+    /// An error is returned if the key is neither an RSA nor an elliptic curve key, or if it is
+    /// an elliptic curve key using explicit curve parameters.
+    pub fn from_public_key<T>(key: &PKeyRef<T>) -> Result<KeyComponents, ErrorStack>
+    where
+        T: HasPublic,
+    {
+        if key.id() == Id::EC {
+            let ec_key = key.ec_key()?;
+            EcComponents::from_public_key(&ec_key).map(KeyComponents::Ec)
+        } else {
+            let rsa = key.rsa()?;
+            Ok(KeyComponents::Rsa(RsaComponents::from_public_key(&rsa)))
+        }
+    }
+
+    /// Returns the public and private components of a key.
+    ///
+    /// An error is returned if the key is neither an RSA nor an elliptic curve key, or if it is
+    /// an elliptic curve key using explicit curve parameters.
+    pub fn from_private_key<T>(key: &PKeyRef<T>) -> Result<KeyComponents, ErrorStack>
+    where
+        T: HasPrivate,
+    {
+        if key.id() == Id::EC {
+            let ec_key = key.ec_key()?;
+            EcComponents::from_private_key(&ec_key).map(KeyComponents::Ec)
+        } else {
+            let rsa = key.rsa()?;
+            Ok(KeyComponents::Rsa(RsaComponents::from_private_key(&rsa)))
+        }
+    }
+
+    /// Constructs a public key from the components.
+    pub fn to_public_key(&self) -> Result<PKey<Public>, ErrorStack> {
+        match *self {
+            KeyComponents::Rsa(ref rsa) => PKey::from_rsa(rsa.to_public_key()?),
+            KeyComponents::Ec(ref ec) => PKey::from_ec_key(ec.to_public_key()?),
+        }
+    }
+
+    /// Constructs a private key from the components.
+    ///
+    /// An error is returned under the same conditions as `RsaComponents::to_private_key` and
+    /// `EcComponents::to_private_key`.
+    pub fn to_private_key(&self) -> Result<PKey<Private>, ErrorStack> {
+        match *self {
+            KeyComponents::Rsa(ref rsa) => PKey::from_rsa(rsa.to_private_key()?),
+            KeyComponents::Ec(ref ec) => PKey::from_ec_key(ec.to_private_key()?),
+        }
+    }
+}
+
+// Encodes a non-negative number in big-endian form, left padded with zeros to `len` bytes.
+fn pad(n: &BigNumRef, len: usize) -> Vec<u8> {
+    let bytes = n.to_vec();
+    let mut out = vec![0; len.saturating_sub(bytes.len())];
+    out.extend_from_slice(&bytes);
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rsa() {
+        let key = Rsa::generate(2048).unwrap();
+        let components = RsaComponents::from_private_key(&key);
+        assert_eq!(components.e, [1, 0, 1]);
+        assert_eq!(components.n.len(), 256);
+
+        let decoded = components.to_private_key().unwrap();
+        assert_eq!(decoded.d(), key.d());
+        assert_eq!(decoded.iqmp(), key.iqmp());
+
+        let public = RsaComponents::from_public_key(&key);
+        assert_eq!(public.d, None);
+        let decoded = public.to_public_key().unwrap();
+        assert_eq!(decoded.n(), key.n());
+        assert!(public.to_private_key().is_err());
+
+        let mut partial = components.clone();
+        partial.q = None;
+        assert!(partial.to_private_key().is_err());
+    }
+
+    #[test]
+    fn ec_padding() {
+        let group = EcGroup::from_curve_name(Nid::SECP521R1).unwrap();
+        let key = EcKey::generate(&group).unwrap();
+        let components = EcComponents::from_private_key(&key).unwrap();
+        assert_eq!(components.curve_name(), Some("P-521"));
+        assert_eq!(components.x.len(), 66);
+        assert_eq!(components.y.len(), 66);
+        assert_eq!(components.d.as_ref().unwrap().len(), 66);
+
+        let decoded = components.to_private_key().unwrap();
+        assert_eq!(decoded.private_key(), key.private_key());
+
+        let mut other = components.clone();
+        other.d = Some(vec![1]);
+        assert!(other.to_private_key().is_err());
+
+        other.d = None;
+        assert!(other.to_private_key().is_err());
+        other.to_public_key().unwrap();
+
+        assert_eq!(EcComponents::curve_from_name("P-521"), Some(Nid::SECP521R1));
+        assert_eq!(EcComponents::curve_from_name("secp521r1"), None);
+    }
+
+    #[test]
+    fn pkey() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let components = KeyComponents::from_private_key(&key).unwrap();
+        assert!(components.to_private_key().unwrap().public_eq(&key));
+
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let components = KeyComponents::from_public_key(&key).unwrap();
+        assert!(components.to_public_key().unwrap().public_eq(&key));
+
+        let key = PKey::hmac(b"secret").unwrap();
+        assert!(KeyComponents::from_private_key(&key).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+//! Key derivation functions.
+//!
+//! This module provides HKDF, as specified in [RFC 5869]. Password-based key derivation functions
+//! such as PBKDF2 and scrypt live in the `pkcs5` module.
+//!
+//! Requires OpenSSL 1.1.0 or newer.
+//!
+//! # Example
+//!
+//! ```rust
+//! use openssl::hash::MessageDigest;
+//! use openssl::kdf::hkdf;
+//!
+//! let mut key = [0; 32];
+//! hkdf(MessageDigest::sha256(), b"input key material", b"salt", b"info", &mut key).unwrap();
+//! ```
+//!
+//! [RFC 5869]: https://tools.ietf.org/html/rfc5869
+use ffi;
+use libc::{c_int, c_void};
+use std::ptr;
+
+use {cvt, cvt_p};
+use error::ErrorStack;
+use hash::MessageDigest;
+
+/// Derives `out.len()` bytes from the input key material `key` with HKDF.
+///
+/// This performs both the extract and expand steps. An empty `salt` is equivalent to a salt of
+/// zeros the length of the digest output.
+///
+/// This corresponds to [`EVP_PKEY_derive`] with an `EVP_PKEY_HKDF` context.
+///
+/// [`EVP_PKEY_derive`]: https://www.openssl.org/docs/man1.1.0/crypto/EVP_PKEY_CTX_set_hkdf_md.html
+pub fn hkdf(
+    digest: MessageDigest,
+    key: &[u8],
+    salt: &[u8],
+    info: &[u8],
+    out: &mut [u8],
+) -> Result<(), ErrorStack> {
+    hkdf_derive(None, digest, key, salt, info, out)
+}
+
+/// Performs the HKDF extract step, returning a pseudorandom key the size of the digest output.
+///
+/// Requires OpenSSL 1.1.1 or newer.
+///
+/// This corresponds to [`EVP_PKEY_derive`] with the `EVP_PKEY_HKDEF_MODE_EXTRACT_ONLY` mode.
+///
+/// [`EVP_PKEY_derive`]: https://www.openssl.org/docs/man1.1.1/man3/EVP_PKEY_CTX_set_hkdf_mode.html
+#[cfg(ossl111)]
+pub fn hkdf_extract(digest: MessageDigest, key: &[u8], salt: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let mut prk = vec![0; digest.size()];
+    hkdf_derive(
+        Some(ffi::EVP_PKEY_HKDEF_MODE_EXTRACT_ONLY),
+        digest,
+        key,
+        salt,
+        &[],
+        &mut prk,
+    )?;
+    Ok(prk)
+}
+
+/// Performs the HKDF expand step, deriving `out.len()` bytes from the pseudorandom key `prk`.
+///
+/// Requires OpenSSL 1.1.1 or newer.
+///
+/// This corresponds to [`EVP_PKEY_derive`] with the `EVP_PKEY_HKDEF_MODE_EXPAND_ONLY` mode.
+///
+/// [`EVP_PKEY_derive`]: https://www.openssl.org/docs/man1.1.1/man3/EVP_PKEY_CTX_set_hkdf_mode.html
+#[cfg(ossl111)]
+pub fn hkdf_expand(
+    digest: MessageDigest,
+    prk: &[u8],
+    info: &[u8],
+    out: &mut [u8],
+) -> Result<(), ErrorStack> {
+    hkdf_derive(
+        Some(ffi::EVP_PKEY_HKDEF_MODE_EXPAND_ONLY),
+        digest,
+        prk,
+        &[],
+        info,
+        out,
+    )
+}
+
+unsafe fn hkdf_ctrl(
+    ctx: *mut ffi::EVP_PKEY_CTX,
+    cmd: c_int,
+    p1: c_int,
+    p2: *mut c_void,
+) -> Result<(), ErrorStack> {
+    cvt(ffi::EVP_PKEY_CTX_ctrl(
+        ctx,
+        -1,
+        ffi::EVP_PKEY_OP_DERIVE,
+        cmd,
+        p1,
+        p2,
+    )).map(|_| ())
+}
+
+fn hkdf_derive(
+    mode: Option<c_int>,
+    digest: MessageDigest,
+    key: &[u8],
+    salt: &[u8],
+    info: &[u8],
+    out: &mut [u8],
+) -> Result<(), ErrorStack> {
+    unsafe {
+        assert!(key.len() <= c_int::max_value() as usize);
+        assert!(salt.len() <= c_int::max_value() as usize);
+        assert!(info.len() <= c_int::max_value() as usize);
+
+        ffi::init();
+        let ctx = cvt_p(ffi::EVP_PKEY_CTX_new_id(ffi::EVP_PKEY_HKDF, ptr::null_mut()))?;
+
+        let ret = (|| {
+            cvt(ffi::EVP_PKEY_derive_init(ctx))?;
+            hkdf_ctrl(ctx, ffi::EVP_PKEY_CTRL_HKDF_MD, 0, digest.as_ptr() as *mut _)?;
+            #[cfg(ossl111)]
+            {
+                if let Some(mode) = mode {
+                    hkdf_ctrl(ctx, ffi::EVP_PKEY_CTRL_HKDF_MODE, mode, ptr::null_mut())?;
+                }
+            }
+            #[cfg(not(ossl111))]
+            assert!(mode.is_none());
+            // OpenSSL rejects empty parameters, but treats missing ones as empty
+            if !salt.is_empty() {
+                hkdf_ctrl(
+                    ctx,
+                    ffi::EVP_PKEY_CTRL_HKDF_SALT,
+                    salt.len() as c_int,
+                    salt.as_ptr() as *mut _,
+                )?;
+            }
+            hkdf_ctrl(
+                ctx,
+                ffi::EVP_PKEY_CTRL_HKDF_KEY,
+                key.len() as c_int,
+                key.as_ptr() as *mut _,
+            )?;
+            if !info.is_empty() {
+                hkdf_ctrl(
+                    ctx,
+                    ffi::EVP_PKEY_CTRL_HKDF_INFO,
+                    info.len() as c_int,
+                    info.as_ptr() as *mut _,
+                )?;
+            }
+
+            let mut len = out.len();
+            cvt(ffi::EVP_PKEY_derive(ctx, out.as_mut_ptr(), &mut len))?;
+            assert_eq!(len, out.len());
+            Ok(())
+        })();
+
+        ffi::EVP_PKEY_CTX_free(ctx);
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hex::{self, FromHex};
+
+    use hash::MessageDigest;
+    use super::*;
+
+    // RFC 5869 test cases 1 and 3
+    const IKM: &'static str = "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b";
+    const SALT: &'static str = "000102030405060708090a0b0c";
+    const INFO: &'static str = "f0f1f2f3f4f5f6f7f8f9";
+    const PRK: &'static str = "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5";
+    const OKM: &'static str = "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf\
+                               34007208d5b887185865";
+    const OKM_NO_SALT: &'static str = "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c\
+                                       738d2d9d201395faa4b61a96c8";
+
+    #[test]
+    fn hkdf_sha256() {
+        let ikm = Vec::from_hex(IKM).unwrap();
+        let salt = Vec::from_hex(SALT).unwrap();
+        let info = Vec::from_hex(INFO).unwrap();
+
+        let mut okm = [0; 42];
+        hkdf(MessageDigest::sha256(), &ikm, &salt, &info, &mut okm).unwrap();
+        assert_eq!(hex::encode(&okm[..]), OKM);
+    }
+
+    #[test]
+    fn hkdf_empty_salt_info() {
+        let ikm = Vec::from_hex(IKM).unwrap();
+
+        let mut okm = [0; 42];
+        hkdf(MessageDigest::sha256(), &ikm, &[], &[], &mut okm).unwrap();
+        assert_eq!(hex::encode(&okm[..]), OKM_NO_SALT);
+    }
+
+    #[test]
+    #[cfg(ossl111)]
+    fn hkdf_extract_expand() {
+        let ikm = Vec::from_hex(IKM).unwrap();
+        let salt = Vec::from_hex(SALT).unwrap();
+        let info = Vec::from_hex(INFO).unwrap();
+
+        let prk = hkdf_extract(MessageDigest::sha256(), &ikm, &salt).unwrap();
+        assert_eq!(hex::encode(&prk), PRK);
+
+        let mut okm = [0; 42];
+        hkdf_expand(MessageDigest::sha256(), &prk, &info, &mut okm).unwrap();
+        assert_eq!(hex::encode(&okm[..]), OKM);
+    }
+}
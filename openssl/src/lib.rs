@@ -8,11 +8,15 @@ extern crate foreign_types;
 extern crate lazy_static;
 extern crate libc;
 extern crate openssl_sys as ffi;
+#[cfg(feature = "serde")]
+extern crate serde;
 
 #[cfg(test)]
 extern crate data_encoding;
 #[cfg(test)]
 extern crate hex;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 #[cfg(test)]
 extern crate tempdir;
 
@@ -29,9 +33,13 @@ mod macros;
 mod bio;
 #[macro_use]
 mod util;
+#[cfg(feature = "serde")]
+mod serde_impls;
 pub mod aes;
 pub mod asn1;
+pub mod base64;
 pub mod bn;
+pub mod cmac;
 #[cfg(not(libressl))]
 pub mod cms;
 pub mod conf;
@@ -40,16 +48,25 @@ pub mod dh;
 pub mod dsa;
 pub mod ec;
 pub mod ecdsa;
+#[cfg(ossl110)]
+pub mod ecies;
+pub mod encrypt;
+pub mod envelope;
 pub mod error;
 pub mod ex_data;
 #[cfg(not(libressl))]
 pub mod fips;
 pub mod hash;
+pub mod jwk;
+#[cfg(ossl110)]
+pub mod kdf;
 pub mod memcmp;
 pub mod nid;
 pub mod ocsp;
+pub mod parse;
 pub mod pkcs12;
 pub mod pkcs5;
+pub mod pkcs7;
 pub mod pkey;
 pub mod rand;
 pub mod rsa;
@@ -59,6 +76,8 @@ pub mod ssl;
 pub mod stack;
 pub mod string;
 pub mod symm;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 pub mod version;
 pub mod x509;
 
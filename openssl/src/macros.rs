@@ -86,6 +86,20 @@ macro_rules! to_pem {
     }
 }
 
+macro_rules! to_text {
+    ($(#[$m:meta])* $n:ident, $f:path $(, $arg:expr)*) => {
+        $(#[$m])*
+        pub fn $n(&self) -> Result<Vec<u8>, ::error::ErrorStack> {
+            unsafe {
+                let bio = try!(::bio::MemBio::new());
+                try!(::cvt($f(bio.as_ptr(), ::foreign_types::ForeignTypeRef::as_ptr(self)
+                              $(, $arg)*)));
+                Ok(bio.get_buf().to_owned())
+            }
+        }
+    }
+}
+
 macro_rules! to_der {
     ($(#[$m:meta])* $n:ident, $f:path) => {
         $(#[$m])*
@@ -130,6 +144,28 @@ macro_rules! from_pem {
     }
 }
 
+macro_rules! from_der_strict {
+    ($(#[$m:meta])* $n:ident, $t:ty, $f:path) => {
+        $(#[$m])*
+        pub fn $n(der: &[u8], max_len: usize) -> Result<$t, ::parse::ParseError> {
+            ::parse::der_strict(der, max_len, |pp, len| unsafe {
+                $f(::std::ptr::null_mut(), pp, len)
+            })
+        }
+    }
+}
+
+macro_rules! from_pem_strict {
+    ($(#[$m:meta])* $n:ident, $t:ty, $f:path) => {
+        $(#[$m])*
+        pub fn $n(pem: &[u8], max_len: usize) -> Result<$t, ::parse::ParseError> {
+            ::parse::pem_strict(pem, max_len, |bio| unsafe {
+                $f(bio, ::std::ptr::null_mut(), None, ::std::ptr::null_mut())
+            })
+        }
+    }
+}
+
 macro_rules! foreign_type_and_impl_send_sync {
     (
         $(#[$impl_attr:meta])*
@@ -10,6 +10,9 @@
 //! The utilities in this module are designed to be resistant
 //! to this type of attack.
 //!
+//! The module also provides [`cleanse`], which erases secret values from memory once they are no
+//! longer needed.
+//!
 //! # Examples
 //!
 //! To perform a constant-time comparision of two arrays of the same length but different
@@ -29,6 +32,8 @@
 //! assert!(!eq(&a, &b));
 //! assert!(!eq(&a, &c));
 //! ```
+//!
+//! [`cleanse`]: fn.cleanse.html
 use libc::size_t;
 use ffi;
 
@@ -73,9 +78,36 @@ pub fn eq(a: &[u8], b: &[u8]) -> bool {
     ret == 0
 }
 
+/// Overwrites the contents of `buf`.
+///
+/// OpenSSL 1.1.0 and newer fill the buffer with zeros, while older versions use a pseudo-random
+/// pattern. Unlike filling the buffer directly, the write will not be removed by the compiler,
+/// even if the buffer is never read again. This should be used to erase keys and other secrets
+/// before their memory is freed.
+///
+/// This corresponds to [`OPENSSL_cleanse`].
+///
+/// # Examples
+///
+/// ```
+/// use openssl::memcmp::cleanse;
+///
+/// let mut key = *b"super secret key";
+/// // use the key...
+/// cleanse(&mut key);
+/// assert_ne!(&key, b"super secret key");
+/// ```
+///
+/// [`OPENSSL_cleanse`]: https://www.openssl.org/docs/man1.1.0/crypto/OPENSSL_cleanse.html
+pub fn cleanse(buf: &mut [u8]) {
+    unsafe {
+        ffi::OPENSSL_cleanse(buf.as_mut_ptr() as *mut _, buf.len() as size_t);
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::eq;
+    use super::{cleanse, eq};
 
     #[test]
     fn test_eq() {
@@ -89,4 +121,15 @@ mod tests {
     fn test_diff_lens() {
         eq(&[], &[1]);
     }
+
+    #[test]
+    fn test_cleanse() {
+        let mut buf = [0xff; 32];
+        cleanse(&mut buf);
+        assert_ne!(buf, [0xff; 32]);
+        #[cfg(ossl110)]
+        assert_eq!(buf, [0; 32]);
+
+        cleanse(&mut []);
+    }
 }
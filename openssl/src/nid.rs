@@ -1,6 +1,20 @@
 //! A collection of numerical identifiers for OpenSSL objects.
 use ffi;
-use libc::c_int;
+use libc::{c_char, c_int};
+use std::ffi::{CStr, CString};
+use std::str;
+
+use error::ErrorStack;
+
+/// The digest and key algorithms making up a signature algorithm.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SignatureAlgorithms {
+    /// The digest algorithm, or `Nid::UNDEF` if the signature algorithm does not use a separate
+    /// digest.
+    pub digest: Nid,
+    /// The public key algorithm.
+    pub pkey: Nid,
+}
 
 /// A numerical identifier for an OpenSSL object.
 ///
@@ -42,6 +56,84 @@ impl Nid {
         self.0
     }
 
+    /// Looks up a `Nid` by its short name, such as `"CN"`.
+    ///
+    /// Returns `None` if the name is not known.
+    ///
+    /// This corresponds to [`OBJ_sn2nid`].
+    ///
+    /// [`OBJ_sn2nid`]: https://www.openssl.org/docs/man1.1.0/crypto/OBJ_sn2nid.html
+    pub fn from_short_name(name: &str) -> Option<Nid> {
+        let name = match CString::new(name) {
+            Ok(name) => name,
+            Err(_) => return None,
+        };
+        ffi::init();
+        match unsafe { ffi::OBJ_sn2nid(name.as_ptr()) } {
+            ffi::NID_undef => None,
+            nid => Some(Nid(nid)),
+        }
+    }
+
+    /// Looks up a `Nid` by its long name, such as `"commonName"`.
+    ///
+    /// Returns `None` if the name is not known.
+    ///
+    /// This corresponds to [`OBJ_ln2nid`].
+    ///
+    /// [`OBJ_ln2nid`]: https://www.openssl.org/docs/man1.1.0/crypto/OBJ_sn2nid.html
+    pub fn from_long_name(name: &str) -> Option<Nid> {
+        let name = match CString::new(name) {
+            Ok(name) => name,
+            Err(_) => return None,
+        };
+        ffi::init();
+        match unsafe { ffi::OBJ_ln2nid(name.as_ptr()) } {
+            ffi::NID_undef => None,
+            nid => Some(Nid(nid)),
+        }
+    }
+
+    /// Returns the short name of the object, such as `"CN"`.
+    ///
+    /// This corresponds to [`OBJ_nid2sn`].
+    ///
+    /// [`OBJ_nid2sn`]: https://www.openssl.org/docs/man1.1.0/crypto/OBJ_nid2sn.html
+    pub fn short_name(&self) -> Result<&'static str, ErrorStack> {
+        unsafe { name(ffi::OBJ_nid2sn(self.0)) }
+    }
+
+    /// Returns the long name of the object, such as `"commonName"`.
+    ///
+    /// This corresponds to [`OBJ_nid2ln`].
+    ///
+    /// [`OBJ_nid2ln`]: https://www.openssl.org/docs/man1.1.0/crypto/OBJ_nid2ln.html
+    pub fn long_name(&self) -> Result<&'static str, ErrorStack> {
+        unsafe { name(ffi::OBJ_nid2ln(self.0)) }
+    }
+
+    /// Returns the digest and public key algorithms of a signature algorithm.
+    ///
+    /// Returns `None` if the `Nid` is not a known signature algorithm.
+    ///
+    /// This corresponds to [`OBJ_find_sigid_algs`].
+    ///
+    /// [`OBJ_find_sigid_algs`]: https://www.openssl.org/docs/man1.1.0/crypto/OBJ_find_sigid_algs.html
+    pub fn signature_algorithms(&self) -> Option<SignatureAlgorithms> {
+        unsafe {
+            let mut digest = 0;
+            let mut pkey = 0;
+            if ffi::OBJ_find_sigid_algs(self.0, &mut digest, &mut pkey) == 1 {
+                Some(SignatureAlgorithms {
+                    digest: Nid(digest),
+                    pkey: Nid(pkey),
+                })
+            } else {
+                None
+            }
+        }
+    }
+
     pub const UNDEF: Nid = Nid(ffi::NID_undef);
     pub const ITU_T: Nid = Nid(ffi::NID_itu_t);
     pub const CCITT: Nid = Nid(ffi::NID_ccitt);
@@ -990,4 +1082,73 @@ impl Nid {
     pub const AES_128_CBC_HMAC_SHA1: Nid = Nid(ffi::NID_aes_128_cbc_hmac_sha1);
     pub const AES_192_CBC_HMAC_SHA1: Nid = Nid(ffi::NID_aes_192_cbc_hmac_sha1);
     pub const AES_256_CBC_HMAC_SHA1: Nid = Nid(ffi::NID_aes_256_cbc_hmac_sha1);
+    #[cfg(ossl111)]
+    pub const SM2: Nid = Nid(ffi::NID_sm2);
+    #[cfg(ossl111)]
+    pub const SM3: Nid = Nid(ffi::NID_sm3);
+    #[cfg(ossl111)]
+    pub const X25519: Nid = Nid(ffi::NID_X25519);
+    #[cfg(ossl111)]
+    pub const ED25519: Nid = Nid(ffi::NID_ED25519);
+    #[cfg(ossl111)]
+    pub const FFDHE2048: Nid = Nid(ffi::NID_ffdhe2048);
+    #[cfg(ossl111)]
+    pub const FFDHE3072: Nid = Nid(ffi::NID_ffdhe3072);
+    #[cfg(ossl111)]
+    pub const FFDHE4096: Nid = Nid(ffi::NID_ffdhe4096);
+    #[cfg(ossl111)]
+    pub const FFDHE6144: Nid = Nid(ffi::NID_ffdhe6144);
+    #[cfg(ossl111)]
+    pub const FFDHE8192: Nid = Nid(ffi::NID_ffdhe8192);
+}
+
+unsafe fn name(p: *const c_char) -> Result<&'static str, ErrorStack> {
+    if p.is_null() {
+        return Err(ErrorStack::get());
+    }
+    Ok(str::from_utf8(CStr::from_ptr(p).to_bytes()).unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use asn1::Asn1Object;
+    use super::Nid;
+
+    #[test]
+    fn names() {
+        assert_eq!(Nid::COMMONNAME.short_name().unwrap(), "CN");
+        assert_eq!(Nid::COMMONNAME.long_name().unwrap(), "commonName");
+        assert_eq!(Nid::from_short_name("CN"), Some(Nid::COMMONNAME));
+        assert_eq!(Nid::from_long_name("commonName"), Some(Nid::COMMONNAME));
+        assert_eq!(Nid::from_short_name("not a name"), None);
+        assert!(Nid::from_raw(-1).short_name().is_err());
+    }
+
+    #[test]
+    fn signature_algorithms() {
+        let algs = Nid::SHA256WITHRSAENCRYPTION.signature_algorithms().unwrap();
+        assert_eq!(algs.digest, Nid::SHA256);
+        assert_eq!(algs.pkey, Nid::RSAENCRYPTION);
+        assert_eq!(Nid::COMMONNAME.signature_algorithms(), None);
+    }
+
+    #[test]
+    fn object_from_str() {
+        let obj = Asn1Object::from_str("1.2.840.113549.1.1.11").unwrap();
+        assert_eq!(obj.nid(), Nid::SHA256WITHRSAENCRYPTION);
+        assert_eq!(obj.text(), "1.2.840.113549.1.1.11");
+        assert_eq!(obj.to_string(), "sha256WithRSAEncryption");
+
+        let obj = Asn1Object::from_str("CN").unwrap();
+        assert_eq!(obj.nid(), Nid::COMMONNAME);
+        assert_eq!(obj.text(), "2.5.4.3");
+
+        // long enough to need more than the initial buffer
+        let oid = "1.3.6.1.4.1.55555.1.2.3.4.5.6.7.8.9.10.11.12.13.14.15.16.17.18.19.20.21.22.23";
+        let obj = Asn1Object::from_str(oid).unwrap();
+        assert_eq!(obj.nid(), Nid::UNDEF);
+        assert_eq!(obj.text(), oid);
+
+        assert!(Asn1Object::from_str("not an oid").is_err());
+    }
 }
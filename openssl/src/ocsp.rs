@@ -111,8 +111,11 @@ pub struct OcspStatus<'a> {
     pub revocation_time: Option<&'a Asn1GeneralizedTimeRef>,
     /// The time that this revocation check was performed.
     pub this_update: &'a Asn1GeneralizedTimeRef,
-    /// The time at which this revocation check expires.
-    pub next_update: &'a Asn1GeneralizedTimeRef,
+    /// The time at which this revocation check expires, if the responder specified one.
+    ///
+    /// A response without a next update time indicates that newer revocation information is
+    /// available at any time.
+    pub next_update: Option<&'a Asn1GeneralizedTimeRef>,
 }
 
 impl<'a> OcspStatus<'a> {
@@ -127,7 +130,7 @@ impl<'a> OcspStatus<'a> {
         unsafe {
             cvt(ffi::OCSP_check_validity(
                 self.this_update.as_ptr(),
-                self.next_update.as_ptr(),
+                self.next_update.map_or(ptr::null_mut(), |t| t.as_ptr()),
                 nsec as c_long,
                 maxsec.map(|n| n as c_long).unwrap_or(-1),
             )).map(|_| ())
@@ -188,12 +191,17 @@ impl OcspBasicResponseRef {
                 } else {
                     Some(Asn1GeneralizedTimeRef::from_ptr(revocation_time))
                 };
+                let next_update = if next_update.is_null() {
+                    None
+                } else {
+                    Some(Asn1GeneralizedTimeRef::from_ptr(next_update))
+                };
                 Some(OcspStatus {
                     status: OcspCertStatus(status),
                     reason: OcspRevokedStatus(status),
                     revocation_time: revocation_time,
                     this_update: Asn1GeneralizedTimeRef::from_ptr(this_update),
-                    next_update: Asn1GeneralizedTimeRef::from_ptr(next_update),
+                    next_update: next_update,
                 })
             } else {
                 None
@@ -0,0 +1,240 @@
+//! Strict parsing of untrusted DER and PEM input.
+//!
+//! The standard `from_der` and `from_pem` constructors follow OpenSSL's lenient behavior: trailing
+//! data after the decoded structure is silently ignored, as is any text preceding a PEM block. The
+//! `*_strict` constructors instead reject such input, enforce a caller-provided size limit before
+//! any decoding takes place, and report the byte offset at which the input was rejected.
+//!
+//! # Examples
+//!
+//! ```
+//! use openssl::parse::ParseErrorKind;
+//! use openssl::x509::X509;
+//!
+//! let pem = include_bytes!("../test/cert.pem");
+//! let cert = X509::from_pem(pem).unwrap();
+//! let mut der = cert.to_der().unwrap();
+//! let len = der.len();
+//!
+//! assert!(X509::from_der_strict(&der, 64 * 1024).is_ok());
+//!
+//! der.extend_from_slice(b"garbage");
+//! let err = X509::from_der_strict(&der, 64 * 1024).err().unwrap();
+//! assert_eq!(err.kind(), ParseErrorKind::TrailingData);
+//! assert_eq!(err.offset(), len);
+//! ```
+use ffi;
+use foreign_types::ForeignType;
+use libc::{c_long, c_uchar};
+use std::cmp;
+use std::error;
+use std::fmt;
+
+use bio::MemBioSlice;
+use error::ErrorStack;
+
+/// The reason strict parsing failed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The input was larger than the permitted limit.
+    TooLong,
+    /// The input did not begin with a PEM header.
+    MissingHeader,
+    /// Unexpected data followed the decoded structure.
+    TrailingData,
+    /// OpenSSL failed to decode the structure.
+    Invalid,
+}
+
+/// An error returned by the strict parsing functions.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    offset: usize,
+    kind: ParseErrorKind,
+    stack: Option<ErrorStack>,
+}
+
+impl ParseError {
+    fn new(offset: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            offset: offset,
+            kind: kind,
+            stack: None,
+        }
+    }
+
+    /// Returns the byte offset into the input at which parsing failed.
+    ///
+    /// For `ParseErrorKind::TooLong` this is the limit, and for `ParseErrorKind::TrailingData` it
+    /// is the first byte following the decoded structure. OpenSSL does not report where within a
+    /// structure decoding failed, so for `ParseErrorKind::Invalid` it is the start of the
+    /// structure.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the reason parsing failed.
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    /// Returns the OpenSSL errors reported while decoding, if any.
+    pub fn error_stack(&self) -> Option<&ErrorStack> {
+        self.stack.as_ref()
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ParseErrorKind::TooLong => write!(fmt, "input exceeds the {} byte limit", self.offset),
+            ParseErrorKind::MissingHeader => {
+                write!(fmt, "missing PEM header at offset {}", self.offset)
+            }
+            ParseErrorKind::TrailingData => {
+                write!(fmt, "trailing data at offset {}", self.offset)
+            }
+            ParseErrorKind::Invalid => {
+                write!(fmt, "invalid structure at offset {}", self.offset)?;
+                if let Some(ref stack) = self.stack {
+                    write!(fmt, ": {}", stack)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn description(&self) -> &str {
+        "a strict parsing error"
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        self.stack.as_ref().map(|e| e as &error::Error)
+    }
+}
+
+fn check_len(input: &[u8], max_len: usize) -> Result<(), ParseError> {
+    if input.len() > max_len {
+        Err(ParseError::new(max_len, ParseErrorKind::TooLong))
+    } else {
+        Ok(())
+    }
+}
+
+fn invalid(offset: usize, stack: ErrorStack) -> ParseError {
+    ParseError {
+        offset: offset,
+        kind: ParseErrorKind::Invalid,
+        stack: Some(stack),
+    }
+}
+
+fn is_space(b: &u8) -> bool {
+    match *b {
+        b' ' | b'\t' | b'\r' | b'\n' => true,
+        _ => false,
+    }
+}
+
+/// Decodes a single DER structure spanning all of `der`.
+pub(crate) fn der_strict<T, F>(der: &[u8], max_len: usize, f: F) -> Result<T, ParseError>
+where
+    T: ForeignType,
+    F: FnOnce(*mut *const c_uchar, c_long) -> *mut T::CType,
+{
+    ffi::init();
+    check_len(der, max_len)?;
+
+    let len = cmp::min(der.len(), c_long::max_value() as usize) as c_long;
+    let mut p = der.as_ptr();
+    let ptr = f(&mut p, len);
+    if ptr.is_null() {
+        return Err(invalid(0, ErrorStack::get()));
+    }
+    let value = unsafe { T::from_ptr(ptr) };
+
+    let consumed = p as usize - der.as_ptr() as usize;
+    if consumed != der.len() {
+        return Err(ParseError::new(consumed, ParseErrorKind::TrailingData));
+    }
+
+    Ok(value)
+}
+
+/// Decodes a single PEM block spanning all of `pem`, ignoring surrounding whitespace.
+pub(crate) fn pem_strict<T, F>(pem: &[u8], max_len: usize, f: F) -> Result<T, ParseError>
+where
+    T: ForeignType,
+    F: FnOnce(*mut ffi::BIO) -> *mut T::CType,
+{
+    ffi::init();
+    check_len(pem, max_len)?;
+
+    let start = pem.iter().position(|b| !is_space(b)).unwrap_or(pem.len());
+    if !pem[start..].starts_with(b"-----BEGIN ") {
+        return Err(ParseError::new(start, ParseErrorKind::MissingHeader));
+    }
+
+    let bio = MemBioSlice::new(pem).map_err(|e| invalid(0, e))?;
+    let ptr = f(bio.as_ptr());
+    if ptr.is_null() {
+        return Err(invalid(start, ErrorStack::get()));
+    }
+    let value = unsafe { T::from_ptr(ptr) };
+
+    let remaining = unsafe { ffi::BIO_ctrl_pending(bio.as_ptr()) } as usize;
+    let end = pem.len() - remaining;
+    if let Some(pos) = pem[end..].iter().position(|b| !is_space(b)) {
+        return Err(ParseError::new(end + pos, ParseErrorKind::TrailingData));
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use x509::X509;
+
+    #[test]
+    fn der_limits() {
+        let cert = include_bytes!("../test/cert.pem");
+        let der = X509::from_pem(cert).unwrap().to_der().unwrap();
+
+        X509::from_der_strict(&der, der.len()).unwrap();
+
+        let err = X509::from_der_strict(&der, der.len() - 1).err().unwrap();
+        assert_eq!(err.kind(), ParseErrorKind::TooLong);
+        assert_eq!(err.offset(), der.len() - 1);
+
+        let err = X509::from_der_strict(&der[..der.len() - 1], der.len()).err().unwrap();
+        assert_eq!(err.kind(), ParseErrorKind::Invalid);
+        assert!(err.error_stack().is_some());
+        assert_eq!(err.offset(), 0);
+    }
+
+    #[test]
+    fn pem_trailing_data() {
+        let cert = include_bytes!("../test/cert.pem");
+        X509::from_pem_strict(cert, cert.len()).unwrap();
+
+        let mut padded = b"\n\n".to_vec();
+        padded.extend_from_slice(cert);
+        padded.extend_from_slice(b"\r\n  \n");
+        X509::from_pem_strict(&padded, padded.len()).unwrap();
+
+        let mut prefixed = b"junk\n".to_vec();
+        prefixed.extend_from_slice(cert);
+        let err = X509::from_pem_strict(&prefixed, prefixed.len()).err().unwrap();
+        assert_eq!(err.kind(), ParseErrorKind::MissingHeader);
+        assert_eq!(err.offset(), 0);
+
+        let mut suffixed = cert.to_vec();
+        suffixed.extend_from_slice(b"\njunk");
+        let err = X509::from_pem_strict(&suffixed, suffixed.len()).err().unwrap();
+        assert_eq!(err.kind(), ParseErrorKind::TrailingData);
+        assert_eq!(err.offset(), cert.len() + 1);
+    }
+}
@@ -32,7 +32,28 @@ impl Pkcs12Ref {
         ffi::i2d_PKCS12
     }
 
+    /// Checks whether `pass` is the password protecting the archive's MAC.
+    ///
+    /// This is much cheaper than [`parse`] as nothing is decrypted, so it can be used to check
+    /// several candidate passwords against an archive that has been deserialized once.
+    ///
+    /// This corresponds to [`PKCS12_verify_mac`].
+    ///
+    /// [`parse`]: #method.parse
+    /// [`PKCS12_verify_mac`]: https://www.openssl.org/docs/man1.1.0/crypto/PKCS12_verify_mac.html
+    pub fn verify_mac(&self, pass: &str) -> bool {
+        unsafe {
+            let pass = CString::new(pass.as_bytes()).unwrap();
+            let ok = ffi::PKCS12_verify_mac(self.as_ptr(), pass.as_ptr(), -1) == 1;
+            // discard the MAC failure so it isn't reported by a later operation
+            ErrorStack::get();
+            ok
+        }
+    }
+
     /// Extracts the contents of the `Pkcs12`.
+    ///
+    /// The archive is not modified, so this may be called repeatedly with different passwords.
     pub fn parse(&self, pass: &str) -> Result<ParsedPkcs12, ErrorStack> {
         unsafe {
             let pass = CString::new(pass.as_bytes()).unwrap();
@@ -100,12 +121,17 @@ impl Pkcs12 {
     }
 }
 
+/// The contents of a PKCS #12 archive.
 pub struct ParsedPkcs12 {
+    /// The private key.
     pub pkey: PKey<Private>,
+    /// The certificate corresponding to `pkey`.
     pub cert: X509,
+    /// Any additional certificates in the archive, typically the CA chain.
     pub chain: Option<Stack<X509>>,
 }
 
+/// A builder for PKCS #12 archives.
 pub struct Pkcs12Builder {
     nid_key: Nid,
     nid_cert: Nid,
@@ -116,12 +142,16 @@ pub struct Pkcs12Builder {
 
 impl Pkcs12Builder {
     /// The encryption algorithm that should be used for the key
+    ///
+    /// Passing `Nid::UNDEF` selects OpenSSL's default.
     pub fn key_algorithm(&mut self, nid: Nid) -> &mut Self {
         self.nid_key = nid;
         self
     }
 
     /// The encryption algorithm that should be used for the cert
+    ///
+    /// Passing `Nid::UNDEF` selects OpenSSL's default.
     pub fn cert_algorithm(&mut self, nid: Nid) -> &mut Self {
         self.nid_cert = nid;
         self
@@ -233,6 +263,22 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_retry_password() {
+        let der = include_bytes!("../test/identity.p12");
+        let pkcs12 = Pkcs12::from_der(der).unwrap();
+
+        assert!(!pkcs12.verify_mac("wrong"));
+        assert!(pkcs12.parse("wrong").is_err());
+
+        assert!(pkcs12.verify_mac("mypass"));
+        let parsed = pkcs12.parse("mypass").unwrap();
+        assert_eq!(
+            hex::encode(parsed.cert.fingerprint(MessageDigest::sha1()).unwrap()),
+            "59172d9313e84459bcff27f967e79e6e9217e584"
+        );
+    }
+
     #[test]
     fn parse_empty_chain() {
         let der = include_bytes!("../test/keystore-empty-chain.p12");
@@ -284,4 +330,44 @@ mod test {
         );
         assert!(parsed.pkey.public_eq(&pkey));
     }
+
+    #[test]
+    fn create_with_chain_and_algorithms() {
+        let cert = include_bytes!("../test/cert.pem");
+        let cert = X509::from_pem(cert).unwrap();
+        let key = include_bytes!("../test/key.pem");
+        let pkey = PKey::private_key_from_pem(key).unwrap();
+        let ca = include_bytes!("../test/root-ca.pem");
+        let ca = X509::from_pem(ca).unwrap();
+
+        let mut chain = Stack::new().unwrap();
+        chain.push(ca.clone()).unwrap();
+
+        let mut builder = Pkcs12::builder();
+        builder
+            .key_algorithm(Nid::PBE_WITHSHA1AND3_KEY_TRIPLEDES_CBC)
+            .cert_algorithm(Nid::PBE_WITHSHA1AND3_KEY_TRIPLEDES_CBC)
+            .key_iter(4096)
+            .mac_iter(1)
+            .ca(chain);
+        let pkcs12 = builder.build("mypass", "foobar", &pkey, &cert).unwrap();
+        let der = pkcs12.to_der().unwrap();
+
+        let pkcs12 = Pkcs12::from_der(&der).unwrap();
+        assert!(pkcs12.parse("wrongpass").is_err());
+        let parsed = pkcs12.parse("mypass").unwrap();
+
+        assert_eq!(
+            parsed.cert.fingerprint(MessageDigest::sha1()).unwrap(),
+            cert.fingerprint(MessageDigest::sha1()).unwrap()
+        );
+        assert!(parsed.pkey.public_eq(&pkey));
+
+        let chain = parsed.chain.unwrap();
+        assert_eq!(chain.len(), 1);
+        assert_eq!(
+            chain[0].fingerprint(MessageDigest::sha1()).unwrap(),
+            ca.fingerprint(MessageDigest::sha1()).unwrap()
+        );
+    }
 }
@@ -0,0 +1,401 @@
+//! PKCS #7 signed and enveloped data.
+//!
+//! This module is a counterpart to the [`cms`] module for peers which only understand PKCS #7
+//! rather than the newer CMS format. It wraps OpenSSL's `PKCS7_*` functions directly.
+//!
+//! [`cms`]: ../cms/index.html
+
+use ffi;
+use foreign_types::{ForeignType, ForeignTypeRef};
+use libc::c_int;
+use std::ptr;
+
+use bio::{MemBio, MemBioSlice};
+use error::ErrorStack;
+use pkey::{HasPrivate, PKeyRef};
+use stack::StackRef;
+use symm::Cipher;
+use x509::store::X509StoreRef;
+use x509::{X509, X509Ref};
+use {cvt, cvt_p};
+
+bitflags! {
+    /// Options controlling the behavior of PKCS #7 operations.
+    pub struct Pkcs7Flags: c_int {
+        const TEXT = ffi::PKCS7_TEXT;
+        const NOCERTS = ffi::PKCS7_NOCERTS;
+        const NOSIGS = ffi::PKCS7_NOSIGS;
+        const NOCHAIN = ffi::PKCS7_NOCHAIN;
+        const NOINTERN = ffi::PKCS7_NOINTERN;
+        const NOVERIFY = ffi::PKCS7_NOVERIFY;
+        const DETACHED = ffi::PKCS7_DETACHED;
+        const BINARY = ffi::PKCS7_BINARY;
+        const NOATTR = ffi::PKCS7_NOATTR;
+        const NOSMIMECAP = ffi::PKCS7_NOSMIMECAP;
+        const NOOLDMIMETYPE = ffi::PKCS7_NOOLDMIMETYPE;
+        const CRLFEOL = ffi::PKCS7_CRLFEOL;
+        const STREAM = ffi::PKCS7_STREAM;
+        const NOCRL = ffi::PKCS7_NOCRL;
+        const PARTIAL = ffi::PKCS7_PARTIAL;
+        const REUSE_DIGEST = ffi::PKCS7_REUSE_DIGEST;
+    }
+}
+
+foreign_type_and_impl_send_sync! {
+    type CType = ffi::PKCS7;
+    fn drop = ffi::PKCS7_free;
+
+    /// A PKCS #7 structure.
+    ///
+    /// Contains signed and/or encrypted data.
+    pub struct Pkcs7;
+
+    /// Reference to `Pkcs7`
+    pub struct Pkcs7Ref;
+}
+
+impl Pkcs7 {
+    from_pem! {
+        /// Deserializes a PEM-encoded PKCS #7 signature.
+        ///
+        /// The input should have a header of `-----BEGIN PKCS7-----`.
+        ///
+        /// This corresponds to [`PEM_read_bio_PKCS7`].
+        ///
+        /// [`PEM_read_bio_PKCS7`]: https://www.openssl.org/docs/man1.0.2/crypto/PEM_read_bio_PKCS7.html
+        from_pem,
+        Pkcs7,
+        ffi::PEM_read_bio_PKCS7
+    }
+
+    from_der! {
+        /// Deserializes a DER-encoded PKCS #7 structure.
+        ///
+        /// This corresponds to [`d2i_PKCS7`].
+        ///
+        /// [`d2i_PKCS7`]: https://www.openssl.org/docs/man1.1.0/crypto/d2i_PKCS7.html
+        from_der,
+        Pkcs7,
+        ffi::d2i_PKCS7
+    }
+
+    /// Parses a message in S/MIME format.
+    ///
+    /// Returns the loaded signature, along with the cleartext message if the signature is
+    /// detached.
+    ///
+    /// This corresponds to [`SMIME_read_PKCS7`].
+    ///
+    /// [`SMIME_read_PKCS7`]: https://www.openssl.org/docs/man1.1.0/crypto/SMIME_read_PKCS7.html
+    pub fn from_smime(input: &[u8]) -> Result<(Pkcs7, Option<Vec<u8>>), ErrorStack> {
+        ffi::init();
+
+        let input_bio = MemBioSlice::new(input)?;
+        let mut bcont_bio = ptr::null_mut();
+        unsafe {
+            let pkcs7 = cvt_p(ffi::SMIME_read_PKCS7(input_bio.as_ptr(), &mut bcont_bio))?;
+            let pkcs7 = Pkcs7::from_ptr(pkcs7);
+            let out = if bcont_bio.is_null() {
+                None
+            } else {
+                let bcont_bio = MemBio::from_ptr(bcont_bio);
+                Some(bcont_bio.get_buf().to_vec())
+            };
+            Ok((pkcs7, out))
+        }
+    }
+
+    /// Creates and returns a PKCS #7 `envelopedData` structure.
+    ///
+    /// `certs` is a list of recipient certificates. `input` is the content to be encrypted.
+    /// `cipher` is the symmetric cipher to use. `flags` is an optional set of flags.
+    ///
+    /// This corresponds to [`PKCS7_encrypt`].
+    ///
+    /// [`PKCS7_encrypt`]: https://www.openssl.org/docs/man1.0.2/crypto/PKCS7_encrypt.html
+    pub fn encrypt(
+        certs: &StackRef<X509>,
+        input: &[u8],
+        cipher: Cipher,
+        flags: Pkcs7Flags,
+    ) -> Result<Pkcs7, ErrorStack> {
+        let input_bio = MemBioSlice::new(input)?;
+
+        unsafe {
+            cvt_p(ffi::PKCS7_encrypt(
+                certs.as_ptr(),
+                input_bio.as_ptr(),
+                cipher.as_ptr(),
+                flags.bits(),
+            )).map(Pkcs7)
+        }
+    }
+
+    /// Creates and returns a PKCS #7 `signedData` structure.
+    ///
+    /// `signcert` is the certificate to sign with, `pkey` is the corresponding private key.
+    /// `certs` is an optional additional set of certificates to include in the PKCS #7 structure
+    /// (for example any intermediate CAs in the chain).
+    ///
+    /// This corresponds to [`PKCS7_sign`].
+    ///
+    /// [`PKCS7_sign`]: https://www.openssl.org/docs/man1.0.2/crypto/PKCS7_sign.html
+    pub fn sign<T>(
+        signcert: &X509Ref,
+        pkey: &PKeyRef<T>,
+        certs: &StackRef<X509>,
+        input: &[u8],
+        flags: Pkcs7Flags,
+    ) -> Result<Pkcs7, ErrorStack>
+    where
+        T: HasPrivate,
+    {
+        let input_bio = MemBioSlice::new(input)?;
+
+        unsafe {
+            cvt_p(ffi::PKCS7_sign(
+                signcert.as_ptr(),
+                pkey.as_ptr(),
+                certs.as_ptr(),
+                input_bio.as_ptr(),
+                flags.bits(),
+            )).map(Pkcs7)
+        }
+    }
+}
+
+impl Pkcs7Ref {
+    /// Converts PKCS #7 structure to S/MIME format.
+    ///
+    /// `input` is the cleartext content, which is only needed for detached signatures.
+    ///
+    /// This corresponds to [`SMIME_write_PKCS7`].
+    ///
+    /// [`SMIME_write_PKCS7`]: https://www.openssl.org/docs/man1.1.0/crypto/SMIME_write_PKCS7.html
+    pub fn to_smime(&self, input: &[u8], flags: Pkcs7Flags) -> Result<Vec<u8>, ErrorStack> {
+        let input_bio = MemBioSlice::new(input)?;
+        let output = MemBio::new()?;
+
+        unsafe {
+            cvt(ffi::SMIME_write_PKCS7(
+                output.as_ptr(),
+                self.as_ptr(),
+                input_bio.as_ptr(),
+                flags.bits(),
+            )).map(|_| output.get_buf().to_owned())
+        }
+    }
+
+    to_pem! {
+        /// Serializes the data into a PEM-encoded PKCS #7 structure.
+        ///
+        /// The output will have a header of `-----BEGIN PKCS7-----`.
+        ///
+        /// This corresponds to [`PEM_write_bio_PKCS7`].
+        ///
+        /// [`PEM_write_bio_PKCS7`]: https://www.openssl.org/docs/man1.0.2/crypto/PEM_write_bio_PKCS7.html
+        to_pem,
+        ffi::PEM_write_bio_PKCS7
+    }
+
+    to_der! {
+        /// Serializes the data into a DER-encoded PKCS #7 structure.
+        ///
+        /// This corresponds to [`i2d_PKCS7`].
+        ///
+        /// [`i2d_PKCS7`]: https://www.openssl.org/docs/man1.1.0/crypto/i2d_PKCS7.html
+        to_der,
+        ffi::i2d_PKCS7
+    }
+
+    /// Decrypts data using the provided private key.
+    ///
+    /// `pkey` is the recipient's private key, and `cert` is the recipient's certificate.
+    ///
+    /// Returns the decrypted message.
+    ///
+    /// This corresponds to [`PKCS7_decrypt`].
+    ///
+    /// [`PKCS7_decrypt`]: https://www.openssl.org/docs/man1.0.2/crypto/PKCS7_decrypt.html
+    pub fn decrypt<T>(
+        &self,
+        pkey: &PKeyRef<T>,
+        cert: &X509Ref,
+        flags: Pkcs7Flags,
+    ) -> Result<Vec<u8>, ErrorStack>
+    where
+        T: HasPrivate,
+    {
+        let output = MemBio::new()?;
+
+        unsafe {
+            cvt(ffi::PKCS7_decrypt(
+                self.as_ptr(),
+                pkey.as_ptr(),
+                cert.as_ptr(),
+                output.as_ptr(),
+                flags.bits(),
+            )).map(|_| output.get_buf().to_owned())
+        }
+    }
+
+    /// Verifies the PKCS #7 `signedData` structure contained by `&self`.
+    ///
+    /// `certs` is a set of certificates in which to search for the signer's certificate.
+    /// `store` is a trusted certificate store (used for chain verification).
+    /// `indata` is the signed data if the content is not present in `&self`.
+    /// The content is written to `out` if it is not `None`.
+    ///
+    /// This corresponds to [`PKCS7_verify`].
+    ///
+    /// [`PKCS7_verify`]: https://www.openssl.org/docs/man1.0.2/crypto/PKCS7_verify.html
+    pub fn verify(
+        &self,
+        certs: &StackRef<X509>,
+        store: &X509StoreRef,
+        indata: Option<&[u8]>,
+        out: Option<&mut Vec<u8>>,
+        flags: Pkcs7Flags,
+    ) -> Result<(), ErrorStack> {
+        let out_bio = MemBio::new()?;
+
+        let indata_bio = match indata {
+            Some(data) => Some(MemBioSlice::new(data)?),
+            None => None,
+        };
+        let indata_bio_ptr = indata_bio.as_ref().map_or(ptr::null_mut(), |p| p.as_ptr());
+
+        unsafe {
+            cvt(ffi::PKCS7_verify(
+                self.as_ptr(),
+                certs.as_ptr(),
+                store.as_ptr(),
+                indata_bio_ptr,
+                out_bio.as_ptr(),
+                flags.bits(),
+            ))?;
+        }
+
+        if let Some(data) = out {
+            data.clear();
+            data.extend_from_slice(out_bio.get_buf());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pkey::PKey;
+    use stack::Stack;
+    use symm::Cipher;
+    use x509::store::X509StoreBuilder;
+    use x509::X509;
+
+    use super::*;
+
+    #[test]
+    fn encrypt_decrypt_smime() {
+        let cert = include_bytes!("../test/cert.pem");
+        let cert = X509::from_pem(cert).unwrap();
+        let mut certs = Stack::new().unwrap();
+        certs.push(cert.clone()).unwrap();
+        let message = "foo";
+        let flags = Pkcs7Flags::STREAM;
+        let pkey = include_bytes!("../test/key.pem");
+        let pkey = PKey::private_key_from_pem(pkey).unwrap();
+
+        let pkcs7 =
+            Pkcs7::encrypt(&certs, message.as_bytes(), Cipher::aes_256_cbc(), flags).unwrap();
+
+        let encrypted = pkcs7.to_smime(message.as_bytes(), flags).unwrap();
+
+        let (pkcs7_decoded, _) = Pkcs7::from_smime(encrypted.as_slice()).unwrap();
+
+        let decoded = pkcs7_decoded
+            .decrypt(&pkey, &cert, Pkcs7Flags::empty())
+            .unwrap();
+
+        assert_eq!(decoded, message.as_bytes());
+    }
+
+    #[test]
+    fn sign_verify_detached() {
+        let cert = include_bytes!("../test/cert.pem");
+        let cert = X509::from_pem(cert).unwrap();
+        let certs = Stack::new().unwrap();
+        let message = "foo";
+        let flags = Pkcs7Flags::STREAM | Pkcs7Flags::DETACHED;
+        let pkey = include_bytes!("../test/key.pem");
+        let pkey = PKey::private_key_from_pem(pkey).unwrap();
+        let mut store_builder = X509StoreBuilder::new().unwrap();
+
+        let root_ca = include_bytes!("../test/root-ca.pem");
+        let root_ca = X509::from_pem(root_ca).unwrap();
+        store_builder.add_cert(root_ca).unwrap();
+
+        let store = store_builder.build();
+
+        let pkcs7 = Pkcs7::sign(&cert, &pkey, &certs, message.as_bytes(), flags).unwrap();
+
+        let signed = pkcs7.to_smime(message.as_bytes(), flags).unwrap();
+
+        let (pkcs7_decoded, content) = Pkcs7::from_smime(signed.as_slice()).unwrap();
+
+        assert_eq!(content.as_ref().map(|v| &v[..]), Some(message.as_bytes()));
+
+        let mut output = Vec::new();
+        pkcs7_decoded
+            .verify(
+                &certs,
+                &store,
+                Some(message.as_bytes()),
+                Some(&mut output),
+                flags,
+            )
+            .unwrap();
+
+        assert_eq!(output, message.as_bytes());
+    }
+
+    #[test]
+    fn sign_verify_der_round_trip() {
+        let cert = include_bytes!("../test/cert.pem");
+        let cert = X509::from_pem(cert).unwrap();
+        let certs = Stack::new().unwrap();
+        let message = "foo";
+        let flags = Pkcs7Flags::BINARY;
+        let pkey = include_bytes!("../test/key.pem");
+        let pkey = PKey::private_key_from_pem(pkey).unwrap();
+        let mut store_builder = X509StoreBuilder::new().unwrap();
+
+        let root_ca = include_bytes!("../test/root-ca.pem");
+        let root_ca = X509::from_pem(root_ca).unwrap();
+        store_builder.add_cert(root_ca).unwrap();
+
+        let store = store_builder.build();
+
+        let pkcs7 = Pkcs7::sign(&cert, &pkey, &certs, message.as_bytes(), flags).unwrap();
+
+        let der = pkcs7.to_der().unwrap();
+        let pkcs7 = Pkcs7::from_der(&der).unwrap();
+        let pem = pkcs7.to_pem().unwrap();
+        let pkcs7 = Pkcs7::from_pem(&pem).unwrap();
+
+        let mut output = Vec::new();
+        pkcs7
+            .verify(&certs, &store, None, Some(&mut output), flags)
+            .unwrap();
+
+        assert_eq!(output, message.as_bytes());
+    }
+
+    #[test]
+    fn invalid_from_smime() {
+        let input = String::from("Invalid SMIME Message");
+        let result = Pkcs7::from_smime(input.as_bytes());
+
+        assert!(result.is_err())
+    }
+}
@@ -45,7 +45,8 @@
 //! }
 //! ```
 
-use libc::c_int;
+use libc::{c_int, c_long};
+use std::cmp;
 use std::ptr;
 use std::mem;
 use std::ffi::CString;
@@ -57,8 +58,11 @@ use bio::MemBioSlice;
 use dh::Dh;
 use dsa::Dsa;
 use ec::EcKey;
+use encrypt::Decrypter;
+use hash::MessageDigest;
 use rsa::Rsa;
 use error::ErrorStack;
+use sign::Signer;
 use util::{invoke_passwd_cb, CallbackState};
 
 /// A tag type indicating that a key only has parameters.
@@ -90,6 +94,15 @@ impl Id {
     pub const DSA: Id = Id(ffi::EVP_PKEY_DSA);
     pub const DH: Id = Id(ffi::EVP_PKEY_DH);
     pub const EC: Id = Id(ffi::EVP_PKEY_EC);
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub const SM2: Id = Id(ffi::EVP_PKEY_SM2);
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub const X25519: Id = Id(ffi::EVP_PKEY_X25519);
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub const ED25519: Id = Id(ffi::EVP_PKEY_ED25519);
 }
 
 /// A trait indicating that a key has parameters.
@@ -186,6 +199,37 @@ impl<T> PKeyRef<T> {
     pub fn id(&self) -> Id {
         unsafe { Id::from_raw(ffi::EVP_PKEY_id(self.as_ptr())) }
     }
+
+    /// Changes the type used to select the algorithm implementation for this key.
+    ///
+    /// This is used to treat an elliptic curve key on the SM2 curve as an SM2 key, by passing
+    /// `Id::SM2`.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`EVP_PKEY_set_alias_type`].
+    ///
+    /// [`EVP_PKEY_set_alias_type`]: https://www.openssl.org/docs/man1.1.1/man3/EVP_PKEY_set_alias_type.html
+    #[cfg(ossl111)]
+    pub fn set_alias_type(&mut self, id: Id) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::EVP_PKEY_set_alias_type(self.as_ptr(), id.as_raw())).map(|_| ()) }
+    }
+
+    /// Compares the parameters of this key with another.
+    ///
+    /// For example, two elliptic curve keys have equal parameters if they are on the same curve.
+    /// Returns `false` if the keys are of different types, or if the key type has no parameters.
+    ///
+    /// This corresponds to [`EVP_PKEY_cmp_parameters`].
+    ///
+    /// [`EVP_PKEY_cmp_parameters`]: https://www.openssl.org/docs/man1.1.0/crypto/EVP_PKEY_cmp_parameters.html
+    pub fn parameters_eq<U>(&self, other: &PKeyRef<U>) -> bool
+    where
+        T: HasParams,
+        U: HasParams,
+    {
+        unsafe { ffi::EVP_PKEY_cmp_parameters(self.as_ptr(), other.as_ptr()) == 1 }
+    }
 }
 
 impl<T> PKeyRef<T>
@@ -223,12 +267,47 @@ where
     }
 
     /// Compares the public component of this key with another.
+    ///
+    /// This can be used to check that a private key corresponds to a certificate's public key.
+    ///
+    /// This corresponds to [`EVP_PKEY_cmp`].
+    ///
+    /// [`EVP_PKEY_cmp`]: https://www.openssl.org/docs/man1.1.0/crypto/EVP_PKEY_cmp.html
     pub fn public_eq<U>(&self, other: &PKeyRef<U>) -> bool
     where
         U: HasPublic,
     {
         unsafe { ffi::EVP_PKEY_cmp(self.as_ptr(), other.as_ptr()) == 1 }
     }
+
+    /// Returns the raw bytes of the public key.
+    ///
+    /// This is only supported by key types with a raw encoding, such as X25519 and Ed25519.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`EVP_PKEY_get_raw_public_key`].
+    ///
+    /// [`EVP_PKEY_get_raw_public_key`]: https://www.openssl.org/docs/man1.1.1/man3/EVP_PKEY_get_raw_public_key.html
+    #[cfg(ossl111)]
+    pub fn raw_public_key(&self) -> Result<Vec<u8>, ErrorStack> {
+        unsafe {
+            let mut len = 0;
+            cvt(ffi::EVP_PKEY_get_raw_public_key(
+                self.as_ptr(),
+                ptr::null_mut(),
+                &mut len,
+            ))?;
+            let mut buf = vec![0; len];
+            cvt(ffi::EVP_PKEY_get_raw_public_key(
+                self.as_ptr(),
+                buf.as_mut_ptr(),
+                &mut len,
+            ))?;
+            buf.truncate(len);
+            Ok(buf)
+        }
+    }
 }
 
 impl<T> PKeyRef<T>
@@ -264,6 +343,51 @@ where
         private_key_to_der,
         ffi::i2d_PrivateKey
     }
+
+    private_key_to_pem! {
+        /// Serializes the private key to a DER-encoded PKCS#8 PrivateKeyInfo structure.
+        ///
+        /// This corresponds to [`i2d_PKCS8PrivateKey_bio`].
+        ///
+        /// [`i2d_PKCS8PrivateKey_bio`]: https://www.openssl.org/docs/man1.1.0/crypto/i2d_PKCS8PrivateKey_bio.html
+        private_key_to_der_pkcs8,
+        /// Serializes the private key to a DER-encoded PKCS#8 EncryptedPrivateKeyInfo structure.
+        ///
+        /// This corresponds to [`i2d_PKCS8PrivateKey_bio`].
+        ///
+        /// [`i2d_PKCS8PrivateKey_bio`]: https://www.openssl.org/docs/man1.1.0/crypto/i2d_PKCS8PrivateKey_bio.html
+        private_key_to_der_pkcs8_passphrase,
+        ffi::i2d_PKCS8PrivateKey_bio
+    }
+
+    /// Returns the raw bytes of the private key.
+    ///
+    /// This is only supported by key types with a raw encoding, such as X25519 and Ed25519.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`EVP_PKEY_get_raw_private_key`].
+    ///
+    /// [`EVP_PKEY_get_raw_private_key`]: https://www.openssl.org/docs/man1.1.1/man3/EVP_PKEY_get_raw_private_key.html
+    #[cfg(ossl111)]
+    pub fn raw_private_key(&self) -> Result<Vec<u8>, ErrorStack> {
+        unsafe {
+            let mut len = 0;
+            cvt(ffi::EVP_PKEY_get_raw_private_key(
+                self.as_ptr(),
+                ptr::null_mut(),
+                &mut len,
+            ))?;
+            let mut buf = vec![0; len];
+            cvt(ffi::EVP_PKEY_get_raw_private_key(
+                self.as_ptr(),
+                buf.as_mut_ptr(),
+                &mut len,
+            ))?;
+            buf.truncate(len);
+            Ok(buf)
+        }
+    }
 }
 
 impl<T> PKey<T> {
@@ -345,6 +469,60 @@ impl<T> PKey<T> {
 }
 
 impl PKey<Private> {
+    #[cfg(ossl111)]
+    fn generate_raw(id: Id) -> Result<PKey<Private>, ErrorStack> {
+        unsafe {
+            ffi::init();
+            let kctx = cvt_p(ffi::EVP_PKEY_CTX_new_id(id.as_raw(), ptr::null_mut()))?;
+
+            let mut key = ptr::null_mut();
+            let ret = cvt(ffi::EVP_PKEY_keygen_init(kctx))
+                .and_then(|_| cvt(ffi::EVP_PKEY_keygen(kctx, &mut key)));
+
+            ffi::EVP_PKEY_CTX_free(kctx);
+
+            ret.map(|_| PKey::from_ptr(key))
+        }
+    }
+
+    /// Generates a new X25519 private key.
+    ///
+    /// To compute shared secrets, use the `derive` module.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub fn generate_x25519() -> Result<PKey<Private>, ErrorStack> {
+        PKey::generate_raw(Id::X25519)
+    }
+
+    /// Generates a new Ed25519 private key.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub fn generate_ed25519() -> Result<PKey<Private>, ErrorStack> {
+        PKey::generate_raw(Id::ED25519)
+    }
+
+    /// Creates a private key of the specified type from its raw byte representation.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`EVP_PKEY_new_raw_private_key`].
+    ///
+    /// [`EVP_PKEY_new_raw_private_key`]: https://www.openssl.org/docs/man1.1.1/man3/EVP_PKEY_new_raw_private_key.html
+    #[cfg(ossl111)]
+    pub fn private_key_from_raw_bytes(bytes: &[u8], id: Id) -> Result<PKey<Private>, ErrorStack> {
+        unsafe {
+            ffi::init();
+            cvt_p(ffi::EVP_PKEY_new_raw_private_key(
+                id.as_raw(),
+                ptr::null_mut(),
+                bytes.as_ptr(),
+                bytes.len(),
+            )).map(|p| PKey::from_ptr(p))
+        }
+    }
+
     /// Creates a new `PKey` containing an HMAC key.
     ///
     /// # Note
@@ -369,7 +547,8 @@ impl PKey<Private> {
     ///
     /// # Note
     ///
-    /// To compute CMAC values, use the `sign` module.
+    /// To compute CMAC values, use the `sign` module. On older OpenSSL versions, the `cmac` module
+    /// can be used instead.
     #[cfg(ossl110)]
     pub fn cmac(cipher: &::symm::Cipher, key: &[u8]) -> Result<PKey<Private>, ErrorStack> {
         unsafe {
@@ -467,6 +646,37 @@ impl PKey<Private> {
         ffi::d2i_AutoPrivateKey
     }
 
+    from_der_strict! {
+        /// Like `private_key_from_der`, but rejects input larger than `max_len` bytes or with
+        /// trailing data.
+        ///
+        /// See the [`parse`] module for details.
+        ///
+        /// [`parse`]: ../parse/index.html
+        private_key_from_der_strict,
+        PKey<Private>,
+        ffi::d2i_AutoPrivateKey
+    }
+
+    /// Deserializes a DER-formatted unencrypted PKCS#8 PrivateKeyInfo structure.
+    ///
+    /// Unlike `private_key_from_der`, key type specific formats are rejected.
+    ///
+    /// This corresponds to [`d2i_PKCS8_PRIV_KEY_INFO`] followed by `EVP_PKCS82PKEY`.
+    ///
+    /// [`d2i_PKCS8_PRIV_KEY_INFO`]: https://www.openssl.org/docs/man1.1.0/crypto/d2i_PKCS8_PRIV_KEY_INFO.html
+    pub fn private_key_from_pkcs8(der: &[u8]) -> Result<PKey<Private>, ErrorStack> {
+        unsafe {
+            ffi::init();
+            let len = cmp::min(der.len(), c_long::max_value() as usize);
+            let mut p = der.as_ptr();
+            let p8 = cvt_p(ffi::d2i_PKCS8_PRIV_KEY_INFO(ptr::null_mut(), &mut p, len as c_long))?;
+            let key = cvt_p(ffi::EVP_PKCS82PKEY(p8)).map(|k| PKey::from_ptr(k));
+            ffi::PKCS8_PRIV_KEY_INFO_free(p8);
+            key
+        }
+    }
+
     /// Deserializes a DER-formatted PKCS#8 private key, using a callback to retrieve the password
     /// if the key is encrpyted.
     ///
@@ -517,6 +727,26 @@ impl PKey<Private> {
 }
 
 impl PKey<Public> {
+    /// Creates a public key of the specified type from its raw byte representation.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`EVP_PKEY_new_raw_public_key`].
+    ///
+    /// [`EVP_PKEY_new_raw_public_key`]: https://www.openssl.org/docs/man1.1.1/man3/EVP_PKEY_new_raw_public_key.html
+    #[cfg(ossl111)]
+    pub fn public_key_from_raw_bytes(bytes: &[u8], id: Id) -> Result<PKey<Public>, ErrorStack> {
+        unsafe {
+            ffi::init();
+            cvt_p(ffi::EVP_PKEY_new_raw_public_key(
+                id.as_raw(),
+                ptr::null_mut(),
+                bytes.as_ptr(),
+                bytes.len(),
+            )).map(|p| PKey::from_ptr(p))
+        }
+    }
+
     from_pem! {
         /// Decodes a PEM-encoded SubjectPublicKeyInfo structure.
         ///
@@ -530,6 +760,18 @@ impl PKey<Public> {
         ffi::PEM_read_bio_PUBKEY
     }
 
+    from_pem_strict! {
+        /// Like `public_key_from_pem`, but rejects input larger than `max_len` bytes or containing
+        /// anything other than whitespace around the PEM block.
+        ///
+        /// See the [`parse`] module for details.
+        ///
+        /// [`parse`]: ../parse/index.html
+        public_key_from_pem_strict,
+        PKey<Public>,
+        ffi::PEM_read_bio_PUBKEY
+    }
+
     from_der! {
         /// Decodes a DER-encoded SubjectPublicKeyInfo structure.
         ///
@@ -540,6 +782,99 @@ impl PKey<Public> {
         PKey<Public>,
         ffi::d2i_PUBKEY
     }
+
+    from_der_strict! {
+        /// Like `public_key_from_der`, but rejects input larger than `max_len` bytes or with
+        /// trailing data.
+        ///
+        /// See the [`parse`] module for details.
+        ///
+        /// [`parse`]: ../parse/index.html
+        public_key_from_der_strict,
+        PKey<Public>,
+        ffi::d2i_PUBKEY
+    }
+}
+
+/// A private key which can only be used to create signatures.
+///
+/// Wrapping a key in a `SigningKey` restricts the operations that can be performed with it at the
+/// type level, so that a key intended for signing cannot accidentally be used for decryption or
+/// key agreement. The public half of the key remains available.
+pub struct SigningKey<T>(PKey<T>);
+
+impl<T> SigningKey<T>
+where
+    T: HasPrivate,
+{
+    /// Restricts `key` to signing operations.
+    pub fn new(key: PKey<T>) -> SigningKey<T> {
+        SigningKey(key)
+    }
+
+    /// Returns a `Signer` which signs data with this key.
+    pub fn signer(&self, digest: MessageDigest) -> Result<Signer, ErrorStack> {
+        Signer::new(digest, &self.0)
+    }
+
+    /// Returns a `Signer` which signs data with this key without a digest.
+    ///
+    /// This is intended for algorithms such as Ed25519 which do not take a separate digest.
+    pub fn signer_without_digest(&self) -> Result<Signer, ErrorStack> {
+        Signer::new_without_digest(&self.0)
+    }
+
+    /// Returns the public half of this key, which can be used to verify its signatures.
+    pub fn public_key(&self) -> Result<PKey<Public>, ErrorStack> {
+        PKey::public_key_from_der(&self.0.public_key_to_der()?)
+    }
+
+    /// Returns the size of the key in bits.
+    pub fn bits(&self) -> u32 {
+        self.0.bits()
+    }
+
+    /// Removes the usage restriction, returning the underlying key.
+    pub fn into_inner(self) -> PKey<T> {
+        self.0
+    }
+}
+
+/// A private key which can only be used to decrypt data.
+///
+/// Wrapping a key in a `DecryptionKey` restricts the operations that can be performed with it at
+/// the type level, so that a key intended for decryption cannot accidentally be used to create
+/// signatures. The public half of the key remains available.
+pub struct DecryptionKey<T>(PKey<T>);
+
+impl<T> DecryptionKey<T>
+where
+    T: HasPrivate,
+{
+    /// Restricts `key` to decryption operations.
+    pub fn new(key: PKey<T>) -> DecryptionKey<T> {
+        DecryptionKey(key)
+    }
+
+    /// Returns a `Decrypter` which decrypts data with this key.
+    pub fn decrypter(&self) -> Result<Decrypter, ErrorStack> {
+        Decrypter::new(&self.0)
+    }
+
+    /// Returns the public half of this key, which can be used to encrypt data to it.
+    pub fn public_key(&self) -> Result<PKey<Public>, ErrorStack> {
+        PKey::public_key_from_der(&self.0.public_key_to_der()?)
+    }
+
+    /// Returns the size of the key in bits.
+    pub fn bits(&self) -> u32 {
+        self.0.bits()
+    }
+
+    /// Removes the usage restriction, returning the underlying key.
+    pub fn into_inner(self) -> PKey<T> {
+        self.0
+    }
 }
 
 #[cfg(test)]
@@ -563,6 +898,32 @@ mod tests {
         assert!(PKey::private_key_from_pem_passphrase(&pem, b"fizzbuzz").is_err());
     }
 
+    #[test]
+    fn test_pkcs8_der() {
+        let ec_key = EcKey::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ec_key = EcKey::generate(ec_key.group()).unwrap();
+        let pkey = PKey::from_ec_key(ec_key).unwrap();
+
+        let der = pkey.private_key_to_der_pkcs8().unwrap();
+        let decoded = PKey::private_key_from_pkcs8(&der).unwrap();
+        assert_eq!(decoded.id(), Id::EC);
+        assert!(decoded.public_eq(&pkey));
+        assert!(PKey::private_key_from_pkcs8(&pkey.private_key_to_der().unwrap()).is_err());
+
+        let der = pkey.private_key_to_der_pkcs8_passphrase(Cipher::aes_256_cbc(), b"foobar")
+            .unwrap();
+        assert!(PKey::private_key_from_pkcs8(&der).is_err());
+        let decoded = PKey::private_key_from_pkcs8_passphrase(&der, b"foobar").unwrap();
+        assert!(decoded.public_eq(&pkey));
+        assert!(PKey::private_key_from_pkcs8_passphrase(&der, b"fizzbuzz").is_err());
+
+        let decoded = PKey::private_key_from_pkcs8_callback(&der, |password| {
+            password[..6].copy_from_slice(b"foobar");
+            Ok(6)
+        }).unwrap();
+        assert!(decoded.public_eq(&pkey));
+    }
+
     #[test]
     fn test_encrypted_pkcs8_passphrase() {
         let key = include_bytes!("../test/pkcs8.der");
@@ -581,6 +942,55 @@ mod tests {
         assert!(password_queried);
     }
 
+    #[test]
+    #[cfg(ossl111)]
+    fn test_x25519() {
+        use derive::Deriver;
+
+        let alice = PKey::generate_x25519().unwrap();
+        assert_eq!(alice.id(), Id::X25519);
+        let bob = PKey::generate_x25519().unwrap();
+
+        let bob_public = bob.raw_public_key().unwrap();
+        assert_eq!(bob_public.len(), 32);
+        let bob_public = PKey::public_key_from_raw_bytes(&bob_public, Id::X25519).unwrap();
+        assert!(bob_public.public_eq(&bob));
+
+        let mut deriver = Deriver::new(&alice).unwrap();
+        deriver.set_peer(&bob_public).unwrap();
+        let secret = deriver.derive_to_vec().unwrap();
+
+        let mut deriver = Deriver::new(&bob).unwrap();
+        deriver.set_peer(&alice).unwrap();
+        assert_eq!(secret, deriver.derive_to_vec().unwrap());
+    }
+
+    #[test]
+    #[cfg(ossl111)]
+    fn test_ed25519_raw_round_trip() {
+        let key = PKey::generate_ed25519().unwrap();
+        assert_eq!(key.id(), Id::ED25519);
+
+        let private = key.raw_private_key().unwrap();
+        assert_eq!(private.len(), 32);
+        let key2 = PKey::private_key_from_raw_bytes(&private, Id::ED25519).unwrap();
+        assert_eq!(key2.raw_private_key().unwrap(), private);
+        assert_eq!(key2.raw_public_key().unwrap(), key.raw_public_key().unwrap());
+    }
+
+    #[test]
+    #[cfg(all(ossl111, not(osslconf = "OPENSSL_NO_SM2")))]
+    fn test_sm2_alias() {
+        use ec::EcGroup;
+
+        let group = EcGroup::from_curve_name(Nid::SM2).unwrap();
+        let ec_key = EcKey::generate(&group).unwrap();
+        let mut pkey = PKey::from_ec_key(ec_key).unwrap();
+        assert_eq!(pkey.id(), Id::EC);
+        pkey.set_alias_type(Id::SM2).unwrap();
+        assert_eq!(pkey.id(), Id::SM2);
+    }
+
     #[test]
     fn test_private_key_from_pem() {
         let key = include_bytes!("../test/key.pem");
@@ -605,6 +1015,19 @@ mod tests {
         PKey::private_key_from_der(key).unwrap();
     }
 
+    #[test]
+    fn test_from_der_strict() {
+        let key = include_bytes!("../test/key.der");
+        PKey::private_key_from_der_strict(key, key.len()).unwrap();
+        assert!(PKey::private_key_from_der_strict(key, key.len() - 1).is_err());
+
+        let mut key = include_bytes!("../test/key.der.pub").to_vec();
+        PKey::public_key_from_der_strict(&key, key.len()).unwrap();
+        key.push(0);
+        let err = PKey::public_key_from_der_strict(&key, key.len()).err().unwrap();
+        assert_eq!(err.offset(), key.len() - 1);
+    }
+
     #[test]
     fn test_pem() {
         let key = include_bytes!("../test/key.pem");
@@ -655,4 +1078,57 @@ mod tests {
         assert_eq!(pkey.id(), Id::EC);
         assert!(pkey.rsa().is_err());
     }
+
+    #[test]
+    fn test_parameters_eq() {
+        use ec::EcGroup;
+
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let a = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let b = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        assert!(a.parameters_eq(&b));
+        assert!(!a.public_eq(&b));
+
+        let group = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+        let c = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        assert!(!a.parameters_eq(&c));
+
+        let rsa = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        assert!(!a.parameters_eq(&rsa));
+    }
+
+    #[test]
+    fn test_signing_key() {
+        use sign::Verifier;
+
+        let key = SigningKey::new(PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap());
+        assert_eq!(key.bits(), 2048);
+
+        let mut signer = key.signer(MessageDigest::sha256()).unwrap();
+        signer.update(b"hello world").unwrap();
+        let signature = signer.sign_to_vec().unwrap();
+
+        let public = key.public_key().unwrap();
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &public).unwrap();
+        verifier.update(b"hello world").unwrap();
+        assert!(verifier.verify(&signature).unwrap());
+    }
+
+    #[test]
+    fn test_decryption_key() {
+        use encrypt::Encrypter;
+
+        let key = DecryptionKey::new(PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap());
+
+        let public = key.public_key().unwrap();
+        let encrypter = Encrypter::new(&public).unwrap();
+        let mut encrypted = vec![0; encrypter.encrypt_len(b"hello world").unwrap()];
+        let len = encrypter.encrypt(b"hello world", &mut encrypted).unwrap();
+        encrypted.truncate(len);
+
+        let decrypter = key.decrypter().unwrap();
+        let mut decrypted = vec![0; decrypter.decrypt_len(&encrypted).unwrap()];
+        let len = decrypter.decrypt(&encrypted, &mut decrypted).unwrap();
+        assert_eq!(&decrypted[..len], b"hello world");
+    }
 }
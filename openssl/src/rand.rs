@@ -10,7 +10,10 @@
 //! let mut buf = [0; 256];
 //! rand_bytes(&mut buf).unwrap();
 //! ```
-use libc::c_int;
+//!
+//! OpenSSL seeds its generator automatically from the operating system. The seeding functions in
+//! this module are only needed to add entropy from other sources, or on platforms without one.
+use libc::{c_double, c_int};
 use ffi;
 
 use cvt;
@@ -40,13 +43,128 @@ pub fn rand_bytes(buf: &mut [u8]) -> Result<(), ErrorStack> {
     }
 }
 
+/// Fill buffer with cryptographically strong pseudo-random bytes from a generator reserved for
+/// private values.
+///
+/// This should be used for values which must remain secret, such as private keys, so that they
+/// are not generated by the same generator as values which are exposed publicly, such as nonces.
+///
+/// Requires OpenSSL 1.1.1 or newer.
+///
+/// This corresponds to [`RAND_priv_bytes`].
+///
+/// [`RAND_priv_bytes`]: https://www.openssl.org/docs/man1.1.1/man3/RAND_priv_bytes.html
+#[cfg(ossl111)]
+pub fn rand_priv_bytes(buf: &mut [u8]) -> Result<(), ErrorStack> {
+    unsafe {
+        ffi::init();
+        assert!(buf.len() <= c_int::max_value() as usize);
+        cvt(ffi::RAND_priv_bytes(buf.as_mut_ptr(), buf.len() as c_int)).map(|_| ())
+    }
+}
+
+/// Controls whether the random devices used for seeding, such as `/dev/urandom`, are kept open
+/// between uses.
+///
+/// They are kept open by default. Closing them can be necessary in processes which close all file
+/// descriptors, for example when daemonizing or before entering a chroot.
+///
+/// Requires OpenSSL 1.1.1 or newer.
+///
+/// This corresponds to [`RAND_keep_random_devices_open`].
+///
+/// [`RAND_keep_random_devices_open`]: https://www.openssl.org/docs/man1.1.1/man3/RAND_keep_random_devices_open.html
+#[cfg(ossl111)]
+pub fn keep_random_devices_open(keep: bool) {
+    unsafe {
+        ffi::init();
+        ffi::RAND_keep_random_devices_open(keep as c_int);
+    }
+}
+
+/// Mixes bytes into the state of the generator.
+///
+/// `entropy` is an estimate of the number of bytes of entropy contained in `buf`. It should be
+/// conservative, and 0 if the bytes are not secret.
+///
+/// This corresponds to [`RAND_add`].
+///
+/// [`RAND_add`]: https://www.openssl.org/docs/man1.1.0/crypto/RAND_add.html
+pub fn rand_add(buf: &[u8], entropy: f64) {
+    unsafe {
+        ffi::init();
+        assert!(buf.len() <= c_int::max_value() as usize);
+        ffi::RAND_add(buf.as_ptr() as *const _, buf.len() as c_int, entropy as c_double);
+    }
+}
+
+/// Mixes bytes into the state of the generator, assuming they are entirely random.
+///
+/// This is equivalent to `rand_add(buf, buf.len() as f64)`.
+///
+/// This corresponds to [`RAND_seed`].
+///
+/// [`RAND_seed`]: https://www.openssl.org/docs/man1.1.0/crypto/RAND_add.html
+pub fn rand_seed(buf: &[u8]) {
+    unsafe {
+        ffi::init();
+        assert!(buf.len() <= c_int::max_value() as usize);
+        ffi::RAND_seed(buf.as_ptr() as *const _, buf.len() as c_int);
+    }
+}
+
+/// Reseeds the generator from the operating system's entropy source.
+///
+/// This corresponds to [`RAND_poll`].
+///
+/// [`RAND_poll`]: https://www.openssl.org/docs/man1.1.0/crypto/RAND_add.html
+pub fn rand_poll() -> Result<(), ErrorStack> {
+    unsafe {
+        ffi::init();
+        cvt(ffi::RAND_poll()).map(|_| ())
+    }
+}
+
+/// Determines if the generator has been seeded with enough entropy.
+///
+/// This corresponds to [`RAND_status`].
+///
+/// [`RAND_status`]: https://www.openssl.org/docs/man1.1.0/crypto/RAND_add.html
+pub fn rand_status() -> bool {
+    unsafe {
+        ffi::init();
+        ffi::RAND_status() == 1
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::rand_bytes;
+    use super::*;
 
     #[test]
     fn test_rand_bytes() {
         let mut buf = [0; 32];
         rand_bytes(&mut buf).unwrap();
     }
+
+    #[test]
+    #[cfg(ossl111)]
+    fn test_rand_priv_bytes() {
+        let mut a = [0; 32];
+        let mut b = [0; 32];
+        rand_priv_bytes(&mut a).unwrap();
+        rand_priv_bytes(&mut b).unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_seeding() {
+        rand_add(b"some additional input", 0.);
+        rand_seed(&[0x42; 32]);
+        rand_poll().unwrap();
+        assert!(rand_status());
+
+        let mut buf = [0; 32];
+        rand_bytes(&mut buf).unwrap();
+    }
 }
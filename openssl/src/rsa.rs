@@ -725,6 +725,7 @@ mod compat {
 
 #[cfg(test)]
 mod test {
+    use error::ErrorKind;
     use symm::Cipher;
 
     use super::*;
@@ -735,6 +736,13 @@ mod test {
         Rsa::private_key_from_pem_passphrase(key, b"mypass").unwrap();
     }
 
+    #[test]
+    fn test_from_wrong_password() {
+        let key = include_bytes!("../test/rsa-encrypted.pem");
+        let err = Rsa::private_key_from_pem_passphrase(key, b"wrongpass").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WrongPassword);
+    }
+
     #[test]
     fn test_from_password_callback() {
         let mut password_queried = false;
@@ -0,0 +1,330 @@
+//! `Serialize` and `Deserialize` implementations, enabled by the `serde` feature.
+//!
+//! Keys and certificates are serialized as PEM strings, and can be deserialized from either PEM
+//! strings or PEM or DER bytes. Sessions are serialized as DER bytes.
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+use std::fmt;
+use std::str;
+
+use dsa::Dsa;
+use ec::EcKey;
+use error::ErrorStack;
+use pkey::{PKey, Private, Public};
+use rsa::Rsa;
+use ssl::SslSession;
+use x509::X509;
+
+const PEM_PREFIX: &'static [u8] = b"-----BEGIN ";
+
+fn serialize_pem<S>(pem: Result<Vec<u8>, ErrorStack>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let pem = pem.map_err(<S::Error as ser::Error>::custom)?;
+    let pem = str::from_utf8(&pem).map_err(<S::Error as ser::Error>::custom)?;
+    serializer.serialize_str(pem)
+}
+
+struct PemVisitor<T> {
+    expecting: &'static str,
+    from_pem: fn(&[u8]) -> Result<T, ErrorStack>,
+    from_der: fn(&[u8]) -> Result<T, ErrorStack>,
+}
+
+impl<'de, T> Visitor<'de> for PemVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.expecting)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        (self.from_pem)(v.as_bytes()).map_err(E::custom)
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        let result = if v.starts_with(PEM_PREFIX) {
+            (self.from_pem)(v)
+        } else {
+            (self.from_der)(v)
+        };
+        result.map_err(E::custom)
+    }
+}
+
+macro_rules! pem_serde {
+    ($t:ty, $to_pem:ident, $from_pem:ident, $from_der:ident, $expecting:expr) => {
+        impl Serialize for $t {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serialize_pem(self.$to_pem(), serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $t {
+            fn deserialize<D>(deserializer: D) -> Result<$t, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                deserializer.deserialize_str(PemVisitor {
+                    expecting: $expecting,
+                    from_pem: <$t>::$from_pem,
+                    from_der: <$t>::$from_der,
+                })
+            }
+        }
+    };
+}
+
+pem_serde!(X509, to_pem, from_pem, from_der, "a PEM or DER encoded certificate");
+pem_serde!(
+    PKey<Private>,
+    private_key_to_pem_pkcs8,
+    private_key_from_pem,
+    private_key_from_der,
+    "a PEM or DER encoded private key"
+);
+pem_serde!(
+    PKey<Public>,
+    public_key_to_pem,
+    public_key_from_pem,
+    public_key_from_der,
+    "a PEM or DER encoded public key"
+);
+pem_serde!(
+    Rsa<Private>,
+    private_key_to_pem,
+    private_key_from_pem,
+    private_key_from_der,
+    "a PEM or DER encoded RSA private key"
+);
+pem_serde!(
+    Rsa<Public>,
+    public_key_to_pem,
+    public_key_from_pem,
+    public_key_from_der,
+    "a PEM or DER encoded RSA public key"
+);
+pem_serde!(
+    Dsa<Private>,
+    private_key_to_pem,
+    private_key_from_pem,
+    private_key_from_der,
+    "a PEM or DER encoded DSA private key"
+);
+pem_serde!(
+    Dsa<Public>,
+    public_key_to_pem,
+    public_key_from_pem,
+    public_key_from_der,
+    "a PEM or DER encoded DSA public key"
+);
+pem_serde!(
+    EcKey<Private>,
+    private_key_to_pem,
+    private_key_from_pem,
+    private_key_from_der,
+    "a PEM or DER encoded EC private key"
+);
+pem_serde!(
+    EcKey<Public>,
+    public_key_to_pem,
+    public_key_from_pem,
+    public_key_from_der,
+    "a PEM or DER encoded EC public key"
+);
+
+impl Serialize for SslSession {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let der = self.to_der().map_err(<S::Error as ser::Error>::custom)?;
+        serializer.serialize_bytes(&der)
+    }
+}
+
+impl<'de> Deserialize<'de> for SslSession {
+    fn deserialize<D>(deserializer: D) -> Result<SslSession, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_byte_buf(SessionVisitor)
+    }
+}
+
+struct SessionVisitor;
+
+impl<'de> Visitor<'de> for SessionVisitor {
+    type Value = SslSession;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("a DER encoded session")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<SslSession, E>
+    where
+        E: de::Error,
+    {
+        SslSession::from_der(v).map_err(E::custom)
+    }
+
+    // formats without a native byte representation serialize bytes as a sequence
+    fn visit_seq<A>(self, mut seq: A) -> Result<SslSession, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut der = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(b) = seq.next_element()? {
+            der.push(b);
+        }
+        self.visit_bytes(&der)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::de::value::{BytesDeserializer, Error, StrDeserializer};
+    use serde::de::{Deserialize, DeserializeOwned, IntoDeserializer};
+    use serde::ser::Serialize;
+    use serde_json;
+
+    use dsa::Dsa;
+    use ec::{EcGroup, EcKey};
+    use nid::Nid;
+    use pkey::{PKey, Private, Public};
+    use rsa::Rsa;
+    use ssl::SslSession;
+    use test_utils;
+    use x509::X509;
+
+    fn from_str<'a, T>(s: &'a str) -> T
+    where
+        T: Deserialize<'a>,
+    {
+        let deserializer: StrDeserializer<Error> = s.into_deserializer();
+        T::deserialize(deserializer).unwrap()
+    }
+
+    fn from_bytes<'a, T>(b: &'a [u8]) -> T
+    where
+        T: Deserialize<'a>,
+    {
+        let deserializer: BytesDeserializer<Error> = BytesDeserializer::new(b);
+        T::deserialize(deserializer).unwrap()
+    }
+
+    fn round_trip<T>(value: &T) -> T
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let json = serde_json::to_string(value).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn deserialize_pem() {
+        let pem = include_str!("../test/cert.pem");
+        let cert = from_str::<X509>(pem);
+        let expected = X509::from_pem(pem.as_bytes()).unwrap();
+        assert_eq!(cert.to_der().unwrap(), expected.to_der().unwrap());
+
+        let pem = include_str!("../test/key.pem");
+        let key = from_str::<PKey<Private>>(pem);
+        let public = from_str::<PKey<Public>>(
+            ::std::str::from_utf8(&key.public_key_to_pem().unwrap()).unwrap(),
+        );
+        assert!(key.public_eq(&public));
+    }
+
+    #[test]
+    fn x509() {
+        let cert = X509::from_pem(include_bytes!("../test/cert.pem")).unwrap();
+        let der = cert.to_der().unwrap();
+
+        let json = serde_json::to_string(&cert).unwrap();
+        assert!(json.starts_with("\"-----BEGIN CERTIFICATE-----"));
+        assert_eq!(round_trip(&cert).to_der().unwrap(), der);
+        assert_eq!(from_bytes::<X509>(&der).to_der().unwrap(), der);
+        assert_eq!(from_bytes::<X509>(&cert.to_pem().unwrap()).to_der().unwrap(), der);
+    }
+
+    #[test]
+    fn pkey() {
+        let key = PKey::private_key_from_pem(include_bytes!("../test/key.pem")).unwrap();
+        let der = key.private_key_to_der().unwrap();
+        assert_eq!(round_trip(&key).private_key_to_der().unwrap(), der);
+        assert_eq!(from_bytes::<PKey<Private>>(&der).private_key_to_der().unwrap(), der);
+
+        let public = PKey::public_key_from_pem(&key.public_key_to_pem().unwrap()).unwrap();
+        let der = public.public_key_to_der().unwrap();
+        assert_eq!(round_trip(&public).public_key_to_der().unwrap(), der);
+        assert_eq!(from_bytes::<PKey<Public>>(&der).public_key_to_der().unwrap(), der);
+    }
+
+    #[test]
+    fn rsa() {
+        let key = Rsa::generate(2048).unwrap();
+        let der = key.private_key_to_der().unwrap();
+        assert_eq!(round_trip(&key).private_key_to_der().unwrap(), der);
+        assert_eq!(from_bytes::<Rsa<Private>>(&der).private_key_to_der().unwrap(), der);
+
+        let public = Rsa::public_key_from_pem(&key.public_key_to_pem().unwrap()).unwrap();
+        let der = public.public_key_to_der().unwrap();
+        assert_eq!(round_trip(&public).public_key_to_der().unwrap(), der);
+        assert_eq!(from_bytes::<Rsa<Public>>(&der).public_key_to_der().unwrap(), der);
+    }
+
+    #[test]
+    fn dsa() {
+        let key = Dsa::generate(1024).unwrap();
+        let der = key.private_key_to_der().unwrap();
+        assert_eq!(round_trip(&key).private_key_to_der().unwrap(), der);
+        assert_eq!(from_bytes::<Dsa<Private>>(&der).private_key_to_der().unwrap(), der);
+
+        let public = Dsa::public_key_from_pem(&key.public_key_to_pem().unwrap()).unwrap();
+        let der = public.public_key_to_der().unwrap();
+        assert_eq!(round_trip(&public).public_key_to_der().unwrap(), der);
+        assert_eq!(from_bytes::<Dsa<Public>>(&der).public_key_to_der().unwrap(), der);
+    }
+
+    #[test]
+    fn ec_key() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = EcKey::generate(&group).unwrap();
+        let der = key.private_key_to_der().unwrap();
+        assert_eq!(round_trip(&key).private_key_to_der().unwrap(), der);
+        assert_eq!(from_bytes::<EcKey<Private>>(&der).private_key_to_der().unwrap(), der);
+
+        let public = EcKey::public_key_from_pem(&key.public_key_to_pem().unwrap()).unwrap();
+        let der = public.public_key_to_der().unwrap();
+        assert_eq!(round_trip(&public).public_key_to_der().unwrap(), der);
+        assert_eq!(from_bytes::<EcKey<Public>>(&der).public_key_to_der().unwrap(), der);
+    }
+
+    #[test]
+    fn session() {
+        let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+        let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+        let connector = test_utils::connector(&cert).unwrap();
+        let (client, _server) = test_utils::connect(&connector, "foobar.com", &acceptor).unwrap();
+        let session = client.ssl().session().unwrap().to_owned();
+        let der = session.to_der().unwrap();
+
+        // JSON has no byte strings, so the session is serialized as a sequence
+        let json = serde_json::to_string(&session).unwrap();
+        assert!(json.starts_with('['));
+        assert_eq!(round_trip(&session).to_der().unwrap(), der);
+
+        assert_eq!(from_bytes::<SslSession>(&der).to_der().unwrap(), der);
+    }
+}
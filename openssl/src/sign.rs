@@ -80,6 +80,10 @@ use ffi::{EVP_MD_CTX_free, EVP_MD_CTX_new};
 use ffi::{EVP_MD_CTX_create as EVP_MD_CTX_new, EVP_MD_CTX_destroy as EVP_MD_CTX_free};
 
 /// Salt lengths that must be used with `set_rsa_pss_saltlen`.
+///
+/// The JWS `PS256`, `PS384`, and `PS512` algorithms ([RFC 7518]) require `DIGEST_LENGTH`.
+///
+/// [RFC 7518]: https://tools.ietf.org/html/rfc7518#section-3.5
 pub struct RsaPssSaltlen(c_int);
 
 impl RsaPssSaltlen {
@@ -99,6 +103,9 @@ impl RsaPssSaltlen {
     /// The salt length is set to the maximum permissible value.
     /// Corresponds to the special value `-2`.
     pub const MAXIMUM_LENGTH: RsaPssSaltlen = RsaPssSaltlen(-2);
+    /// When verifying, the salt length is recovered from the signature.
+    /// Corresponds to the special value `-2`, which selects `MAXIMUM_LENGTH` when signing.
+    pub const AUTO: RsaPssSaltlen = RsaPssSaltlen(-2);
 }
 
 /// A type which computes cryptographic signatures of data.
@@ -306,6 +313,59 @@ impl<'a> Signer<'a> {
         buf.truncate(len);
         Ok(buf)
     }
+
+    /// Signs the data in `data_buf`, writing the signature into `sig_buf` and returning its length.
+    ///
+    /// This is the only way to sign with key types which do not support streaming, such as
+    /// Ed25519, in which case the `Signer` must have been created without a digest. `update` must
+    /// not have been called.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// OpenSSL documentation at [`EVP_DigestSign`].
+    ///
+    /// [`EVP_DigestSign`]: https://www.openssl.org/docs/man1.1.1/man3/EVP_DigestSign.html
+    #[cfg(ossl111)]
+    pub fn sign_oneshot(
+        &mut self,
+        sig_buf: &mut [u8],
+        data_buf: &[u8],
+    ) -> Result<usize, ErrorStack> {
+        unsafe {
+            let mut sig_len = sig_buf.len();
+            cvt(ffi::EVP_DigestSign(
+                self.md_ctx,
+                sig_buf.as_mut_ptr(),
+                &mut sig_len,
+                data_buf.as_ptr(),
+                data_buf.len(),
+            ))?;
+            Ok(sig_len)
+        }
+    }
+
+    /// Returns the signature of the data in `data_buf`.
+    ///
+    /// This is a simple convenience wrapper over `sign_oneshot`.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub fn sign_oneshot_to_vec(&mut self, data_buf: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+        let mut sig_len = 0;
+        unsafe {
+            cvt(ffi::EVP_DigestSign(
+                self.md_ctx,
+                ptr::null_mut(),
+                &mut sig_len,
+                data_buf.as_ptr(),
+                data_buf.len(),
+            ))?;
+        }
+        let mut sig_buf = vec![0; sig_len];
+        let len = self.sign_oneshot(&mut sig_buf, data_buf)?;
+        sig_buf.truncate(len);
+        Ok(sig_buf)
+    }
 }
 
 impl<'a> Write for Signer<'a> {
@@ -345,6 +405,31 @@ impl<'a> Verifier<'a> {
     ///
     /// [`EVP_DigestVerifyInit`]: https://www.openssl.org/docs/manmaster/man3/EVP_DigestVerifyInit.html
     pub fn new<T>(type_: MessageDigest, pkey: &'a PKeyRef<T>) -> Result<Verifier<'a>, ErrorStack>
+    where
+        T: HasPublic,
+    {
+        Verifier::new_intern(Some(type_), pkey)
+    }
+
+    /// Creates a new `Verifier` without a digest.
+    ///
+    /// This is used with key types which do not support streaming, such as Ed25519, together with
+    /// `verify_oneshot`.
+    ///
+    /// OpenSSL documentation at [`EVP_DigestVerifyInit`].
+    ///
+    /// [`EVP_DigestVerifyInit`]: https://www.openssl.org/docs/manmaster/man3/EVP_DigestVerifyInit.html
+    pub fn new_without_digest<T>(pkey: &'a PKeyRef<T>) -> Result<Verifier<'a>, ErrorStack>
+    where
+        T: HasPublic,
+    {
+        Verifier::new_intern(None, pkey)
+    }
+
+    fn new_intern<T>(
+        type_: Option<MessageDigest>,
+        pkey: &'a PKeyRef<T>,
+    ) -> Result<Verifier<'a>, ErrorStack>
     where
         T: HasPublic,
     {
@@ -356,7 +441,7 @@ impl<'a> Verifier<'a> {
             let r = ffi::EVP_DigestVerifyInit(
                 ctx,
                 &mut pctx,
-                type_.as_ptr(),
+                type_.map(|t| t.as_ptr()).unwrap_or(ptr::null()),
                 ptr::null_mut(),
                 pkey.as_ptr(),
             );
@@ -470,6 +555,38 @@ impl<'a> Verifier<'a> {
             }
         }
     }
+
+    /// Determines if the data in `buf` matches the provided signature.
+    ///
+    /// This is the only way to verify signatures from key types which do not support streaming,
+    /// such as Ed25519, in which case the `Verifier` must have been created without a digest.
+    /// `update` must not have been called.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// OpenSSL documentation at [`EVP_DigestVerify`].
+    ///
+    /// [`EVP_DigestVerify`]: https://www.openssl.org/docs/man1.1.1/man3/EVP_DigestVerify.html
+    #[cfg(ossl111)]
+    pub fn verify_oneshot(&mut self, signature: &[u8], buf: &[u8]) -> Result<bool, ErrorStack> {
+        unsafe {
+            let r = ffi::EVP_DigestVerify(
+                self.md_ctx,
+                signature.as_ptr(),
+                signature.len(),
+                buf.as_ptr(),
+                buf.len(),
+            );
+            match r {
+                1 => Ok(true),
+                0 => {
+                    ErrorStack::get(); // discard error stack
+                    Ok(false)
+                }
+                _ => Err(ErrorStack::get()),
+            }
+        }
+    }
 }
 
 impl<'a> Write for Verifier<'a> {
@@ -503,6 +620,7 @@ mod test {
 
     use hash::MessageDigest;
     use sign::{RsaPssSaltlen, Signer, Verifier};
+    use dsa::Dsa;
     use ec::{EcGroup, EcKey};
     use nid::Nid;
     use rsa::{Padding, Rsa};
@@ -678,6 +796,22 @@ mod test {
         assert_eq!(signer.sign_to_vec().unwrap(), expected);
     }
 
+    #[test]
+    #[cfg(ossl111)]
+    fn eddsa() {
+        let key = PKey::generate_ed25519().unwrap();
+
+        let mut signer = Signer::new_without_digest(&key).unwrap();
+        let signature = signer.sign_oneshot_to_vec(b"hello world").unwrap();
+        assert_eq!(signature.len(), 64);
+
+        let mut verifier = Verifier::new_without_digest(&key).unwrap();
+        assert!(verifier.verify_oneshot(&signature, b"hello world").unwrap());
+
+        let mut verifier = Verifier::new_without_digest(&key).unwrap();
+        assert!(!verifier.verify_oneshot(&signature, b"hello there").unwrap());
+    }
+
     #[test]
     fn ec() {
         let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
@@ -693,6 +827,24 @@ mod test {
         assert!(verifier.verify(&signature).unwrap());
     }
 
+    #[test]
+    fn dsa() {
+        let key = Dsa::generate(2048).unwrap();
+        let key = PKey::from_dsa(key).unwrap();
+
+        let mut signer = Signer::new(MessageDigest::sha256(), &key).unwrap();
+        signer.update(b"hello world").unwrap();
+        let signature = signer.sign_to_vec().unwrap();
+
+        let public = PKey::public_key_from_der(&key.public_key_to_der().unwrap()).unwrap();
+        let dsa = public.dsa().unwrap();
+        assert_eq!(dsa.p(), key.dsa().unwrap().p());
+
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &public).unwrap();
+        verifier.update(b"hello world").unwrap();
+        assert!(verifier.verify(&signature).unwrap());
+    }
+
     #[test]
     fn rsa_sign_verify() {
         let key = include_bytes!("../test/rsa.pem");
@@ -0,0 +1,226 @@
+use ffi;
+use foreign_types::ForeignTypeRef;
+use libc::c_int;
+use std::fmt;
+
+use error::ErrorStack;
+use ssl::{compat, Ssl, SslCipherRef, SslContextRef, SslVersion};
+
+// the lowest number of bits of security a cipher may provide without being reported as weak
+const MIN_SECRET_BITS: i32 = 112;
+
+/// The check which produced an `AuditFinding`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AuditCheck {
+    /// An export-grade cipher is enabled.
+    ExportCipher,
+    /// A cipher which does not encrypt traffic is enabled.
+    NullCipher,
+    /// A cipher which does not authenticate the peer is enabled.
+    AnonymousCipher,
+    /// An RC4 cipher is enabled.
+    Rc4Cipher,
+    /// A cipher providing fewer than 112 bits of security is enabled.
+    WeakCipher,
+    /// SSLv3 is enabled.
+    InsecureProtocol,
+    /// TLS 1.0 or TLS 1.1 is enabled.
+    DeprecatedProtocol,
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+/// A weakness in the configuration of an `SslContext`.
+#[derive(Debug, Clone)]
+pub struct AuditFinding {
+    check: AuditCheck,
+    message: String,
+}
+
+impl AuditFinding {
+    fn new(check: AuditCheck, message: String) -> AuditFinding {
+        AuditFinding { check, message }
+    }
+
+    /// Returns the check which produced this finding.
+    pub fn check(&self) -> AuditCheck {
+        self.check
+    }
+
+    /// Returns a description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for AuditFinding {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.message)
+    }
+}
+
+/// The ciphers and protocol versions enabled by an `SslContext`, and any weaknesses among them.
+///
+/// This is returned by [`SslContextRef::audit`].
+///
+/// [`SslContextRef::audit`]: struct.SslContextRef.html#method.audit
+#[derive(Debug, Clone)]
+pub struct SecurityAudit {
+    protocols: Vec<SslVersion>,
+    ciphers: Vec<String>,
+    findings: Vec<AuditFinding>,
+}
+
+impl SecurityAudit {
+    /// Returns the enabled protocol versions, from lowest to highest.
+    pub fn protocols(&self) -> &[SslVersion] {
+        &self.protocols
+    }
+
+    /// Returns the names of the enabled ciphers, in order of preference.
+    pub fn ciphers(&self) -> &[String] {
+        &self.ciphers
+    }
+
+    /// Returns the weaknesses found.
+    pub fn findings(&self) -> &[AuditFinding] {
+        &self.findings
+    }
+
+    /// Determines if no weaknesses were found.
+    pub fn is_secure(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+pub fn audit(ctx: &SslContextRef) -> Result<SecurityAudit, ErrorStack> {
+    let mut findings = vec![];
+
+    let protocols = enabled_protocols(ctx);
+    for &(version, name) in &protocols {
+        if version == SslVersion::SSL3 {
+            findings.push(AuditFinding::new(
+                AuditCheck::InsecureProtocol,
+                format!("{} is enabled", name),
+            ));
+        } else if version == SslVersion::TLS1 || version == SslVersion::TLS1_1 {
+            findings.push(AuditFinding::new(
+                AuditCheck::DeprecatedProtocol,
+                format!("{} is enabled, but is deprecated", name),
+            ));
+        }
+    }
+
+    // the cipher list is only exposed directly by the context on OpenSSL 1.1.0 and newer
+    let ssl = Ssl::new(ctx)?;
+    let mut ciphers = vec![];
+    if let Some(stack) = ssl.ciphers() {
+        for cipher in stack {
+            check_cipher(cipher, &mut findings);
+            ciphers.push(cipher.name().to_string());
+        }
+    }
+
+    Ok(SecurityAudit {
+        protocols: protocols.into_iter().map(|(version, _)| version).collect(),
+        ciphers,
+        findings,
+    })
+}
+
+fn check_cipher(cipher: &SslCipherRef, findings: &mut Vec<AuditFinding>) {
+    let name = cipher.name();
+    let description = cipher.description();
+    let encryption = field(&description, "Enc=").unwrap_or("");
+    let authentication = field(&description, "Au=").unwrap_or("");
+
+    // OpenSSL 1.0.x marks export ciphers at the end of their descriptions
+    if name.starts_with("EXP") || description.split_whitespace().any(|f| f == "export") {
+        findings.push(AuditFinding::new(
+            AuditCheck::ExportCipher,
+            format!("the export cipher {} is enabled", name),
+        ));
+    }
+
+    if encryption == "None" {
+        findings.push(AuditFinding::new(
+            AuditCheck::NullCipher,
+            format!("the cipher {} does not encrypt traffic", name),
+        ));
+    } else {
+        let bits = cipher.bits().secret;
+        if bits < MIN_SECRET_BITS {
+            findings.push(AuditFinding::new(
+                AuditCheck::WeakCipher,
+                format!(
+                    "the cipher {} provides {} bits of security, but at least {} are required",
+                    name, bits, MIN_SECRET_BITS
+                ),
+            ));
+        }
+    }
+
+    if authentication == "None" {
+        findings.push(AuditFinding::new(
+            AuditCheck::AnonymousCipher,
+            format!("the cipher {} does not authenticate the peer", name),
+        ));
+    }
+
+    if encryption.starts_with("RC4") {
+        findings.push(AuditFinding::new(
+            AuditCheck::Rc4Cipher,
+            format!("the RC4 cipher {} is enabled", name),
+        ));
+    }
+}
+
+// Returns the value of a `key=value` field of a cipher description.
+fn field<'a>(description: &'a str, key: &str) -> Option<&'a str> {
+    description
+        .split_whitespace()
+        .find(|f| f.starts_with(key))
+        .map(|f| &f[key.len()..])
+}
+
+fn enabled_protocols(ctx: &SslContextRef) -> Vec<(SslVersion, &'static str)> {
+    let options = unsafe { compat::SSL_CTX_get_options(ctx.as_ptr()) };
+    let (min, max) = proto_version_bounds(ctx);
+
+    // protocol versions paired with the options disabling them, from lowest to highest
+    #[cfg_attr(not(ossl111), allow(unused_mut))]
+    let mut protocols = vec![
+        (SslVersion::SSL3, ffi::SSL_OP_NO_SSLv3, "SSLv3"),
+        (SslVersion::TLS1, ffi::SSL_OP_NO_TLSv1, "TLSv1.0"),
+        (SslVersion::TLS1_1, ffi::SSL_OP_NO_TLSv1_1, "TLSv1.1"),
+        (SslVersion::TLS1_2, ffi::SSL_OP_NO_TLSv1_2, "TLSv1.2"),
+    ];
+    #[cfg(ossl111)]
+    protocols.push((SslVersion::TLS1_3, ffi::SSL_OP_NO_TLSv1_3, "TLSv1.3"));
+
+    protocols
+        .into_iter()
+        // LibreSSL does not support SSLv3, and defines its option as 0
+        .filter(|&(_, op, _)| op != 0 && options & op == 0)
+        .filter(|&(version, _, _)| {
+            (min == 0 || version.0 >= min) && (max == 0 || version.0 <= max)
+        })
+        .map(|(version, _, name)| (version, name))
+        .collect()
+}
+
+#[cfg(any(ossl110g, ossl10x))]
+fn proto_version_bounds(ctx: &SslContextRef) -> (c_int, c_int) {
+    unsafe {
+        (
+            compat::SSL_CTX_get_min_proto_version(ctx.as_ptr()),
+            compat::SSL_CTX_get_max_proto_version(ctx.as_ptr()),
+        )
+    }
+}
+
+// the bounds cannot be queried before OpenSSL 1.1.0g
+#[cfg(not(any(ossl110g, ossl10x)))]
+fn proto_version_bounds(_: &SslContextRef) -> (c_int, c_int) {
+    (0, 0)
+}
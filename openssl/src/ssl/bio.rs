@@ -8,6 +8,8 @@ use std::mem;
 use std::panic::{AssertUnwindSafe, catch_unwind};
 use std::ptr;
 use std::slice;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use cvt_p;
 use error::ErrorStack;
@@ -16,6 +18,7 @@ pub struct StreamState<S> {
     pub stream: S,
     pub error: Option<io::Error>,
     pub panic: Option<Box<Any + Send>>,
+    pub aborted: Arc<AtomicBool>,
 }
 
 /// Safe wrapper for BIO_METHOD
@@ -37,6 +40,7 @@ pub fn new<S: Read + Write>(stream: S) -> Result<(*mut BIO, BioMethod), ErrorSta
         stream: stream,
         error: None,
         panic: None,
+        aborted: Arc::new(AtomicBool::new(false)),
     });
 
     unsafe {
@@ -58,6 +62,20 @@ pub unsafe fn take_panic<S>(bio: *mut BIO) -> Option<Box<Any + Send>> {
     state.panic.take()
 }
 
+/// Removes the stream from the BIO, leaving it uninitialized.
+pub unsafe fn take_stream<S>(bio: *mut BIO) -> S {
+    let data = compat::BIO_get_data(bio);
+    assert!(!data.is_null());
+    let state = *Box::<StreamState<S>>::from_raw(data as *mut _);
+    compat::BIO_set_data(bio, ptr::null_mut());
+    compat::BIO_set_init(bio, 0);
+    state.stream
+}
+
+pub unsafe fn abort_flag<S>(bio: *mut BIO) -> Arc<AtomicBool> {
+    state::<S>(bio).aborted.clone()
+}
+
 pub unsafe fn get_ref<'a, S: 'a>(bio: *mut BIO) -> &'a S {
     let state: &'a StreamState<S> = mem::transmute(compat::BIO_get_data(bio));
     &state.stream
@@ -75,6 +93,10 @@ unsafe extern "C" fn bwrite<S: Write>(bio: *mut BIO, buf: *const c_char, len: c_
     BIO_clear_retry_flags(bio);
 
     let state = state::<S>(bio);
+    if state.aborted.load(Ordering::SeqCst) {
+        state.error = Some(aborted_error());
+        return -1;
+    }
     let buf = slice::from_raw_parts(buf as *const _, len as usize);
 
     match catch_unwind(AssertUnwindSafe(|| state.stream.write(buf))) {
@@ -97,6 +119,10 @@ unsafe extern "C" fn bread<S: Read>(bio: *mut BIO, buf: *mut c_char, len: c_int)
     BIO_clear_retry_flags(bio);
 
     let state = state::<S>(bio);
+    if state.aborted.load(Ordering::SeqCst) {
+        state.error = Some(aborted_error());
+        return -1;
+    }
     let buf = slice::from_raw_parts_mut(buf as *mut _, len as usize);
 
     match catch_unwind(AssertUnwindSafe(|| state.stream.read(buf))) {
@@ -115,6 +141,10 @@ unsafe extern "C" fn bread<S: Read>(bio: *mut BIO, buf: *mut c_char, len: c_int)
     }
 }
 
+fn aborted_error() -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted, "the stream was aborted")
+}
+
 fn retriable_error(err: &io::Error) -> bool {
     match err.kind() {
         io::ErrorKind::WouldBlock |
@@ -166,8 +196,9 @@ unsafe extern "C" fn destroy<S>(bio: *mut BIO) -> c_int {
     }
 
     let data = compat::BIO_get_data(bio);
-    assert!(!data.is_null());
-    Box::<StreamState<S>>::from_raw(data as *mut _);
+    if !data.is_null() {
+        Box::<StreamState<S>>::from_raw(data as *mut _);
+    }
     compat::BIO_set_data(bio, ptr::null_mut());
     compat::BIO_set_init(bio, 0);
     1
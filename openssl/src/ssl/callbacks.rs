@@ -16,11 +16,13 @@ use dh::Dh;
 #[cfg(any(ossl101, ossl102))]
 use ec::EcKey;
 use error::ErrorStack;
+#[cfg(ossl111)]
+use hash::MessageDigest;
 use pkey::Params;
 #[cfg(any(ossl102, ossl110))]
 use ssl::AlpnError;
 #[cfg(ossl111)]
-use ssl::ExtensionContext;
+use ssl::{ClientHelloResponse, ExtensionContext};
 use ssl::{SniError, Ssl, SslAlert, SslContext, SslContextRef, SslRef, SslSession, SslSessionRef};
 #[cfg(ossl111)]
 use x509::X509Ref;
@@ -48,7 +50,7 @@ where
 }
 
 #[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
-pub extern "C" fn raw_psk<F>(
+pub extern "C" fn raw_client_psk<F>(
     ssl: *mut ffi::SSL,
     hint: *const c_char,
     identity: *mut c_char,
@@ -68,7 +70,7 @@ where
 
         let callback = ssl.ssl_context()
             .ex_data(callback_idx)
-            .expect("BUG: psk callback missing") as *const F;
+            .expect("BUG: psk client callback missing") as *const F;
         let hint = if hint != ptr::null() {
             Some(CStr::from_ptr(hint).to_bytes())
         } else {
@@ -84,6 +86,40 @@ where
     }
 }
 
+#[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+pub extern "C" fn raw_server_psk<F>(
+    ssl: *mut ffi::SSL,
+    identity: *const c_char,
+    psk: *mut c_uchar,
+    max_psk_len: c_uint,
+) -> c_uint
+where
+    F: Fn(&mut SslRef, Option<&[u8]>, &mut [u8]) -> Result<usize, ErrorStack>
+        + 'static
+        + Sync
+        + Send,
+{
+    unsafe {
+        let ssl = SslRef::from_ptr_mut(ssl);
+        let callback_idx = SslContext::cached_ex_index::<F>();
+
+        let callback = ssl.ssl_context()
+            .ex_data(callback_idx)
+            .expect("BUG: psk server callback missing") as *const F;
+        let identity = if identity != ptr::null() {
+            Some(CStr::from_ptr(identity).to_bytes())
+        } else {
+            None
+        };
+        // Give the callback mutable slice into which it can write the psk.
+        let psk_sl = slice::from_raw_parts_mut(psk as *mut u8, max_psk_len as usize);
+        match (*callback)(ssl, identity, psk_sl) {
+            Ok(psk_len) => psk_len as u32,
+            _ => 0,
+        }
+    }
+}
+
 pub extern "C" fn ssl_raw_verify<F>(
     preverify_ok: c_int,
     x509_ctx: *mut ffi::X509_STORE_CTX,
@@ -106,6 +142,37 @@ where
     }
 }
 
+#[cfg(ossl111)]
+pub extern "C" fn raw_client_hello<F>(
+    ssl: *mut ffi::SSL,
+    al: *mut c_int,
+    _arg: *mut c_void,
+) -> c_int
+where
+    F: Fn(&mut SslRef, &mut SslAlert) -> Result<ClientHelloResponse, ErrorStack>
+        + 'static
+        + Sync
+        + Send,
+{
+    unsafe {
+        let ssl = SslRef::from_ptr_mut(ssl);
+        let callback = ssl.ssl_context()
+            .ex_data(SslContext::cached_ex_index::<F>())
+            .expect("BUG: client hello callback missing") as *const F;
+        let mut alert = SslAlert(*al);
+
+        let r = (*callback)(ssl, &mut alert);
+        *al = alert.0;
+        match r {
+            Ok(response) => response.0,
+            Err(e) => {
+                e.put();
+                ffi::SSL_CLIENT_HELLO_ERROR
+            }
+        }
+    }
+}
+
 pub extern "C" fn raw_sni<F>(ssl: *mut ffi::SSL, al: *mut c_int, _arg: *mut c_void) -> c_int
 where
     F: Fn(&mut SslRef, &mut SslAlert) -> Result<(), SniError> + 'static + Sync + Send,
@@ -349,7 +416,7 @@ where
     match (*callback)(ssl, data) {
         Some(session) => {
             let p = session.as_ptr();
-            mem::forget(p);
+            mem::forget(session);
             *copy = 0;
             p
         }
@@ -372,6 +439,41 @@ where
     callback(ssl, line);
 }
 
+#[cfg(all(ossl110, not(ossl111)))]
+pub unsafe extern "C" fn raw_keylog_info<F>(ssl: *const ffi::SSL, where_: c_int, _: c_int)
+where
+    F: Fn(&SslRef, &str) + 'static + Sync + Send,
+{
+    if where_ & ffi::SSL_CB_HANDSHAKE_DONE == 0 {
+        return;
+    }
+
+    let ssl = SslRef::from_ptr(ssl as *mut _);
+    let callback = ssl.ssl_context()
+        .ex_data(SslContext::cached_ex_index::<F>())
+        .expect("BUG: keylog callback missing");
+    let session = match ssl.session() {
+        Some(session) => session,
+        None => return,
+    };
+
+    let mut client_random = [0; 32];
+    let client_random_len = ssl.client_random(&mut client_random);
+    let mut master_key = vec![0; session.master_key_len()];
+    let master_key_len = session.master_key(&mut master_key);
+
+    let mut line = String::from("CLIENT_RANDOM ");
+    for b in &client_random[..client_random_len] {
+        line.push_str(&format!("{:02X}", b));
+    }
+    line.push(' ');
+    for b in &master_key[..master_key_len] {
+        line.push_str(&format!("{:02X}", b));
+    }
+
+    callback(ssl, &line);
+}
+
 #[cfg(ossl111)]
 pub unsafe extern "C" fn raw_stateless_cookie_generate<F>(
     ssl: *mut ffi::SSL,
@@ -470,6 +572,102 @@ where
     }
 }
 
+#[cfg(ossl111)]
+pub struct PskIdentityState(Vec<u8>);
+
+#[cfg(ossl111)]
+pub unsafe extern "C" fn raw_psk_use_session<F>(
+    ssl: *mut ffi::SSL,
+    md: *const ffi::EVP_MD,
+    id: *mut *const c_uchar,
+    idlen: *mut size_t,
+    sess: *mut *mut ffi::SSL_SESSION,
+) -> c_int
+where
+    F: Fn(&mut SslRef, Option<MessageDigest>) -> Result<Option<(Vec<u8>, SslSession)>, ErrorStack>
+        + 'static
+        + Sync
+        + Send,
+{
+    let ssl = SslRef::from_ptr_mut(ssl);
+    let callback = ssl.ssl_context()
+        .ex_data(SslContext::cached_ex_index::<F>())
+        .expect("BUG: psk use session callback missing") as *const F;
+    let md = if md.is_null() {
+        None
+    } else {
+        Some(MessageDigest::from_ptr(md))
+    };
+
+    match (*callback)(ssl, md) {
+        Ok(Some((identity, session))) => {
+            // OpenSSL copies the identity once we return, but it needs to outlive this frame
+            let idx = Ssl::cached_ex_index::<PskIdentityState>();
+            let mut identity = Some(identity);
+            let new = match ssl.ex_data_mut(idx) {
+                Some(state) => {
+                    state.0 = identity.take().unwrap();
+                    false
+                }
+                None => true,
+            };
+            if new {
+                ssl.set_ex_data(idx, PskIdentityState(identity.take().unwrap()));
+            }
+            let identity = &ssl.ex_data(idx).unwrap().0;
+            *id = identity.as_ptr();
+            *idlen = identity.len();
+
+            let p = session.as_ptr();
+            mem::forget(session);
+            *sess = p;
+            1
+        }
+        Ok(None) => {
+            *sess = ptr::null_mut();
+            1
+        }
+        Err(e) => {
+            e.put();
+            0
+        }
+    }
+}
+
+#[cfg(ossl111)]
+pub unsafe extern "C" fn raw_psk_find_session<F>(
+    ssl: *mut ffi::SSL,
+    identity: *const c_uchar,
+    identity_len: size_t,
+    sess: *mut *mut ffi::SSL_SESSION,
+) -> c_int
+where
+    F: Fn(&mut SslRef, &[u8]) -> Result<Option<SslSession>, ErrorStack> + 'static + Sync + Send,
+{
+    let ssl = SslRef::from_ptr_mut(ssl);
+    let callback = ssl.ssl_context()
+        .ex_data(SslContext::cached_ex_index::<F>())
+        .expect("BUG: psk find session callback missing") as *const F;
+    let identity = slice::from_raw_parts(identity as *const u8, identity_len);
+
+    match (*callback)(ssl, identity) {
+        Ok(Some(session)) => {
+            let p = session.as_ptr();
+            mem::forget(session);
+            *sess = p;
+            1
+        }
+        Ok(None) => {
+            *sess = ptr::null_mut();
+            1
+        }
+        Err(e) => {
+            e.put();
+            0
+        }
+    }
+}
+
 #[cfg(ossl111)]
 pub struct CustomExtAddState<T>(Option<T>);
 
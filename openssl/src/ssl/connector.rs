@@ -1,11 +1,18 @@
+use ffi;
+use libc::c_int;
+use std::error;
+use std::fmt;
 use std::io::{Read, Write};
 use std::ops::{Deref, DerefMut};
+use std::str;
 
 use dh::Dh;
 use error::ErrorStack;
-use ssl::{HandshakeError, Ssl, SslContext, SslContextBuilder, SslMethod, SslMode, SslOptions,
-          SslRef, SslStream, SslVerifyMode};
+use hash::{hash, MessageDigest};
+use ssl::{ErrorCode, HandshakeError, Ssl, SslContext, SslContextBuilder, SslContextRef, SslEngine,
+          SslMethod, SslMode, SslOptions, SslRef, SslStream, SslVerifyMode};
 use version;
+use x509::{X509Ref, X509StoreContextRef, X509VerifyResult};
 
 fn ctx(method: SslMethod) -> Result<SslContextBuilder, ErrorStack> {
     let mut ctx = SslContextBuilder::new(method)?;
@@ -75,6 +82,16 @@ impl SslConnector {
             verify_hostname: true,
         })
     }
+
+    /// Consumes the `SslConnector`, returning the inner raw `SslContext`.
+    pub fn into_context(self) -> SslContext {
+        self.0
+    }
+
+    /// Returns a shared reference to the inner raw `SslContext`.
+    pub fn context(&self) -> &SslContextRef {
+        &*self.0
+    }
 }
 
 /// A builder for `SslConnector`s.
@@ -141,13 +158,92 @@ impl ConnectConfiguration {
         self.verify_hostname = verify_hostname;
     }
 
+    /// Requires that at least one certificate in the peer's verified chain matches one of `pins`.
+    ///
+    /// This enables verification of the peer's certificate, and is checked in addition to the
+    /// usual chain and hostname verification. The handshake fails if no certificate matches.
+    pub fn set_spki_pins(&mut self, pins: SpkiPinSet) {
+        self.ssl
+            .set_verify_callback(SslVerifyMode::PEER, move |preverify_ok, x509_ctx| {
+                let preverify_ok = verify_chain(preverify_ok, x509_ctx);
+                if !preverify_ok || x509_ctx.error_depth() != 0 || pins.matches_chain(x509_ctx) {
+                    return preverify_ok;
+                }
+
+                x509_ctx.set_error(X509VerifyResult::APPLICATION_VERIFICATION);
+                false
+            });
+    }
+
+    /// Like `set_spki_pins`, but a chain which does not match any of `pins` is still accepted.
+    ///
+    /// Instead, `report` is called with the store context of the leaf certificate, allowing the
+    /// mismatch to be logged while a pin set is rolled out.
+    pub fn set_spki_pins_report_only<F>(&mut self, pins: SpkiPinSet, report: F)
+    where
+        F: Fn(&X509StoreContextRef) + 'static + Sync + Send,
+    {
+        self.ssl
+            .set_verify_callback(SslVerifyMode::PEER, move |preverify_ok, x509_ctx| {
+                let preverify_ok = verify_chain(preverify_ok, x509_ctx);
+                if preverify_ok && x509_ctx.error_depth() == 0 && !pins.matches_chain(x509_ctx) {
+                    report(x509_ctx);
+                }
+                preverify_ok
+            });
+    }
+
     /// Initiates a client-side TLS session on a stream.
     ///
     /// The domain is used for SNI and hostname verification if enabled.
-    pub fn connect<S>(mut self, domain: &str, stream: S) -> Result<SslStream<S>, HandshakeError<S>>
+    pub fn connect<S>(self, domain: &str, stream: S) -> Result<SslStream<S>, HandshakeError<S>>
     where
         S: Read + Write,
     {
+        self.into_ssl(domain)?.connect(stream)
+    }
+
+    /// Generates the first flight of a client-side TLS handshake without performing any I/O.
+    ///
+    /// The domain is used for SNI and hostname verification if enabled.
+    ///
+    /// The returned `FirstFlight` contains the bytes which would be sent to the server when
+    /// connecting. This allows the caller to send them along with the connection request itself,
+    /// for example as TCP Fast Open data, saving a round trip. The handshake is then completed with
+    /// `FirstFlight::connect`.
+    pub fn first_flight(self, domain: &str) -> Result<FirstFlight, ErrorStack> {
+        let ssl = self.into_ssl(domain)?;
+
+        let mut engine = SslEngine::new(ssl)?;
+        engine.set_connect_state();
+        match engine.handshake() {
+            Ok(()) => {}
+            Err(ref e) if e.code() == ErrorCode::WANT_READ => {}
+            Err(e) => return Err(e.ssl_error().cloned().unwrap_or_else(ErrorStack::get)),
+        }
+
+        let mut data = vec![];
+        let mut buf = [0; 4096];
+        loop {
+            match engine.produce_outgoing(&mut buf) {
+                0 => break,
+                n => data.extend_from_slice(&buf[..n]),
+            }
+        }
+
+        Ok(FirstFlight {
+            ssl: engine.ssl,
+            data,
+        })
+    }
+
+    /// Returns an `Ssl` configured to connect to the provided domain.
+    ///
+    /// The domain is used for SNI and hostname verification if enabled.
+    ///
+    /// This allows the TLS handshake to be started separately, for example once a tunnel through a
+    /// proxy has been established on the underlying stream, by calling `Ssl::connect`.
+    pub fn into_ssl(mut self, domain: &str) -> Result<Ssl, ErrorStack> {
         if self.sni {
             self.ssl.set_hostname(domain)?;
         }
@@ -156,7 +252,7 @@ impl ConnectConfiguration {
             setup_verify_hostname(&mut self.ssl, domain)?;
         }
 
-        self.ssl.connect(stream)
+        Ok(self.ssl)
     }
 }
 
@@ -174,6 +270,155 @@ impl DerefMut for ConnectConfiguration {
     }
 }
 
+/// The first flight of a client-side TLS handshake, generated ahead of connecting.
+///
+/// Created by `ConnectConfiguration::first_flight`.
+pub struct FirstFlight {
+    ssl: Ssl,
+    data: Vec<u8>,
+}
+
+impl FirstFlight {
+    /// Returns the handshake bytes which must be sent to the server before continuing.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Completes the TLS handshake on a stream.
+    ///
+    /// The contents of `data` must already have been written to the stream, for example as part of
+    /// a TCP Fast Open connection request.
+    pub fn connect<S>(self, stream: S) -> Result<SslStream<S>, HandshakeError<S>>
+    where
+        S: Read + Write,
+    {
+        self.ssl.connect(stream)
+    }
+}
+
+impl Deref for FirstFlight {
+    type Target = SslRef;
+
+    fn deref(&self) -> &SslRef {
+        &self.ssl
+    }
+}
+
+/// A set of pinned public keys.
+///
+/// A pin is the base64 encoded SHA-256 digest of a certificate's DER encoded
+/// SubjectPublicKeyInfo, in the format used by HTTP Public Key Pinning ([RFC 7469]). Pins are
+/// checked against a connection with `ConnectConfiguration::set_spki_pins`.
+///
+/// [RFC 7469]: https://tools.ietf.org/html/rfc7469
+#[derive(Clone, Debug, Default)]
+pub struct SpkiPinSet(Vec<[u8; 32]>);
+
+impl SpkiPinSet {
+    /// Creates an empty pin set.
+    pub fn new() -> SpkiPinSet {
+        SpkiPinSet::default()
+    }
+
+    /// Creates a pin set from base64 encoded pins.
+    pub fn from_base64<I, S>(pins: I) -> Result<SpkiPinSet, InvalidPin>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut set = SpkiPinSet::new();
+        for pin in pins {
+            set.add(pin.as_ref())?;
+        }
+        Ok(set)
+    }
+
+    /// Adds a base64 encoded pin to the set.
+    pub fn add(&mut self, pin: &str) -> Result<(), InvalidPin> {
+        match decode_pin(pin) {
+            Some(digest) => {
+                self.0.push(digest);
+                Ok(())
+            }
+            None => Err(InvalidPin(pin.to_string())),
+        }
+    }
+
+    /// Returns the base64 encoded pin of a certificate's public key.
+    pub fn pin(cert: &X509Ref) -> Result<String, ErrorStack> {
+        let digest = spki_digest(cert)?;
+        let mut buf = [0; 45];
+        unsafe {
+            let len = ffi::EVP_EncodeBlock(
+                buf.as_mut_ptr(),
+                digest.as_ptr(),
+                digest.len() as c_int,
+            );
+            Ok(str::from_utf8(&buf[..len as usize]).unwrap().to_string())
+        }
+    }
+
+    /// Determines if the public key of `cert` matches a pin in the set.
+    pub fn matches(&self, cert: &X509Ref) -> Result<bool, ErrorStack> {
+        let digest = spki_digest(cert)?;
+        Ok(self.0.iter().any(|pin| pin[..] == digest[..]))
+    }
+
+    fn matches_chain(&self, x509_ctx: &X509StoreContextRef) -> bool {
+        match x509_ctx.chain() {
+            Some(chain) => chain
+                .iter()
+                .any(|cert| self.matches(cert).unwrap_or(false)),
+            None => false,
+        }
+    }
+}
+
+/// An error returned when a pin is not a base64 encoded SHA-256 digest.
+#[derive(Clone, Debug)]
+pub struct InvalidPin(String);
+
+impl InvalidPin {
+    /// Returns the invalid pin.
+    pub fn pin(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for InvalidPin {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "invalid SPKI pin `{}`", self.0)
+    }
+}
+
+impl error::Error for InvalidPin {
+    fn description(&self) -> &str {
+        "invalid SPKI pin"
+    }
+}
+
+fn spki_digest(cert: &X509Ref) -> Result<Vec<u8>, ErrorStack> {
+    let spki = cert.public_key()?.public_key_to_der()?;
+    Ok(hash(MessageDigest::sha256(), &spki)?.to_vec())
+}
+
+fn decode_pin(pin: &str) -> Option<[u8; 32]> {
+    // a base64 encoded SHA-256 digest is 44 characters long, ending in a single padding byte
+    if pin.len() != 44 || !pin.ends_with('=') || pin[..43].contains('=') {
+        return None;
+    }
+
+    let mut buf = [0; 33];
+    let len = unsafe { ffi::EVP_DecodeBlock(buf.as_mut_ptr(), pin.as_ptr(), pin.len() as c_int) };
+    if len != 33 {
+        return None;
+    }
+
+    let mut digest = [0; 32];
+    digest.copy_from_slice(&buf[..32]);
+    Some(digest)
+}
+
 /// A type which wraps server-side streams in a TLS session.
 ///
 /// OpenSSL's default configuration is highly insecure. This connector manages the OpenSSL
@@ -251,6 +496,16 @@ ssbzSibBsu/6iGtCOGEoXJf//////////wIBAg==
         let ssl = Ssl::new(&self.0)?;
         ssl.accept(stream)
     }
+
+    /// Consumes the `SslAcceptor`, returning the inner raw `SslContext`.
+    pub fn into_context(self) -> SslContext {
+        self.0
+    }
+
+    /// Returns a shared reference to the inner raw `SslContext`.
+    pub fn context(&self) -> &SslContextRef {
+        &*self.0
+    }
 }
 
 /// A builder for `SslAcceptor`s.
@@ -306,6 +561,18 @@ fn setup_verify(ctx: &mut SslContextBuilder) {
     ctx.set_verify_callback(SslVerifyMode::PEER, verify::verify_callback);
 }
 
+// OpenSSL 1.0.1 hostname verification lives in the context's verify callback, which a callback
+// set on the `Ssl` replaces.
+#[cfg(any(ossl102, ossl110))]
+fn verify_chain(preverify_ok: bool, _: &mut X509StoreContextRef) -> bool {
+    preverify_ok
+}
+
+#[cfg(ossl101)]
+fn verify_chain(preverify_ok: bool, x509_ctx: &mut X509StoreContextRef) -> bool {
+    verify::verify_callback(preverify_ok, x509_ctx)
+}
+
 #[cfg(any(ossl102, ossl110))]
 fn setup_verify_hostname(ssl: &mut Ssl, domain: &str) -> Result<(), ErrorStack> {
     use x509::verify::X509CheckFlags;
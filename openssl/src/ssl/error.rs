@@ -40,6 +40,24 @@ impl ErrorCode {
 
     /// An error occurred in the SSL library.
     pub const SSL: ErrorCode = ErrorCode(ffi::SSL_ERROR_SSL);
+
+    /// The client hello callback paused the handshake.
+    ///
+    /// Retry the handshake once the callback is ready to continue.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub const WANT_CLIENT_HELLO_CB: ErrorCode = ErrorCode(ffi::SSL_ERROR_WANT_CLIENT_HELLO_CB);
+
+    // Determines if a handshake returning this code was interrupted rather than failed.
+    pub(crate) fn is_would_block(&self) -> bool {
+        match *self {
+            ErrorCode::WANT_READ | ErrorCode::WANT_WRITE => true,
+            #[cfg(ossl111)]
+            ErrorCode::WANT_CLIENT_HELLO_CB => true,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -49,17 +67,26 @@ pub(crate) enum InnerError {
 }
 
 /// An SSL error.
+///
+/// The [`code`] distinguishes between conditions which can be retried, such as `WANT_READ` and
+/// `WANT_WRITE`, a clean shutdown of the session by the peer, reported as `ZERO_RETURN`, and fatal
+/// errors, reported as `SYSCALL` or `SSL`.
+///
+/// [`code`]: #method.code
 #[derive(Debug)]
 pub struct Error {
     pub(crate) code: ErrorCode,
     pub(crate) cause: Option<InnerError>,
+    pub(crate) verify_result: Option<X509VerifyResult>,
 }
 
 impl Error {
+    /// Returns the error code reported by OpenSSL.
     pub fn code(&self) -> ErrorCode {
         self.code
     }
 
+    /// Returns the error returned by the underlying stream, if any.
     pub fn io_error(&self) -> Option<&io::Error> {
         match self.cause {
             Some(InnerError::Io(ref e)) => Some(e),
@@ -67,6 +94,9 @@ impl Error {
         }
     }
 
+    /// Converts the error into the error returned by the underlying stream.
+    ///
+    /// The original error is returned if there is no such error.
     pub fn into_io_error(self) -> Result<io::Error, Error> {
         match self.cause {
             Some(InnerError::Io(e)) => Ok(e),
@@ -74,12 +104,20 @@ impl Error {
         }
     }
 
+    /// Returns the errors reported by the OpenSSL library, if any.
     pub fn ssl_error(&self) -> Option<&ErrorStack> {
         match self.cause {
             Some(InnerError::Ssl(ref e)) => Some(e),
             _ => None,
         }
     }
+
+    /// Returns the result of the peer certificate verification, if it failed.
+    ///
+    /// This is only set for `SSL` errors, and indicates why a handshake was rejected.
+    pub fn verify_result(&self) -> Option<X509VerifyResult> {
+        self.verify_result
+    }
 }
 
 impl From<ErrorStack> for Error {
@@ -87,6 +125,7 @@ impl From<ErrorStack> for Error {
         Error {
             code: ErrorCode::SSL,
             cause: Some(InnerError::Ssl(e)),
+            verify_result: None,
         }
     }
 }
@@ -99,6 +138,10 @@ impl fmt::Display for Error {
                 Some(_) => fmt.write_str("a nonblocking read call would have blocked"),
                 None => fmt.write_str("the operation should be retried"),
             },
+            ErrorCode::WANT_WRITE => match self.io_error() {
+                Some(_) => fmt.write_str("a nonblocking write call would have blocked"),
+                None => fmt.write_str("the operation should be retried"),
+            },
             ErrorCode::SYSCALL => match self.io_error() {
                 Some(err) => write!(fmt, "the inner stream returned an error: {}", err),
                 None => fmt.write_str("unexpected EOF"),
@@ -108,8 +151,15 @@ impl fmt::Display for Error {
                 if let Some(ref err) = self.ssl_error() {
                     write!(fmt, ": {}", err)?
                 }
+                if let Some(verify) = self.verify_result {
+                    write!(fmt, ": {}", verify)?
+                }
                 Ok(())
             }
+            #[cfg(ossl111)]
+            ErrorCode::WANT_CLIENT_HELLO_CB => {
+                fmt.write_str("the client hello callback paused the handshake")
+            }
             ErrorCode(code) => write!(fmt, "unknown error code {}", code),
         }
     }
@@ -136,9 +186,11 @@ pub enum HandshakeError<S> {
     SetupFailure(ErrorStack),
     /// The handshake failed.
     Failure(MidHandshakeSslStream<S>),
-    /// The handshake encountered a `WouldBlock` error midway through.
+    /// The handshake encountered a `WouldBlock` error midway through, or was paused by a client
+    /// hello callback.
     ///
-    /// This error will never be returned for blocking streams.
+    /// For blocking streams, this error is only returned when a client hello callback pauses the
+    /// handshake.
     WouldBlock(MidHandshakeSslStream<S>),
 }
 
@@ -165,11 +217,7 @@ impl<S: fmt::Debug> fmt::Display for HandshakeError<S> {
         match *self {
             HandshakeError::SetupFailure(ref e) => write!(f, ": {}", e)?,
             HandshakeError::Failure(ref s) | HandshakeError::WouldBlock(ref s) => {
-                write!(f, ": {}", s.error())?;
-                let verify = s.ssl().verify_result();
-                if verify != X509VerifyResult::OK {
-                    write!(f, ": {}", verify)?;
-                }
+                write!(f, ": {}", s.error())?
             }
         }
         Ok(())
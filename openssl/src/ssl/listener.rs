@@ -0,0 +1,428 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use error::ErrorStack;
+use ssl::{Error, ErrorCode, HandshakeError, MidHandshakeSslStream, SslAcceptor, SslStream};
+
+/// A TCP listener which performs TLS handshakes on the connections it accepts.
+///
+/// Handshakes can be performed on the accepting thread with [`accept`] and [`incoming`], or
+/// offloaded to other threads with [`accept_tcp`] and [`accept_batch`], which return
+/// [`PendingHandshake`]s without reading from the connection.
+///
+/// # Examples
+///
+/// ```no_run
+/// use openssl::ssl::{SslAcceptor, SslFiletype, SslListener, SslMethod};
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+/// acceptor.set_private_key_file("key.pem", SslFiletype::PEM).unwrap();
+/// acceptor.set_certificate_chain_file("certs.pem").unwrap();
+///
+/// let mut listener = SslListener::bind("0.0.0.0:8443", acceptor.build()).unwrap();
+/// listener.set_handshake_timeout(Some(Duration::from_secs(10)));
+///
+/// loop {
+///     let pending = match listener.accept_tcp() {
+///         Ok(pending) => pending,
+///         Err(e) => {
+///             println!("accept failed: {}", e);
+///             continue;
+///         }
+///     };
+///     thread::spawn(move || match pending.handshake() {
+///         Ok(stream) => { /* use the stream */ }
+///         Err(e) => println!("handshake failed ({:?}): {}", e.kind(), e),
+///     });
+/// }
+/// ```
+///
+/// [`accept`]: #method.accept
+/// [`incoming`]: #method.incoming
+/// [`accept_tcp`]: #method.accept_tcp
+/// [`accept_batch`]: #method.accept_batch
+/// [`PendingHandshake`]: struct.PendingHandshake.html
+pub struct SslListener {
+    listener: TcpListener,
+    acceptor: Arc<SslAcceptor>,
+    handshake_timeout: Option<Duration>,
+}
+
+impl SslListener {
+    /// Creates a new `SslListener` accepting connections from `listener`.
+    pub fn new(listener: TcpListener, acceptor: SslAcceptor) -> SslListener {
+        SslListener {
+            listener,
+            acceptor: Arc::new(acceptor),
+            handshake_timeout: None,
+        }
+    }
+
+    /// Creates a new `SslListener` bound to the specified address.
+    pub fn bind<A>(addr: A, acceptor: SslAcceptor) -> io::Result<SslListener>
+    where
+        A: ToSocketAddrs,
+    {
+        TcpListener::bind(addr).map(|l| SslListener::new(l, acceptor))
+    }
+
+    /// Sets the maximum time a handshake may take.
+    ///
+    /// The timeout starts when a handshake begins, rather than when the connection is accepted.
+    /// Handshakes have no time limit by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `timeout` is zero.
+    pub fn set_handshake_timeout(&mut self, timeout: Option<Duration>) {
+        assert!(timeout != Some(Duration::from_secs(0)), "handshake timeout must not be zero");
+        self.handshake_timeout = timeout;
+    }
+
+    /// Returns the maximum time a handshake may take.
+    pub fn handshake_timeout(&self) -> Option<Duration> {
+        self.handshake_timeout
+    }
+
+    /// Returns the local address the listener is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Returns a shared reference to the underlying `TcpListener`.
+    pub fn get_ref(&self) -> &TcpListener {
+        &self.listener
+    }
+
+    /// Returns the acceptor used to configure handshakes.
+    pub fn acceptor(&self) -> &SslAcceptor {
+        &self.acceptor
+    }
+
+    /// Accepts a connection and performs a handshake with it on the calling thread.
+    pub fn accept(&self) -> Result<(SslStream<TcpStream>, SocketAddr), AcceptError> {
+        let pending = self.accept_tcp()?;
+        let addr = pending.peer_addr();
+        pending.handshake().map(|stream| (stream, addr))
+    }
+
+    /// Accepts a connection without starting the handshake.
+    ///
+    /// The returned `PendingHandshake` can be sent to another thread to complete the handshake.
+    pub fn accept_tcp(&self) -> Result<PendingHandshake, AcceptError> {
+        match self.listener.accept() {
+            Ok((stream, addr)) => Ok(self.pending(stream, addr)),
+            Err(e) => Err(AcceptError::io(AcceptErrorKind::Accept, None, e)),
+        }
+    }
+
+    /// Accepts up to `max` connections without starting their handshakes.
+    ///
+    /// The listener must be in blocking mode. This blocks until the first connection is accepted,
+    /// and then accepts further connections only if they are already waiting. The listener is
+    /// briefly placed in nonblocking mode to do so, which may cause concurrent calls to `accept`
+    /// from other threads to fail with a `WouldBlock` error.
+    ///
+    /// An error is only returned if the first connection cannot be accepted.
+    pub fn accept_batch(&self, max: usize) -> Result<Vec<PendingHandshake>, AcceptError> {
+        let mut batch = vec![];
+        if max == 0 {
+            return Ok(batch);
+        }
+        batch.push(self.accept_tcp()?);
+
+        if max > 1 && self.listener.set_nonblocking(true).is_ok() {
+            while batch.len() < max {
+                match self.listener.accept() {
+                    Ok((stream, addr)) => batch.push(self.pending(stream, addr)),
+                    Err(_) => break,
+                }
+            }
+            let _ = self.listener.set_nonblocking(false);
+        }
+
+        Ok(batch)
+    }
+
+    /// Returns an iterator over connections, performing each handshake on the calling thread.
+    ///
+    /// The iterator never returns `None`.
+    pub fn incoming(&self) -> Incoming {
+        Incoming { listener: self }
+    }
+
+    fn pending(&self, stream: TcpStream, addr: SocketAddr) -> PendingHandshake {
+        PendingHandshake {
+            stream,
+            addr,
+            acceptor: self.acceptor.clone(),
+            timeout: self.handshake_timeout,
+        }
+    }
+}
+
+/// An iterator over the connections accepted by an `SslListener`.
+///
+/// This is returned by [`SslListener::incoming`].
+///
+/// [`SslListener::incoming`]: struct.SslListener.html#method.incoming
+pub struct Incoming<'a> {
+    listener: &'a SslListener,
+}
+
+impl<'a> Iterator for Incoming<'a> {
+    type Item = Result<SslStream<TcpStream>, AcceptError>;
+
+    fn next(&mut self) -> Option<Result<SslStream<TcpStream>, AcceptError>> {
+        Some(self.listener.accept().map(|(stream, _)| stream))
+    }
+}
+
+/// An accepted connection whose handshake has not yet been performed.
+pub struct PendingHandshake {
+    stream: TcpStream,
+    addr: SocketAddr,
+    acceptor: Arc<SslAcceptor>,
+    timeout: Option<Duration>,
+}
+
+impl PendingHandshake {
+    /// Returns the address of the peer.
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns a shared reference to the connection.
+    pub fn get_ref(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    /// Performs the handshake, blocking the calling thread until it completes.
+    ///
+    /// The connection is placed in blocking mode. If the listener has a handshake timeout, it is
+    /// enforced with read and write timeouts on the connection, which are cleared once the
+    /// handshake completes.
+    pub fn handshake(self) -> Result<SslStream<TcpStream>, AcceptError> {
+        let addr = self.addr;
+        let deadline = self.timeout.map(|t| Instant::now() + t);
+
+        if let Err(e) = self.stream.set_nonblocking(false) {
+            return Err(AcceptError::io(AcceptErrorKind::Io, Some(addr), e));
+        }
+        if let Err(e) = set_timeout(&self.stream, self.timeout) {
+            return Err(AcceptError::io(AcceptErrorKind::Io, Some(addr), e));
+        }
+
+        let mut result = self.acceptor.accept(self.stream);
+        loop {
+            match result {
+                Ok(stream) => {
+                    if let Err(e) = set_timeout(stream.get_ref(), None) {
+                        return Err(AcceptError::io(AcceptErrorKind::Io, Some(addr), e));
+                    }
+                    return Ok(stream);
+                }
+                Err(HandshakeError::SetupFailure(e)) => {
+                    return Err(AcceptError::setup(addr, e));
+                }
+                Err(HandshakeError::Failure(mid)) => {
+                    return Err(AcceptError::ssl(addr, mid.into_error()));
+                }
+                Err(HandshakeError::WouldBlock(mid)) => {
+                    // a socket timeout expired, but time may remain before the deadline
+                    result = match remaining(deadline) {
+                        Some(remaining) => resume(mid, remaining, addr)?,
+                        None => {
+                            return Err(AcceptError::timed_out(addr, mid.into_error()));
+                        }
+                    };
+                }
+            }
+        }
+    }
+}
+
+fn set_timeout(stream: &TcpStream, timeout: Option<Duration>) -> io::Result<()> {
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)
+}
+
+fn remaining(deadline: Option<Instant>) -> Option<Duration> {
+    let deadline = match deadline {
+        Some(deadline) => deadline,
+        None => return None,
+    };
+    let now = Instant::now();
+    if now < deadline {
+        Some(deadline - now)
+    } else {
+        None
+    }
+}
+
+fn resume(
+    mid: MidHandshakeSslStream<TcpStream>,
+    remaining: Duration,
+    addr: SocketAddr,
+) -> Result<Result<SslStream<TcpStream>, HandshakeError<TcpStream>>, AcceptError> {
+    match set_timeout(mid.get_ref(), Some(remaining)) {
+        Ok(()) => Ok(mid.handshake()),
+        Err(e) => Err(AcceptError::io(AcceptErrorKind::Io, Some(addr), e)),
+    }
+}
+
+/// The classification of an `AcceptError`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AcceptErrorKind {
+    /// Accepting a connection from the listener failed.
+    ///
+    /// This may indicate a problem with the listener itself, such as running out of file
+    /// descriptors, rather than with a particular connection.
+    Accept,
+    /// The `Ssl` for the connection could not be created.
+    Setup,
+    /// The handshake did not complete before the timeout.
+    TimedOut,
+    /// The peer closed the connection during the handshake.
+    Closed,
+    /// The handshake failed due to a TLS protocol error, such as a certificate verification
+    /// failure or an unsupported protocol version.
+    Protocol,
+    /// Another I/O error occurred on the connection.
+    Io,
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+#[derive(Debug)]
+enum AcceptErrorInner {
+    Io(io::Error),
+    Ssl(Error),
+    Setup(ErrorStack),
+}
+
+/// An error accepting a connection from an `SslListener`.
+#[derive(Debug)]
+pub struct AcceptError {
+    kind: AcceptErrorKind,
+    peer_addr: Option<SocketAddr>,
+    inner: AcceptErrorInner,
+}
+
+impl AcceptError {
+    fn io(kind: AcceptErrorKind, peer_addr: Option<SocketAddr>, e: io::Error) -> AcceptError {
+        AcceptError {
+            kind,
+            peer_addr,
+            inner: AcceptErrorInner::Io(e),
+        }
+    }
+
+    fn setup(peer_addr: SocketAddr, e: ErrorStack) -> AcceptError {
+        AcceptError {
+            kind: AcceptErrorKind::Setup,
+            peer_addr: Some(peer_addr),
+            inner: AcceptErrorInner::Setup(e),
+        }
+    }
+
+    fn timed_out(peer_addr: SocketAddr, e: Error) -> AcceptError {
+        AcceptError {
+            kind: AcceptErrorKind::TimedOut,
+            peer_addr: Some(peer_addr),
+            inner: AcceptErrorInner::Ssl(e),
+        }
+    }
+
+    fn ssl(peer_addr: SocketAddr, e: Error) -> AcceptError {
+        let kind = match e.io_error() {
+            Some(io) => match io.kind() {
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => AcceptErrorKind::TimedOut,
+                io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::BrokenPipe
+                | io::ErrorKind::UnexpectedEof => AcceptErrorKind::Closed,
+                _ => AcceptErrorKind::Io,
+            },
+            None => match e.code() {
+                ErrorCode::SSL => AcceptErrorKind::Protocol,
+                // SYSCALL without an I/O error indicates an unexpected EOF
+                ErrorCode::ZERO_RETURN | ErrorCode::SYSCALL => AcceptErrorKind::Closed,
+                _ => AcceptErrorKind::Io,
+            },
+        };
+
+        AcceptError {
+            kind,
+            peer_addr: Some(peer_addr),
+            inner: AcceptErrorInner::Ssl(e),
+        }
+    }
+
+    /// Returns the classification of the error.
+    pub fn kind(&self) -> AcceptErrorKind {
+        self.kind
+    }
+
+    /// Returns the address of the peer, if a connection was accepted.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Returns the I/O error which occurred outside of the handshake, if any.
+    pub fn io_error(&self) -> Option<&io::Error> {
+        match self.inner {
+            AcceptErrorInner::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns the error reported by the handshake, if any.
+    pub fn ssl_error(&self) -> Option<&Error> {
+        match self.inner {
+            AcceptErrorInner::Ssl(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AcceptError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            AcceptErrorKind::Accept => fmt.write_str("failed to accept a connection")?,
+            AcceptErrorKind::Setup => fmt.write_str("failed to set up the handshake")?,
+            AcceptErrorKind::TimedOut => fmt.write_str("the handshake timed out")?,
+            AcceptErrorKind::Closed => fmt.write_str("the peer closed the connection")?,
+            AcceptErrorKind::Protocol => fmt.write_str("the handshake failed")?,
+            _ => fmt.write_str("an I/O error occurred during the handshake")?,
+        }
+        if let Some(addr) = self.peer_addr {
+            write!(fmt, " with {}", addr)?;
+        }
+        match self.inner {
+            AcceptErrorInner::Io(ref e) => write!(fmt, ": {}", e),
+            AcceptErrorInner::Ssl(ref e) => write!(fmt, ": {}", e),
+            AcceptErrorInner::Setup(ref e) => write!(fmt, ": {}", e),
+        }
+    }
+}
+
+impl error::Error for AcceptError {
+    fn description(&self) -> &str {
+        "an error accepting a TLS connection"
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match self.inner {
+            AcceptErrorInner::Io(ref e) => Some(e),
+            AcceptErrorInner::Ssl(ref e) => Some(e),
+            AcceptErrorInner::Setup(ref e) => Some(e),
+        }
+    }
+}
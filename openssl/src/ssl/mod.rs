@@ -69,12 +69,14 @@ use std::io;
 use std::io::prelude::*;
 use std::marker::PhantomData;
 use std::mem::{self, ManuallyDrop};
+use std::net::TcpStream;
 use std::ops::{Deref, DerefMut};
 use std::panic::resume_unwind;
 use std::path::Path;
 use std::ptr;
 use std::slice;
 use std::str;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 use dh::{Dh, DhRef};
@@ -90,7 +92,7 @@ use pkey::{HasPrivate, PKeyRef, Params, Private};
 use ssl::bio::BioMethod;
 use ssl::callbacks::*;
 use ssl::error::InnerError;
-use stack::{Stack, StackRef};
+use stack::{Stack, StackRef, Stackable};
 #[cfg(any(ossl102, ossl110))]
 use x509::store::X509Store;
 use x509::store::{X509StoreBuilderRef, X509StoreRef};
@@ -99,15 +101,24 @@ use x509::verify::X509VerifyParamRef;
 use x509::{X509, X509Name, X509Ref, X509StoreContextRef, X509VerifyResult};
 use {cvt, cvt_n, cvt_p, init};
 
+pub use ssl::audit::{AuditCheck, AuditFinding, SecurityAudit};
 pub use ssl::connector::{
-    ConnectConfiguration, SslAcceptor, SslAcceptorBuilder, SslConnector, SslConnectorBuilder,
+    ConnectConfiguration, FirstFlight, InvalidPin, SpkiPinSet, SslAcceptor, SslAcceptorBuilder,
+    SslConnector, SslConnectorBuilder,
 };
 pub use ssl::error::{Error, ErrorCode, HandshakeError};
+pub use ssl::listener::{AcceptError, AcceptErrorKind, Incoming, PendingHandshake, SslListener};
+pub use ssl::session_store::{MemorySessionStore, SessionStore};
+pub use ssl::split::{SslReadHalf, SslWriteHalf};
 
-mod bio;
+mod audit;
+pub(crate) mod bio;
 mod callbacks;
 mod connector;
 mod error;
+mod listener;
+mod session_store;
+mod split;
 #[cfg(test)]
 mod test;
 
@@ -186,6 +197,15 @@ bitflags! {
         #[cfg(ossl111)]
         const NO_TLSV1_3 = ffi::SSL_OP_NO_TLSv1_3;
 
+        /// Disables renegotiation.
+        ///
+        /// A server rejects renegotiation requests from the client, and a client rejects
+        /// HelloRequest messages from the server.
+        ///
+        /// Requires OpenSSL 1.1.1 or newer.
+        #[cfg(ossl111)]
+        const NO_RENEGOTIATION = ffi::SSL_OP_NO_RENEGOTIATION;
+
         /// Disables the use of DTLSv1.0
         ///
         /// Requires OpenSSL 1.0.2 or newer.
@@ -367,6 +387,17 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// The shutdown state of a session.
+    pub struct ShutdownState: c_int {
+        /// A close notify message has been sent to the peer.
+        const SENT = ffi::SSL_SENT_SHUTDOWN;
+
+        /// A close notify message has been received from the peer.
+        const RECEIVED = ffi::SSL_RECEIVED_SHUTDOWN;
+    }
+}
+
 #[cfg(ossl111)]
 bitflags! {
     /// Which messages and under which conditions an extension should be added or expected.
@@ -385,14 +416,21 @@ bitflags! {
         const TLS1_3_ONLY = ffi::SSL_EXT_TLS1_3_ONLY;
         /// Ignore this extension during parsing if we are resuming
         const IGNORE_ON_RESUMPTION = ffi::SSL_EXT_IGNORE_ON_RESUMPTION;
+        /// The extension is sent in the ClientHello
         const CLIENT_HELLO = ffi::SSL_EXT_CLIENT_HELLO;
         /// Really means TLS1.2 or below
         const TLS1_2_SERVER_HELLO = ffi::SSL_EXT_TLS1_2_SERVER_HELLO;
+        /// The extension is sent in a TLS1.3 ServerHello
         const TLS1_3_SERVER_HELLO = ffi::SSL_EXT_TLS1_3_SERVER_HELLO;
+        /// The extension is sent in TLS1.3 EncryptedExtensions
         const TLS1_3_ENCRYPTED_EXTENSIONS = ffi::SSL_EXT_TLS1_3_ENCRYPTED_EXTENSIONS;
+        /// The extension is sent in a TLS1.3 HelloRetryRequest
         const TLS1_3_HELLO_RETRY_REQUEST = ffi::SSL_EXT_TLS1_3_HELLO_RETRY_REQUEST;
+        /// The extension is sent with each certificate in a TLS1.3 Certificate message
         const TLS1_3_CERTIFICATE = ffi::SSL_EXT_TLS1_3_CERTIFICATE;
+        /// The extension is sent in a TLS1.3 NewSessionTicket
         const TLS1_3_NEW_SESSION_TICKET = ffi::SSL_EXT_TLS1_3_NEW_SESSION_TICKET;
+        /// The extension is sent in a TLS1.3 CertificateRequest
         const TLS1_3_CERTIFICATE_REQUEST = ffi::SSL_EXT_TLS1_3_CERTIFICATE_REQUEST;
     }
 }
@@ -523,6 +561,52 @@ impl AlpnError {
     pub const NOACK: AlpnError = AlpnError(ffi::SSL_TLSEXT_ERR_NOACK);
 }
 
+#[cfg(ossl111)]
+const TLSEXT_TYPE_SERVER_NAME: u16 = 0;
+#[cfg(ossl111)]
+const TLSEXT_TYPE_ALPN: u16 = 16;
+#[cfg(ossl111)]
+const TLSEXT_NAMETYPE_HOST_NAME: u8 = 0;
+
+// the maximum amount of plaintext carried by a single TLS record
+const MAX_RECORD_LEN: usize = 16384;
+
+// Splits a value prefixed by a big-endian length of `len_bytes` bytes from the rest of `buf`.
+#[cfg(ossl111)]
+fn length_prefixed(buf: &[u8], len_bytes: usize) -> Option<(&[u8], &[u8])> {
+    if buf.len() < len_bytes {
+        return None;
+    }
+    let len = buf[..len_bytes]
+        .iter()
+        .fold(0, |len, &b| len << 8 | b as usize);
+    let buf = &buf[len_bytes..];
+    if buf.len() < len {
+        None
+    } else {
+        Some((&buf[..len], &buf[len..]))
+    }
+}
+
+/// The outcome of a client hello callback.
+///
+/// Requires OpenSSL 1.1.1 or newer.
+#[cfg(ossl111)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ClientHelloResponse(c_int);
+
+#[cfg(ossl111)]
+impl ClientHelloResponse {
+    /// Continue the handshake.
+    pub const SUCCESS: ClientHelloResponse = ClientHelloResponse(ffi::SSL_CLIENT_HELLO_SUCCESS);
+
+    /// Pause the handshake.
+    ///
+    /// The handshake returns a `WouldBlock` error with the code `WANT_CLIENT_HELLO_CB`, and the
+    /// callback is invoked again when it is resumed.
+    pub const RETRY: ClientHelloResponse = ClientHelloResponse(ffi::SSL_CLIENT_HELLO_RETRY);
+}
+
 /// An SSL/TLS protocol version.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct SslVersion(c_int);
@@ -547,6 +631,42 @@ impl SslVersion {
     pub const TLS1_3: SslVersion = SslVersion(ffi::TLS1_3_VERSION);
 }
 
+/// The type of a TLS 1.3 key update.
+///
+/// Requires OpenSSL 1.1.1 or newer.
+#[cfg(ossl111)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KeyUpdateType(c_int);
+
+#[cfg(ossl111)]
+impl KeyUpdateType {
+    /// Update the sending keys only.
+    pub const NOT_REQUESTED: KeyUpdateType = KeyUpdateType(ffi::SSL_KEY_UPDATE_NOT_REQUESTED);
+
+    /// Update the sending keys and request that the peer update its sending keys as well.
+    pub const REQUESTED: KeyUpdateType = KeyUpdateType(ffi::SSL_KEY_UPDATE_REQUESTED);
+}
+
+/// Counters of the application data transferred over an `SslStream`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct TrafficStats {
+    /// The number of plaintext bytes read from the stream.
+    pub bytes_read: u64,
+    /// The number of plaintext bytes written to the stream.
+    pub bytes_written: u64,
+    /// The number of plaintext bytes written to the stream since the last rekey.
+    pub bytes_written_since_rekey: u64,
+    /// The number of TLS records of application data written to the stream.
+    ///
+    /// This assumes the default maximum record size of 16384 bytes, and does not count the empty
+    /// records some older protocol versions insert as a countermeasure against BEAST.
+    pub records_written: u64,
+    /// The number of TLS records of application data written to the stream since the last rekey.
+    pub records_written_since_rekey: u64,
+    /// The number of rekeys initiated by this side of the connection.
+    pub rekeys: u64,
+}
+
 /// A standard implementation of protocol selection for Application Layer Protocol Negotiation
 /// (ALPN).
 ///
@@ -823,6 +943,19 @@ impl SslContextBuilder {
         }
     }
 
+    /// Add the provided CA certificate to the list sent by the server to the client when
+    /// requesting client-side TLS authentication.
+    ///
+    /// The CA certificate must still be added to the trust root - it is not automatically set
+    /// as trusted by this method.
+    ///
+    /// This corresponds to [`SSL_CTX_add_client_CA`].
+    ///
+    /// [`SSL_CTX_add_client_CA`]: https://www.openssl.org/docs/manmaster/man3/SSL_CTX_set_client_CA_list.html
+    pub fn add_client_ca(&mut self, cacert: &X509Ref) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::SSL_CTX_add_client_CA(self.as_ptr(), cacert.as_ptr())).map(|_| ()) }
+    }
+
     /// Set the context identifier for sessions.
     ///
     /// This value identifies the server's session cache to clients, telling them when they're
@@ -969,6 +1102,78 @@ impl SslContextBuilder {
         }
     }
 
+    /// Sets the list of supported TLS 1.3 cipher suites.
+    ///
+    /// The list is a colon-separated list of suite names, such as
+    /// `"TLS_AES_256_GCM_SHA384:TLS_CHACHA20_POLY1305_SHA256"`. It is independent of the list set
+    /// by [`set_cipher_list`], which only applies to TLS 1.2 and older.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_CTX_set_ciphersuites`].
+    ///
+    /// [`set_cipher_list`]: #method.set_cipher_list
+    /// [`SSL_CTX_set_ciphersuites`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_ciphersuites.html
+    #[cfg(ossl111)]
+    pub fn set_ciphersuites(&mut self, cipher_suites: &str) -> Result<(), ErrorStack> {
+        let cipher_suites = CString::new(cipher_suites).unwrap();
+        unsafe {
+            cvt(ffi::SSL_CTX_set_ciphersuites(
+                self.as_ptr(),
+                cipher_suites.as_ptr() as *const _,
+            )).map(|_| ())
+        }
+    }
+
+    /// Sets the security level of the context.
+    ///
+    /// The security level restricts the ciphers, key sizes, and protocol versions which may be
+    /// used, from 0, which permits everything OpenSSL supports, to 5. The default is determined
+    /// when OpenSSL is built, and is typically 1.
+    ///
+    /// Requires OpenSSL 1.1.0 or newer.
+    ///
+    /// This corresponds to [`SSL_CTX_set_security_level`].
+    ///
+    /// [`SSL_CTX_set_security_level`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_CTX_set_security_level.html
+    #[cfg(ossl110)]
+    pub fn set_security_level(&mut self, level: u32) {
+        unsafe { ffi::SSL_CTX_set_security_level(self.as_ptr(), level as c_int) }
+    }
+
+    /// Sets the list of supported key exchange groups, in order of preference.
+    ///
+    /// The list is a colon-separated list of group names, such as `"X25519:P-256"`. On the client
+    /// side, key shares are sent for the first group in the list.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_CTX_set1_groups_list`].
+    ///
+    /// [`SSL_CTX_set1_groups_list`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set1_groups_list.html
+    #[cfg(ossl111)]
+    pub fn set_groups_list(&mut self, groups: &str) -> Result<(), ErrorStack> {
+        let groups = CString::new(groups).unwrap();
+        unsafe {
+            cvt(ffi::SSL_CTX_set1_groups_list(self.as_ptr(), groups.as_ptr()) as c_int).map(|_| ())
+        }
+    }
+
+    /// Sets the maximum amount of TLS 1.3 early data which will be accepted or sent.
+    ///
+    /// Servers accept early data in resumed sessions if this is non-zero. Clients may only send
+    /// early data in a session resumed from a server which advertised support for it.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_CTX_set_max_early_data`].
+    ///
+    /// [`SSL_CTX_set_max_early_data`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_max_early_data.html
+    #[cfg(ossl111)]
+    pub fn set_max_early_data(&mut self, bytes: u32) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::SSL_CTX_set_max_early_data(self.as_ptr(), bytes)).map(|_| ()) }
+    }
+
     /// Enables ECDHE key exchange with an automatically chosen curve list.
     ///
     /// Requires OpenSSL 1.0.2.
@@ -1021,15 +1226,16 @@ impl SslContextBuilder {
     /// A value of `None` will enable protocol versions down the the lowest version supported by
     /// OpenSSL.
     ///
-    /// This corresponds to [`SSL_CTX_set_min_proto_version`].
-    ///
-    /// Requires OpenSSL 1.1.0 or newer.
+    /// This corresponds to [`SSL_CTX_set_min_proto_version`]. On OpenSSL 1.0.x and LibreSSL, it
+    /// is emulated by setting and clearing the `NO_SSLV3`, `NO_TLSV1`, `NO_TLSV1_1`, and
+    /// `NO_TLSV1_2` options.
+    /// An error is returned for versions which cannot be enabled through those options, such as
+    /// TLSv1.3.
     ///
     /// [`SSL_CTX_set_min_proto_version`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_set_min_proto_version.html
-    #[cfg(any(ossl110))]
     pub fn set_min_proto_version(&mut self, version: Option<SslVersion>) -> Result<(), ErrorStack> {
         unsafe {
-            cvt(ffi::SSL_CTX_set_min_proto_version(
+            cvt(compat::SSL_CTX_set_min_proto_version(
                 self.as_ptr(),
                 version.map_or(0, |v| v.0),
             )).map(|_| ())
@@ -1038,18 +1244,19 @@ impl SslContextBuilder {
 
     /// Sets the maximum supported protocol version.
     ///
-    /// A value of `None` will enable protocol versions down the the highest version supported by
+    /// A value of `None` will enable protocol versions up to the highest version supported by
     /// OpenSSL.
     ///
-    /// This corresponds to [`SSL_CTX_set_max_proto_version`].
-    ///
-    /// Requires OpenSSL 1.1.0 or newer.
+    /// This corresponds to [`SSL_CTX_set_max_proto_version`]. On OpenSSL 1.0.x and LibreSSL, it
+    /// is emulated by setting and clearing the `NO_SSLV3`, `NO_TLSV1`, `NO_TLSV1_1`, and
+    /// `NO_TLSV1_2` options.
+    /// An error is returned for versions which cannot be enabled through those options, such as
+    /// TLSv1.3.
     ///
     /// [`SSL_CTX_set_max_proto_version`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_set_min_proto_version.html
-    #[cfg(any(ossl110))]
     pub fn set_max_proto_version(&mut self, version: Option<SslVersion>) -> Result<(), ErrorStack> {
         unsafe {
-            cvt(ffi::SSL_CTX_set_max_proto_version(
+            cvt(compat::SSL_CTX_set_max_proto_version(
                 self.as_ptr(),
                 version.map_or(0, |v| v.0),
             )).map(|_| ())
@@ -1061,15 +1268,16 @@ impl SslContextBuilder {
     /// A value of `None` indicates that all versions down the the lowest version supported by
     /// OpenSSL are enabled.
     ///
-    /// This corresponds to [`SSL_CTX_get_min_proto_version`].
+    /// This corresponds to [`SSL_CTX_get_min_proto_version`]. On OpenSSL 1.0.x and LibreSSL, it
+    /// is derived from the protocol options.
     ///
-    /// Requires OpenSSL 1.1.0g or newer.
+    /// Requires OpenSSL 1.1.0g or newer when linking against OpenSSL 1.1.0.
     ///
     /// [`SSL_CTX_get_min_proto_version`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_set_min_proto_version.html
-    #[cfg(any(ossl110g))]
+    #[cfg(any(ossl110g, ossl10x))]
     pub fn min_proto_version(&mut self) -> Option<SslVersion> {
         unsafe {
-            let r = ffi::SSL_CTX_get_min_proto_version(self.as_ptr());
+            let r = compat::SSL_CTX_get_min_proto_version(self.as_ptr());
             if r == 0 {
                 None
             } else {
@@ -1080,18 +1288,19 @@ impl SslContextBuilder {
 
     /// Gets the maximum supported protocol version.
     ///
-    /// A value of `None` indicates that all versions down the the highest version supported by
+    /// A value of `None` indicates that all versions up to the highest version supported by
     /// OpenSSL are enabled.
     ///
-    /// This corresponds to [`SSL_CTX_get_max_proto_version`].
+    /// This corresponds to [`SSL_CTX_get_max_proto_version`]. On OpenSSL 1.0.x and LibreSSL, it
+    /// is derived from the protocol options.
     ///
-    /// Requires OpenSSL 1.1.0g or newer.
+    /// Requires OpenSSL 1.1.0g or newer when linking against OpenSSL 1.1.0.
     ///
     /// [`SSL_CTX_get_max_proto_version`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_set_min_proto_version.html
-    #[cfg(any(ossl110g))]
+    #[cfg(any(ossl110g, ossl10x))]
     pub fn max_proto_version(&mut self) -> Option<SslVersion> {
         unsafe {
-            let r = ffi::SSL_CTX_get_max_proto_version(self.as_ptr());
+            let r = compat::SSL_CTX_get_max_proto_version(self.as_ptr());
             if r == 0 {
                 None
             } else {
@@ -1226,16 +1435,148 @@ impl SslContextBuilder {
     ///
     /// [`SSL_CTX_set_psk_client_callback`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_CTX_set_psk_client_callback.html
     #[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+    pub fn set_psk_client_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&mut SslRef, Option<&[u8]>, &mut [u8], &mut [u8]) -> Result<usize, ErrorStack>
+            + 'static
+            + Sync
+            + Send,
+    {
+        unsafe {
+            self.set_ex_data(SslContext::cached_ex_index::<F>(), callback);
+            ffi::SSL_CTX_set_psk_client_callback(self.as_ptr(), Some(raw_client_psk::<F>));
+        }
+    }
+
+    /// Sets the callback for providing an identity and pre-shared key for a TLS-PSK client.
+    ///
+    /// The callback will be called with the SSL context, an identity hint if one was provided
+    /// by the server, a mutable slice for each of the identity and pre-shared key bytes. The
+    /// identity must be written as a null-terminated C string.
+    ///
+    /// This method has been renamed to [`set_psk_client_callback`], which should be used instead.
+    ///
+    /// This corresponds to [`SSL_CTX_set_psk_client_callback`].
+    ///
+    /// [`set_psk_client_callback`]: #method.set_psk_client_callback
+    /// [`SSL_CTX_set_psk_client_callback`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_CTX_set_psk_client_callback.html
+    #[deprecated(since = "0.10.8", note = "renamed to `set_psk_client_callback`")]
+    #[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
     pub fn set_psk_callback<F>(&mut self, callback: F)
     where
         F: Fn(&mut SslRef, Option<&[u8]>, &mut [u8], &mut [u8]) -> Result<usize, ErrorStack>
             + 'static
             + Sync
             + Send,
+    {
+        self.set_psk_client_callback(callback)
+    }
+
+    /// Sets the callback for looking up the pre-shared key of a TLS-PSK client.
+    ///
+    /// The callback will be called with the SSL context, the identity provided by the client if
+    /// any, and a mutable slice for the pre-shared key bytes. It should return the length of the
+    /// key written into the slice, or an error if the identity is not recognized.
+    ///
+    /// This corresponds to [`SSL_CTX_set_psk_server_callback`].
+    ///
+    /// [`SSL_CTX_set_psk_server_callback`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_CTX_set_psk_client_callback.html
+    #[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+    pub fn set_psk_server_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&mut SslRef, Option<&[u8]>, &mut [u8]) -> Result<usize, ErrorStack>
+            + 'static
+            + Sync
+            + Send,
+    {
+        unsafe {
+            self.set_ex_data(SslContext::cached_ex_index::<F>(), callback);
+            ffi::SSL_CTX_set_psk_server_callback(self.as_ptr(), Some(raw_server_psk::<F>));
+        }
+    }
+
+    /// Sets the identity hint sent by a TLS-PSK server.
+    ///
+    /// Clients receive the hint in the callback configured by [`set_psk_client_callback`]. The
+    /// hint is not used by TLSv1.3.
+    ///
+    /// This corresponds to [`SSL_CTX_use_psk_identity_hint`].
+    ///
+    /// [`set_psk_client_callback`]: #method.set_psk_client_callback
+    /// [`SSL_CTX_use_psk_identity_hint`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_use_psk_identity_hint.html
+    #[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+    pub fn set_psk_identity_hint(&mut self, hint: &str) -> Result<(), ErrorStack> {
+        let hint = CString::new(hint).unwrap();
+        unsafe {
+            cvt(ffi::SSL_CTX_use_psk_identity_hint(
+                self.as_ptr(),
+                hint.as_ptr() as *const _,
+            )).map(|_| ())
+        }
+    }
+
+    /// Sets the callback providing a TLSv1.3 external pre-shared key to a client.
+    ///
+    /// The callback is passed the handshake digest the key must be usable with, or `None` on the
+    /// first `ClientHello` of a connection. It should return the identity of the key and an
+    /// `SslSession` carrying the key itself, or `None` to not offer a PSK. The session must be
+    /// configured with [`SslSessionRef::set_master_key`], [`SslSessionRef::set_cipher`] and a
+    /// protocol version of TLSv1.3.
+    ///
+    /// Unlike [`set_psk_client_callback`], this does not rely on legacy PSK cipher suites.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_CTX_set_psk_use_session_callback`].
+    ///
+    /// [`SslSessionRef::set_master_key`]: struct.SslSessionRef.html#method.set_master_key
+    /// [`SslSessionRef::set_cipher`]: struct.SslSessionRef.html#method.set_cipher
+    /// [`set_psk_client_callback`]: #method.set_psk_client_callback
+    /// [`SSL_CTX_set_psk_use_session_callback`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_psk_use_session_callback.html
+    #[cfg(ossl111)]
+    pub fn set_psk_use_session_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&mut SslRef, Option<MessageDigest>)
+                -> Result<Option<(Vec<u8>, SslSession)>, ErrorStack>
+            + 'static
+            + Sync
+            + Send,
+    {
+        unsafe {
+            self.set_ex_data(SslContext::cached_ex_index::<F>(), callback);
+            ffi::SSL_CTX_set_psk_use_session_callback(
+                self.as_ptr(),
+                Some(raw_psk_use_session::<F>),
+            );
+        }
+    }
+
+    /// Sets the callback looking up a TLSv1.3 external pre-shared key on a server.
+    ///
+    /// The callback is passed the identity offered by the client. It should return an
+    /// `SslSession` carrying the corresponding key, configured as described in
+    /// [`set_psk_use_session_callback`], or `None` if the identity is not recognized.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_CTX_set_psk_find_session_callback`].
+    ///
+    /// [`set_psk_use_session_callback`]: #method.set_psk_use_session_callback
+    /// [`SSL_CTX_set_psk_find_session_callback`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_psk_find_session_callback.html
+    #[cfg(ossl111)]
+    pub fn set_psk_find_session_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&mut SslRef, &[u8]) -> Result<Option<SslSession>, ErrorStack>
+            + 'static
+            + Sync
+            + Send,
     {
         unsafe {
             self.set_ex_data(SslContext::cached_ex_index::<F>(), callback);
-            ffi::SSL_CTX_set_psk_client_callback(self.as_ptr(), Some(raw_psk::<F>));
+            ffi::SSL_CTX_set_psk_find_session_callback(
+                self.as_ptr(),
+                Some(raw_psk_find_session::<F>),
+            );
         }
     }
 
@@ -1307,25 +1648,92 @@ impl SslContextBuilder {
         ffi::SSL_CTX_sess_set_get_cb(self.as_ptr(), Some(callbacks::raw_get_session::<F>));
     }
 
+    /// Stores the sessions established by a server in an external `SessionStore`.
+    ///
+    /// New sessions are added to the store rather than the internal cache, and are looked up in it
+    /// when a client attempts to resume a session, allowing clients to resume sessions established
+    /// by other servers sharing the store. This replaces the new, remove, and get session
+    /// callbacks, and sets the session cache mode to `SERVER | NO_INTERNAL_STORE`.
+    ///
+    /// Servers sharing a store must use the same session ID context. Session tickets are resumed
+    /// without consulting the store, so they should be disabled with `SslOptions::NO_TICKET`
+    /// unless the servers also share ticket keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use openssl::ssl::{MemorySessionStore, SslContext, SslMethod, SslOptions};
+    /// use std::sync::Arc;
+    ///
+    /// let store = Arc::new(MemorySessionStore::new());
+    ///
+    /// let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    /// ctx.set_session_id_context(b"my server").unwrap();
+    /// ctx.set_options(SslOptions::NO_TICKET);
+    /// ctx.set_session_store(store.clone());
+    /// ```
+    pub fn set_session_store<S>(&mut self, store: S)
+    where
+        S: SessionStore,
+    {
+        session_store::install(self, store)
+    }
+
     /// Sets the TLS key logging callback.
     ///
     /// The callback is invoked whenever TLS key material is generated, and is passed a line of NSS
     /// SSLKEYLOGFILE-formatted text. This can be used by tools like Wireshark to decrypt message
     /// traffic. The line does not contain a trailing newline.
     ///
-    /// Requires OpenSSL 1.1.1 or newer.
+    /// Requires OpenSSL 1.1.0 or newer. OpenSSL 1.1.0 has no native support for key logging, so it
+    /// is emulated with an info callback which reports a `CLIENT_RANDOM` line once each handshake
+    /// completes, replacing any info callback previously configured on the context.
     ///
     /// This corresponds to [`SSL_CTX_set_keylog_callback`].
     ///
     /// [`SSL_CTX_set_keylog_callback`]: https://www.openssl.org/docs/manmaster/man3/SSL_CTX_set_keylog_callback.html
-    #[cfg(ossl111)]
+    #[cfg(ossl110)]
     pub fn set_keylog_callback<F>(&mut self, callback: F)
     where
         F: Fn(&SslRef, &str) + 'static + Sync + Send,
     {
         unsafe {
             self.set_ex_data(SslContext::cached_ex_index::<F>(), callback);
+            #[cfg(ossl111)]
             ffi::SSL_CTX_set_keylog_callback(self.as_ptr(), Some(callbacks::raw_keylog::<F>));
+            #[cfg(not(ossl111))]
+            ffi::SSL_CTX_set_info_callback(self.as_ptr(), Some(callbacks::raw_keylog_info::<F>));
+        }
+    }
+
+    /// Sets a callback which is called on the server when a ClientHello is received.
+    ///
+    /// The callback runs before any other processing of the ClientHello, including SNI, ALPN, and
+    /// cipher selection, and can inspect it with the `client_hello_*` methods of `SslRef`. It may
+    /// change the connection's configuration, for example with `set_ssl_context`, pause the
+    /// handshake by returning `ClientHelloResponse::RETRY`, or abort it with an error, in which
+    /// case the alert is sent to the client.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_CTX_set_client_hello_cb`].
+    ///
+    /// [`SSL_CTX_set_client_hello_cb`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_client_hello_cb.html
+    #[cfg(ossl111)]
+    pub fn set_client_hello_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&mut SslRef, &mut SslAlert) -> Result<ClientHelloResponse, ErrorStack>
+            + 'static
+            + Sync
+            + Send,
+    {
+        unsafe {
+            self.set_ex_data(SslContext::cached_ex_index::<F>(), callback);
+            ffi::SSL_CTX_set_client_hello_cb(
+                self.as_ptr(),
+                Some(raw_client_hello::<F>),
+                ptr::null_mut(),
+            );
         }
     }
 
@@ -1441,6 +1849,15 @@ impl SslContextBuilder {
 
     /// Adds a custom extension for a TLS/DTLS client or server for all supported protocol versions.
     ///
+    /// `context` specifies the messages the extension may appear in. `add_cb` is called when one
+    /// of those messages is constructed, and returns the extension's contents, or `None` to omit
+    /// it. `parse_cb` is called with the contents of the extension when it is received. On a
+    /// server, `add_cb` is only called if the client sent the extension. Either callback may abort
+    /// the handshake by returning the alert to send to the peer.
+    ///
+    /// For extensions in the `TLS1_3_CERTIFICATE` context, the callbacks are also passed the
+    /// certificate the extension is attached to, along with its index in the chain.
+    ///
     /// Requires OpenSSL 1.1.1 or newer.
     ///
     /// This corresponds to [`SSL_CTX_add_custom_ext`].
@@ -1607,6 +2024,18 @@ impl SslContextRef {
         }
     }
 
+    /// Returns the maximum amount of TLS 1.3 early data which will be accepted or sent.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_CTX_get_max_early_data`].
+    ///
+    /// [`SSL_CTX_get_max_early_data`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_max_early_data.html
+    #[cfg(ossl111)]
+    pub fn max_early_data(&self) -> u32 {
+        unsafe { ffi::SSL_CTX_get_max_early_data(self.as_ptr()) }
+    }
+
     /// Returns a shared reference to the certificate store used for verification.
     ///
     /// This corresponds to [`SSL_CTX_get_cert_store`].
@@ -1628,6 +2057,55 @@ impl SslContextRef {
         }
     }
 
+    /// Returns the security level of the context.
+    ///
+    /// Requires OpenSSL 1.1.0 or newer.
+    ///
+    /// This corresponds to [`SSL_CTX_get_security_level`].
+    ///
+    /// [`SSL_CTX_get_security_level`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_CTX_set_security_level.html
+    #[cfg(ossl110)]
+    pub fn security_level(&self) -> u32 {
+        unsafe { ffi::SSL_CTX_get_security_level(self.as_ptr()) as u32 }
+    }
+
+    /// Returns the configured cipher list, in order of preference.
+    ///
+    /// This includes ciphers which are disabled by other settings, such as the protocol versions
+    /// enabled. Use [`SslRef::supported_ciphers`] to determine the ciphers which can actually be
+    /// negotiated.
+    ///
+    /// Requires OpenSSL 1.1.0 or newer.
+    ///
+    /// This corresponds to [`SSL_CTX_get_ciphers`].
+    ///
+    /// [`SslRef::supported_ciphers`]: struct.SslRef.html#method.supported_ciphers
+    /// [`SSL_CTX_get_ciphers`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_get_ciphers.html
+    #[cfg(ossl110)]
+    pub fn ciphers(&self) -> Option<&StackRef<SslCipher>> {
+        unsafe {
+            let ptr = ffi::SSL_CTX_get_ciphers(self.as_ptr());
+            if ptr.is_null() {
+                None
+            } else {
+                Some(StackRef::from_ptr(ptr))
+            }
+        }
+    }
+
+    /// Checks the ciphers and protocol versions enabled by this context for known weaknesses.
+    ///
+    /// Export, NULL, anonymous, and RC4 ciphers, ciphers with fewer than 112 bits of security,
+    /// SSLv3, and TLS 1.0 and 1.1 are reported. This is intended as a startup self-check, and only
+    /// reflects the context's configuration; protocol versions which the linked OpenSSL was built
+    /// without may still be listed as enabled.
+    ///
+    /// On OpenSSL 1.1.0 before 1.1.0g, the minimum and maximum protocol versions cannot be queried,
+    /// so only the protocol options are taken into account.
+    pub fn audit(&self) -> Result<SecurityAudit, ErrorStack> {
+        audit::audit(self)
+    }
+
     /// Returns a reference to the extra data at the specified index.
     ///
     /// This corresponds to [`SSL_CTX_get_ex_data`].
@@ -1686,6 +2164,10 @@ impl DerefMut for SslCipher {
     }
 }
 
+impl Stackable for SslCipher {
+    type StackType = ffi::stack_st_SSL_CIPHER;
+}
+
 /// Reference to an [`SslCipher`].
 ///
 /// [`SslCipher`]: struct.SslCipher.html
@@ -1710,6 +2192,25 @@ impl SslCipherRef {
         str::from_utf8(name.to_bytes()).unwrap()
     }
 
+    /// Returns the RFC-standard name of the cipher, if one exists.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_CIPHER_standard_name`].
+    ///
+    /// [`SSL_CIPHER_standard_name`]: https://www.openssl.org/docs/manmaster/man3/SSL_CIPHER_get_name.html
+    #[cfg(ossl111)]
+    pub fn standard_name(&self) -> Option<&str> {
+        unsafe {
+            let ptr = ffi::SSL_CIPHER_standard_name(self.as_ptr());
+            if ptr.is_null() {
+                None
+            } else {
+                Some(str::from_utf8(CStr::from_ptr(ptr as *const _).to_bytes()).unwrap())
+            }
+        }
+    }
+
     /// Returns the SSL/TLS protocol version that first defined the cipher.
     ///
     /// This corresponds to [`SSL_CIPHER_get_version`].
@@ -1816,6 +2317,20 @@ impl Clone for SslSession {
 }
 
 impl SslSession {
+    /// Creates a new, empty session.
+    ///
+    /// This is primarily useful to construct TLSv1.3 external pre-shared keys.
+    ///
+    /// This corresponds to [`SSL_SESSION_new`].
+    ///
+    /// [`SSL_SESSION_new`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_SESSION_new.html
+    pub fn new() -> Result<SslSession, ErrorStack> {
+        unsafe {
+            ffi::init();
+            cvt_p(ffi::SSL_SESSION_new()).map(SslSession)
+        }
+    }
+
     from_der! {
         /// Deserializes a DER-encoded session structure.
         ///
@@ -1853,7 +2368,56 @@ impl SslSessionRef {
         }
     }
 
-    /// Returns the length of the master key.
+    /// Returns the time at which the session was established, in seconds since the Unix epoch.
+    ///
+    /// This corresponds to [`SSL_SESSION_get_time`].
+    ///
+    /// [`SSL_SESSION_get_time`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_SESSION_get_time.html
+    pub fn time(&self) -> i64 {
+        unsafe { ffi::SSL_SESSION_get_time(self.as_ptr()) as i64 }
+    }
+
+    /// Returns the lifetime of the session in seconds.
+    ///
+    /// The session expires, and will no longer be resumed, once this many seconds have passed
+    /// since the time returned by [`time`].
+    ///
+    /// This corresponds to [`SSL_SESSION_get_timeout`].
+    ///
+    /// [`time`]: #method.time
+    /// [`SSL_SESSION_get_timeout`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_SESSION_get_time.html
+    pub fn timeout(&self) -> i64 {
+        unsafe { ffi::SSL_SESSION_get_timeout(self.as_ptr()) as i64 }
+    }
+
+    /// Sets the lifetime of the session in seconds.
+    ///
+    /// Timeouts too large to be represented by a C `long` are clamped.
+    ///
+    /// This corresponds to [`SSL_SESSION_set_timeout`].
+    ///
+    /// [`SSL_SESSION_set_timeout`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_SESSION_get_time.html
+    pub fn set_timeout(&mut self, timeout: u32) {
+        // the cast of the maximum truncates to u32::MAX when a long is 64 bits wide
+        let timeout = cmp::min(timeout, c_long::max_value() as u32) as c_long;
+        unsafe {
+            ffi::SSL_SESSION_set_timeout(self.as_ptr(), timeout);
+        }
+    }
+
+    /// Returns the protocol version negotiated for the session.
+    ///
+    /// Requires OpenSSL 1.1.0 or newer.
+    ///
+    /// This corresponds to [`SSL_SESSION_get_protocol_version`].
+    ///
+    /// [`SSL_SESSION_get_protocol_version`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_SESSION_get_protocol_version.html
+    #[cfg(ossl110)]
+    pub fn protocol_version(&self) -> SslVersion {
+        unsafe { SslVersion(ffi::SSL_SESSION_get_protocol_version(self.as_ptr())) }
+    }
+
+    /// Returns the length of the master key.
     ///
     /// This corresponds to [`SSL_SESSION_get_master_key`].
     ///
@@ -1873,6 +2437,52 @@ impl SslSessionRef {
         unsafe { compat::SSL_SESSION_get_master_key(self.as_ptr(), buf.as_mut_ptr(), buf.len()) }
     }
 
+    /// Sets the master key of the session.
+    ///
+    /// For TLSv1.3 external pre-shared keys, this is the pre-shared key itself.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_SESSION_set1_master_key`].
+    ///
+    /// [`SSL_SESSION_set1_master_key`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_SESSION_set1_master_key.html
+    #[cfg(ossl111)]
+    pub fn set_master_key(&mut self, key: &[u8]) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::SSL_SESSION_set1_master_key(
+                self.as_ptr(),
+                key.as_ptr(),
+                key.len(),
+            )).map(|_| ())
+        }
+    }
+
+    /// Sets the cipher associated with the session.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_SESSION_set_cipher`].
+    ///
+    /// [`SSL_SESSION_set_cipher`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_SESSION_set_cipher.html
+    #[cfg(ossl111)]
+    pub fn set_cipher(&mut self, cipher: &SslCipherRef) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::SSL_SESSION_set_cipher(self.as_ptr(), cipher.as_ptr())).map(|_| ()) }
+    }
+
+    /// Sets the protocol version of the session.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_SESSION_set_protocol_version`].
+    ///
+    /// [`SSL_SESSION_set_protocol_version`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_SESSION_set_protocol_version.html
+    #[cfg(ossl111)]
+    pub fn set_protocol_version(&mut self, version: SslVersion) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::SSL_SESSION_set_protocol_version(self.as_ptr(), version.0)).map(|_| ())
+        }
+    }
+
     to_der! {
         /// Serializes the session into a DER-encoded structure.
         ///
@@ -1951,7 +2561,7 @@ impl Ssl {
     /// This corresponds to [`SSL_new`].
     ///
     /// [`SSL_new`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_new.html
-    pub fn new(ctx: &SslContext) -> Result<Ssl, ErrorStack> {
+    pub fn new(ctx: &SslContextRef) -> Result<Ssl, ErrorStack> {
         unsafe {
             let ssl = cvt_p(ffi::SSL_new(ctx.as_ptr()))?;
             Ok(Ssl::from_ptr(ssl))
@@ -2133,6 +2743,98 @@ impl SslRef {
         }
     }
 
+    /// Returns the configured cipher list, in order of preference.
+    ///
+    /// This includes ciphers which are disabled by other settings, such as the protocol versions
+    /// enabled. Use [`supported_ciphers`] to determine the ciphers which can actually be
+    /// negotiated.
+    ///
+    /// This corresponds to [`SSL_get_ciphers`].
+    ///
+    /// [`supported_ciphers`]: #method.supported_ciphers
+    /// [`SSL_get_ciphers`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_get_ciphers.html
+    pub fn ciphers(&self) -> Option<&StackRef<SslCipher>> {
+        unsafe {
+            let ptr = ffi::SSL_get_ciphers(self.as_ptr());
+            if ptr.is_null() {
+                None
+            } else {
+                Some(StackRef::from_ptr(ptr))
+            }
+        }
+    }
+
+    /// Returns the ciphers which can be negotiated, in order of preference.
+    ///
+    /// Ciphers which are not usable with the enabled protocol versions are excluded. On the client
+    /// side, this is the list of ciphers sent in the ClientHello.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    ///
+    /// This corresponds to [`SSL_get1_supported_ciphers`].
+    ///
+    /// [`SSL_get1_supported_ciphers`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_get_ciphers.html
+    #[cfg(any(ossl102, ossl110))]
+    pub fn supported_ciphers(&self) -> Option<Stack<SslCipher>> {
+        unsafe {
+            let ptr = ffi::SSL_get1_supported_ciphers(self.as_ptr());
+            if ptr.is_null() {
+                None
+            } else {
+                Some(Stack::from_ptr(ptr))
+            }
+        }
+    }
+
+    /// Sets the security level of the session.
+    ///
+    /// See [`SslContextBuilder::set_security_level`] for details.
+    ///
+    /// Requires OpenSSL 1.1.0 or newer.
+    ///
+    /// This corresponds to [`SSL_set_security_level`].
+    ///
+    /// [`SslContextBuilder::set_security_level`]: struct.SslContextBuilder.html#method.set_security_level
+    /// [`SSL_set_security_level`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_CTX_set_security_level.html
+    #[cfg(ossl110)]
+    pub fn set_security_level(&mut self, level: u32) {
+        unsafe { ffi::SSL_set_security_level(self.as_ptr(), level as c_int) }
+    }
+
+    /// Returns the security level of the session.
+    ///
+    /// Requires OpenSSL 1.1.0 or newer.
+    ///
+    /// This corresponds to [`SSL_get_security_level`].
+    ///
+    /// [`SSL_get_security_level`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_CTX_set_security_level.html
+    #[cfg(ossl110)]
+    pub fn security_level(&self) -> u32 {
+        unsafe { ffi::SSL_get_security_level(self.as_ptr()) as u32 }
+    }
+
+    /// Looks up a cipher by its two byte IANA identifier.
+    ///
+    /// For example, `TLS_AES_128_GCM_SHA256` is identified by `[0x13, 0x01]`.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_CIPHER_find`].
+    ///
+    /// [`SSL_CIPHER_find`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CIPHER_find.html
+    #[cfg(ossl111)]
+    pub fn find_cipher(&self, id: [u8; 2]) -> Option<&SslCipherRef> {
+        unsafe {
+            let ptr = ffi::SSL_CIPHER_find(self.as_ptr(), id.as_ptr());
+
+            if ptr.is_null() {
+                None
+            } else {
+                Some(SslCipherRef::from_ptr(ptr as *mut _))
+            }
+        }
+    }
+
     /// Returns a short string describing the state of the session.
     ///
     /// This corresponds to [`SSL_state_string`].
@@ -2211,6 +2913,50 @@ impl SslRef {
         }
     }
 
+    /// Returns the verified certificate chain of the peer, including the leaf certificate.
+    ///
+    /// If verification was not successful (i.e. [`verify_result`] does not return
+    /// [`X509VerifyResult::OK`]), this chain may be incomplete or invalid.
+    ///
+    /// Requires OpenSSL 1.1.0 or newer.
+    ///
+    /// This corresponds to [`SSL_get0_verified_chain`].
+    ///
+    /// [`verify_result`]: #method.verify_result
+    /// [`X509VerifyResult::OK`]: ../x509/struct.X509VerifyResult.html#associatedconstant.OK
+    /// [`SSL_get0_verified_chain`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_get0_verified_chain.html
+    #[cfg(ossl110)]
+    pub fn verified_chain(&self) -> Option<&StackRef<X509>> {
+        unsafe {
+            let ptr = ffi::SSL_get0_verified_chain(self.as_ptr());
+            if ptr.is_null() {
+                None
+            } else {
+                Some(StackRef::from_ptr(ptr))
+            }
+        }
+    }
+
+    /// Returns the list of CA names associated with the session.
+    ///
+    /// On the server side, this is the list which will be sent to the client when requesting a
+    /// certificate. On the client side, it is the list received from the server, and is only
+    /// available once the server has requested a certificate during the handshake.
+    ///
+    /// This corresponds to [`SSL_get_client_CA_list`].
+    ///
+    /// [`SSL_get_client_CA_list`]: https://www.openssl.org/docs/manmaster/man3/SSL_get_client_CA_list.html
+    pub fn client_ca_list(&self) -> Option<&StackRef<X509Name>> {
+        unsafe {
+            let ptr = ffi::SSL_get_client_CA_list(self.as_ptr());
+            if ptr.is_null() {
+                None
+            } else {
+                Some(StackRef::from_ptr(ptr))
+            }
+        }
+    }
+
     /// Like [`SslContext::certificate`].
     ///
     /// This corresponds to `SSL_get_certificate`.
@@ -2317,6 +3063,22 @@ impl SslRef {
         unsafe { ffi::SSL_pending(self.as_ptr()) as usize }
     }
 
+    /// Determines if any data, processed or not, is buffered by OpenSSL.
+    ///
+    /// Unlike `pending`, this also reports records which have been read from the underlying
+    /// stream, for example due to read-ahead, but not yet decrypted. Such data may still turn
+    /// out not to contain any application data.
+    ///
+    /// Requires OpenSSL 1.1.0 or newer.
+    ///
+    /// This corresponds to [`SSL_has_pending`].
+    ///
+    /// [`SSL_has_pending`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_has_pending.html
+    #[cfg(ossl110)]
+    pub fn has_pending(&self) -> bool {
+        unsafe { ffi::SSL_has_pending(self.as_ptr()) != 0 }
+    }
+
     /// Returns the servername sent by the client via Server Name Indication (SNI).
     ///
     /// It is only useful on the server side.
@@ -2335,130 +3097,393 @@ impl SslRef {
         }
     }
 
-    /// Changes the context corresponding to the current connection.
+    /// Determines if the client's hello message is in the SSLv2 format.
     ///
-    /// It is most commonly used in the Server Name Indication (SNI) callback.
+    /// This can only be used inside of the client hello callback. Otherwise, `false` is returned.
     ///
-    /// This corresponds to `SSL_set_SSL_CTX`.
-    pub fn set_ssl_context(&mut self, ctx: &SslContextRef) -> Result<(), ErrorStack> {
-        unsafe { cvt_p(ffi::SSL_set_SSL_CTX(self.as_ptr(), ctx.as_ptr())).map(|_| ()) }
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_client_hello_isv2`].
+    ///
+    /// [`SSL_client_hello_isv2`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_client_hello_cb.html
+    #[cfg(ossl111)]
+    pub fn client_hello_isv2(&self) -> bool {
+        unsafe { ffi::SSL_client_hello_isv2(self.as_ptr()) != 0 }
     }
 
-    /// Returns the context corresponding to the current connection.
+    /// Returns the legacy version field of the client's hello message.
     ///
-    /// This corresponds to [`SSL_get_SSL_CTX`].
+    /// This can only be used inside of the client hello callback. Otherwise, `None` is returned.
     ///
-    /// [`SSL_get_SSL_CTX`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_get_SSL_CTX.html
-    pub fn ssl_context(&self) -> &SslContextRef {
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_client_hello_get0_legacy_version`].
+    ///
+    /// [`SSL_client_hello_get0_legacy_version`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_client_hello_cb.html
+    #[cfg(ossl111)]
+    pub fn client_hello_legacy_version(&self) -> Option<SslVersion> {
         unsafe {
-            let ssl_ctx = ffi::SSL_get_SSL_CTX(self.as_ptr());
-            SslContextRef::from_ptr(ssl_ctx)
+            let version = ffi::SSL_client_hello_get0_legacy_version(self.as_ptr());
+            if version == 0 {
+                None
+            } else {
+                Some(SslVersion(version as c_int))
+            }
         }
     }
 
-    /// Returns a mutable reference to the X509 verification configuration.
+    /// Returns the random field of the client's hello message.
     ///
-    /// Requires OpenSSL 1.0.2 or newer.
+    /// This can only be used inside of the client hello callback. Otherwise, `None` is returned.
     ///
-    /// This corresponds to [`SSL_get0_param`].
+    /// Requires OpenSSL 1.1.1 or newer.
     ///
-    /// [`SSL_get0_param`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_get0_param.html
-    #[cfg(any(ossl102, ossl110))]
-    pub fn param_mut(&mut self) -> &mut X509VerifyParamRef {
-        unsafe { X509VerifyParamRef::from_ptr_mut(ffi::SSL_get0_param(self.as_ptr())) }
+    /// This corresponds to [`SSL_client_hello_get0_random`].
+    ///
+    /// [`SSL_client_hello_get0_random`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_client_hello_cb.html
+    #[cfg(ossl111)]
+    pub fn client_hello_random(&self) -> Option<&[u8]> {
+        unsafe {
+            let mut ptr = ptr::null();
+            let len = ffi::SSL_client_hello_get0_random(self.as_ptr(), &mut ptr);
+            if len == 0 {
+                None
+            } else {
+                Some(slice::from_raw_parts(ptr, len))
+            }
+        }
     }
 
-    /// Returns the certificate verification result.
+    /// Returns the session ID field of the client's hello message.
     ///
-    /// This corresponds to [`SSL_get_verify_result`].
+    /// This can only be used inside of the client hello callback. Otherwise, `None` is returned.
+    /// `None` is also returned if the client sent an empty session ID.
     ///
-    /// [`SSL_get_verify_result`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_get_verify_result.html
-    pub fn verify_result(&self) -> X509VerifyResult {
-        unsafe { X509VerifyResult::from_raw(ffi::SSL_get_verify_result(self.as_ptr()) as c_int) }
-    }
-
-    /// Returns a shared reference to the SSL session.
+    /// Requires OpenSSL 1.1.1 or newer.
     ///
-    /// This corresponds to [`SSL_get_session`].
+    /// This corresponds to [`SSL_client_hello_get0_session_id`].
     ///
-    /// [`SSL_get_session`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_get_session.html
-    pub fn session(&self) -> Option<&SslSessionRef> {
+    /// [`SSL_client_hello_get0_session_id`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_client_hello_cb.html
+    #[cfg(ossl111)]
+    pub fn client_hello_session_id(&self) -> Option<&[u8]> {
         unsafe {
-            let p = ffi::SSL_get_session(self.as_ptr());
-            if p.is_null() {
+            let mut ptr = ptr::null();
+            let len = ffi::SSL_client_hello_get0_session_id(self.as_ptr(), &mut ptr);
+            if len == 0 {
                 None
             } else {
-                Some(SslSessionRef::from_ptr(p))
+                Some(slice::from_raw_parts(ptr, len))
             }
         }
     }
 
-    /// Copies the client_random value sent by the client in the TLS handshake into a buffer.
+    /// Returns the ciphers field of the client's hello message.
     ///
-    /// Returns the number of bytes copied, or if the buffer is empty, the size of the client_random
-    /// value.
+    /// This is the list of cipher suites offered by the client, each encoded as its two byte
+    /// IANA identifier, in the client's order of preference.
     ///
-    /// Requires OpenSSL 1.1.0 or newer.
+    /// This can only be used inside of the client hello callback. Otherwise, `None` is returned.
     ///
-    /// This corresponds to [`SSL_get_client_random`].
+    /// Requires OpenSSL 1.1.1 or newer.
     ///
-    /// [`SSL_get_client_random`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_get_client_random.html
-    #[cfg(any(ossl110))]
-    pub fn client_random(&self, buf: &mut [u8]) -> usize {
+    /// This corresponds to [`SSL_client_hello_get0_ciphers`].
+    ///
+    /// [`SSL_client_hello_get0_ciphers`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_client_hello_cb.html
+    #[cfg(ossl111)]
+    pub fn client_hello_ciphers(&self) -> Option<&[u8]> {
         unsafe {
-            ffi::SSL_get_client_random(self.as_ptr(), buf.as_mut_ptr() as *mut c_uchar, buf.len())
+            let mut ptr = ptr::null();
+            let len = ffi::SSL_client_hello_get0_ciphers(self.as_ptr(), &mut ptr);
+            if len == 0 {
+                None
+            } else {
+                Some(slice::from_raw_parts(ptr, len))
+            }
         }
     }
 
-    /// Copies the server_random value sent by the server in the TLS handshake into a buffer.
+    /// Returns the compression methods field of the client's hello message.
     ///
-    /// Returns the number of bytes copied, or if the buffer is empty, the size of the server_random
-    /// value.
+    /// This can only be used inside of the client hello callback. Otherwise, `None` is returned.
     ///
-    /// Requires OpenSSL 1.1.0 or newer.
+    /// Requires OpenSSL 1.1.1 or newer.
     ///
-    /// This corresponds to [`SSL_get_server_random`].
+    /// This corresponds to [`SSL_client_hello_get0_compression_methods`].
     ///
-    /// [`SSL_get_server_random`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_get_client_random.html
-    #[cfg(any(ossl110))]
-    pub fn server_random(&self, buf: &mut [u8]) -> usize {
+    /// [`SSL_client_hello_get0_compression_methods`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_client_hello_cb.html
+    #[cfg(ossl111)]
+    pub fn client_hello_compression_methods(&self) -> Option<&[u8]> {
         unsafe {
-            ffi::SSL_get_server_random(self.as_ptr(), buf.as_mut_ptr() as *mut c_uchar, buf.len())
+            let mut ptr = ptr::null();
+            let len = ffi::SSL_client_hello_get0_compression_methods(self.as_ptr(), &mut ptr);
+            if len == 0 {
+                None
+            } else {
+                Some(slice::from_raw_parts(ptr, len))
+            }
         }
     }
 
-    /// Derives keying material for application use in accordance to RFC 5705.
+    /// Returns the types of the extensions in the client's hello message, in the order they were
+    /// sent.
     ///
-    /// This corresponds to [`SSL_export_keying_material`].
+    /// This can only be used inside of the client hello callback. Otherwise, `None` is returned.
     ///
-    /// [`SSL_export_keying_material`]: https://www.openssl.org/docs/manmaster/man3/SSL_export_keying_material.html
-    pub fn export_keying_material(
-        &self,
-        out: &mut [u8],
-        label: &str,
-        context: Option<&[u8]>,
-    ) -> Result<(), ErrorStack> {
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_client_hello_get1_extensions_present`].
+    ///
+    /// [`SSL_client_hello_get1_extensions_present`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_client_hello_cb.html
+    #[cfg(ossl111)]
+    pub fn client_hello_extension_types(&self) -> Option<Vec<u16>> {
         unsafe {
-            let (context, contextlen, use_context) = match context {
-                Some(context) => (context.as_ptr() as *const c_uchar, context.len(), 1),
-                None => (ptr::null(), 0, 0),
+            let mut ptr = ptr::null_mut();
+            let mut len = 0;
+            let r =
+                ffi::SSL_client_hello_get1_extensions_present(self.as_ptr(), &mut ptr, &mut len);
+            if r != 1 {
+                ErrorStack::get();
+                return None;
+            }
+
+            let types = if len == 0 {
+                vec![]
+            } else {
+                slice::from_raw_parts(ptr, len)
+                    .iter()
+                    .map(|&t| t as u16)
+                    .collect()
             };
-            cvt(ffi::SSL_export_keying_material(
-                self.as_ptr(),
-                out.as_mut_ptr() as *mut c_uchar,
-                out.len(),
-                label.as_ptr() as *const c_char,
-                label.len(),
-                context,
-                contextlen,
-                use_context,
-            )).map(|_| ())
+            ffi::CRYPTO_free(
+                ptr as *mut c_void,
+                concat!(file!(), "\0").as_ptr() as *const c_char,
+                line!() as c_int,
+            );
+            Some(types)
         }
     }
 
-    /// Sets the session to be used.
+    /// Returns the contents of an extension in the client's hello message.
     ///
-    /// This should be called before the handshake to attempt to reuse a previously established
+    /// `None` is returned if the client did not send the extension, or if this is not called
+    /// inside of the client hello callback.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_client_hello_get0_ext`].
+    ///
+    /// [`SSL_client_hello_get0_ext`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_client_hello_cb.html
+    #[cfg(ossl111)]
+    pub fn client_hello_ext(&self, ext_type: u16) -> Option<&[u8]> {
+        unsafe {
+            let mut ptr = ptr::null();
+            let mut len = 0;
+            let r = ffi::SSL_client_hello_get0_ext(
+                self.as_ptr(),
+                ext_type as c_uint,
+                &mut ptr,
+                &mut len,
+            );
+            if r == 1 {
+                Some(slice::from_raw_parts(ptr, len))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the host name sent in the server name indication extension of the client's hello
+    /// message.
+    ///
+    /// Unlike `servername`, this can be used inside of the client hello callback, before the
+    /// extension has been processed. `None` is returned if the client did not send a host name or
+    /// the extension is malformed.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub fn client_hello_servername(&self) -> Option<&str> {
+        // ServerNameList: a list of (name type, length-prefixed name) pairs
+        let mut list = match self.client_hello_ext(TLSEXT_TYPE_SERVER_NAME)
+            .and_then(|ext| length_prefixed(ext, 2))
+        {
+            Some((list, _)) => list,
+            None => return None,
+        };
+        while !list.is_empty() {
+            let name_type = list[0];
+            let (name, rest) = match length_prefixed(&list[1..], 2) {
+                Some(r) => r,
+                None => return None,
+            };
+            if name_type == TLSEXT_NAMETYPE_HOST_NAME {
+                return str::from_utf8(name).ok();
+            }
+            list = rest;
+        }
+        None
+    }
+
+    /// Returns the protocols offered in the ALPN extension of the client's hello message, in the
+    /// client's order of preference.
+    ///
+    /// `None` is returned if the client did not send the extension or it is malformed.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(ossl111)]
+    pub fn client_hello_alpn_protocols(&self) -> Option<Vec<&[u8]>> {
+        // ProtocolNameList: a list of length-prefixed names
+        let mut list = match self.client_hello_ext(TLSEXT_TYPE_ALPN)
+            .and_then(|ext| length_prefixed(ext, 2))
+        {
+            Some((list, _)) => list,
+            None => return None,
+        };
+        let mut protocols = vec![];
+        while !list.is_empty() {
+            let (protocol, rest) = match length_prefixed(list, 1) {
+                Some(r) => r,
+                None => return None,
+            };
+            protocols.push(protocol);
+            list = rest;
+        }
+        Some(protocols)
+    }
+
+    /// Changes the context corresponding to the current connection.
+    ///
+    /// It is most commonly used in the Server Name Indication (SNI) callback.
+    ///
+    /// This corresponds to `SSL_set_SSL_CTX`.
+    pub fn set_ssl_context(&mut self, ctx: &SslContextRef) -> Result<(), ErrorStack> {
+        unsafe { cvt_p(ffi::SSL_set_SSL_CTX(self.as_ptr(), ctx.as_ptr())).map(|_| ()) }
+    }
+
+    /// Returns the context corresponding to the current connection.
+    ///
+    /// This corresponds to [`SSL_get_SSL_CTX`].
+    ///
+    /// [`SSL_get_SSL_CTX`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_get_SSL_CTX.html
+    pub fn ssl_context(&self) -> &SslContextRef {
+        unsafe {
+            let ssl_ctx = ffi::SSL_get_SSL_CTX(self.as_ptr());
+            SslContextRef::from_ptr(ssl_ctx)
+        }
+    }
+
+    /// Returns a mutable reference to the X509 verification configuration.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    ///
+    /// This corresponds to [`SSL_get0_param`].
+    ///
+    /// [`SSL_get0_param`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_get0_param.html
+    #[cfg(any(ossl102, ossl110))]
+    pub fn param_mut(&mut self) -> &mut X509VerifyParamRef {
+        unsafe { X509VerifyParamRef::from_ptr_mut(ffi::SSL_get0_param(self.as_ptr())) }
+    }
+
+    /// Returns the certificate verification result.
+    ///
+    /// This corresponds to [`SSL_get_verify_result`].
+    ///
+    /// [`SSL_get_verify_result`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_get_verify_result.html
+    pub fn verify_result(&self) -> X509VerifyResult {
+        unsafe { X509VerifyResult::from_raw(ffi::SSL_get_verify_result(self.as_ptr()) as c_int) }
+    }
+
+    // Returns the certificate verification result if it may have caused an `SSL` error.
+    fn failed_verify_result(&self, code: ErrorCode) -> Option<X509VerifyResult> {
+        let verify = self.verify_result();
+        if code == ErrorCode::SSL && verify != X509VerifyResult::OK {
+            Some(verify)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a shared reference to the SSL session.
+    ///
+    /// This corresponds to [`SSL_get_session`].
+    ///
+    /// [`SSL_get_session`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_get_session.html
+    pub fn session(&self) -> Option<&SslSessionRef> {
+        unsafe {
+            let p = ffi::SSL_get_session(self.as_ptr());
+            if p.is_null() {
+                None
+            } else {
+                Some(SslSessionRef::from_ptr(p))
+            }
+        }
+    }
+
+    /// Copies the client_random value sent by the client in the TLS handshake into a buffer.
+    ///
+    /// Returns the number of bytes copied, or if the buffer is empty, the size of the client_random
+    /// value.
+    ///
+    /// Requires OpenSSL 1.1.0 or newer.
+    ///
+    /// This corresponds to [`SSL_get_client_random`].
+    ///
+    /// [`SSL_get_client_random`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_get_client_random.html
+    #[cfg(any(ossl110))]
+    pub fn client_random(&self, buf: &mut [u8]) -> usize {
+        unsafe {
+            ffi::SSL_get_client_random(self.as_ptr(), buf.as_mut_ptr() as *mut c_uchar, buf.len())
+        }
+    }
+
+    /// Copies the server_random value sent by the server in the TLS handshake into a buffer.
+    ///
+    /// Returns the number of bytes copied, or if the buffer is empty, the size of the server_random
+    /// value.
+    ///
+    /// Requires OpenSSL 1.1.0 or newer.
+    ///
+    /// This corresponds to [`SSL_get_server_random`].
+    ///
+    /// [`SSL_get_server_random`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_get_client_random.html
+    #[cfg(any(ossl110))]
+    pub fn server_random(&self, buf: &mut [u8]) -> usize {
+        unsafe {
+            ffi::SSL_get_server_random(self.as_ptr(), buf.as_mut_ptr() as *mut c_uchar, buf.len())
+        }
+    }
+
+    /// Derives keying material for application use in accordance to RFC 5705.
+    ///
+    /// This corresponds to [`SSL_export_keying_material`].
+    ///
+    /// [`SSL_export_keying_material`]: https://www.openssl.org/docs/manmaster/man3/SSL_export_keying_material.html
+    pub fn export_keying_material(
+        &self,
+        out: &mut [u8],
+        label: &str,
+        context: Option<&[u8]>,
+    ) -> Result<(), ErrorStack> {
+        unsafe {
+            let (context, contextlen, use_context) = match context {
+                Some(context) => (context.as_ptr() as *const c_uchar, context.len(), 1),
+                None => (ptr::null(), 0, 0),
+            };
+            cvt(ffi::SSL_export_keying_material(
+                self.as_ptr(),
+                out.as_mut_ptr() as *mut c_uchar,
+                out.len(),
+                label.as_ptr() as *const c_char,
+                label.len(),
+                context,
+                contextlen,
+                use_context,
+            )).map(|_| ())
+        }
+    }
+
+    /// Sets the session to be used.
+    ///
+    /// This should be called before the handshake to attempt to reuse a previously established
     /// session. If the server is not willing to reuse the session, a new one will be transparently
     /// negotiated.
     ///
@@ -2535,6 +3560,259 @@ impl SslRef {
         }
     }
 
+    /// Sets the certificate store used for verifying the peer's certificate, overriding the one
+    /// configured on the context.
+    ///
+    /// The store's reference count is incremented, so it may continue to be used elsewhere.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    ///
+    /// This corresponds to [`SSL_set1_verify_cert_store`].
+    ///
+    /// [`SSL_set1_verify_cert_store`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_set1_verify_cert_store.html
+    #[cfg(any(ossl102, ossl110))]
+    pub fn set_verify_cert_store(&mut self, cert_store: &X509StoreRef) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::SSL_set1_verify_cert_store(self.as_ptr(), cert_store.as_ptr()) as c_int)
+                .map(|_| ())
+        }
+    }
+
+    /// Returns the session's shutdown state.
+    ///
+    /// This corresponds to [`SSL_get_shutdown`].
+    ///
+    /// [`SSL_get_shutdown`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_get_shutdown.html
+    pub fn get_shutdown(&self) -> ShutdownState {
+        unsafe { ShutdownState::from_bits_truncate(ffi::SSL_get_shutdown(self.as_ptr())) }
+    }
+
+    /// Sets the session's shutdown state.
+    ///
+    /// This does not send or receive any messages. Setting both `SENT` and `RECEIVED` marks the
+    /// session as cleanly closed, which allows it to be resumed later even if the connection was
+    /// torn down without a close notify exchange.
+    ///
+    /// This corresponds to [`SSL_set_shutdown`].
+    ///
+    /// [`SSL_set_shutdown`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_set_shutdown.html
+    pub fn set_shutdown(&mut self, state: ShutdownState) {
+        unsafe { ffi::SSL_set_shutdown(self.as_ptr(), state.bits()) }
+    }
+
+    /// Sets the minimum supported protocol version for this session.
+    ///
+    /// A value of `None` will enable protocol versions down the the lowest version supported by
+    /// OpenSSL.
+    ///
+    /// This corresponds to [`SSL_set_min_proto_version`]. On OpenSSL 1.0.x and LibreSSL, it is
+    /// emulated by setting and clearing the `NO_SSLV3`, `NO_TLSV1`, `NO_TLSV1_1`, and `NO_TLSV1_2`
+    /// options. An error is returned for versions which cannot be enabled through those options,
+    /// such as TLSv1.3.
+    ///
+    /// [`SSL_set_min_proto_version`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_set_min_proto_version.html
+    pub fn set_min_proto_version(&mut self, version: Option<SslVersion>) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(compat::SSL_set_min_proto_version(
+                self.as_ptr(),
+                version.map_or(0, |v| v.0),
+            )).map(|_| ())
+        }
+    }
+
+    /// Sets the maximum supported protocol version for this session.
+    ///
+    /// A value of `None` will enable protocol versions up to the highest version supported by
+    /// OpenSSL.
+    ///
+    /// This corresponds to [`SSL_set_max_proto_version`]. On OpenSSL 1.0.x and LibreSSL, it is
+    /// emulated by setting and clearing the `NO_SSLV3`, `NO_TLSV1`, `NO_TLSV1_1`, and `NO_TLSV1_2`
+    /// options. An error is returned for versions which cannot be enabled through those options,
+    /// such as TLSv1.3.
+    ///
+    /// [`SSL_set_max_proto_version`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_set_min_proto_version.html
+    pub fn set_max_proto_version(&mut self, version: Option<SslVersion>) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(compat::SSL_set_max_proto_version(
+                self.as_ptr(),
+                version.map_or(0, |v| v.0),
+            )).map(|_| ())
+        }
+    }
+
+    /// Gets the minimum supported protocol version for this session.
+    ///
+    /// A value of `None` indicates that all versions down the the lowest version supported by
+    /// OpenSSL are enabled.
+    ///
+    /// This corresponds to [`SSL_get_min_proto_version`]. On OpenSSL 1.0.x and LibreSSL, it is
+    /// derived from the protocol options.
+    ///
+    /// Requires OpenSSL 1.1.0g or newer when linking against OpenSSL 1.1.0.
+    ///
+    /// [`SSL_get_min_proto_version`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_set_min_proto_version.html
+    #[cfg(any(ossl110g, ossl10x))]
+    pub fn min_proto_version(&self) -> Option<SslVersion> {
+        unsafe {
+            match compat::SSL_get_min_proto_version(self.as_ptr()) {
+                0 => None,
+                r => Some(SslVersion(r)),
+            }
+        }
+    }
+
+    /// Gets the maximum supported protocol version for this session.
+    ///
+    /// A value of `None` indicates that all versions up to the highest version supported by
+    /// OpenSSL are enabled.
+    ///
+    /// This corresponds to [`SSL_get_max_proto_version`]. On OpenSSL 1.0.x and LibreSSL, it is
+    /// derived from the protocol options.
+    ///
+    /// Requires OpenSSL 1.1.0g or newer when linking against OpenSSL 1.1.0.
+    ///
+    /// [`SSL_get_max_proto_version`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_set_min_proto_version.html
+    #[cfg(any(ossl110g, ossl10x))]
+    pub fn max_proto_version(&self) -> Option<SslVersion> {
+        unsafe {
+            match compat::SSL_get_max_proto_version(self.as_ptr()) {
+                0 => None,
+                r => Some(SslVersion(r)),
+            }
+        }
+    }
+
+    /// Sets the list of supported TLS 1.3 cipher suites for this session.
+    ///
+    /// See [`SslContextBuilder::set_ciphersuites`] for details.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_set_ciphersuites`].
+    ///
+    /// [`SslContextBuilder::set_ciphersuites`]: struct.SslContextBuilder.html#method.set_ciphersuites
+    /// [`SSL_set_ciphersuites`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_ciphersuites.html
+    #[cfg(ossl111)]
+    pub fn set_ciphersuites(&mut self, cipher_suites: &str) -> Result<(), ErrorStack> {
+        let cipher_suites = CString::new(cipher_suites).unwrap();
+        unsafe {
+            cvt(ffi::SSL_set_ciphersuites(
+                self.as_ptr(),
+                cipher_suites.as_ptr() as *const _,
+            )).map(|_| ())
+        }
+    }
+
+    /// Sets the maximum amount of TLS 1.3 early data which will be accepted or sent.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_set_max_early_data`].
+    ///
+    /// [`SSL_set_max_early_data`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_max_early_data.html
+    #[cfg(ossl111)]
+    pub fn set_max_early_data(&mut self, bytes: u32) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::SSL_set_max_early_data(self.as_ptr(), bytes)).map(|_| ()) }
+    }
+
+    /// Returns the maximum amount of TLS 1.3 early data which will be accepted or sent.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_get_max_early_data`].
+    ///
+    /// [`SSL_get_max_early_data`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_max_early_data.html
+    #[cfg(ossl111)]
+    pub fn max_early_data(&self) -> u32 {
+        unsafe { ffi::SSL_get_max_early_data(self.as_ptr()) }
+    }
+
+    /// Determines if early data sent by the client was accepted by the server.
+    ///
+    /// Returns `false` if no early data was sent or the server rejected it, in which case a
+    /// client should resend it after the handshake completes.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_get_early_data_status`].
+    ///
+    /// [`SSL_get_early_data_status`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_get_early_data_status.html
+    #[cfg(ossl111)]
+    pub fn early_data_accepted(&self) -> bool {
+        unsafe { ffi::SSL_get_early_data_status(self.as_ptr()) == ffi::SSL_EARLY_DATA_ACCEPTED }
+    }
+
+    /// Schedules a TLS 1.3 key update.
+    ///
+    /// The KeyUpdate message is sent with the next write to the session.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_key_update`].
+    ///
+    /// [`SSL_key_update`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_key_update.html
+    #[cfg(ossl111)]
+    pub fn key_update(&mut self, update_type: KeyUpdateType) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::SSL_key_update(self.as_ptr(), update_type.0)).map(|_| ()) }
+    }
+
+    /// Returns the type of the key update which has been scheduled but not yet sent, if any.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_get_key_update_type`].
+    ///
+    /// [`SSL_get_key_update_type`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_get_key_update_type.html
+    #[cfg(ossl111)]
+    pub fn pending_key_update(&self) -> Option<KeyUpdateType> {
+        unsafe {
+            match ffi::SSL_get_key_update_type(self.as_ptr()) {
+                ffi::SSL_KEY_UPDATE_NONE => None,
+                t => Some(KeyUpdateType(t)),
+            }
+        }
+    }
+
+    /// Schedules a renegotiation of the session.
+    ///
+    /// The renegotiation handshake is started by the next read or write. Renegotiation is not
+    /// supported by TLS 1.3; use [`key_update`] instead.
+    ///
+    /// This corresponds to [`SSL_renegotiate`].
+    ///
+    /// [`key_update`]: #method.key_update
+    /// [`SSL_renegotiate`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_renegotiate.html
+    pub fn renegotiate(&mut self) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::SSL_renegotiate(self.as_ptr())).map(|_| ()) }
+    }
+
+    /// Schedules an abbreviated renegotiation of the session.
+    ///
+    /// Like [`renegotiate`], but the current session is offered for resumption, so a full
+    /// handshake is avoided if the peer accepts it.
+    ///
+    /// This corresponds to [`SSL_renegotiate_abbreviated`].
+    ///
+    /// [`renegotiate`]: #method.renegotiate
+    /// [`SSL_renegotiate_abbreviated`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_renegotiate.html
+    pub fn renegotiate_abbreviated(&mut self) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::SSL_renegotiate_abbreviated(self.as_ptr())).map(|_| ()) }
+    }
+
+    /// Determines if a renegotiation has been scheduled or is in progress.
+    ///
+    /// This corresponds to [`SSL_renegotiate_pending`].
+    ///
+    /// [`SSL_renegotiate_pending`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_renegotiate.html
+    pub fn renegotiate_pending(&self) -> bool {
+        unsafe { ffi::SSL_renegotiate_pending(self.as_ptr()) != 0 }
+    }
+
+    #[cfg(ossl111)]
+    fn is_tls13(&self) -> bool {
+        unsafe { ffi::SSL_version(self.as_ptr()) == ffi::TLS1_3_VERSION }
+    }
+
     /// Determines if this `Ssl` is configured for server-side or client-side use.
     ///
     /// This corresponds to [`SSL_is_server`].
@@ -2596,6 +3874,30 @@ impl SslRef {
     }
 }
 
+/// A handle which can abort an `SslStream` from another thread.
+///
+/// Once aborted, every subsequent read from or write to the underlying stream made by OpenSSL fails
+/// with an `io::ErrorKind::ConnectionAborted` error, and retrying an in-progress handshake fails
+/// immediately. This allows a client racing several connection attempts to cancel the losers.
+///
+/// Aborting does not interrupt a read or write which is already blocked in the underlying stream.
+/// With blocking sockets, the socket should additionally be shut down to wake the handshaking
+/// thread.
+#[derive(Debug, Clone)]
+pub struct AbortHandle(Arc<AtomicBool>);
+
+impl AbortHandle {
+    /// Aborts the stream.
+    pub fn abort(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Determines if the stream has been aborted.
+    pub fn is_aborted(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
 /// An SSL stream midway through the handshake process.
 #[derive(Debug)]
 pub struct MidHandshakeSslStream<S> {
@@ -2629,22 +3931,40 @@ impl<S> MidHandshakeSslStream<S> {
         self.error
     }
 
+    /// Returns a handle which can be used to abort the handshake from another thread.
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.stream.abort_handle()
+    }
+
     /// Restarts the handshake process.
     ///
+    /// If the handshake has been aborted via an `AbortHandle`, this fails immediately.
+    ///
     /// This corresponds to [`SSL_do_handshake`].
     ///
     /// [`SSL_do_handshake`]: https://www.openssl.org/docs/manmaster/man3/SSL_do_handshake.html
     pub fn handshake(mut self) -> Result<SslStream<S>, HandshakeError<S>> {
+        if self.abort_handle().is_aborted() {
+            self.error = Error {
+                code: ErrorCode::SYSCALL,
+                cause: Some(InnerError::Io(io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    "the stream was aborted",
+                ))),
+                verify_result: None,
+            };
+            return Err(HandshakeError::Failure(self));
+        }
+
         let ret = unsafe { ffi::SSL_do_handshake(self.stream.ssl.as_ptr()) };
         if ret > 0 {
             Ok(self.stream)
         } else {
             self.error = self.stream.make_error(ret);
-            match self.error.code() {
-                ErrorCode::WANT_READ | ErrorCode::WANT_WRITE => {
-                    Err(HandshakeError::WouldBlock(self))
-                }
-                _ => Err(HandshakeError::Failure(self)),
+            if self.error.code().is_would_block() {
+                Err(HandshakeError::WouldBlock(self))
+            } else {
+                Err(HandshakeError::Failure(self))
             }
         }
     }
@@ -2654,6 +3974,9 @@ impl<S> MidHandshakeSslStream<S> {
 pub struct SslStream<S> {
     ssl: ManuallyDrop<Ssl>,
     method: ManuallyDrop<BioMethod>,
+    stats: TrafficStats,
+    rekey_limit: Option<u64>,
+    rekey_record_limit: Option<u64>,
     _p: PhantomData<S>,
 }
 
@@ -2688,6 +4011,9 @@ impl<S: Read + Write> SslStream<S> {
             SslStream {
                 ssl: ManuallyDrop::new(ssl),
                 method: ManuallyDrop::new(method),
+                stats: TrafficStats::default(),
+                rekey_limit: None,
+                rekey_record_limit: None,
                 _p: PhantomData,
             }
         }
@@ -2713,6 +4039,7 @@ impl<S: Read + Write> SslStream<S> {
 
         let ret = self.ssl.read(buf);
         if ret > 0 {
+            self.stats.bytes_read += ret as u64;
             Ok(ret as usize)
         } else {
             Err(self.make_error(ret))
@@ -2724,6 +4051,9 @@ impl<S: Read + Write> SslStream<S> {
     /// It is particularly useful with a nonblocking socket, where the error value will identify if
     /// OpenSSL is waiting on read or write readiness.
     ///
+    /// If a rekey limit on bytes or records has been set and reached, a rekey is scheduled before
+    /// writing.
+    ///
     /// This corresponds to [`SSL_write`].
     ///
     /// [`SSL_write`]: https://www.openssl.org/docs/manmaster/man3/SSL_write.html
@@ -2733,14 +4063,192 @@ impl<S: Read + Write> SslStream<S> {
             return Ok(0);
         }
 
+        let bytes_reached = self
+            .rekey_limit
+            .map_or(false, |l| self.stats.bytes_written_since_rekey >= l);
+        let records_reached = self
+            .rekey_record_limit
+            .map_or(false, |l| self.stats.records_written_since_rekey >= l);
+        if bytes_reached || records_reached {
+            self.rekey()?;
+        }
+
         let ret = self.ssl.write(buf);
         if ret > 0 {
+            // OpenSSL splits each write into as many full records as it can
+            let records = ((ret as usize + MAX_RECORD_LEN - 1) / MAX_RECORD_LEN) as u64;
+            self.stats.bytes_written += ret as u64;
+            self.stats.bytes_written_since_rekey += ret as u64;
+            self.stats.records_written += records;
+            self.stats.records_written_since_rekey += records;
             Ok(ret as usize)
         } else {
             Err(self.make_error(ret))
         }
     }
 
+    /// Like `peek`, but returns an `ssl::Error` rather than an `io::Error`.
+    ///
+    /// This corresponds to [`SSL_peek`].
+    ///
+    /// [`SSL_peek`]: https://www.openssl.org/docs/manmaster/man3/SSL_peek.html
+    pub fn ssl_peek(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        // See `ssl_read` for why we short-circuit on zero-length buffers
+        if buf.len() == 0 {
+            return Ok(0);
+        }
+
+        let len = cmp::min(c_int::max_value() as usize, buf.len()) as c_int;
+        let ret = unsafe { ffi::SSL_peek(self.ssl.as_ptr(), buf.as_mut_ptr() as *mut c_void, len) };
+        if ret > 0 {
+            Ok(ret as usize)
+        } else {
+            Err(self.make_error(ret))
+        }
+    }
+
+    /// Reads decrypted data without consuming it.
+    ///
+    /// The data will be returned again by the next call to `read` or `peek`. Like `read`, this
+    /// blocks until data is available, and returns 0 at end of file.
+    ///
+    /// The number of bytes which can be peeked without reading from the underlying stream is
+    /// returned by [`SslRef::pending`].
+    ///
+    /// [`SslRef::pending`]: struct.SslRef.html#method.pending
+    pub fn peek(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.retry_read(|s| s.ssl_peek(buf))
+    }
+
+    /// Like `read_vectored`, but returns an `ssl::Error` rather than an `io::Error`.
+    ///
+    /// Buffers after the first are only filled from data OpenSSL has already decrypted, so this
+    /// reads from the underlying stream at most once.
+    pub fn ssl_read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> Result<usize, Error> {
+        let mut total = 0;
+        for buf in bufs.iter_mut().filter(|b| !b.is_empty()) {
+            if total > 0 && self.ssl.pending() == 0 {
+                break;
+            }
+            let n = match self.ssl_read(buf) {
+                Ok(n) => n,
+                Err(e) => {
+                    if total == 0 {
+                        return Err(e);
+                    }
+                    break;
+                }
+            };
+            total += n;
+            if n < buf.len() {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Reads into several buffers, filling each in order.
+    ///
+    /// Returns the total number of bytes read, which may be less than the combined length of the
+    /// buffers even if more data will become available.
+    pub fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> io::Result<usize> {
+        self.retry_read(|s| s.ssl_read_vectored(bufs))
+    }
+
+    /// Like `read_uninit`, but returns an `ssl::Error` rather than an `io::Error`.
+    ///
+    /// This corresponds to [`SSL_read`].
+    ///
+    /// [`SSL_read`]: https://www.openssl.org/docs/manmaster/man3/SSL_read.html
+    pub fn ssl_read_uninit(&mut self, buf: &mut Vec<u8>) -> Result<usize, Error> {
+        let len = buf.len();
+        let spare = cmp::min(c_int::max_value() as usize, buf.capacity() - len) as c_int;
+        // See `ssl_read` for why we short-circuit on zero-length buffers
+        if spare == 0 {
+            return Ok(0);
+        }
+
+        let ret = unsafe {
+            let ptr = buf.as_mut_ptr().offset(len as isize);
+            ffi::SSL_read(self.ssl.as_ptr(), ptr as *mut c_void, spare)
+        };
+        if ret > 0 {
+            unsafe { buf.set_len(len + ret as usize) };
+            self.stats.bytes_read += ret as u64;
+            Ok(ret as usize)
+        } else {
+            Err(self.make_error(ret))
+        }
+    }
+
+    /// Reads into the spare capacity of `buf`, appending to its contents.
+    ///
+    /// Unlike `read`, the destination does not need to be initialized first. Returns the number
+    /// of bytes appended, which is 0 at end of file or if `buf` has no spare capacity.
+    pub fn read_uninit(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        self.retry_read(|s| s.ssl_read_uninit(buf))
+    }
+
+    /// Like `write_vectored`, but returns an `ssl::Error` rather than an `io::Error`.
+    ///
+    /// If this fails with `WANT_READ` or `WANT_WRITE`, it must be retried with the same buffers.
+    /// Since they are coalesced into a new buffer on each call, the session must have been
+    /// configured with `SslMode::ACCEPT_MOVING_WRITE_BUFFER`, as `SslConnector` and `SslAcceptor`
+    /// do by default.
+    pub fn ssl_write_vectored(&mut self, bufs: &[&[u8]]) -> Result<usize, Error> {
+        let mut bufs = bufs.iter().filter(|b| !b.is_empty());
+        let first = match bufs.next() {
+            Some(first) => first,
+            None => return Ok(0),
+        };
+        if first.len() >= MAX_RECORD_LEN {
+            return self.ssl_write(first);
+        }
+
+        let mut record = Vec::with_capacity(MAX_RECORD_LEN);
+        record.extend_from_slice(first);
+        for buf in bufs {
+            let len = cmp::min(buf.len(), MAX_RECORD_LEN - record.len());
+            record.extend_from_slice(&buf[..len]);
+            if record.len() == MAX_RECORD_LEN {
+                break;
+            }
+        }
+        self.ssl_write(&record)
+    }
+
+    /// Writes data from several buffers, in order.
+    ///
+    /// Small buffers are coalesced so that they are sent in a single TLS record, rather than one
+    /// record and write to the underlying stream per buffer. Returns the total number of bytes
+    /// written, which may be less than the combined length of the buffers.
+    pub fn write_vectored(&mut self, bufs: &[&[u8]]) -> io::Result<usize> {
+        self.retry_write(|s| s.ssl_write_vectored(bufs))
+    }
+
+    /// Schedules a rekey of the session.
+    ///
+    /// A TLS 1.3 session sends a KeyUpdate message requesting that the peer also update its keys,
+    /// and older protocol versions perform a renegotiation. Either is carried out by the next read
+    /// or write.
+    pub fn rekey(&mut self) -> Result<(), Error> {
+        #[cfg(ossl111)]
+        {
+            if self.ssl.is_tls13() {
+                self.ssl.key_update(KeyUpdateType::REQUESTED)?;
+            } else {
+                self.ssl.renegotiate()?;
+            }
+        }
+        #[cfg(not(ossl111))]
+        self.ssl.renegotiate()?;
+
+        self.stats.bytes_written_since_rekey = 0;
+        self.stats.records_written_since_rekey = 0;
+        self.stats.rekeys += 1;
+        Ok(())
+    }
+
     /// Shuts down the session.
     ///
     /// The shutdown process consists of two steps. The first step sends a close notify message to
@@ -2761,9 +4269,54 @@ impl<S: Read + Write> SslStream<S> {
             n => Err(self.make_error(n)),
         }
     }
+
+    /// Performs a full bidirectional shutdown of the session, collecting any application data
+    /// the peer sends before its close notify message.
+    ///
+    /// A close notify message is sent if one has not been already, and the stream is then read
+    /// until the peer's close notify arrives. Received application data is appended to
+    /// `plaintext`. Once this returns `Ok`, no TLS framing remains in flight in either direction
+    /// and the underlying stream can be recovered with [`into_inner`] and used in the clear or for
+    /// a new TLS session.
+    ///
+    /// With a nonblocking stream this may return a `WANT_READ` or `WANT_WRITE` error, in which
+    /// case it should be called again once the stream is ready. Data appended to `plaintext`
+    /// before the error is not lost.
+    ///
+    /// Read-ahead must not be enabled on the session, as any bytes OpenSSL buffered past the
+    /// peer's close notify would otherwise be discarded.
+    ///
+    /// [`into_inner`]: #method.into_inner
+    pub fn shutdown_and_drain(&mut self, plaintext: &mut Vec<u8>) -> Result<(), Error> {
+        if !self.ssl.get_shutdown().contains(ShutdownState::SENT) {
+            self.shutdown()?;
+        }
+
+        let mut buf = [0; 4096];
+        while !self.ssl.get_shutdown().contains(ShutdownState::RECEIVED) {
+            match self.ssl_read(&mut buf) {
+                Ok(n) => plaintext.extend_from_slice(&buf[..n]),
+                Err(ref e) if e.code() == ErrorCode::ZERO_RETURN => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl<S> SslStream<S> {
+    /// Consumes the `SslStream`, returning the underlying stream.
+    ///
+    /// No shutdown is performed - use [`shutdown_and_drain`] first to cleanly remove TLS from the
+    /// connection. Otherwise the stream will be left in an indeterminate position within the TLS
+    /// record layer.
+    ///
+    /// [`shutdown_and_drain`]: #method.shutdown_and_drain
+    pub fn into_inner(self) -> S {
+        unsafe { bio::take_stream(self.ssl.get_raw_rbio()) }
+    }
+
     fn make_error(&mut self, ret: c_int) -> Error {
         self.check_panic();
 
@@ -2786,7 +4339,11 @@ impl<S> SslStream<S> {
             _ => None,
         };
 
-        Error { code, cause }
+        Error {
+            code,
+            cause,
+            verify_result: self.ssl.failed_verify_result(code),
+        }
     }
 
     fn check_panic(&mut self) {
@@ -2811,25 +4368,141 @@ impl<S> SslStream<S> {
     ///
     /// # Warning
     ///
-    /// It is inadvisable to read from or write to the underlying stream as it
-    /// will most likely corrupt the SSL session.
-    pub fn get_mut(&mut self) -> &mut S {
-        unsafe {
-            let bio = self.ssl.get_raw_rbio();
-            bio::get_mut(bio)
-        }
+    /// It is inadvisable to read from or write to the underlying stream as it
+    /// will most likely corrupt the SSL session.
+    pub fn get_mut(&mut self) -> &mut S {
+        unsafe {
+            let bio = self.ssl.get_raw_rbio();
+            bio::get_mut(bio)
+        }
+    }
+
+    /// Returns a shared reference to the `Ssl` object associated with this stream.
+    pub fn ssl(&self) -> &SslRef {
+        &self.ssl
+    }
+
+    /// Returns the session's shutdown state.
+    ///
+    /// This corresponds to [`SSL_get_shutdown`].
+    ///
+    /// [`SSL_get_shutdown`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_get_shutdown.html
+    pub fn get_shutdown(&self) -> ShutdownState {
+        self.ssl.get_shutdown()
+    }
+
+    /// Sets the session's shutdown state.
+    ///
+    /// This corresponds to [`SSL_set_shutdown`].
+    ///
+    /// [`SSL_set_shutdown`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_set_shutdown.html
+    pub fn set_shutdown(&mut self, state: ShutdownState) {
+        self.ssl.set_shutdown(state)
+    }
+
+    /// Returns a handle which can be used to abort the stream from another thread.
+    pub fn abort_handle(&self) -> AbortHandle {
+        unsafe { AbortHandle(bio::abort_flag::<S>(self.ssl.get_raw_rbio())) }
+    }
+
+    /// Returns counters of the application data transferred over the stream.
+    pub fn traffic_stats(&self) -> TrafficStats {
+        self.stats
+    }
+
+    /// Sets the number of plaintext bytes which may be written before the session is
+    /// automatically rekeyed.
+    ///
+    /// Once the limit is reached, the next write first schedules a rekey as described in
+    /// [`rekey`]. Defaults to `None`, which disables automatic rekeying.
+    ///
+    /// [`rekey`]: #method.rekey
+    pub fn set_rekey_limit(&mut self, limit: Option<u64>) {
+        self.rekey_limit = limit;
+    }
+
+    /// Sets the number of TLS records which may be written before the session is automatically
+    /// rekeyed.
+    ///
+    /// This behaves like [`set_rekey_limit`], but counts records as described in
+    /// [`TrafficStats::records_written`]. Both limits may be set, in which case the session is
+    /// rekeyed when either is reached. Defaults to `None`.
+    ///
+    /// [`set_rekey_limit`]: #method.set_rekey_limit
+    /// [`TrafficStats::records_written`]: struct.TrafficStats.html#structfield.records_written
+    pub fn set_rekey_record_limit(&mut self, limit: Option<u64>) {
+        self.rekey_record_limit = limit;
     }
+}
 
-    /// Returns a shared reference to the `Ssl` object associated with this stream.
-    pub fn ssl(&self) -> &SslRef {
-        &self.ssl
+impl SslStream<TcpStream> {
+    /// Splits the stream into halves which can be used to read and write from different threads.
+    ///
+    /// The halves share the session, and access to it is synchronized internally. A read waits
+    /// for data to arrive on the socket before locking the session, so a blocked read does not
+    /// prevent the other half from writing.
+    ///
+    /// Read-ahead must not be enabled on the session, as a read could otherwise wait for data
+    /// which OpenSSL has already buffered.
+    ///
+    /// Only streams over a `TcpStream` can be split, as the reading half clones the socket to
+    /// wait for it to become readable with `TcpStream::peek`. An error is returned if the socket
+    /// cannot be cloned.
+    ///
+    /// Splitting clears `SSL_MODE_AUTO_RETRY` on the session, and it is not restored. Without it
+    /// a read which only receives a handshake message, such as a TLSv1.3 session ticket, would
+    /// block waiting for application data while holding the session. The reading half retries
+    /// such reads itself, so it still blocks until application data or end of file is received.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use openssl::ssl::{SslConnector, SslMethod};
+    /// use std::io::{Read, Write};
+    /// use std::net::TcpStream;
+    /// use std::thread;
+    ///
+    /// let connector = SslConnector::builder(SslMethod::tls()).unwrap().build();
+    /// let stream = TcpStream::connect("example.com:443").unwrap();
+    /// let stream = connector.connect("example.com", stream).unwrap();
+    ///
+    /// let (mut reader, mut writer) = stream.split().unwrap();
+    /// let guard = thread::spawn(move || {
+    ///     let mut response = vec![];
+    ///     reader.read_to_end(&mut response).unwrap();
+    ///     response
+    /// });
+    /// writer.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+    /// let response = guard.join().unwrap();
+    /// ```
+    pub fn split(self) -> io::Result<(SslReadHalf, SslWriteHalf)> {
+        split::split(self)
     }
 }
 
 impl<S: Read + Write> Read for SslStream<S> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.retry_read(|s| s.ssl_read(buf))
+    }
+}
+
+impl<S: Read + Write> Write for SslStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.retry_write(|s| s.ssl_write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.get_mut().flush()
+    }
+}
+
+impl<S: Read + Write> SslStream<S> {
+    fn retry_read<F>(&mut self, mut f: F) -> io::Result<usize>
+    where
+        F: FnMut(&mut Self) -> Result<usize, Error>,
+    {
         loop {
-            match self.ssl_read(buf) {
+            match f(self) {
                 Ok(n) => return Ok(n),
                 Err(ref e) if e.code() == ErrorCode::ZERO_RETURN => return Ok(0),
                 Err(ref e) if e.code() == ErrorCode::SYSCALL && e.io_error().is_none() => {
@@ -2843,12 +4516,13 @@ impl<S: Read + Write> Read for SslStream<S> {
             }
         }
     }
-}
 
-impl<S: Read + Write> Write for SslStream<S> {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    fn retry_write<F>(&mut self, mut f: F) -> io::Result<usize>
+    where
+        F: FnMut(&mut Self) -> Result<usize, Error>,
+    {
         loop {
-            match self.ssl_write(buf) {
+            match f(self) {
                 Ok(n) => return Ok(n),
                 Err(ref e) if e.code() == ErrorCode::WANT_READ && e.io_error().is_none() => {}
                 Err(e) => {
@@ -2858,10 +4532,6 @@ impl<S: Read + Write> Write for SslStream<S> {
             }
         }
     }
-
-    fn flush(&mut self) -> io::Result<()> {
-        self.get_mut().flush()
-    }
 }
 
 /// A partially constructed `SslStream`, useful for unusual handshakes.
@@ -2912,14 +4582,16 @@ where
             Ok(stream)
         } else {
             let error = stream.make_error(ret);
-            match error.code() {
-                ErrorCode::WANT_READ | ErrorCode::WANT_WRITE => Err(HandshakeError::WouldBlock(
-                    MidHandshakeSslStream { stream, error },
-                )),
-                _ => Err(HandshakeError::Failure(MidHandshakeSslStream {
+            if error.code().is_would_block() {
+                Err(HandshakeError::WouldBlock(MidHandshakeSslStream {
+                    stream,
+                    error,
+                }))
+            } else {
+                Err(HandshakeError::Failure(MidHandshakeSslStream {
                     stream,
                     error,
-                })),
+                }))
             }
         }
     }
@@ -2932,19 +4604,101 @@ where
             Ok(stream)
         } else {
             let error = stream.make_error(ret);
-            match error.code() {
-                ErrorCode::WANT_READ | ErrorCode::WANT_WRITE => Err(HandshakeError::WouldBlock(
-                    MidHandshakeSslStream { stream, error },
-                )),
-                _ => Err(HandshakeError::Failure(MidHandshakeSslStream {
+            if error.code().is_would_block() {
+                Err(HandshakeError::WouldBlock(MidHandshakeSslStream {
+                    stream,
+                    error,
+                }))
+            } else {
+                Err(HandshakeError::Failure(MidHandshakeSslStream {
                     stream,
                     error,
-                })),
+                }))
             }
         }
     }
 
-    // Future work: early IO methods
+    /// Configures the stream to act as the client side of the session.
+    ///
+    /// This is required before calling [`write_early_data`].
+    ///
+    /// This corresponds to [`SSL_set_connect_state`].
+    ///
+    /// [`write_early_data`]: #method.write_early_data
+    /// [`SSL_set_connect_state`]: https://www.openssl.org/docs/manmaster/man3/SSL_set_connect_state.html
+    pub fn set_connect_state(&mut self) {
+        unsafe { ffi::SSL_set_connect_state(self.inner.ssl.as_ptr()) }
+    }
+
+    /// Configures the stream to act as the server side of the session.
+    ///
+    /// This is required before calling [`read_early_data`].
+    ///
+    /// This corresponds to [`SSL_set_accept_state`].
+    ///
+    /// [`read_early_data`]: #method.read_early_data
+    /// [`SSL_set_accept_state`]: https://www.openssl.org/docs/manmaster/man3/SSL_set_connect_state.html
+    pub fn set_accept_state(&mut self) {
+        unsafe { ffi::SSL_set_accept_state(self.inner.ssl.as_ptr()) }
+    }
+
+    /// Reads TLS 1.3 early data sent by the client.
+    ///
+    /// This should be called repeatedly on the server before [`accept`] until it returns 0,
+    /// indicating that all early data has been read or that none was sent.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_read_early_data`].
+    ///
+    /// [`accept`]: #method.accept
+    /// [`SSL_read_early_data`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_read_early_data.html
+    #[cfg(ossl111)]
+    pub fn read_early_data(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut read = 0;
+        let ret = unsafe {
+            ffi::SSL_read_early_data(
+                self.inner.ssl.as_ptr(),
+                buf.as_mut_ptr() as *mut c_void,
+                buf.len(),
+                &mut read,
+            )
+        };
+        match ret {
+            ffi::SSL_READ_EARLY_DATA_SUCCESS => Ok(read),
+            ffi::SSL_READ_EARLY_DATA_FINISH => Ok(0),
+            _ => Err(self.inner.make_error(ret)),
+        }
+    }
+
+    /// Sends TLS 1.3 early data to the server.
+    ///
+    /// This may be called on the client before [`connect`] if the session being resumed allows
+    /// early data. Returns the number of bytes written.
+    ///
+    /// Requires OpenSSL 1.1.1 or newer.
+    ///
+    /// This corresponds to [`SSL_write_early_data`].
+    ///
+    /// [`connect`]: #method.connect
+    /// [`SSL_write_early_data`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_write_early_data.html
+    #[cfg(ossl111)]
+    pub fn write_early_data(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let mut written = 0;
+        let ret = unsafe {
+            ffi::SSL_write_early_data(
+                self.inner.ssl.as_ptr(),
+                buf.as_ptr() as *const c_void,
+                buf.len(),
+                &mut written,
+            )
+        };
+        if ret > 0 {
+            Ok(written)
+        } else {
+            Err(self.inner.make_error(ret))
+        }
+    }
 }
 
 impl<S> SslStreamBuilder<S> {
@@ -2973,6 +4727,233 @@ impl<S> SslStreamBuilder<S> {
     pub fn ssl(&self) -> &SslRef {
         &self.inner.ssl
     }
+
+    /// Returns a handle which can be used to abort the handshake from another thread.
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.inner.abort_handle()
+    }
+}
+
+/// A TLS session driven through in-memory buffers rather than a stream.
+///
+/// Unlike `SslStream`, an `SslEngine` performs no I/O of its own. Encrypted data received from the
+/// peer is passed to the engine with [`consume_incoming`], and encrypted data destined for the peer
+/// is retrieved with [`produce_outgoing`]. This allows a session to be driven by I/O models which
+/// don't fit the `Read` and `Write` traits, such as completion-based APIs or custom schedulers.
+///
+/// [`consume_incoming`]: #method.consume_incoming
+/// [`produce_outgoing`]: #method.produce_outgoing
+pub struct SslEngine {
+    ssl: Ssl,
+    want_read: bool,
+}
+
+impl fmt::Debug for SslEngine {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("SslEngine")
+            .field("ssl", &self.ssl)
+            .field("want_read", &self.want_read)
+            .finish()
+    }
+}
+
+impl SslEngine {
+    /// Creates a new `SslEngine` which exchanges encrypted data through memory buffers.
+    pub fn new(ssl: Ssl) -> Result<SslEngine, ErrorStack> {
+        unsafe {
+            let rbio = cvt_p(ffi::BIO_new(ffi::BIO_s_mem()))?;
+            let wbio = match cvt_p(ffi::BIO_new(ffi::BIO_s_mem())) {
+                Ok(wbio) => wbio,
+                Err(e) => {
+                    ffi::BIO_free_all(rbio);
+                    return Err(e);
+                }
+            };
+            ffi::SSL_set_bio(ssl.as_ptr(), rbio, wbio);
+        }
+
+        Ok(SslEngine {
+            ssl,
+            want_read: false,
+        })
+    }
+
+    /// Configures the engine to act as the client side of the session.
+    ///
+    /// This corresponds to [`SSL_set_connect_state`].
+    ///
+    /// [`SSL_set_connect_state`]: https://www.openssl.org/docs/manmaster/man3/SSL_set_connect_state.html
+    pub fn set_connect_state(&mut self) {
+        unsafe { ffi::SSL_set_connect_state(self.ssl.as_ptr()) }
+    }
+
+    /// Configures the engine to act as the server side of the session.
+    ///
+    /// This corresponds to [`SSL_set_accept_state`].
+    ///
+    /// [`SSL_set_accept_state`]: https://www.openssl.org/docs/manmaster/man3/SSL_set_connect_state.html
+    pub fn set_accept_state(&mut self) {
+        unsafe { ffi::SSL_set_accept_state(self.ssl.as_ptr()) }
+    }
+
+    /// Advances the handshake as far as possible with the data consumed so far.
+    ///
+    /// An error with the code `ErrorCode::WANT_READ` indicates that more data from the peer is
+    /// required to make progress. Any data made available by [`produce_outgoing`] should be sent to
+    /// the peer after each call.
+    ///
+    /// This corresponds to [`SSL_do_handshake`].
+    ///
+    /// [`produce_outgoing`]: #method.produce_outgoing
+    /// [`SSL_do_handshake`]: https://www.openssl.org/docs/manmaster/man3/SSL_do_handshake.html
+    pub fn handshake(&mut self) -> Result<(), Error> {
+        let ret = unsafe { ffi::SSL_do_handshake(self.ssl.as_ptr()) };
+        if ret > 0 {
+            self.want_read = false;
+            Ok(())
+        } else {
+            Err(self.make_error(ret))
+        }
+    }
+
+    /// Returns `true` if the last operation could not complete without more data from the peer.
+    pub fn wants_read(&self) -> bool {
+        self.want_read
+    }
+
+    /// Returns `true` if there is encrypted data waiting to be sent to the peer.
+    pub fn wants_write(&self) -> bool {
+        unsafe { ffi::BIO_ctrl_pending(ffi::SSL_get_wbio(self.ssl.as_ptr())) > 0 }
+    }
+
+    /// Passes encrypted data received from the peer to the engine.
+    ///
+    /// Returns the number of bytes consumed.
+    pub fn consume_incoming(&mut self, buf: &[u8]) -> Result<usize, ErrorStack> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let len = cmp::min(c_int::max_value() as usize, buf.len()) as c_int;
+        let ret = unsafe {
+            cvt(ffi::BIO_write(
+                self.ssl.get_raw_rbio(),
+                buf.as_ptr() as *const c_void,
+                len,
+            ))?
+        };
+        self.want_read = false;
+        Ok(ret as usize)
+    }
+
+    /// Retrieves encrypted data which should be sent to the peer.
+    ///
+    /// Returns the number of bytes written into `buf`, or 0 if no data is pending.
+    pub fn produce_outgoing(&mut self, buf: &mut [u8]) -> usize {
+        let len = cmp::min(c_int::max_value() as usize, buf.len()) as c_int;
+        let ret = unsafe {
+            ffi::BIO_read(
+                ffi::SSL_get_wbio(self.ssl.as_ptr()),
+                buf.as_mut_ptr() as *mut c_void,
+                len,
+            )
+        };
+        if ret > 0 {
+            ret as usize
+        } else {
+            0
+        }
+    }
+
+    /// Reads decrypted application data.
+    ///
+    /// An error with the code `ErrorCode::WANT_READ` indicates that more data from the peer must be
+    /// consumed before any can be returned.
+    ///
+    /// This corresponds to [`SSL_read`].
+    ///
+    /// [`SSL_read`]: https://www.openssl.org/docs/manmaster/man3/SSL_read.html
+    pub fn ssl_read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        // See SslStream::ssl_read for why we short-circuit on zero-length buffers
+        if buf.len() == 0 {
+            return Ok(0);
+        }
+
+        let ret = self.ssl.read(buf);
+        if ret > 0 {
+            self.want_read = false;
+            Ok(ret as usize)
+        } else {
+            Err(self.make_error(ret))
+        }
+    }
+
+    /// Encrypts application data.
+    ///
+    /// The encrypted data can be retrieved with [`produce_outgoing`].
+    ///
+    /// This corresponds to [`SSL_write`].
+    ///
+    /// [`produce_outgoing`]: #method.produce_outgoing
+    /// [`SSL_write`]: https://www.openssl.org/docs/manmaster/man3/SSL_write.html
+    pub fn ssl_write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if buf.len() == 0 {
+            return Ok(0);
+        }
+
+        let ret = self.ssl.write(buf);
+        if ret > 0 {
+            self.want_read = false;
+            Ok(ret as usize)
+        } else {
+            Err(self.make_error(ret))
+        }
+    }
+
+    /// Shuts down the session.
+    ///
+    /// See [`SslStream::shutdown`] for details.
+    ///
+    /// This corresponds to [`SSL_shutdown`].
+    ///
+    /// [`SslStream::shutdown`]: struct.SslStream.html#method.shutdown
+    /// [`SSL_shutdown`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_shutdown.html
+    pub fn shutdown(&mut self) -> Result<ShutdownResult, Error> {
+        match unsafe { ffi::SSL_shutdown(self.ssl.as_ptr()) } {
+            0 => Ok(ShutdownResult::Sent),
+            1 => Ok(ShutdownResult::Received),
+            n => Err(self.make_error(n)),
+        }
+    }
+
+    /// Returns a shared reference to the `Ssl` object associated with this engine.
+    pub fn ssl(&self) -> &SslRef {
+        &self.ssl
+    }
+
+    fn make_error(&mut self, ret: c_int) -> Error {
+        let code = self.ssl.get_error(ret);
+        self.want_read = code == ErrorCode::WANT_READ;
+
+        let cause = match code {
+            ErrorCode::SSL => Some(InnerError::Ssl(ErrorStack::get())),
+            ErrorCode::SYSCALL => {
+                let errs = ErrorStack::get();
+                if errs.errors().is_empty() {
+                    None
+                } else {
+                    Some(InnerError::Ssl(errs))
+                }
+            }
+            _ => None,
+        };
+
+        Error {
+            code,
+            cause,
+            verify_result: self.ssl.failed_verify_result(code),
+        }
+    }
 }
 
 /// The result of a shutdown request.
@@ -2993,8 +4974,15 @@ mod compat {
     use libc::c_int;
 
     pub use ffi::{
-        SSL_CTX_clear_options, SSL_CTX_get_options, SSL_CTX_set_options, SSL_CTX_up_ref,
-        SSL_SESSION_get_master_key, SSL_SESSION_up_ref, SSL_is_server,
+        SSL_CTX_clear_options, SSL_CTX_get_options, SSL_CTX_set_max_proto_version,
+        SSL_CTX_set_min_proto_version, SSL_CTX_set_options, SSL_CTX_up_ref,
+        SSL_SESSION_get_master_key, SSL_SESSION_up_ref, SSL_is_server, SSL_set_max_proto_version,
+        SSL_set_min_proto_version,
+    };
+    #[cfg(ossl110g)]
+    pub use ffi::{
+        SSL_CTX_get_max_proto_version, SSL_CTX_get_min_proto_version, SSL_get_max_proto_version,
+        SSL_get_min_proto_version,
     };
 
     pub unsafe fn get_new_idx(f: ffi::CRYPTO_EX_free) -> c_int {
@@ -3058,6 +5046,135 @@ mod compat {
         ) as c_ulong
     }
 
+    // protocol versions paired with the options disabling them, from lowest to highest
+    const PROTOCOLS: [(c_int, c_ulong); 4] = [
+        (ffi::SSL3_VERSION, ffi::SSL_OP_NO_SSLv3),
+        (ffi::TLS1_VERSION, ffi::SSL_OP_NO_TLSv1),
+        (ffi::TLS1_1_VERSION, ffi::SSL_OP_NO_TLSv1_1),
+        (ffi::TLS1_2_VERSION, ffi::SSL_OP_NO_TLSv1_2),
+    ];
+
+    unsafe fn SSL_get_options(ssl: *mut ffi::SSL) -> c_ulong {
+        ffi::SSL_ctrl(ssl, ffi::SSL_CTRL_OPTIONS, 0, ptr::null_mut()) as c_ulong
+    }
+
+    unsafe fn SSL_set_options(ssl: *mut ffi::SSL, op: c_ulong) -> c_ulong {
+        ffi::SSL_ctrl(ssl, ffi::SSL_CTRL_OPTIONS, op as c_long, ptr::null_mut()) as c_ulong
+    }
+
+    unsafe fn SSL_clear_options(ssl: *mut ffi::SSL, op: c_ulong) -> c_ulong {
+        ffi::SSL_ctrl(ssl, ffi::SSL_CTRL_CLEAR_OPTIONS, op as c_long, ptr::null_mut()) as c_ulong
+    }
+
+    // Returns the lowest and highest versions enabled by `options`, or 0 if they are the lowest
+    // and highest versions supported.
+    fn proto_version_range(options: c_ulong) -> (c_int, c_int) {
+        // LibreSSL does not support SSLv3, and defines its option as 0
+        let supported = PROTOCOLS
+            .iter()
+            .filter(|&&(_, op)| op != 0)
+            .collect::<Vec<_>>();
+        let enabled = supported
+            .iter()
+            .filter(|&&&(_, op)| options & op == 0)
+            .map(|&&(version, _)| version)
+            .collect::<Vec<_>>();
+
+        match (enabled.first(), enabled.last()) {
+            (Some(&min), Some(&max)) => (
+                if min == supported[0].0 { 0 } else { min },
+                if max == supported[supported.len() - 1].0 { 0 } else { max },
+            ),
+            _ => (0, 0),
+        }
+    }
+
+    // Returns the options to set and to clear to enable only the versions from `min` to `max`, or
+    // `None` if either bound is a version the options cannot enable, such as TLSv1.3.
+    fn proto_version_options(min: c_int, max: c_int) -> Option<(c_ulong, c_ulong)> {
+        let is_supported = |version: c_int| {
+            version == 0 || PROTOCOLS.iter().any(|&(v, op)| v == version && op != 0)
+        };
+        if !is_supported(min) || !is_supported(max) {
+            return None;
+        }
+
+        let mut set = 0;
+        let mut clear = 0;
+        for &(version, op) in &PROTOCOLS {
+            if (min != 0 && version < min) || (max != 0 && version > max) {
+                set |= op;
+            } else {
+                clear |= op;
+            }
+        }
+        Some((set, clear))
+    }
+
+    pub unsafe fn SSL_CTX_set_min_proto_version(ctx: *mut ffi::SSL_CTX, version: c_int) -> c_int {
+        let (_, max) = proto_version_range(SSL_CTX_get_options(ctx));
+        match proto_version_options(version, max) {
+            Some((set, clear)) => {
+                SSL_CTX_set_options(ctx, set);
+                SSL_CTX_clear_options(ctx, clear);
+                1
+            }
+            None => 0,
+        }
+    }
+
+    pub unsafe fn SSL_CTX_set_max_proto_version(ctx: *mut ffi::SSL_CTX, version: c_int) -> c_int {
+        let (min, _) = proto_version_range(SSL_CTX_get_options(ctx));
+        match proto_version_options(min, version) {
+            Some((set, clear)) => {
+                SSL_CTX_set_options(ctx, set);
+                SSL_CTX_clear_options(ctx, clear);
+                1
+            }
+            None => 0,
+        }
+    }
+
+    pub unsafe fn SSL_CTX_get_min_proto_version(ctx: *mut ffi::SSL_CTX) -> c_int {
+        proto_version_range(SSL_CTX_get_options(ctx)).0
+    }
+
+    pub unsafe fn SSL_CTX_get_max_proto_version(ctx: *mut ffi::SSL_CTX) -> c_int {
+        proto_version_range(SSL_CTX_get_options(ctx)).1
+    }
+
+    pub unsafe fn SSL_set_min_proto_version(ssl: *mut ffi::SSL, version: c_int) -> c_int {
+        let (_, max) = proto_version_range(SSL_get_options(ssl));
+        match proto_version_options(version, max) {
+            Some((set, clear)) => {
+                SSL_set_options(ssl, set);
+                SSL_clear_options(ssl, clear);
+                1
+            }
+            None => 0,
+        }
+    }
+
+    pub unsafe fn SSL_set_max_proto_version(ssl: *mut ffi::SSL, version: c_int) -> c_int {
+        let (min, _) = proto_version_range(SSL_get_options(ssl));
+        match proto_version_options(min, version) {
+            Some((set, clear)) => {
+                SSL_set_options(ssl, set);
+                SSL_clear_options(ssl, clear);
+                1
+            }
+            None => 0,
+        }
+    }
+
+    pub unsafe fn SSL_get_min_proto_version(ssl: *mut ffi::SSL) -> c_int {
+        proto_version_range(SSL_get_options(ssl)).0
+    }
+
+    pub unsafe fn SSL_get_max_proto_version(ssl: *mut ffi::SSL) -> c_int {
+        proto_version_range(SSL_get_options(ssl)).1
+    }
+
     pub unsafe fn get_new_idx(f: ffi::CRYPTO_EX_free) -> c_int {
         ffi::SSL_CTX_get_ex_new_index(0, ptr::null_mut(), None, None, Some(f))
     }
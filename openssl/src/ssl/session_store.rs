@@ -0,0 +1,154 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ssl::{SslContextBuilder, SslSession, SslSessionCacheMode};
+
+/// An external cache of server-side sessions.
+///
+/// OpenSSL caches the sessions established by a server in memory, so a client can only resume a
+/// session with the process which established it. Servers behind a load balancer can instead
+/// share sessions through a `SessionStore` backed by a networked cache, allowing a client to
+/// resume its session with any of them.
+///
+/// Sessions are stored in their DER encoding, keyed by their session ID. Implementations are
+/// called from within handshakes, so should respond promptly, and may treat failures of the
+/// underlying cache as misses.
+///
+/// A store is installed with [`SslContextBuilder::set_session_store`].
+///
+/// [`SslContextBuilder::set_session_store`]: struct.SslContextBuilder.html#method.set_session_store
+pub trait SessionStore: Sync + Send + 'static {
+    /// Returns the DER-encoded session with the specified ID, if present.
+    fn get(&self, id: &[u8]) -> Option<Vec<u8>>;
+
+    /// Stores a DER-encoded session, which expires after `timeout`.
+    fn put(&self, id: &[u8], session: &[u8], timeout: Duration);
+
+    /// Removes the session with the specified ID.
+    fn remove(&self, id: &[u8]);
+}
+
+impl<S> SessionStore for Arc<S>
+where
+    S: SessionStore,
+{
+    fn get(&self, id: &[u8]) -> Option<Vec<u8>> {
+        (**self).get(id)
+    }
+
+    fn put(&self, id: &[u8], session: &[u8], timeout: Duration) {
+        (**self).put(id, session, timeout)
+    }
+
+    fn remove(&self, id: &[u8]) {
+        (**self).remove(id)
+    }
+}
+
+/// A `SessionStore` which holds sessions in memory, expiring them like a key-value cache.
+///
+/// This is primarily useful to share sessions between several `SslContext`s in one process, and
+/// as an example of a store implementation.
+pub struct MemorySessionStore {
+    sessions: Mutex<HashMap<Vec<u8>, StoredSession>>,
+}
+
+struct StoredSession {
+    der: Vec<u8>,
+    expires: Instant,
+}
+
+impl MemorySessionStore {
+    /// Creates an empty store.
+    pub fn new() -> MemorySessionStore {
+        MemorySessionStore {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the number of stored sessions, including expired ones which have not been evicted.
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().len()
+    }
+
+    /// Determines if no sessions are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all sessions.
+    pub fn clear(&self) {
+        self.sessions.lock().unwrap().clear();
+    }
+}
+
+impl Default for MemorySessionStore {
+    fn default() -> MemorySessionStore {
+        MemorySessionStore::new()
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn get(&self, id: &[u8]) -> Option<Vec<u8>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let expired = match sessions.get(id) {
+            Some(session) if session.expires > Instant::now() => return Some(session.der.clone()),
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            sessions.remove(id);
+        }
+        None
+    }
+
+    fn put(&self, id: &[u8], session: &[u8], timeout: Duration) {
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, s| s.expires > now);
+        sessions.insert(
+            id.to_vec(),
+            StoredSession {
+                der: session.to_vec(),
+                expires: now + timeout,
+            },
+        );
+    }
+
+    fn remove(&self, id: &[u8]) {
+        self.sessions.lock().unwrap().remove(id);
+    }
+}
+
+pub fn install<S>(ctx: &mut SslContextBuilder, store: S)
+where
+    S: SessionStore,
+{
+    let store = Arc::new(store);
+
+    // sessions held in the internal cache are removed from it, and so from the store, when the
+    // context is freed
+    let mode = SslSessionCacheMode::SERVER | SslSessionCacheMode::NO_INTERNAL_STORE;
+    ctx.set_session_cache_mode(mode);
+
+    let put_store = store.clone();
+    ctx.set_new_session_callback(move |_, session| {
+        // sessions which cannot be encoded are simply not shared
+        if let Ok(der) = session.to_der() {
+            let timeout = Duration::from_secs(cmp::max(session.timeout(), 0) as u64);
+            put_store.put(session.id(), &der, timeout);
+        }
+    });
+
+    let remove_store = store.clone();
+    ctx.set_remove_session_callback(move |_, session| remove_store.remove(session.id()));
+
+    unsafe {
+        // a deserialized session is not associated with any context
+        ctx.set_get_session_callback(move |_, id| {
+            store.get(id).and_then(|der| SslSession::from_der(&der).ok())
+        });
+    }
+}
@@ -0,0 +1,101 @@
+use ffi;
+use foreign_types::ForeignTypeRef;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+
+use ssl::{Error, ErrorCode, ShutdownResult, SslStream};
+
+/// The reading half of an `SslStream<TcpStream>`, created by [`SslStream::split`].
+///
+/// Reads wait for the socket to become readable before locking the session, and transparently
+/// retry when only handshake messages were received, as `SSL_MODE_AUTO_RETRY` is cleared on the
+/// session by the split.
+///
+/// [`SslStream::split`]: struct.SslStream.html#method.split
+pub struct SslReadHalf {
+    stream: Arc<Mutex<SslStream<TcpStream>>>,
+    socket: TcpStream,
+}
+
+/// The writing half of an `SslStream<TcpStream>`, created by [`SslStream::split`].
+///
+/// [`SslStream::split`]: struct.SslStream.html#method.split
+pub struct SslWriteHalf {
+    stream: Arc<Mutex<SslStream<TcpStream>>>,
+}
+
+pub fn split(stream: SslStream<TcpStream>) -> io::Result<(SslReadHalf, SslWriteHalf)> {
+    let socket = stream.get_ref().try_clone()?;
+
+    // without this, a read which only receives a post-handshake message such as a TLS 1.3 session
+    // ticket would block waiting for application data while holding the lock. This is documented
+    // on SslStream::split, as the mode stays cleared on the session.
+    unsafe {
+        ffi::SSL_clear_mode(stream.ssl().as_ptr(), ffi::SSL_MODE_AUTO_RETRY);
+    }
+
+    let stream = Arc::new(Mutex::new(stream));
+    let read = SslReadHalf {
+        stream: stream.clone(),
+        socket,
+    };
+    let write = SslWriteHalf { stream };
+    Ok((read, write))
+}
+
+impl SslReadHalf {
+    // Blocks until data is available to be read, without holding the lock.
+    fn wait_readable(&self) -> io::Result<()> {
+        if self.stream.lock().unwrap().ssl().pending() > 0 {
+            return Ok(());
+        }
+        self.socket.peek(&mut [0]).map(|_| ())
+    }
+}
+
+impl Read for SslReadHalf {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            self.wait_readable()?;
+
+            let mut stream = self.stream.lock().unwrap();
+            match stream.ssl_read(buf) {
+                Ok(n) => return Ok(n),
+                Err(ref e) if e.code() == ErrorCode::ZERO_RETURN => return Ok(0),
+                Err(ref e) if e.code() == ErrorCode::SYSCALL && e.io_error().is_none() => {
+                    return Ok(0)
+                }
+                Err(ref e) if e.code() == ErrorCode::WANT_READ && e.io_error().is_none() => {}
+                Err(e) => {
+                    return Err(e.into_io_error()
+                        .unwrap_or_else(|e| io::Error::new(io::ErrorKind::Other, e)))
+                }
+            }
+        }
+    }
+}
+
+impl SslWriteHalf {
+    /// Sends a close notify message to the peer.
+    ///
+    /// The reading half will return end of file once the peer's close notify message is
+    /// received.
+    ///
+    /// This corresponds to [`SSL_shutdown`].
+    ///
+    /// [`SSL_shutdown`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_shutdown.html
+    pub fn shutdown(&mut self) -> Result<ShutdownResult, Error> {
+        self.stream.lock().unwrap().shutdown()
+    }
+}
+
+impl Write for SslWriteHalf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.lock().unwrap().flush()
+    }
+}
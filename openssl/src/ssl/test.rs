@@ -6,25 +6,28 @@ use std::io::prelude::*;
 use std::io::{self, BufReader};
 use std::iter;
 use std::mem;
-use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
 use std::path::Path;
 use std::process::{Child, ChildStdin, Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering, ATOMIC_BOOL_INIT};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tempdir::TempDir;
 
 use dh::Dh;
+use error::ErrorKind;
 use hash::MessageDigest;
 use ocsp::{OcspResponse, OcspResponseStatus};
-use pkey::PKey;
+use pkey::{Id, PKey};
 use ssl;
-#[cfg(any(ossl110, ossl111))]
-use ssl::SslVersion;
 use ssl::{
-    Error, HandshakeError, MidHandshakeSslStream, ShutdownResult, Ssl, SslAcceptor, SslConnector,
-    SslContext, SslFiletype, SslMethod, SslSessionCacheMode, SslStream, SslVerifyMode, StatusType,
+    AcceptErrorKind, Error, ErrorCode, HandshakeError, MemorySessionStore, MidHandshakeSslStream,
+    SessionStore, ShutdownResult, ShutdownState, SpkiPinSet, Ssl, SslAcceptor, SslConnector,
+    SslContext, SslEngine, SslFiletype, SslListener, SslMethod, SslOptions, SslSessionCacheMode,
+    SslStream, SslStreamBuilder, SslVerifyMode, SslVersion, StatusType,
 };
+use test_utils;
 #[cfg(any(ossl102, ossl110))]
 use x509::verify::X509CheckFlags;
 use x509::{X509, X509Name, X509StoreContext, X509VerifyResult};
@@ -935,57 +938,885 @@ fn connector_client_server_mozilla_intermediate() {
     t.join().unwrap();
 }
 
+fn listener() -> SslListener {
+    let key = PKey::private_key_from_pem(KEY).unwrap();
+    let cert = X509::from_pem(CERT).unwrap();
+    let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    acceptor.set_private_key(&key).unwrap();
+    acceptor.set_certificate(&cert).unwrap();
+    SslListener::bind("127.0.0.1:0", acceptor.build()).unwrap()
+}
+
+fn connect_to(addr: SocketAddr) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
+        connector.set_ca_file("test/root-ca.pem").unwrap();
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut stream = connector.build().connect("foobar.com", stream).unwrap();
+        stream.write_all(b"hello").unwrap();
+    })
+}
+
+#[test]
+fn listener_accept() {
+    let listener = listener();
+    let t = connect_to(listener.local_addr().unwrap());
+
+    let (mut stream, addr) = listener.accept().unwrap();
+    assert_eq!(addr, stream.get_ref().peer_addr().unwrap());
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(b"hello", &buf);
+
+    t.join().unwrap();
+}
+
+#[test]
+fn listener_accept_batch() {
+    let listener = listener();
+    let addr = listener.local_addr().unwrap();
+    let clients = (0..2).map(|_| connect_to(addr)).collect::<Vec<_>>();
+
+    let mut pending = vec![];
+    while pending.len() < 2 {
+        pending.extend(listener.accept_batch(2 - pending.len()).unwrap());
+    }
+
+    let workers = pending
+        .into_iter()
+        .map(|pending| {
+            thread::spawn(move || {
+                let mut stream = pending.handshake().unwrap();
+                let mut buf = [0; 5];
+                stream.read_exact(&mut buf).unwrap();
+                assert_eq!(b"hello", &buf);
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for t in clients.into_iter().chain(workers) {
+        t.join().unwrap();
+    }
+}
+
+#[test]
+fn listener_errors() {
+    let mut listener = listener();
+    listener.set_handshake_timeout(Some(Duration::from_millis(100)));
+    let addr = listener.local_addr().unwrap();
+
+    // a client which never sends its hello
+    let idle = TcpStream::connect(addr).unwrap();
+    let err = listener.accept().unwrap_err();
+    assert_eq!(err.kind(), AcceptErrorKind::TimedOut);
+    assert_eq!(err.peer_addr(), Some(idle.local_addr().unwrap()));
+    drop(idle);
+
+    // a client which is not speaking TLS
+    let mut garbage = TcpStream::connect(addr).unwrap();
+    garbage.write_all(&[0; 512]).unwrap();
+    let err = listener.accept().unwrap_err();
+    assert_eq!(err.kind(), AcceptErrorKind::Protocol);
+
+    // a client which disconnects immediately
+    drop(TcpStream::connect(addr).unwrap());
+    let err = listener.accept().unwrap_err();
+    assert_eq!(err.kind(), AcceptErrorKind::Closed);
+}
+
+#[test]
+fn connector_into_ssl() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let t = thread::spawn(move || {
+        let key = PKey::private_key_from_pem(KEY).unwrap();
+        let cert = X509::from_pem(CERT).unwrap();
+        let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+        acceptor.set_private_key(&key).unwrap();
+        acceptor.set_certificate(&cert).unwrap();
+        let acceptor = acceptor.build();
+        let mut stream = listener.accept().unwrap().0;
+
+        // emulate a proxy which acknowledges the tunnel before TLS starts
+        let mut buf = [0; 7];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(b"CONNECT", &buf);
+        stream.write_all(b"OK").unwrap();
+
+        let mut stream = acceptor.accept(stream).unwrap();
+        stream.write_all(b"hello").unwrap();
+    });
+
+    let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
+    connector.set_ca_file("test/root-ca.pem").unwrap();
+    let connector = connector.build();
+
+    let ssl = connector
+        .configure()
+        .unwrap()
+        .into_ssl("foobar.com")
+        .unwrap();
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream.write_all(b"CONNECT").unwrap();
+    let mut buf = [0; 2];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(b"OK", &buf);
+
+    let mut stream = ssl.connect(stream).unwrap();
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(b"hello", &buf);
+
+    t.join().unwrap();
+}
+
+#[test]
+fn connector_first_flight() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let t = thread::spawn(move || {
+        let key = PKey::private_key_from_pem(KEY).unwrap();
+        let cert = X509::from_pem(CERT).unwrap();
+        let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+        acceptor.set_private_key(&key).unwrap();
+        acceptor.set_certificate(&cert).unwrap();
+        let acceptor = acceptor.build();
+        let stream = listener.accept().unwrap().0;
+        let mut stream = acceptor.accept(stream).unwrap();
+
+        stream.write_all(b"hello").unwrap();
+    });
+
+    let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
+    connector.set_ca_file("test/root-ca.pem").unwrap();
+    let connector = connector.build();
+
+    let flight = connector
+        .configure()
+        .unwrap()
+        .first_flight("foobar.com")
+        .unwrap();
+    assert!(!flight.data().is_empty());
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream.write_all(flight.data()).unwrap();
+    let mut stream = flight.connect(stream).unwrap();
+
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(b"hello", &buf);
+
+    t.join().unwrap();
+}
+
+#[test]
+fn connector_spki_pins() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let t = thread::spawn(move || {
+        let key = PKey::private_key_from_pem(KEY).unwrap();
+        let cert = X509::from_pem(CERT).unwrap();
+        let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+        acceptor.set_private_key(&key).unwrap();
+        acceptor.set_certificate(&cert).unwrap();
+        let acceptor = acceptor.build();
+        for _ in 0..3 {
+            let stream = listener.accept().unwrap().0;
+            let _ = acceptor.accept(stream);
+        }
+    });
+
+    let root_pin = SpkiPinSet::pin(&X509::from_pem(ROOT_CERT).unwrap()).unwrap();
+    assert_eq!(root_pin.len(), 44);
+    let other_pin = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+
+    let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
+    connector.set_ca_file("test/root-ca.pem").unwrap();
+    let connector = connector.build();
+
+    let mut config = connector.configure().unwrap();
+    config.set_spki_pins(SpkiPinSet::from_base64(&[other_pin, &root_pin[..]]).unwrap());
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    config.connect("foobar.com", stream).unwrap();
+
+    let mut config = connector.configure().unwrap();
+    config.set_spki_pins(SpkiPinSet::from_base64(&[other_pin]).unwrap());
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    assert!(config.connect("foobar.com", stream).is_err());
+
+    let reported = Arc::new(AtomicBool::new(false));
+    let mut config = connector.configure().unwrap();
+    config.set_spki_pins_report_only(SpkiPinSet::from_base64(&[other_pin]).unwrap(), {
+        let reported = reported.clone();
+        move |_| reported.store(true, Ordering::SeqCst)
+    });
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    config.connect("foobar.com", stream).unwrap();
+    assert!(reported.load(Ordering::SeqCst));
+
+    t.join().unwrap();
+}
+
+#[test]
+fn spki_pin_set_invalid() {
+    assert!(SpkiPinSet::from_base64(&["foo"]).is_err());
+    assert!(SpkiPinSet::from_base64(&["AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=="]).is_err());
+    assert!(SpkiPinSet::from_base64(&["!AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="]).is_err());
+}
+
+#[test]
+fn connector_client_server_mozilla_modern() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let t = thread::spawn(move || {
+        let key = PKey::private_key_from_pem(KEY).unwrap();
+        let cert = X509::from_pem(CERT).unwrap();
+        let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+        acceptor.set_private_key(&key).unwrap();
+        acceptor.set_certificate(&cert).unwrap();
+        let acceptor = acceptor.build();
+        let stream = listener.accept().unwrap().0;
+        let mut stream = acceptor.accept(stream).unwrap();
+
+        stream.write_all(b"hello").unwrap();
+    });
+
+    let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
+    connector.set_ca_file("test/root-ca.pem").unwrap();
+    let connector = connector.build();
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut stream = connector.connect("foobar.com", stream).unwrap();
+
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(b"hello", &buf);
+
+    t.join().unwrap();
+}
+
+#[test]
+#[cfg(any(ossl102, ossl110))]
+fn acceptor_context() {
+    let key = PKey::private_key_from_pem(KEY).unwrap();
+    let cert = X509::from_pem(CERT).unwrap();
+    let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    acceptor.set_private_key(&key).unwrap();
+    acceptor.set_certificate(&cert).unwrap();
+    let acceptor = acceptor.build();
+
+    let ctx_cert = acceptor.context().certificate().unwrap();
+    assert_eq!(ctx_cert.to_der().unwrap(), cert.to_der().unwrap());
+
+    let ctx = acceptor.into_context();
+    assert!(ctx.private_key().unwrap().public_eq(&key));
+
+    let connector = SslConnector::builder(SslMethod::tls()).unwrap().build();
+    assert!(connector.context().certificate().is_none());
+    Ssl::new(&connector.into_context()).unwrap();
+}
+
+#[test]
+fn memory_echo() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+    let connector = test_utils::connector(&cert).unwrap();
+
+    let (mut client, mut server) =
+        test_utils::connect(&connector, "foobar.com", &acceptor).unwrap();
+
+    // interleave traffic in both directions before reading anything
+    let mut buf = [0; 5];
+    for _ in 0..10 {
+        client.write_all(b"hello").unwrap();
+        server.write_all(b"world").unwrap();
+        server.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hello");
+        server.write_all(&buf).unwrap();
+        client.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"world");
+        client.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hello");
+    }
+
+    let data = vec![0xa5; 100 * 1024];
+    client.write_all(&data).unwrap();
+    let mut out = vec![0; data.len()];
+    server.read_exact(&mut out).unwrap();
+    assert_eq!(out, data);
+
+    drop(client);
+    assert_eq!(server.read(&mut buf).unwrap(), 0);
+}
+
+#[test]
+fn memory_rsa_identity() {
+    let (cert, key) = test_utils::IdentityBuilder::new("foobar.com")
+        .rsa(2048)
+        .dns("www.foobar.com")
+        .build()
+        .unwrap();
+    assert_eq!(key.id(), Id::RSA);
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+    let connector = test_utils::connector(&cert).unwrap();
+
+    test_utils::connect(&connector, "foobar.com", &acceptor).unwrap();
+    test_utils::connect(&connector, "www.foobar.com", &acceptor).unwrap();
+    assert!(test_utils::connect(&connector, "mail.foobar.com", &acceptor).is_err());
+}
+
+#[test]
+fn peek() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+    let connector = test_utils::connector(&cert).unwrap();
+
+    let (mut client, mut server) =
+        test_utils::connect(&connector, "foobar.com", &acceptor).unwrap();
+    client.write_all(b"hello").unwrap();
+
+    let mut buf = [0; 4];
+    assert_eq!(server.peek(&mut buf).unwrap(), 4);
+    assert_eq!(&buf, b"hell");
+    assert_eq!(server.ssl().pending(), 5);
+    #[cfg(ossl110)]
+    assert!(server.ssl().has_pending());
+
+    let mut buf = [0; 5];
+    server.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello");
+    assert_eq!(server.ssl().pending(), 0);
+}
+
+#[test]
+fn renegotiate_pending() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+    let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
+    connector.cert_store_mut().add_cert(cert).unwrap();
+    // renegotiation is not supported by TLS 1.3
+    connector
+        .set_max_proto_version(Some(SslVersion::TLS1_2))
+        .unwrap();
+    let connector = connector.build();
+
+    let (mut client, _server) = test_utils::connect(&connector, "foobar.com", &acceptor).unwrap();
+    assert!(!client.ssl().renegotiate_pending());
+    client.rekey().unwrap();
+    assert!(client.ssl().renegotiate_pending());
+}
+
+#[test]
+#[cfg(ossl111)]
+fn no_renegotiation() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+    let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
+    connector.cert_store_mut().add_cert(cert).unwrap();
+    connector
+        .set_max_proto_version(Some(SslVersion::TLS1_2))
+        .unwrap();
+    connector.set_options(ssl::SslOptions::NO_RENEGOTIATION);
+    let connector = connector.build();
+
+    let (mut client, _server) = test_utils::connect(&connector, "foobar.com", &acceptor).unwrap();
+    assert!(client.rekey().is_err());
+    assert!(!client.ssl().renegotiate_pending());
+}
+
+#[test]
+fn memory_handshake_failure() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+
+    let connector = test_utils::connector(&cert).unwrap();
+    assert!(test_utils::connect(&connector, "barfoo.com", &acceptor).is_err());
+
+    let (other, _) = test_utils::generate_identity("foobar.com").unwrap();
+    let connector = test_utils::connector(&other).unwrap();
+    assert!(test_utils::connect(&connector, "foobar.com", &acceptor).is_err());
+}
+
+#[test]
+fn handshake_error_verify_result() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+    let (other, _) = test_utils::generate_identity("foobar.com").unwrap();
+    let connector = test_utils::connector(&other).unwrap();
+
+    match test_utils::connect(&connector, "foobar.com", &acceptor) {
+        Err(HandshakeError::Failure(mid)) => {
+            assert_eq!(mid.error().code(), ErrorCode::SSL);
+            let verify = mid.error().verify_result().unwrap();
+            assert_eq!(verify, mid.ssl().verify_result());
+            assert!(mid.error().to_string().contains(verify.error_string()));
+        }
+        _ => panic!("expected a handshake failure"),
+    }
+}
+
+#[test]
+fn connection_properties() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let t = thread::spawn(move || {
+        let key = PKey::private_key_from_pem(KEY).unwrap();
+        let cert = X509::from_pem(CERT).unwrap();
+        let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+        acceptor.set_private_key(&key).unwrap();
+        acceptor.set_certificate(&cert).unwrap();
+        let acceptor = acceptor.build();
+        let stream = listener.accept().unwrap().0;
+        let mut stream = acceptor.accept(stream).unwrap();
+
+        stream.write_all(b"hello").unwrap();
+    });
+
+    let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
+    connector.set_ca_file("test/root-ca.pem").unwrap();
+    let connector = connector.build();
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut stream = connector.connect("foobar.com", stream).unwrap();
+
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+
+    let ssl = stream.ssl();
+    assert!(ssl.version2().is_some());
+    let cipher = ssl.current_cipher().unwrap();
+    assert!(!cipher.name().is_empty());
+    assert!(cipher.bits().secret > 0);
+    assert!(!cipher.description().is_empty());
+    assert_eq!(ssl.verify_result(), X509VerifyResult::OK);
+    assert_eq!(ssl.peer_cert_chain().unwrap().len(), 1);
+    #[cfg(ossl110)]
+    assert_eq!(ssl.verified_chain().unwrap().len(), 2);
+
+    t.join().unwrap();
+}
+
+#[test]
+fn shutdown() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        let stream = listener.accept().unwrap().0;
+        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        ctx.set_certificate_file(&Path::new("test/cert.pem"), SslFiletype::PEM)
+            .unwrap();
+        ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
+            .unwrap();
+        let ssl = Ssl::new(&ctx.build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+
+        stream.write_all(b"hello").unwrap();
+        let mut buf = [0; 1];
+        assert_eq!(stream.read(&mut buf).unwrap(), 0);
+        assert_eq!(stream.shutdown().unwrap(), ShutdownResult::Received);
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = ssl.connect(stream).unwrap();
+
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(b"hello", &buf);
+
+    assert_eq!(stream.shutdown().unwrap(), ShutdownResult::Sent);
+    assert_eq!(stream.shutdown().unwrap(), ShutdownResult::Received);
+}
+
+#[test]
+fn set_shutdown() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+    let connector = test_utils::connector(&cert).unwrap();
+
+    let (mut client, server) = test_utils::connect(&connector, "foobar.com", &acceptor).unwrap();
+    assert!(client.get_shutdown().is_empty());
+
+    // nothing is sent to the peer
+    client.set_shutdown(ShutdownState::SENT | ShutdownState::RECEIVED);
+    assert_eq!(client.get_shutdown(), ShutdownState::SENT | ShutdownState::RECEIVED);
+    assert_eq!(client.shutdown().unwrap(), ShutdownResult::Received);
+    assert_eq!(server.get_ref().pending(), 0);
+}
+
+#[test]
+fn shutdown_and_drain() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let t = thread::spawn(move || {
+        let stream = listener.accept().unwrap().0;
+        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        ctx.set_certificate_file(&Path::new("test/cert.pem"), SslFiletype::PEM)
+            .unwrap();
+        ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
+            .unwrap();
+        let ssl = Ssl::new(&ctx.build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+
+        stream.write_all(b"hello").unwrap();
+        let mut plaintext = vec![];
+        stream.shutdown_and_drain(&mut plaintext).unwrap();
+        assert!(plaintext.is_empty());
+        assert_eq!(
+            stream.ssl().get_shutdown(),
+            ShutdownState::SENT | ShutdownState::RECEIVED
+        );
+
+        let mut stream = stream.into_inner();
+        stream.write_all(b"plain").unwrap();
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(b"ping", &buf);
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = ssl.connect(stream).unwrap();
+    assert!(stream.ssl().get_shutdown().is_empty());
+
+    let mut plaintext = vec![];
+    stream.shutdown_and_drain(&mut plaintext).unwrap();
+    assert_eq!(plaintext, b"hello");
+
+    let mut stream = stream.into_inner();
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(b"plain", &buf);
+    stream.write_all(b"ping").unwrap();
+
+    t.join().unwrap();
+}
+
+#[test]
+#[cfg(ossl111)]
+fn auto_rekey() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let t = thread::spawn(move || {
+        let stream = listener.accept().unwrap().0;
+        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        ctx.set_certificate_file(&Path::new("test/cert.pem"), SslFiletype::PEM)
+            .unwrap();
+        ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
+            .unwrap();
+        let ssl = Ssl::new(&ctx.build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+
+        let mut buf = [0; 24];
+        stream.read_exact(&mut buf).unwrap();
+        stream.write_all(b"done").unwrap();
+
+        let stats = stream.traffic_stats();
+        assert_eq!(stats.bytes_read, 24);
+        assert_eq!(stats.bytes_written, 4);
+        assert_eq!(stats.rekeys, 0);
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_min_proto_version(Some(SslVersion::TLS1_3)).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = ssl.connect(stream).unwrap();
+    stream.set_rekey_limit(Some(10));
+
+    for _ in 0..3 {
+        stream.write_all(b"01234567").unwrap();
+    }
+    let mut buf = [0; 4];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(b"done", &buf);
+
+    let stats = stream.traffic_stats();
+    assert_eq!(stats.bytes_written, 24);
+    assert_eq!(stats.bytes_written_since_rekey, 8);
+    assert_eq!(stats.records_written, 3);
+    assert_eq!(stats.records_written_since_rekey, 1);
+    assert_eq!(stats.rekeys, 1);
+    assert_eq!(stats.bytes_read, 4);
+
+    t.join().unwrap();
+}
+
+#[test]
+#[cfg(ossl111)]
+fn auto_rekey_records() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+    let connector = test_utils::connector(&cert).unwrap();
+
+    let (mut client, mut server) =
+        test_utils::connect(&connector, "foobar.com", &acceptor).unwrap();
+    client.set_rekey_record_limit(Some(2));
+
+    // 100KiB spans 7 records
+    let data = vec![0xa5; 100 * 1024];
+    client.write_all(&data).unwrap();
+    let stats = client.traffic_stats();
+    assert_eq!(stats.records_written, 7);
+    assert_eq!(stats.rekeys, 0);
+
+    client.write_all(b"hello").unwrap();
+    let stats = client.traffic_stats();
+    assert_eq!(stats.records_written, 8);
+    assert_eq!(stats.records_written_since_rekey, 1);
+    assert_eq!(stats.rekeys, 1);
+
+    let mut out = vec![0; data.len() + 5];
+    server.read_exact(&mut out).unwrap();
+    assert_eq!(&out[..data.len()], &data[..]);
+    assert_eq!(&out[data.len()..], b"hello");
+}
+
+#[test]
+fn abort_handshake() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    // The server never responds, leaving the client stuck waiting for the ServerHello.
+    let t = thread::spawn(move || {
+        let mut stream = listener.accept().unwrap().0;
+        let mut buf = vec![];
+        let _ = stream.read_to_end(&mut buf);
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream.set_nonblocking(true).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let builder = SslStreamBuilder::new(ssl, stream);
+    let handle = builder.abort_handle();
+    assert!(!handle.is_aborted());
+
+    let mid = match builder.connect() {
+        Err(HandshakeError::WouldBlock(mid)) => mid,
+        _ => panic!("expected the handshake to block"),
+    };
+
+    thread::spawn(move || handle.abort()).join().unwrap();
+    assert!(mid.abort_handle().is_aborted());
+
+    match mid.handshake() {
+        Err(HandshakeError::Failure(mid)) => {
+            let err = mid.error().io_error().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::ConnectionAborted);
+            mid.get_ref().shutdown(Shutdown::Both).unwrap();
+        }
+        _ => panic!("expected the handshake to fail"),
+    }
+
+    t.join().unwrap();
+}
+
+fn engine_transfer(from: &mut SslEngine, to: &mut SslEngine) {
+    let mut buf = [0; 4096];
+    while from.wants_write() {
+        let len = from.produce_outgoing(&mut buf);
+        assert_eq!(to.consume_incoming(&buf[..len]).unwrap(), len);
+    }
+}
+
+fn engine_handshake(engine: &mut SslEngine) -> bool {
+    match engine.handshake() {
+        Ok(()) => true,
+        Err(ref e) if e.code() == ErrorCode::WANT_READ => {
+            assert!(engine.wants_read());
+            false
+        }
+        Err(e) => panic!("handshake failed: {}", e),
+    }
+}
+
+#[test]
+fn engine() {
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_certificate_file(&Path::new("test/cert.pem"), SslFiletype::PEM)
+        .unwrap();
+    ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
+        .unwrap();
+    let mut server = SslEngine::new(Ssl::new(&ctx.build()).unwrap()).unwrap();
+    server.set_accept_state();
+
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let mut client = SslEngine::new(Ssl::new(&ctx.build()).unwrap()).unwrap();
+    client.set_connect_state();
+
+    let mut done = false;
+    for _ in 0..10 {
+        let client_done = engine_handshake(&mut client);
+        engine_transfer(&mut client, &mut server);
+        let server_done = engine_handshake(&mut server);
+        engine_transfer(&mut server, &mut client);
+        if client_done && server_done {
+            done = true;
+            break;
+        }
+    }
+    assert!(done);
+
+    assert_eq!(client.ssl_write(b"hello").unwrap(), 5);
+    engine_transfer(&mut client, &mut server);
+
+    let mut buf = [0; 5];
+    assert_eq!(server.ssl_read(&mut buf).unwrap(), 5);
+    assert_eq!(&buf, b"hello");
+
+    let err = server.ssl_read(&mut buf).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::WANT_READ);
+    assert!(server.wants_read());
+    assert!(!server.wants_write());
+}
+
 #[test]
-fn connector_client_server_mozilla_modern() {
+#[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+fn psk_ciphers() {
+    const CIPHER: &'static str = "PSK-AES128-CBC-SHA";
+    const PSK: &'static [u8] = b"thisisaverysecurekey";
+    const CLIENT_IDENT: &'static [u8] = b"thisisaclient";
+    static CLIENT_CALLED: AtomicBool = ATOMIC_BOOL_INIT;
+    static SERVER_CALLED: AtomicBool = ATOMIC_BOOL_INIT;
+
     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
     let port = listener.local_addr().unwrap().port();
 
-    let t = thread::spawn(move || {
-        let key = PKey::private_key_from_pem(KEY).unwrap();
-        let cert = X509::from_pem(CERT).unwrap();
-        let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
-        acceptor.set_private_key(&key).unwrap();
-        acceptor.set_certificate(&cert).unwrap();
-        let acceptor = acceptor.build();
+    thread::spawn(move || {
         let stream = listener.accept().unwrap().0;
-        let mut stream = acceptor.accept(stream).unwrap();
+        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        ctx.set_cipher_list(CIPHER).unwrap();
+        ctx.set_psk_identity_hint("thisisahint").unwrap();
+        ctx.set_psk_server_callback(move |_, identity, psk| {
+            assert_eq!(identity.unwrap_or(&[]), CLIENT_IDENT);
+            psk[..PSK.len()].copy_from_slice(PSK);
+            SERVER_CALLED.store(true, Ordering::SeqCst);
+            Ok(PSK.len())
+        });
+        let ssl = Ssl::new(&ctx.build()).unwrap();
+        ssl.accept(stream).unwrap();
+    });
 
-        stream.write_all(b"hello").unwrap();
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_cipher_list(CIPHER).unwrap();
+    #[cfg(ossl111)]
+    ctx.set_options(ssl::SslOptions::NO_TLSV1_3);
+    ctx.set_psk_client_callback(move |_, hint, identity, psk| {
+        assert_eq!(hint, Some(&b"thisisahint"[..]));
+        identity[..CLIENT_IDENT.len()].copy_from_slice(CLIENT_IDENT);
+        identity[CLIENT_IDENT.len()] = 0;
+        psk[..PSK.len()].copy_from_slice(PSK);
+        CLIENT_CALLED.store(true, Ordering::SeqCst);
+        Ok(PSK.len())
     });
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    ssl.connect(stream).unwrap();
 
-    let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
-    connector.set_ca_file("test/root-ca.pem").unwrap();
-    let connector = connector.build();
+    assert!(CLIENT_CALLED.load(Ordering::SeqCst));
+    assert!(SERVER_CALLED.load(Ordering::SeqCst));
+}
+
+#[test]
+#[cfg(ossl111)]
+fn tls13_external_psk() {
+    const PSK: &'static [u8] = b"thisisaverysecurekeythatislonger";
+    const CLIENT_IDENT: &'static [u8] = b"thisisaclient";
+    static CLIENT_CALLED: AtomicBool = ATOMIC_BOOL_INIT;
+    static SERVER_CALLED: AtomicBool = ATOMIC_BOOL_INIT;
+
+    fn psk_session(ssl: &ssl::SslRef) -> ssl::SslSession {
+        // TLS_AES_128_GCM_SHA256
+        let cipher = ssl.find_cipher([0x13, 0x01]).unwrap();
+        let mut session = ssl::SslSession::new().unwrap();
+        session.set_master_key(PSK).unwrap();
+        session.set_cipher(cipher).unwrap();
+        session.set_protocol_version(SslVersion::TLS1_3).unwrap();
+        session
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let guard = thread::spawn(move || {
+        let stream = listener.accept().unwrap().0;
+        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        ctx.set_min_proto_version(Some(SslVersion::TLS1_3)).unwrap();
+        ctx.set_psk_find_session_callback(|ssl, identity| {
+            if identity != CLIENT_IDENT {
+                return Ok(None);
+            }
+            SERVER_CALLED.store(true, Ordering::SeqCst);
+            Ok(Some(psk_session(ssl)))
+        });
+        let ssl = Ssl::new(&ctx.build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+        stream.write_all(&[0]).unwrap();
+    });
 
     let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
-    let mut stream = connector.connect("foobar.com", stream).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_min_proto_version(Some(SslVersion::TLS1_3)).unwrap();
+    ctx.set_psk_use_session_callback(|ssl, md| {
+        if let Some(md) = md {
+            assert_eq!(md.as_ptr(), MessageDigest::sha256().as_ptr());
+        }
+        CLIENT_CALLED.store(true, Ordering::SeqCst);
+        Ok(Some((CLIENT_IDENT.to_vec(), psk_session(ssl))))
+    });
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = ssl.connect(stream).unwrap();
+    stream.read_exact(&mut [0]).unwrap();
 
-    let mut buf = [0; 5];
-    stream.read_exact(&mut buf).unwrap();
-    assert_eq!(b"hello", &buf);
+    assert_eq!(stream.ssl().version2(), Some(SslVersion::TLS1_3));
+    assert!(stream.ssl().peer_certificate().is_none());
 
-    t.join().unwrap();
+    guard.join().unwrap();
+    assert!(CLIENT_CALLED.load(Ordering::SeqCst));
+    assert!(SERVER_CALLED.load(Ordering::SeqCst));
 }
 
 #[test]
-fn shutdown() {
+fn client_ca_list() {
+    let names = X509Name::load_client_ca_file("test/root-ca.pem").unwrap();
+    assert_eq!(names.len(), 1);
+
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_client_ca_list(names);
+}
+
+#[test]
+fn client_ca_list_sent() {
     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
     let port = listener.local_addr().unwrap().port();
 
-    thread::spawn(move || {
+    let t = thread::spawn(move || {
         let stream = listener.accept().unwrap().0;
         let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
         ctx.set_certificate_file(&Path::new("test/cert.pem"), SslFiletype::PEM)
             .unwrap();
         ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
             .unwrap();
+        ctx.set_verify(SslVerifyMode::PEER);
+        ctx.add_client_ca(&X509::from_pem(ROOT_CERT).unwrap())
+            .unwrap();
         let ssl = Ssl::new(&ctx.build()).unwrap();
+        assert_eq!(ssl.client_ca_list().unwrap().len(), 1);
         let mut stream = ssl.accept(stream).unwrap();
 
         stream.write_all(b"hello").unwrap();
-        let mut buf = [0; 1];
-        assert_eq!(stream.read(&mut buf).unwrap(), 0);
-        assert_eq!(stream.shutdown().unwrap(), ShutdownResult::Received);
     });
 
     let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
@@ -995,19 +1826,9 @@ fn shutdown() {
 
     let mut buf = [0; 5];
     stream.read_exact(&mut buf).unwrap();
-    assert_eq!(b"hello", &buf);
-
-    assert_eq!(stream.shutdown().unwrap(), ShutdownResult::Sent);
-    assert_eq!(stream.shutdown().unwrap(), ShutdownResult::Received);
-}
-
-#[test]
-fn client_ca_list() {
-    let names = X509Name::load_client_ca_file("test/root-ca.pem").unwrap();
-    assert_eq!(names.len(), 1);
+    assert_eq!(stream.ssl().client_ca_list().unwrap().len(), 1);
 
-    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-    ctx.set_client_ca_list(names);
+    t.join().unwrap();
 }
 
 #[test]
@@ -1147,100 +1968,300 @@ fn tmp_ecdh_callback_ssl() {
     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
     let port = listener.local_addr().unwrap().port();
 
-    thread::spawn(move || {
+    thread::spawn(move || {
+        let stream = listener.accept().unwrap().0;
+        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        ctx.set_certificate_file(&Path::new("test/cert.pem"), SslFiletype::PEM)
+            .unwrap();
+        ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
+            .unwrap();
+        let mut ssl = Ssl::new(&ctx.build()).unwrap();
+        ssl.set_tmp_ecdh_callback(|_, _, _| {
+            CALLED_BACK.store(true, Ordering::SeqCst);
+            EcKey::from_curve_name(Nid::X9_62_PRIME256V1)
+        });
+        ssl.accept(stream).unwrap();
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_cipher_list("ECDH").unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    ssl.connect(stream).unwrap();
+
+    assert!(CALLED_BACK.load(Ordering::SeqCst));
+}
+
+#[test]
+fn idle_session() {
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap().build();
+    let ssl = Ssl::new(&ctx).unwrap();
+    assert!(ssl.session().is_none());
+}
+
+#[test]
+#[cfg_attr(libressl250, ignore)]
+fn active_session() {
+    let connector = SslConnector::builder(SslMethod::tls()).unwrap().build();
+
+    let s = TcpStream::connect("google.com:443").unwrap();
+    let socket = connector.connect("google.com", s).unwrap();
+    let session = socket.ssl().session().unwrap();
+    let len = session.master_key_len();
+    let mut buf = vec![0; len - 1];
+    let copied = session.master_key(&mut buf);
+    assert_eq!(copied, buf.len());
+    let mut buf = vec![0; len + 1];
+    let copied = session.master_key(&mut buf);
+    assert_eq!(copied, len);
+}
+
+#[test]
+fn session_lifetime() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+    let connector = test_utils::connector(&cert).unwrap();
+
+    let (client, _server) = test_utils::connect(&connector, "foobar.com", &acceptor).unwrap();
+    let mut session = client.ssl().session().unwrap().to_owned();
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    assert!((session.time() - now).abs() < 60);
+    assert!(session.timeout() > 0);
+
+    session.set_timeout(10);
+    assert_eq!(session.timeout(), 10);
+
+    #[cfg(ossl110)]
+    assert_eq!(Some(session.protocol_version()), client.ssl().version2());
+}
+
+#[test]
+fn status_callbacks() {
+    static CALLED_BACK_SERVER: AtomicBool = ATOMIC_BOOL_INIT;
+    static CALLED_BACK_CLIENT: AtomicBool = ATOMIC_BOOL_INIT;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let guard = thread::spawn(move || {
+        let stream = listener.accept().unwrap().0;
+        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        ctx.set_certificate_file(&Path::new("test/cert.pem"), SslFiletype::PEM)
+            .unwrap();
+        ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
+            .unwrap();
+        ctx.set_status_callback(|ssl| {
+            CALLED_BACK_SERVER.store(true, Ordering::SeqCst);
+            let response = OcspResponse::create(OcspResponseStatus::UNAUTHORIZED, None).unwrap();
+            let response = response.to_der().unwrap();
+            ssl.set_ocsp_status(&response).unwrap();
+            Ok(true)
+        }).unwrap();
+        let ssl = Ssl::new(&ctx.build()).unwrap();
+        ssl.accept(stream).unwrap();
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_status_callback(|ssl| {
+        CALLED_BACK_CLIENT.store(true, Ordering::SeqCst);
+        let response = OcspResponse::from_der(ssl.ocsp_status().unwrap()).unwrap();
+        assert_eq!(response.status(), OcspResponseStatus::UNAUTHORIZED);
+        Ok(true)
+    }).unwrap();
+    let mut ssl = Ssl::new(&ctx.build()).unwrap();
+    ssl.set_status_type(StatusType::OCSP).unwrap();
+    ssl.connect(stream).unwrap();
+
+    assert!(CALLED_BACK_SERVER.load(Ordering::SeqCst));
+    assert!(CALLED_BACK_CLIENT.load(Ordering::SeqCst));
+
+    guard.join().unwrap();
+}
+
+#[test]
+fn new_session_callback() {
+    static CALLED_BACK: AtomicBool = ATOMIC_BOOL_INIT;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        let stream = listener.accept().unwrap().0;
+        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        ctx.set_certificate_file(&Path::new("test/cert.pem"), SslFiletype::PEM)
+            .unwrap();
+        ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
+            .unwrap();
+        ctx.set_session_id_context(b"foo").unwrap();
+        let ssl = Ssl::new(&ctx.build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+        stream.write_all(&[0]).unwrap();
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_session_cache_mode(SslSessionCacheMode::CLIENT | SslSessionCacheMode::NO_INTERNAL);
+    ctx.set_new_session_callback(|_, _| CALLED_BACK.store(true, Ordering::SeqCst));
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = ssl.connect(stream).unwrap();
+    // read 1 byte to make sure the session is received for TLSv1.3
+    let mut buf = [0];
+    stream.read_exact(&mut buf).unwrap();
+
+    assert!(CALLED_BACK.load(Ordering::SeqCst));
+}
+
+#[test]
+fn split() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let guard = thread::spawn(move || {
+        let stream = listener.accept().unwrap().0;
+        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        ctx.set_certificate_file(&Path::new("test/cert.pem"), SslFiletype::PEM)
+            .unwrap();
+        ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
+            .unwrap();
+        let ssl = Ssl::new(&ctx.build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+        stream.write_all(b"pong").unwrap();
+        stream.shutdown().unwrap();
+    });
+
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap().build();
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let stream = Ssl::new(&ctx).unwrap().connect(stream).unwrap();
+    let (mut reader, mut writer) = stream.split().unwrap();
+
+    // the server only responds once it receives the writer's message, so the read can't complete
+    // unless the blocked reader allows the writer to proceed
+    let (tx, rx) = mpsc::channel();
+    let reader = thread::spawn(move || {
+        tx.send(()).unwrap();
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf).unwrap();
+        buf
+    });
+    rx.recv().unwrap();
+    writer.write_all(b"ping").unwrap();
+
+    assert_eq!(reader.join().unwrap(), b"pong");
+    guard.join().unwrap();
+}
+
+#[test]
+fn vectored_io() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let guard = thread::spawn(move || {
         let stream = listener.accept().unwrap().0;
         let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
         ctx.set_certificate_file(&Path::new("test/cert.pem"), SslFiletype::PEM)
             .unwrap();
         ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
             .unwrap();
-        let mut ssl = Ssl::new(&ctx.build()).unwrap();
-        ssl.set_tmp_ecdh_callback(|_, _, _| {
-            CALLED_BACK.store(true, Ordering::SeqCst);
-            EcKey::from_curve_name(Nid::X9_62_PRIME256V1)
-        });
-        ssl.accept(stream).unwrap();
+        let ssl = Ssl::new(&ctx.build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+
+        let mut buf = [0; 11];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hello world");
+        assert_eq!(stream.write_vectored(&[&b"foo"[..], b"", b"bar"]).unwrap(), 6);
+        stream.write_all(b"baz").unwrap();
+        stream.shutdown().unwrap();
     });
 
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap().build();
     let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
-    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-    ctx.set_cipher_list("ECDH").unwrap();
-    let ssl = Ssl::new(&ctx.build()).unwrap();
-    ssl.connect(stream).unwrap();
+    let mut stream = Ssl::new(&ctx).unwrap().connect(stream).unwrap();
 
-    assert!(CALLED_BACK.load(Ordering::SeqCst));
-}
+    let n = stream.write_vectored(&[&b"hello"[..], b" ", b"world"]).unwrap();
+    assert_eq!(n, 11);
 
-#[test]
-fn idle_session() {
-    let ctx = SslContext::builder(SslMethod::tls()).unwrap().build();
-    let ssl = Ssl::new(&ctx).unwrap();
-    assert!(ssl.session().is_none());
-}
+    // both halves of the coalesced record are read at once
+    let (mut a, mut b) = ([0; 3], [0; 3]);
+    assert_eq!(stream.read_vectored(&mut [&mut a[..], &mut b[..]]).unwrap(), 6);
+    assert_eq!(&a, b"foo");
+    assert_eq!(&b, b"bar");
 
-#[test]
-#[cfg_attr(libressl250, ignore)]
-fn active_session() {
-    let connector = SslConnector::builder(SslMethod::tls()).unwrap().build();
+    let mut buf = Vec::with_capacity(10);
+    buf.push(b'>');
+    assert_eq!(stream.read_uninit(&mut buf).unwrap(), 3);
+    assert_eq!(buf, b">baz");
+    assert_eq!(stream.read_uninit(&mut Vec::new()).unwrap(), 0);
 
-    let s = TcpStream::connect("google.com:443").unwrap();
-    let socket = connector.connect("google.com", s).unwrap();
-    let session = socket.ssl().session().unwrap();
-    let len = session.master_key_len();
-    let mut buf = vec![0; len - 1];
-    let copied = session.master_key(&mut buf);
-    assert_eq!(copied, buf.len());
-    let mut buf = vec![0; len + 1];
-    let copied = session.master_key(&mut buf);
-    assert_eq!(copied, len);
+    guard.join().unwrap();
 }
 
 #[test]
-fn status_callbacks() {
-    static CALLED_BACK_SERVER: AtomicBool = ATOMIC_BOOL_INIT;
-    static CALLED_BACK_CLIENT: AtomicBool = ATOMIC_BOOL_INIT;
+fn session_store() {
+    let store = Arc::new(MemorySessionStore::new());
 
     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
     let port = listener.local_addr().unwrap().port();
 
+    let server_store = store.clone();
     let guard = thread::spawn(move || {
-        let stream = listener.accept().unwrap().0;
-        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-        ctx.set_certificate_file(&Path::new("test/cert.pem"), SslFiletype::PEM)
-            .unwrap();
-        ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
-            .unwrap();
-        ctx.set_status_callback(|ssl| {
-            CALLED_BACK_SERVER.store(true, Ordering::SeqCst);
-            let response = OcspResponse::create(OcspResponseStatus::UNAUTHORIZED, None).unwrap();
-            let response = response.to_der().unwrap();
-            ssl.set_ocsp_status(&response).unwrap();
-            Ok(true)
-        }).unwrap();
-        let ssl = Ssl::new(&ctx.build()).unwrap();
-        ssl.accept(stream).unwrap();
+        // each connection is accepted with a new context, as if by a different server
+        for _ in 0..2 {
+            let stream = listener.accept().unwrap().0;
+            let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+            ctx.set_certificate_file(&Path::new("test/cert.pem"), SslFiletype::PEM)
+                .unwrap();
+            ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
+                .unwrap();
+            ctx.set_session_id_context(b"foo").unwrap();
+            ctx.set_options(SslOptions::NO_TICKET);
+            ctx.set_session_store(server_store.clone());
+            let ssl = Ssl::new(&ctx.build()).unwrap();
+            let mut stream = ssl.accept(stream).unwrap();
+            stream.write_all(&[0]).unwrap();
+        }
     });
 
-    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let session = Arc::new(Mutex::new(None));
     let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-    ctx.set_status_callback(|ssl| {
-        CALLED_BACK_CLIENT.store(true, Ordering::SeqCst);
-        let response = OcspResponse::from_der(ssl.ocsp_status().unwrap()).unwrap();
-        assert_eq!(response.status(), OcspResponseStatus::UNAUTHORIZED);
-        Ok(true)
-    }).unwrap();
-    let mut ssl = Ssl::new(&ctx.build()).unwrap();
-    ssl.set_status_type(StatusType::OCSP).unwrap();
-    ssl.connect(stream).unwrap();
+    ctx.set_session_cache_mode(SslSessionCacheMode::CLIENT);
+    let new_session = session.clone();
+    ctx.set_new_session_callback(move |_, s| *new_session.lock().unwrap() = Some(s));
+    let ctx = ctx.build();
 
-    assert!(CALLED_BACK_SERVER.load(Ordering::SeqCst));
-    assert!(CALLED_BACK_CLIENT.load(Ordering::SeqCst));
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut stream = Ssl::new(&ctx).unwrap().connect(stream).unwrap();
+    // read 1 byte to make sure the session is received for TLSv1.3
+    let mut buf = [0];
+    stream.read_exact(&mut buf).unwrap();
+    assert!(!stream.ssl().session_reused());
+
+    let session = session.lock().unwrap().take().unwrap();
+    assert!(!store.is_empty());
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ssl = Ssl::new(&ctx).unwrap();
+    unsafe {
+        ssl.set_session(&session).unwrap();
+    }
+    let mut stream = ssl.connect(stream).unwrap();
+    stream.read_exact(&mut buf).unwrap();
+    assert!(stream.ssl().session_reused());
 
     guard.join().unwrap();
 }
 
 #[test]
-fn new_session_callback() {
+#[cfg(ossl110)]
+fn keylog_callback() {
     static CALLED_BACK: AtomicBool = ATOMIC_BOOL_INIT;
 
     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
@@ -1253,7 +2274,6 @@ fn new_session_callback() {
             .unwrap();
         ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
             .unwrap();
-        ctx.set_session_id_context(b"foo").unwrap();
         let ssl = Ssl::new(&ctx.build()).unwrap();
         let mut stream = ssl.accept(stream).unwrap();
         stream.write_all(&[0]).unwrap();
@@ -1261,11 +2281,13 @@ fn new_session_callback() {
 
     let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
     let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-    ctx.set_session_cache_mode(SslSessionCacheMode::CLIENT | SslSessionCacheMode::NO_INTERNAL);
-    ctx.set_new_session_callback(|_, _| CALLED_BACK.store(true, Ordering::SeqCst));
+    ctx.set_keylog_callback(|_, line| {
+        assert!(!line.ends_with('\n'));
+        assert!(line.split(' ').count() == 3);
+        CALLED_BACK.store(true, Ordering::SeqCst);
+    });
     let ssl = Ssl::new(&ctx.build()).unwrap();
     let mut stream = ssl.connect(stream).unwrap();
-    // read 1 byte to make sure the session is received for TLSv1.3
     let mut buf = [0];
     stream.read_exact(&mut buf).unwrap();
 
@@ -1315,7 +2337,6 @@ fn keying_export() {
 }
 
 #[test]
-#[cfg(any(ossl110))]
 fn no_version_overlap() {
     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
     let addr = listener.local_addr().unwrap();
@@ -1328,9 +2349,9 @@ fn no_version_overlap() {
         ctx.set_private_key_file(&Path::new("test/key.pem"), SslFiletype::PEM)
             .unwrap();
         ctx.set_max_proto_version(Some(SslVersion::TLS1_1)).unwrap();
-        #[cfg(ossl110g)]
+        #[cfg(any(ossl110g, ossl10x))]
         assert_eq!(ctx.min_proto_version(), None);
-        #[cfg(ossl110g)]
+        #[cfg(any(ossl110g, ossl10x))]
         assert_eq!(ctx.max_proto_version(), Some(SslVersion::TLS1_1));
         let ssl = Ssl::new(&ctx.build()).unwrap();
         ssl.accept(stream).unwrap_err();
@@ -1339,9 +2360,9 @@ fn no_version_overlap() {
     let stream = TcpStream::connect(addr).unwrap();
     let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
     ctx.set_min_proto_version(Some(SslVersion::TLS1_2)).unwrap();
-    #[cfg(ossl110g)]
+    #[cfg(any(ossl110g, ossl10x))]
     assert_eq!(ctx.min_proto_version(), Some(SslVersion::TLS1_2));
-    #[cfg(ossl110g)]
+    #[cfg(any(ossl110g, ossl10x))]
     assert_eq!(ctx.max_proto_version(), None);
     let ssl = Ssl::new(&ctx.build()).unwrap();
     ssl.connect(stream).unwrap_err();
@@ -1349,6 +2370,59 @@ fn no_version_overlap() {
     guard.join().unwrap();
 }
 
+#[test]
+fn proto_version_range() {
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_min_proto_version(Some(SslVersion::TLS1_1)).unwrap();
+    ctx.set_max_proto_version(Some(SslVersion::TLS1_1)).unwrap();
+    #[cfg(any(ossl110g, ossl10x))]
+    {
+        assert_eq!(ctx.min_proto_version(), Some(SslVersion::TLS1_1));
+        assert_eq!(ctx.max_proto_version(), Some(SslVersion::TLS1_1));
+    }
+    #[cfg(ossl10x)]
+    {
+        use ssl::SslOptions;
+
+        let options = ctx.options();
+        assert!(options.contains(SslOptions::NO_TLSV1 | SslOptions::NO_TLSV1_2));
+        assert!(!options.contains(SslOptions::NO_TLSV1_1));
+    }
+
+    ctx.set_max_proto_version(None).unwrap();
+    #[cfg(any(ossl110g, ossl10x))]
+    {
+        assert_eq!(ctx.min_proto_version(), Some(SslVersion::TLS1_1));
+        assert_eq!(ctx.max_proto_version(), None);
+    }
+
+    // TLSv1.3 can't be enabled through the protocol options
+    #[cfg(ossl10x)]
+    {
+        let tls1_3 = SslVersion(0x0304);
+        assert!(ctx.set_min_proto_version(Some(tls1_3)).is_err());
+        assert!(ctx.set_max_proto_version(Some(tls1_3)).is_err());
+        assert_eq!(ctx.min_proto_version(), Some(SslVersion::TLS1_1));
+        assert_eq!(ctx.max_proto_version(), None);
+    }
+
+    let mut ssl = Ssl::new(&ctx.build()).unwrap();
+    #[cfg(any(ossl110g, ossl10x))]
+    assert_eq!(ssl.min_proto_version(), Some(SslVersion::TLS1_1));
+    ssl.set_min_proto_version(Some(SslVersion::TLS1_2)).unwrap();
+    #[cfg(any(ossl110g, ossl10x))]
+    assert_eq!(ssl.min_proto_version(), Some(SslVersion::TLS1_2));
+    ssl.set_max_proto_version(Some(SslVersion::TLS1_2)).unwrap();
+    #[cfg(any(ossl110g, ossl10x))]
+    assert_eq!(ssl.max_proto_version(), Some(SslVersion::TLS1_2));
+    #[cfg(ossl10x)]
+    {
+        let tls1_3 = SslVersion(0x0304);
+        assert!(ssl.set_max_proto_version(Some(tls1_3)).is_err());
+        assert_eq!(ssl.max_proto_version(), Some(SslVersion::TLS1_2));
+    }
+}
+
 #[test]
 #[cfg(ossl111)]
 fn custom_extensions() {
@@ -1392,6 +2466,153 @@ fn custom_extensions() {
     assert!(FOUND_EXTENSION.load(Ordering::SeqCst));
 }
 
+#[test]
+#[cfg(ossl111)]
+fn custom_extension_response() {
+    use ssl::{ExtensionContext, SslAlert};
+
+    static FOUND_RESPONSE: AtomicBool = ATOMIC_BOOL_INIT;
+
+    let context = ExtensionContext::CLIENT_HELLO | ExtensionContext::TLS1_2_SERVER_HELLO
+        | ExtensionContext::TLS1_3_ENCRYPTED_EXTENSIONS;
+
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_certificate(&cert).unwrap();
+    ctx.set_private_key(&key).unwrap();
+    ctx.add_custom_ext(
+        12345,
+        context,
+        |_, _, _| Ok(Some(b"world")),
+        |_, _, data, _| {
+            if data == b"hello" {
+                Ok(())
+            } else {
+                Err(SslAlert::ILLEGAL_PARAMETER)
+            }
+        },
+    ).unwrap();
+    let server_ctx = ctx.build();
+
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.add_custom_ext(
+        12345,
+        context,
+        |_, _, _| Ok(Some(b"hello")),
+        |_, ectx, data, _| {
+            assert!(!ectx.contains(ExtensionContext::CLIENT_HELLO));
+            FOUND_RESPONSE.store(data == b"world", Ordering::SeqCst);
+            Ok(())
+        },
+    ).unwrap();
+    let client_ctx = ctx.build();
+
+    test_utils::handshake(
+        Ssl::new(&client_ctx).unwrap(),
+        Ssl::new(&server_ctx).unwrap(),
+    ).unwrap();
+    assert!(FOUND_RESPONSE.load(Ordering::SeqCst));
+
+    // a parse error aborts the handshake with the returned alert
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.add_custom_ext(
+        12345,
+        context,
+        |_, _, _| Ok(Some(b"goodbye")),
+        |_, _, _, _| Ok(()),
+    ).unwrap();
+    let client_ctx = ctx.build();
+
+    assert!(
+        test_utils::handshake(
+            Ssl::new(&client_ctx).unwrap(),
+            Ssl::new(&server_ctx).unwrap(),
+        ).is_err()
+    );
+}
+
+#[test]
+#[cfg(ossl111)]
+fn tls13_ciphersuites_and_groups() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_certificate(&cert).unwrap();
+    ctx.set_private_key(&key).unwrap();
+    ctx.set_groups_list("P-384").unwrap();
+    let server_ctx = ctx.build();
+
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_ciphersuites("TLS_CHACHA20_POLY1305_SHA256").unwrap();
+    assert!(ctx.set_groups_list("not-a-group").is_err());
+    // the server only supports P-384, so a HelloRetryRequest is needed
+    ctx.set_groups_list("X25519:P-384").unwrap();
+    let client_ctx = ctx.build();
+
+    let (client, _) = test_utils::handshake(
+        Ssl::new(&client_ctx).unwrap(),
+        Ssl::new(&server_ctx).unwrap(),
+    ).unwrap();
+    let cipher = client.ssl().current_cipher().unwrap();
+    assert_eq!(cipher.standard_name(), Some("TLS_CHACHA20_POLY1305_SHA256"));
+
+    let mut ssl = Ssl::new(&client_ctx).unwrap();
+    ssl.set_ciphersuites("TLS_AES_128_GCM_SHA256").unwrap();
+    let (client, _) = test_utils::handshake(ssl, Ssl::new(&server_ctx).unwrap()).unwrap();
+    let cipher = client.ssl().current_cipher().unwrap();
+    assert_eq!(cipher.standard_name(), Some("TLS_AES_128_GCM_SHA256"));
+}
+
+#[test]
+#[cfg(ossl111)]
+fn early_data() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_certificate(&cert).unwrap();
+    ctx.set_private_key(&key).unwrap();
+    ctx.set_max_early_data(1024).unwrap();
+    let server_ctx = ctx.build();
+    assert_eq!(server_ctx.max_early_data(), 1024);
+
+    let client_ctx = SslContext::builder(SslMethod::tls()).unwrap().build();
+
+    // establish a session to resume
+    let (mut client, mut server) = test_utils::handshake(
+        Ssl::new(&client_ctx).unwrap(),
+        Ssl::new(&server_ctx).unwrap(),
+    ).unwrap();
+    assert!(!client.ssl().early_data_accepted());
+    server.write_all(b"x").unwrap();
+    let mut buf = [0; 5];
+    client.read_exact(&mut buf[..1]).unwrap();
+    let session = client.ssl().session().unwrap().to_owned();
+
+    let mut ssl = Ssl::new(&client_ctx).unwrap();
+    unsafe { ssl.set_session(&session).unwrap() };
+    let (client_stream, server_stream) = test_utils::pipe();
+
+    let mut client = SslStreamBuilder::new(ssl, client_stream);
+    client.set_connect_state();
+    assert_eq!(client.write_early_data(b"hello").unwrap(), 5);
+
+    let mut server = SslStreamBuilder::new(Ssl::new(&server_ctx).unwrap(), server_stream);
+    server.set_accept_state();
+    let len = server.read_early_data(&mut buf).unwrap();
+    assert_eq!(&buf[..len], b"hello");
+
+    let mut client = client.connect().unwrap();
+    assert_eq!(server.read_early_data(&mut buf).unwrap(), 0);
+    let mut server = server.accept().unwrap();
+
+    assert!(client.ssl().early_data_accepted());
+    assert!(server.ssl().early_data_accepted());
+
+    client.write_all(b"world").unwrap();
+    server.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"world");
+}
+
 fn _check_kinds() {
     fn is_send<T: Send>() {}
     fn is_sync<T: Sync>() {}
@@ -1536,3 +2757,245 @@ fn stateless() {
     send(client_stream.get_mut(), server_stream.get_mut());
     hs(server_stream.handshake()).unwrap();
 }
+
+#[test]
+fn audit_modern() {
+    let ctx = SslAcceptor::mozilla_modern(SslMethod::tls())
+        .unwrap()
+        .build()
+        .into_context();
+    let audit = ctx.audit().unwrap();
+    assert!(audit.is_secure(), "{:?}", audit.findings());
+    assert!(audit.protocols().contains(&SslVersion::TLS1_2));
+    assert!(!audit.protocols().contains(&SslVersion::TLS1));
+    assert!(
+        audit
+            .ciphers()
+            .iter()
+            .any(|c| c == "ECDHE-RSA-AES128-GCM-SHA256")
+    );
+}
+
+#[test]
+fn audit_weak() {
+    use ssl::AuditCheck;
+
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_cipher_list("eNULL:aNULL").unwrap();
+    let audit = ctx.build().audit().unwrap();
+    assert!(!audit.is_secure());
+
+    let checks = audit
+        .findings()
+        .iter()
+        .map(|f| f.check())
+        .collect::<Vec<_>>();
+    assert!(checks.contains(&AuditCheck::NullCipher));
+    assert!(checks.contains(&AuditCheck::AnonymousCipher));
+    assert!(checks.contains(&AuditCheck::DeprecatedProtocol));
+}
+
+#[test]
+#[cfg(ossl110)]
+fn security_level() {
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_security_level(2);
+    let ctx = ctx.build();
+    assert_eq!(ctx.security_level(), 2);
+
+    let mut ssl = Ssl::new(&ctx).unwrap();
+    assert_eq!(ssl.security_level(), 2);
+    ssl.set_security_level(3);
+    assert_eq!(ssl.security_level(), 3);
+    assert_eq!(ctx.security_level(), 2);
+}
+
+#[test]
+fn cipher_lists() {
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_cipher_list("AES128-SHA:ECDHE-RSA-AES128-GCM-SHA256")
+        .unwrap();
+    ctx.set_max_proto_version(Some(SslVersion::TLS1_1)).unwrap();
+    let ctx = ctx.build();
+
+    #[cfg(ossl110)]
+    {
+        let names = ctx.ciphers()
+            .unwrap()
+            .iter()
+            .map(|c| c.name())
+            .collect::<Vec<_>>();
+        assert!(names.contains(&"AES128-SHA"));
+        assert!(names.contains(&"ECDHE-RSA-AES128-GCM-SHA256"));
+    }
+
+    let ssl = Ssl::new(&ctx).unwrap();
+    let names = ssl.ciphers()
+        .unwrap()
+        .iter()
+        .map(|c| c.name())
+        .collect::<Vec<_>>();
+    assert!(names.contains(&"AES128-SHA"));
+    assert!(names.contains(&"ECDHE-RSA-AES128-GCM-SHA256"));
+
+    #[cfg(any(ossl102, ossl110))]
+    {
+        let supported = ssl.supported_ciphers()
+            .unwrap()
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect::<Vec<_>>();
+        assert!(supported.iter().any(|c| c == "AES128-SHA"));
+        assert!(supported.iter().all(|c| names.contains(&&**c)));
+        // GCM ciphers require TLS 1.2
+        #[cfg(ossl110)]
+        assert!(!supported.iter().any(|c| c == "ECDHE-RSA-AES128-GCM-SHA256"));
+    }
+}
+
+#[test]
+#[cfg(ossl111)]
+fn client_hello() {
+    use ssl::{ClientHelloResponse, ErrorCode};
+
+    static CALLED_BACK: AtomicBool = ATOMIC_BOOL_INIT;
+    static RETRIED: AtomicBool = ATOMIC_BOOL_INIT;
+
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_certificate(&cert).unwrap();
+    ctx.set_private_key(&key).unwrap();
+    ctx.set_client_hello_callback(|ssl, _| {
+        // the handshake is paused the first time the callback runs
+        if !RETRIED.swap(true, Ordering::SeqCst) {
+            return Ok(ClientHelloResponse::RETRY);
+        }
+
+        assert!(!ssl.client_hello_isv2());
+        assert_eq!(ssl.client_hello_legacy_version(), Some(SslVersion::TLS1_2));
+        assert_eq!(ssl.client_hello_random().unwrap().len(), 32);
+        assert_eq!(ssl.client_hello_servername(), Some("foobar.com"));
+        assert_eq!(
+            ssl.client_hello_alpn_protocols(),
+            Some(vec![&b"spdy/1"[..], &b"http/1.1"[..]])
+        );
+        assert_eq!(ssl.client_hello_ciphers().unwrap().len() % 2, 0);
+        let types = ssl.client_hello_extension_types().unwrap();
+        assert!(types.contains(&0));
+        assert!(types.contains(&16));
+        assert!(ssl.client_hello_ext(0).is_some());
+        assert!(ssl.client_hello_ext(12345).is_none());
+        CALLED_BACK.store(true, Ordering::SeqCst);
+        Ok(ClientHelloResponse::SUCCESS)
+    });
+    let server_ctx = ctx.build();
+
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_alpn_protos(b"\x06spdy/1\x08http/1.1").unwrap();
+    let mut client = Ssl::new(&ctx.build()).unwrap();
+    client.set_hostname("foobar.com").unwrap();
+
+    let (client_stream, server_stream) = test_utils::pipe();
+    let _client = match client.connect(client_stream) {
+        Err(HandshakeError::WouldBlock(mid)) => mid,
+        _ => panic!("expected the client to block"),
+    };
+    let server = match Ssl::new(&server_ctx).unwrap().accept(server_stream) {
+        Err(HandshakeError::WouldBlock(mid)) => mid,
+        _ => panic!("expected the server to pause"),
+    };
+    assert_eq!(server.error().code(), ErrorCode::WANT_CLIENT_HELLO_CB);
+    assert!(!CALLED_BACK.load(Ordering::SeqCst));
+
+    match server.handshake() {
+        Err(HandshakeError::WouldBlock(mid)) => {
+            assert_eq!(mid.error().code(), ErrorCode::WANT_READ)
+        }
+        _ => panic!("expected the server to wait for the client"),
+    }
+    assert!(CALLED_BACK.load(Ordering::SeqCst));
+}
+
+// Runs a handshake which is expected to fail on both sides, returning the kinds of the client and
+// server errors.
+fn failed_handshake_kinds(client: Ssl, server: Ssl) -> (ErrorKind, ErrorKind) {
+    fn kind(r: Result<SslStream<TcpStream>, HandshakeError<TcpStream>>) -> ErrorKind {
+        match r {
+            Err(HandshakeError::Failure(mid)) => mid.error().ssl_error().unwrap().kind(),
+            Err(e) => panic!("unexpected error {:?}", e),
+            Ok(_) => panic!("the handshake succeeded"),
+        }
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let guard = thread::spawn(move || {
+        let stream = listener.accept().unwrap().0;
+        kind(server.accept(stream))
+    });
+
+    let stream = TcpStream::connect(addr).unwrap();
+    let client = kind(client.connect(stream));
+    (client, guard.join().unwrap())
+}
+
+#[test]
+fn error_kind_unknown_ca() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+    let connector = SslConnector::builder(SslMethod::tls()).unwrap().build();
+
+    let client = connector.configure().unwrap().into_ssl("foobar.com").unwrap();
+    let server = Ssl::new(acceptor.context()).unwrap();
+    let (client, server) = failed_handshake_kinds(client, server);
+    assert_eq!(client, ErrorKind::CertificateVerifyFailed);
+    assert_eq!(server, ErrorKind::UnknownCa);
+}
+
+#[test]
+fn error_kind_certificate_expired() {
+    // the certificate expires as soon as it is created
+    let (cert, key) = test_utils::IdentityBuilder::new("foobar.com")
+        .days(0)
+        .build()
+        .unwrap();
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+    let connector = test_utils::connector(&cert).unwrap();
+
+    let client = connector.configure().unwrap().into_ssl("foobar.com").unwrap();
+    let server = Ssl::new(acceptor.context()).unwrap();
+    let (client, server) = failed_handshake_kinds(client, server);
+    assert_eq!(client, ErrorKind::CertificateVerifyFailed);
+    assert_eq!(server, ErrorKind::CertificateExpired);
+}
+
+#[test]
+#[cfg(ossl110)]
+fn error_kind_bad_certificate() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+    let connector = test_utils::connector(&cert).unwrap();
+
+    // a hostname mismatch is reported to the peer as a bad certificate
+    let client = connector.configure().unwrap().into_ssl("bazquux.com").unwrap();
+    let server = Ssl::new(acceptor.context()).unwrap();
+    let (client, server) = failed_handshake_kinds(client, server);
+    assert_eq!(client, ErrorKind::CertificateVerifyFailed);
+    assert_eq!(server, ErrorKind::BadCertificate);
+}
+
+#[test]
+fn error_kind_handshake_failure() {
+    let (cert, key) = test_utils::generate_identity("foobar.com").unwrap();
+    let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+    let connector = test_utils::connector(&cert).unwrap();
+
+    // TLSv1.2 servers reject a missing client certificate with a handshake failure alert
+    let mut client = connector.configure().unwrap().into_ssl("foobar.com").unwrap();
+    client.set_max_proto_version(Some(SslVersion::TLS1_2)).unwrap();
+    let mut server = Ssl::new(acceptor.context()).unwrap();
+    server.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+    let (client, _) = failed_handshake_kinds(client, server);
+    assert_eq!(client, ErrorKind::HandshakeFailure);
+}
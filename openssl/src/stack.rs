@@ -9,7 +9,7 @@ use ffi;
 
 use {cvt, cvt_p};
 use error::ErrorStack;
-use std::ops::{Deref, DerefMut, Index, IndexMut};
+use std::ops::{Deref, DerefMut, Index, IndexMut, Range};
 
 #[cfg(ossl10x)]
 use ffi::{sk_pop as OPENSSL_sk_pop, sk_free as OPENSSL_sk_free, sk_num as OPENSSL_sk_num,
@@ -34,6 +34,7 @@ pub trait Stackable: ForeignType {
 pub struct Stack<T: Stackable>(*mut T::StackType);
 
 impl<T: Stackable> Stack<T> {
+    /// Creates a new, empty stack.
     pub fn new() -> Result<Stack<T>, ErrorStack> {
         unsafe {
             ffi::init();
@@ -59,7 +60,7 @@ impl<T: Stackable> iter::IntoIterator for Stack<T> {
     fn into_iter(self) -> IntoIter<T> {
         let it = IntoIter {
             stack: self.0,
-            idx: 0,
+            idxs: 0..self.len() as c_int,
         };
         mem::forget(self);
         it
@@ -112,15 +113,10 @@ impl<T: Stackable> DerefMut for Stack<T> {
     }
 }
 
+/// An iterator over the owned contents of a stack.
 pub struct IntoIter<T: Stackable> {
     stack: *mut T::StackType,
-    idx: c_int,
-}
-
-impl<T: Stackable> IntoIter<T> {
-    fn stack_len(&self) -> c_int {
-        unsafe { OPENSSL_sk_num(self.stack as *mut _) }
-    }
+    idxs: Range<c_int>,
 }
 
 impl<T: Stackable> Drop for IntoIter<T> {
@@ -137,19 +133,24 @@ impl<T: Stackable> Iterator for IntoIter<T> {
 
     fn next(&mut self) -> Option<T> {
         unsafe {
-            if self.idx == self.stack_len() {
-                None
-            } else {
-                let ptr = OPENSSL_sk_value(self.stack as *mut _, self.idx);
-                self.idx += 1;
-                Some(T::from_ptr(ptr as *mut _))
-            }
+            self.idxs
+                .next()
+                .map(|i| T::from_ptr(OPENSSL_sk_value(self.stack as *mut _, i) as *mut _))
         }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let size = (self.stack_len() - self.idx) as usize;
-        (size, Some(size))
+        self.idxs.size_hint()
+    }
+}
+
+impl<T: Stackable> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        unsafe {
+            self.idxs
+                .next_back()
+                .map(|i| T::from_ptr(OPENSSL_sk_value(self.stack as *mut _, i) as *mut _))
+        }
     }
 }
 
@@ -171,6 +172,12 @@ impl<T: Stackable> StackRef<T> {
         unsafe { OPENSSL_sk_num(self.as_stack()) as usize }
     }
 
+    /// Determines if the stack is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over references to the stack's contents.
     pub fn iter(&self) -> Iter<T> {
         // Unfortunately we can't simply convert the stack into a
         // slice and use that because OpenSSL 1.1.0 doesn't directly
@@ -180,15 +187,14 @@ impl<T: Stackable> StackRef<T> {
 
         Iter {
             stack: self,
-            pos: 0,
+            idxs: 0..self.len(),
         }
     }
 
+    /// Returns an iterator over mutable references to the stack's contents.
     pub fn iter_mut(&mut self) -> IterMut<T> {
-        IterMut {
-            stack: self,
-            pos: 0,
-        }
+        let idxs = 0..self.len();
+        IterMut { stack: self, idxs }
     }
 
     /// Returns a reference to the element at the given index in the
@@ -299,26 +305,32 @@ where
     T: 'a,
 {
     stack: &'a StackRef<T>,
-    pos: usize,
+    idxs: Range<usize>,
 }
 
 impl<'a, T: Stackable> iter::Iterator for Iter<'a, T> {
     type Item = &'a T::Ref;
 
     fn next(&mut self) -> Option<&'a T::Ref> {
-        let n = self.stack.get(self.pos);
-
-        if n.is_some() {
-            self.pos += 1;
+        unsafe {
+            self.idxs
+                .next()
+                .map(|i| T::Ref::from_ptr(self.stack._get(i)))
         }
-
-        n
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let rem = self.stack.len() - self.pos;
+        self.idxs.size_hint()
+    }
+}
 
-        (rem, Some(rem))
+impl<'a, T: Stackable> iter::DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T::Ref> {
+        unsafe {
+            self.idxs
+                .next_back()
+                .map(|i| T::Ref::from_ptr(self.stack._get(i)))
+        }
     }
 }
 
@@ -327,34 +339,95 @@ impl<'a, T: Stackable> iter::ExactSizeIterator for Iter<'a, T> {}
 /// A mutable iterator over the stack's contents.
 pub struct IterMut<'a, T: Stackable + 'a> {
     stack: &'a mut StackRef<T>,
-    pos: usize,
+    idxs: Range<usize>,
 }
 
 impl<'a, T: Stackable> iter::Iterator for IterMut<'a, T> {
     type Item = &'a mut T::Ref;
 
     fn next(&mut self) -> Option<&'a mut T::Ref> {
-        if self.pos >= self.stack.len() {
-            None
-        } else {
-            // Rust won't allow us to get a mutable reference into
-            // `stack` in this situation since it can't statically
-            // guarantee that we won't return several references to
-            // the same object, so we have to use unsafe code for
-            // mutable iterators.
-            let n = unsafe { Some(T::Ref::from_ptr_mut(self.stack._get(self.pos))) };
-
-            self.pos += 1;
-
-            n
+        // Rust won't allow us to get a mutable reference into
+        // `stack` in this situation since it can't statically
+        // guarantee that we won't return several references to
+        // the same object, so we have to use unsafe code for
+        // mutable iterators.
+        unsafe {
+            self.idxs
+                .next()
+                .map(|i| T::Ref::from_ptr_mut(self.stack._get(i)))
         }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let rem = self.stack.len() - self.pos;
+        self.idxs.size_hint()
+    }
+}
 
-        (rem, Some(rem))
+impl<'a, T: Stackable> iter::DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T::Ref> {
+        unsafe {
+            self.idxs
+                .next_back()
+                .map(|i| T::Ref::from_ptr_mut(self.stack._get(i)))
+        }
     }
 }
 
 impl<'a, T: Stackable> iter::ExactSizeIterator for IterMut<'a, T> {}
+
+#[cfg(test)]
+mod test {
+    use x509::{X509, X509Ref};
+    use super::*;
+
+    fn stack() -> Stack<X509> {
+        let cert = include_bytes!("../test/cert.pem");
+        let root = include_bytes!("../test/root-ca.pem");
+
+        let mut stack = Stack::new().unwrap();
+        stack.push(X509::from_pem(cert).unwrap()).unwrap();
+        stack.push(X509::from_pem(root).unwrap()).unwrap();
+        stack
+    }
+
+    fn der(cert: &X509Ref) -> Vec<u8> {
+        cert.to_der().unwrap()
+    }
+
+    #[test]
+    fn iter() {
+        let mut stack = stack();
+        assert_eq!(stack.len(), 2);
+        assert!(!stack.is_empty());
+
+        let certs = stack.iter().map(der).collect::<Vec<_>>();
+        assert_eq!(stack.iter().len(), 2);
+        assert_eq!(der(&stack[1]), certs[1]);
+
+        let reversed = stack.iter().rev().map(der).collect::<Vec<_>>();
+        assert_eq!(reversed, [certs[1].clone(), certs[0].clone()]);
+        assert_eq!(stack.iter_mut().rev().count(), 2);
+        assert_eq!((&stack).into_iter().count(), 2);
+
+        let last = stack.pop().unwrap();
+        assert_eq!(der(&last), certs[1]);
+        assert_eq!(stack.len(), 1);
+    }
+
+    #[test]
+    fn into_iter() {
+        let stack = stack();
+        let certs = stack.iter().map(der).collect::<Vec<_>>();
+
+        let mut iter = stack.into_iter();
+        assert_eq!(iter.len(), 2);
+        let last = iter.next_back().unwrap();
+        assert_eq!(der(&last), certs[1]);
+        assert_eq!(iter.len(), 1);
+        drop(iter);
+
+        let empty = Stack::<X509>::new().unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.into_iter().count(), 0);
+    }
+}
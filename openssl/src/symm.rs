@@ -52,13 +52,20 @@
 //! println!("Decrypted: '{}'", output_string);
 //! ```
 
+#[allow(deprecated, unused_imports)]
+use std::ascii::AsciiExt;
 use std::cmp;
+use std::collections::HashSet;
+use std::error;
+use std::ffi::CStr;
+use std::fmt;
 use std::ptr;
 use libc::c_int;
 use ffi;
 
 use {cvt, cvt_p};
 use error::ErrorStack;
+use memcmp;
 use nid::Nid;
 
 #[derive(Copy, Clone)]
@@ -82,6 +89,7 @@ impl Cipher {
     ///
     /// [`EVP_get_cipherbynid`]: https://www.openssl.org/docs/man1.0.2/crypto/EVP_get_cipherbyname.html
     pub fn from_nid(nid: Nid) -> Option<Cipher> {
+        ffi::init();
         let ptr = unsafe { ffi::EVP_get_cipherbyname(ffi::OBJ_nid2sn(nid.as_raw())) };
         if ptr.is_null() {
             None
@@ -210,6 +218,57 @@ impl Cipher {
         unsafe { Cipher(ffi::EVP_chacha20_poly1305()) }
     }
 
+    /// Requires OpenSSL 1.1.0 or newer.
+    #[cfg(all(ossl110, not(osslconf = "OPENSSL_NO_OCB")))]
+    pub fn aes_128_ocb() -> Cipher {
+        unsafe { Cipher(ffi::EVP_aes_128_ocb()) }
+    }
+
+    /// Requires OpenSSL 1.1.0 or newer.
+    #[cfg(all(ossl110, not(osslconf = "OPENSSL_NO_OCB")))]
+    pub fn aes_256_ocb() -> Cipher {
+        unsafe { Cipher(ffi::EVP_aes_256_ocb()) }
+    }
+
+    #[cfg(not(osslconf = "OPENSSL_NO_CAMELLIA"))]
+    pub fn camellia_128_cbc() -> Cipher {
+        unsafe { Cipher(ffi::EVP_camellia_128_cbc()) }
+    }
+
+    #[cfg(not(osslconf = "OPENSSL_NO_CAMELLIA"))]
+    pub fn camellia_192_cbc() -> Cipher {
+        unsafe { Cipher(ffi::EVP_camellia_192_cbc()) }
+    }
+
+    #[cfg(not(osslconf = "OPENSSL_NO_CAMELLIA"))]
+    pub fn camellia_256_cbc() -> Cipher {
+        unsafe { Cipher(ffi::EVP_camellia_256_cbc()) }
+    }
+
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(all(ossl111, not(osslconf = "OPENSSL_NO_ARIA")))]
+    pub fn aria_128_cbc() -> Cipher {
+        unsafe { Cipher(ffi::EVP_aria_128_cbc()) }
+    }
+
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(all(ossl111, not(osslconf = "OPENSSL_NO_ARIA")))]
+    pub fn aria_128_gcm() -> Cipher {
+        unsafe { Cipher(ffi::EVP_aria_128_gcm()) }
+    }
+
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(all(ossl111, not(osslconf = "OPENSSL_NO_ARIA")))]
+    pub fn aria_256_cbc() -> Cipher {
+        unsafe { Cipher(ffi::EVP_aria_256_cbc()) }
+    }
+
+    /// Requires OpenSSL 1.1.1 or newer.
+    #[cfg(all(ossl111, not(osslconf = "OPENSSL_NO_ARIA")))]
+    pub fn aria_256_gcm() -> Cipher {
+        unsafe { Cipher(ffi::EVP_aria_256_gcm()) }
+    }
+
     pub unsafe fn from_ptr(ptr: *const ffi::EVP_CIPHER) -> Cipher {
         Cipher(ptr)
     }
@@ -245,6 +304,31 @@ impl Cipher {
         unsafe { EVP_CIPHER_block_size(self.0) as usize }
     }
 
+    /// Returns the `Nid` of the cipher.
+    ///
+    /// This corresponds to [`EVP_CIPHER_nid`].
+    ///
+    /// [`EVP_CIPHER_nid`]: https://www.openssl.org/docs/man1.1.0/crypto/EVP_CIPHER_nid.html
+    pub fn nid(&self) -> Nid {
+        unsafe { Nid::from_raw(EVP_CIPHER_nid(self.0)) }
+    }
+
+    /// Determines if OpenSSL will use AES-NI instructions for this cipher on the current CPU.
+    ///
+    /// This inspects the CPU capability vector OpenSSL populates at startup, so it reflects any
+    /// masking applied through the `OPENSSL_ia32cap` environment variable. It always returns
+    /// `false` for non-AES ciphers, on non-x86 targets, and with LibreSSL.
+    pub fn is_hardware_accelerated(&self) -> bool {
+        ffi::init();
+
+        let name = unsafe { ffi::OBJ_nid2sn(self.nid().as_raw()) };
+        if name.is_null() {
+            return false;
+        }
+        let name = unsafe { CStr::from_ptr(name) }.to_string_lossy().to_ascii_lowercase();
+        name.contains("aes") && aesni_available()
+    }
+
     /// Determines whether the cipher is using CCM mode
     fn is_ccm(&self) -> bool {
         // NOTE: OpenSSL returns pointers to static structs, which makes this work as expected
@@ -259,6 +343,12 @@ unsafe impl Send for Cipher {}
 ///
 /// Padding is enabled by default.
 ///
+/// Data can be processed incrementally with `update`, so inputs never need to be held in memory
+/// in their entirety. With AEAD ciphers such as AES GCM, additional authenticated data is provided
+/// with `aad_update` before any calls to `update`. When encrypting, the tag is retrieved with
+/// `get_tag` after `finalize`. When decrypting, it is provided with `set_tag` before `finalize`,
+/// which will fail if the tag does not match.
+///
 /// # Examples
 ///
 /// Encrypt some plaintext in chunks, then decrypt the ciphertext back into plaintext, in AES 128
@@ -718,8 +808,227 @@ pub fn decrypt_aead(
     Ok(out)
 }
 
+/// Prevents a nonce from being used more than once with a key.
+///
+/// Encrypting two messages with the same key and nonce breaks the security of most stream and
+/// AEAD ciphers. With AES GCM, for example, it reveals the XOR of the plaintexts and allows tags
+/// to be forged. A `NonceGuard` holds a key and records each nonce used to encrypt with it,
+/// returning an error if one is used again.
+///
+/// Every nonce used is retained for the lifetime of the guard, so a guard should not be used to
+/// encrypt an unbounded number of messages. A `NonceCounter` can instead be used to generate
+/// nonces which are never repeated.
+///
+/// # Examples
+///
+/// ```
+/// use openssl::symm::{Cipher, NonceCounter, NonceGuard};
+///
+/// let key = b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0A\x0B\x0C\x0D\x0E\x0F";
+/// let mut guard = NonceGuard::new(Cipher::aes_128_gcm(), key);
+/// let mut counter = NonceCounter::new(b"\x00\x00\x00\x01", 12);
+///
+/// let mut tag = [0; 16];
+/// let nonce = counter.next_nonce().unwrap();
+/// guard.encrypt_aead(&nonce, b"", b"hello world", &mut tag).unwrap();
+///
+/// // encrypting again with the same nonce fails
+/// assert!(guard.encrypt_aead(&nonce, b"", b"hello world", &mut tag).is_err());
+/// ```
+pub struct NonceGuard {
+    cipher: Cipher,
+    key: Vec<u8>,
+    used: HashSet<Vec<u8>>,
+}
+
+impl NonceGuard {
+    /// Creates a guard for encrypting with `cipher` and `key`.
+    pub fn new(cipher: Cipher, key: &[u8]) -> NonceGuard {
+        NonceGuard {
+            cipher,
+            key: key.to_vec(),
+            used: HashSet::new(),
+        }
+    }
+
+    /// Returns a `Crypter` which encrypts with the guard's key and `nonce`.
+    ///
+    /// Returns an error if `nonce` has already been used with this guard.
+    pub fn encrypter(&mut self, nonce: &[u8]) -> Result<Crypter, NonceError> {
+        if self.used.contains(nonce) {
+            return Err(NonceError::Reused);
+        }
+        let crypter = Crypter::new(self.cipher, Mode::Encrypt, &self.key, Some(nonce))?;
+        self.used.insert(nonce.to_vec());
+        Ok(crypter)
+    }
+
+    /// Like `encrypt_aead`, but with the guard's cipher and key.
+    ///
+    /// Returns an error if `nonce` has already been used with this guard.
+    pub fn encrypt_aead(
+        &mut self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &[u8],
+        tag: &mut [u8],
+    ) -> Result<Vec<u8>, NonceError> {
+        if self.used.contains(nonce) {
+            return Err(NonceError::Reused);
+        }
+        // the nonce is recorded even if encryption fails, since it may already have been applied
+        self.used.insert(nonce.to_vec());
+        encrypt_aead(self.cipher, &self.key, Some(nonce), aad, data, tag).map_err(NonceError::Ssl)
+    }
+
+    /// Returns the number of nonces which have been used with this guard.
+    pub fn len(&self) -> usize {
+        self.used.len()
+    }
+
+    /// Determines if no nonces have been used with this guard.
+    pub fn is_empty(&self) -> bool {
+        self.used.is_empty()
+    }
+}
+
+impl Drop for NonceGuard {
+    fn drop(&mut self) {
+        memcmp::cleanse(&mut self.key);
+    }
+}
+
+/// Generates nonces from a fixed prefix followed by a big-endian counter.
+///
+/// This is the deterministic construction recommended for AES GCM by NIST SP 800-38D. The prefix
+/// should be unique to each party encrypting with a key, and the counter ensures that no nonce is
+/// generated twice. Once the counter is exhausted, no more nonces are generated and the key must
+/// be replaced.
+pub struct NonceCounter {
+    prefix: Vec<u8>,
+    counter_len: usize,
+    next: u64,
+    exhausted: bool,
+}
+
+impl NonceCounter {
+    /// Creates a counter generating `len` byte nonces starting with `prefix`.
+    ///
+    /// The counter occupies the remaining `len - prefix.len()` bytes of each nonce.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless the counter occupies between 1 and 8 bytes.
+    pub fn new(prefix: &[u8], len: usize) -> NonceCounter {
+        assert!(prefix.len() < len, "the nonce must be longer than its prefix");
+        let counter_len = len - prefix.len();
+        assert!(counter_len <= 8, "the counter must not be longer than 8 bytes");
+
+        NonceCounter {
+            prefix: prefix.to_vec(),
+            counter_len,
+            next: 0,
+            exhausted: false,
+        }
+    }
+
+    /// Returns the next nonce.
+    ///
+    /// Returns an error once every value of the counter has been used.
+    pub fn next_nonce(&mut self) -> Result<Vec<u8>, NonceError> {
+        if self.exhausted {
+            return Err(NonceError::Exhausted);
+        }
+
+        let value = self.next;
+        if value == self.max() {
+            self.exhausted = true;
+        } else {
+            self.next += 1;
+        }
+
+        let mut nonce = self.prefix.clone();
+        for i in (0..self.counter_len).rev() {
+            nonce.push((value >> (i * 8)) as u8);
+        }
+        Ok(nonce)
+    }
+
+    /// Returns the number of nonces which have been generated.
+    pub fn count(&self) -> u64 {
+        // the count of a full 8 byte counter does not fit in a u64 once exhausted
+        if self.exhausted {
+            self.max().saturating_add(1)
+        } else {
+            self.next
+        }
+    }
+
+    fn max(&self) -> u64 {
+        if self.counter_len == 8 {
+            u64::max_value()
+        } else {
+            (1 << (self.counter_len * 8)) - 1
+        }
+    }
+}
+
+/// An error returned by a `NonceGuard` or `NonceCounter`.
+#[derive(Debug)]
+pub enum NonceError {
+    /// The nonce has already been used with the key.
+    Reused,
+    /// Every nonce a counter can generate has been used.
+    Exhausted,
+    /// The cipher context could not be initialized, or encryption failed.
+    Ssl(ErrorStack),
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl From<ErrorStack> for NonceError {
+    fn from(e: ErrorStack) -> NonceError {
+        NonceError::Ssl(e)
+    }
+}
+
+impl fmt::Display for NonceError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NonceError::Reused => fmt.write_str("the nonce has already been used with this key"),
+            NonceError::Exhausted => fmt.write_str("the nonce counter is exhausted"),
+            NonceError::Ssl(ref e) => fmt::Display::fmt(e, fmt),
+            NonceError::__Nonexhaustive => unreachable!(),
+        }
+    }
+}
+
+impl error::Error for NonceError {
+    fn description(&self) -> &str {
+        "a nonce misuse error"
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            NonceError::Ssl(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(libressl)))]
+fn aesni_available() -> bool {
+    // AES-NI is bit 57 of the 64-bit capability vector, i.e. bit 25 of its second word.
+    unsafe { *ffi::OPENSSL_ia32cap_loc().offset(1) & (1 << 25) != 0 }
+}
+
+#[cfg(not(all(any(target_arch = "x86", target_arch = "x86_64"), not(libressl))))]
+fn aesni_available() -> bool {
+    false
+}
+
 #[cfg(ossl110)]
-use ffi::{EVP_CIPHER_block_size, EVP_CIPHER_iv_length, EVP_CIPHER_key_length};
+use ffi::{EVP_CIPHER_block_size, EVP_CIPHER_iv_length, EVP_CIPHER_key_length, EVP_CIPHER_nid};
 
 #[cfg(ossl10x)]
 #[allow(bad_style)]
@@ -738,6 +1047,10 @@ mod compat {
     pub unsafe fn EVP_CIPHER_key_length(ptr: *const EVP_CIPHER) -> c_int {
         (*ptr).key_len
     }
+
+    pub unsafe fn EVP_CIPHER_nid(ptr: *const EVP_CIPHER) -> c_int {
+        (*ptr).nid
+    }
 }
 #[cfg(ossl10x)]
 use self::compat::*;
@@ -1098,6 +1411,63 @@ mod tests {
         assert_eq!(pt, hex::encode(out));
     }
 
+    #[test]
+    fn test_aes128_gcm_stream() {
+        let key = Vec::from_hex("0e00c76561d2bd9b40c3c15427e2b08f").unwrap();
+        let iv = Vec::from_hex(
+            "492cadaccd3ca3fbc9cf9f06eb3325c4e159850b0dbe98199b89b7af528806610b6f63998e1eae80c348e7\
+             4cbb921d8326631631fc6a5d304f39166daf7ea15fa1977f101819adb510b50fe9932e12c5a85aa3fd1e73\
+             d8d760af218be829903a77c63359d75edd91b4f6ed5465a72662f5055999e059e7654a8edc921aa0d496",
+        ).unwrap();
+        let pt = Vec::from_hex(
+            "fef03c2d7fb15bf0d2df18007d99f967c878ad59359034f7bb2c19af120685d78e32f6b8b83b032019956c\
+             a9c0195721476b85",
+        ).unwrap();
+        let aad = Vec::from_hex(
+            "d8f1163d8c840292a2b2dacf4ac7c36aff8733f18fabb4fa5594544125e03d1e6e5d6d0fd61656c8d8f327\
+             c92839ae5539bb469c9257f109ebff85aad7bd220fdaa95c022dbd0c7bb2d878ad504122c943045d3c5eba\
+             8f1f56c0",
+        ).unwrap();
+        let ct = "4f6cf471be7cbd2575cd5a1747aea8fe9dea83e51936beac3e68f66206922060c697ffa7af80ad6bb68f2c\
+             f4fc97416ee52abe";
+        let tag = "e20b6655";
+
+        let cipher = Cipher::aes_128_gcm();
+        let mut c = Crypter::new(cipher, Mode::Encrypt, &key, Some(&iv)).unwrap();
+        c.aad_update(&aad[..10]).unwrap();
+        c.aad_update(&aad[10..]).unwrap();
+        let mut out = vec![0; pt.len() + cipher.block_size()];
+        let mut count = 0;
+        for chunk in pt.chunks(7) {
+            count += c.update(chunk, &mut out[count..]).unwrap();
+        }
+        count += c.finalize(&mut out[count..]).unwrap();
+        out.truncate(count);
+        let mut actual_tag = [0; 4];
+        c.get_tag(&mut actual_tag).unwrap();
+        assert_eq!(ct, hex::encode(&out));
+        assert_eq!(tag, hex::encode(actual_tag));
+
+        let mut c = Crypter::new(cipher, Mode::Decrypt, &key, Some(&iv)).unwrap();
+        c.set_tag(&actual_tag).unwrap();
+        c.aad_update(&aad).unwrap();
+        let mut plain = vec![0; out.len() + cipher.block_size()];
+        let mut count = 0;
+        for chunk in out.chunks(5) {
+            count += c.update(chunk, &mut plain[count..]).unwrap();
+        }
+        count += c.finalize(&mut plain[count..]).unwrap();
+        plain.truncate(count);
+        assert_eq!(pt, plain);
+
+        let mut c = Crypter::new(cipher, Mode::Decrypt, &key, Some(&iv)).unwrap();
+        c.set_tag(&[0; 4]).unwrap();
+        c.aad_update(&aad).unwrap();
+        let mut plain = vec![0; out.len() + cipher.block_size()];
+        let count = c.update(&out, &mut plain).unwrap();
+        assert!(c.finalize(&mut plain[count..]).is_err());
+    }
+
     #[test]
     fn test_aes128_ccm() {
         let key = "3ee186594f110fb788a8bf8aa8be5d4a";
@@ -1206,6 +1576,72 @@ mod tests {
         assert!(out.is_err());
     }
 
+    #[test]
+    #[cfg(not(osslconf = "OPENSSL_NO_CAMELLIA"))]
+    fn test_camellia_128_cbc() {
+        // RFC 3713 test vector, a single block with a zero IV
+        let key = "0123456789abcdeffedcba9876543210";
+        let iv = "00000000000000000000000000000000";
+        let pt = "0123456789abcdeffedcba9876543210";
+        let ct = "67673138549669730857065648eabe43";
+
+        cipher_test_nopad(Cipher::camellia_128_cbc(), pt, ct, key, iv);
+    }
+
+    #[test]
+    #[cfg(all(ossl111, not(osslconf = "OPENSSL_NO_ARIA")))]
+    fn test_aria_128_cbc() {
+        // RFC 5794 test vector, a single block with a zero IV
+        let key = "000102030405060708090a0b0c0d0e0f";
+        let iv = "00000000000000000000000000000000";
+        let pt = "00112233445566778899aabbccddeeff";
+        let ct = "d718fbd6ab644c739da95f3be6451778";
+
+        cipher_test_nopad(Cipher::aria_128_cbc(), pt, ct, key, iv);
+    }
+
+    #[test]
+    #[cfg(all(ossl110, not(osslconf = "OPENSSL_NO_OCB")))]
+    fn test_aes_128_ocb() {
+        // RFC 7253 test vector
+        let key = "000102030405060708090a0b0c0d0e0f";
+        let iv = "bbaa99887766554433221101";
+        let aad = "0001020304050607";
+        let pt = "0001020304050607";
+        let ct = "6820b3657b6f615a";
+        let tag = "5725bda0d3b4eb3a257c9af1f8f03009";
+
+        let mut actual_tag = [0; 16];
+        let out = encrypt_aead(
+            Cipher::aes_128_ocb(),
+            &Vec::from_hex(key).unwrap(),
+            Some(&Vec::from_hex(iv).unwrap()),
+            &Vec::from_hex(aad).unwrap(),
+            &Vec::from_hex(pt).unwrap(),
+            &mut actual_tag,
+        ).unwrap();
+        assert_eq!(ct, hex::encode(out));
+        assert_eq!(tag, hex::encode(actual_tag));
+
+        let out = decrypt_aead(
+            Cipher::aes_128_ocb(),
+            &Vec::from_hex(key).unwrap(),
+            Some(&Vec::from_hex(iv).unwrap()),
+            &Vec::from_hex(aad).unwrap(),
+            &Vec::from_hex(ct).unwrap(),
+            &Vec::from_hex(tag).unwrap(),
+        ).unwrap();
+        assert_eq!(pt, hex::encode(out));
+    }
+
+    #[test]
+    fn test_from_nid() {
+        let cipher = Cipher::from_nid(Nid::AES_128_CBC).unwrap();
+        assert_eq!(cipher.as_ptr(), Cipher::aes_128_cbc().as_ptr());
+        assert_eq!(cipher.nid(), Nid::AES_128_CBC);
+        assert!(Cipher::from_nid(Nid::UNDEF).is_none());
+    }
+
     #[test]
     #[cfg(any(ossl110))]
     fn test_chacha20() {
@@ -1259,4 +1695,66 @@ mod tests {
         ).unwrap();
         assert_eq!(pt, hex::encode(out));
     }
+
+    #[test]
+    fn test_nid_and_acceleration() {
+        assert_eq!(Cipher::aes_128_cbc().nid(), Nid::AES_128_CBC);
+        assert_eq!(Cipher::aes_256_gcm().nid(), Nid::AES_256_GCM);
+        assert_eq!(Cipher::rc4().nid(), Nid::RC4);
+
+        assert!(!Cipher::rc4().is_hardware_accelerated());
+        // Both AES modes share the same CPU capability check.
+        assert_eq!(
+            Cipher::aes_128_cbc().is_hardware_accelerated(),
+            Cipher::aes_256_gcm().is_hardware_accelerated()
+        );
+    }
+
+    #[test]
+    fn test_nonce_guard() {
+        let key = [0; 16];
+        let mut guard = NonceGuard::new(Cipher::aes_128_gcm(), &key);
+        assert!(guard.is_empty());
+
+        let mut tag = [0; 16];
+        let out = guard
+            .encrypt_aead(&[1; 12], b"aad", b"hello world", &mut tag)
+            .unwrap();
+        let expected = decrypt_aead(
+            Cipher::aes_128_gcm(),
+            &key,
+            Some(&[1; 12]),
+            b"aad",
+            &out,
+            &tag,
+        ).unwrap();
+        assert_eq!(expected, b"hello world");
+
+        match guard.encrypt_aead(&[1; 12], b"aad", b"hello world", &mut tag) {
+            Err(NonceError::Reused) => {}
+            _ => panic!("expected a reused nonce error"),
+        }
+        assert!(guard.encrypter(&[1; 12]).is_err());
+        guard.encrypter(&[2; 12]).unwrap();
+        assert!(guard.encrypter(&[2; 12]).is_err());
+        assert_eq!(guard.len(), 2);
+    }
+
+    #[test]
+    fn test_nonce_counter() {
+        let mut counter = NonceCounter::new(b"\x01\x02", 4);
+        assert_eq!(counter.next_nonce().unwrap(), b"\x01\x02\x00\x00");
+        assert_eq!(counter.next_nonce().unwrap(), b"\x01\x02\x00\x01");
+        assert_eq!(counter.count(), 2);
+
+        let mut counter = NonceCounter::new(b"", 1);
+        for i in 0..256 {
+            assert_eq!(counter.next_nonce().unwrap(), [i as u8]);
+        }
+        match counter.next_nonce() {
+            Err(NonceError::Exhausted) => {}
+            _ => panic!("expected an exhausted counter error"),
+        }
+        assert_eq!(counter.count(), 256);
+    }
 }
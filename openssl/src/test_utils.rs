@@ -0,0 +1,343 @@
+//! Utilities for testing TLS integrations without sockets.
+//!
+//! This module provides an in-memory duplex pipe, throwaway keys and certificates, and a helper
+//! which drives the client and server sides of a handshake to completion in a single thread. No
+//! ports or fixture files are needed.
+//!
+//! Requires the `test-utils` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::io::{Read, Write};
+//! use openssl::test_utils;
+//!
+//! let (cert, key) = test_utils::generate_identity("example.com").unwrap();
+//! let acceptor = test_utils::acceptor(&cert, &key).unwrap();
+//! let connector = test_utils::connector(&cert).unwrap();
+//!
+//! let (mut client, mut server) = test_utils::connect(&connector, "example.com", &acceptor)
+//!     .unwrap();
+//!
+//! client.write_all(b"hello").unwrap();
+//! let mut buf = [0; 5];
+//! server.read_exact(&mut buf).unwrap();
+//! assert_eq!(&buf, b"hello");
+//! ```
+use std::cmp;
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+
+use asn1::Asn1Time;
+use bn::{BigNum, MsbOption};
+use ec::{EcGroup, EcKey};
+use error::ErrorStack;
+use hash::MessageDigest;
+use nid::Nid;
+use pkey::{PKey, PKeyRef, Private};
+use rsa::Rsa;
+use ssl::{HandshakeError, MidHandshakeSslStream, Ssl, SslAcceptor, SslConnector, SslMethod,
+          SslStream};
+use x509::{X509, X509Name, X509Ref};
+use x509::extension::SubjectAlternativeName;
+
+const MAX_HANDSHAKE_ROUNDS: usize = 100;
+
+#[derive(Debug)]
+struct Buffer {
+    data: VecDeque<u8>,
+    closed: bool,
+}
+
+/// One end of an in-memory duplex pipe.
+///
+/// Data written to one end can be read from the other. Reads return a `WouldBlock` error when no
+/// data is available, and end of file once the other end has been dropped.
+#[derive(Debug)]
+pub struct MemoryStream {
+    read: Arc<Mutex<Buffer>>,
+    write: Arc<Mutex<Buffer>>,
+}
+
+impl MemoryStream {
+    /// Returns the number of bytes waiting to be read from this end.
+    pub fn pending(&self) -> usize {
+        self.read.lock().unwrap().data.len()
+    }
+}
+
+impl Read for MemoryStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read = self.read.lock().unwrap();
+        if read.data.is_empty() {
+            if read.closed {
+                return Ok(0);
+            }
+            return Err(io::Error::new(io::ErrorKind::WouldBlock, "pipe is empty"));
+        }
+
+        let len = cmp::min(read.data.len(), buf.len());
+        for (dst, src) in buf.iter_mut().zip(read.data.drain(..len)) {
+            *dst = src;
+        }
+        Ok(len)
+    }
+}
+
+impl Write for MemoryStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut write = self.write.lock().unwrap();
+        if write.closed {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe is closed"));
+        }
+        write.data.extend(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for MemoryStream {
+    fn drop(&mut self) {
+        self.read.lock().unwrap().closed = true;
+        self.write.lock().unwrap().closed = true;
+    }
+}
+
+/// Creates a connected pair of in-memory streams.
+pub fn pipe() -> (MemoryStream, MemoryStream) {
+    let a = Arc::new(Mutex::new(Buffer {
+        data: VecDeque::new(),
+        closed: false,
+    }));
+    let b = Arc::new(Mutex::new(Buffer {
+        data: VecDeque::new(),
+        closed: false,
+    }));
+
+    let first = MemoryStream {
+        read: a.clone(),
+        write: b.clone(),
+    };
+    let second = MemoryStream { read: b, write: a };
+    (first, second)
+}
+
+/// Generates a throwaway P-256 key and a self-signed certificate for `domain`.
+///
+/// The certificate is valid for one day, and contains `domain` as both its common name and its
+/// only subject alternative name.
+///
+/// This is equivalent to `IdentityBuilder::new(domain).build()`.
+pub fn generate_identity(domain: &str) -> Result<(X509, PKey<Private>), ErrorStack> {
+    IdentityBuilder::new(domain).build()
+}
+
+/// A builder for throwaway keys and self-signed certificates.
+///
+/// By default a P-256 key is generated, and the certificate is valid for one day with the common
+/// name as its only subject alternative name.
+///
+/// # Examples
+///
+/// ```
+/// use openssl::test_utils::IdentityBuilder;
+///
+/// let (cert, key) = IdentityBuilder::new("localhost")
+///     .rsa(2048)
+///     .ip("127.0.0.1")
+///     .days(7)
+///     .build()
+///     .unwrap();
+/// assert!(cert.public_key().unwrap().public_eq(&key));
+/// ```
+pub struct IdentityBuilder {
+    common_name: String,
+    dns_names: Vec<String>,
+    ips: Vec<String>,
+    rsa_bits: Option<u32>,
+    days: u32,
+}
+
+impl IdentityBuilder {
+    /// Creates a builder for an identity with the given common name.
+    ///
+    /// The common name is also included as a DNS subject alternative name.
+    pub fn new(common_name: &str) -> IdentityBuilder {
+        IdentityBuilder {
+            common_name: common_name.to_string(),
+            dns_names: vec![],
+            ips: vec![],
+            rsa_bits: None,
+            days: 1,
+        }
+    }
+
+    /// Generates an RSA key of the given size rather than a P-256 key.
+    pub fn rsa(&mut self, bits: u32) -> &mut IdentityBuilder {
+        self.rsa_bits = Some(bits);
+        self
+    }
+
+    /// Adds a DNS subject alternative name.
+    pub fn dns(&mut self, dns: &str) -> &mut IdentityBuilder {
+        self.dns_names.push(dns.to_string());
+        self
+    }
+
+    /// Adds an IP address subject alternative name.
+    pub fn ip(&mut self, ip: &str) -> &mut IdentityBuilder {
+        self.ips.push(ip.to_string());
+        self
+    }
+
+    /// Sets the number of days the certificate is valid for.
+    pub fn days(&mut self, days: u32) -> &mut IdentityBuilder {
+        self.days = days;
+        self
+    }
+
+    /// Generates the key and certificate.
+    pub fn build(&self) -> Result<(X509, PKey<Private>), ErrorStack> {
+        let key = match self.rsa_bits {
+            Some(bits) => PKey::from_rsa(Rsa::generate(bits)?)?,
+            None => {
+                let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+                PKey::from_ec_key(EcKey::generate(&group)?)?
+            }
+        };
+
+        let mut name = X509Name::builder()?;
+        name.append_entry_by_nid(Nid::COMMONNAME, &self.common_name)?;
+        let name = name.build();
+
+        let mut serial = BigNum::new()?;
+        serial.rand(64, MsbOption::MAYBE_ZERO, false)?;
+
+        let mut builder = X509::builder()?;
+        builder.set_version(2)?;
+        builder.set_serial_number(&*serial.to_asn1_integer()?)?;
+        builder.set_subject_name(&name)?;
+        builder.set_issuer_name(&name)?;
+        builder.set_pubkey(&key)?;
+        builder.set_not_before(&*Asn1Time::days_from_now(0)?)?;
+        builder.set_not_after(&*Asn1Time::days_from_now(self.days)?)?;
+
+        let mut san = SubjectAlternativeName::new();
+        san.dns(&self.common_name);
+        for dns in &self.dns_names {
+            san.dns(dns);
+        }
+        for ip in &self.ips {
+            san.ip(ip);
+        }
+        let san = san.build(&builder.x509v3_context(None, None))?;
+        builder.append_extension(san)?;
+        builder.sign(&key, MessageDigest::sha256())?;
+
+        Ok((builder.build(), key))
+    }
+}
+
+/// Returns an acceptor using Mozilla's intermediate configuration with the given identity.
+pub fn acceptor(cert: &X509Ref, key: &PKeyRef<Private>) -> Result<SslAcceptor, ErrorStack> {
+    let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls())?;
+    acceptor.set_certificate(cert)?;
+    acceptor.set_private_key(key)?;
+    acceptor.check_private_key()?;
+    Ok(acceptor.build())
+}
+
+/// Returns a connector which trusts `cert` in addition to the default root certificates.
+pub fn connector(cert: &X509Ref) -> Result<SslConnector, ErrorStack> {
+    let mut connector = SslConnector::builder(SslMethod::tls())?;
+    connector.cert_store_mut().add_cert(cert.to_owned())?;
+    Ok(connector.build())
+}
+
+/// Performs a handshake between a client and server over an in-memory pipe.
+///
+/// The client is configured by `connector` with `domain` used for SNI and hostname verification,
+/// and the server by `acceptor`. Both sides are driven from the calling thread. The client stream
+/// is returned first.
+pub fn connect(
+    connector: &SslConnector,
+    domain: &str,
+    acceptor: &SslAcceptor,
+) -> Result<(SslStream<MemoryStream>, SslStream<MemoryStream>), HandshakeError<MemoryStream>> {
+    let client = connector.configure()?.into_ssl(domain)?;
+    let server = Ssl::new(acceptor.context())?;
+    handshake(client, server)
+}
+
+/// Performs a handshake between two `Ssl`s over an in-memory pipe.
+///
+/// `client` and `server` are put into connect and accept mode respectively, and driven from the
+/// calling thread until both complete. The client stream is returned first.
+///
+/// If either side fails, its error is returned. A `WouldBlock` error is returned if the handshake
+/// stops making progress.
+pub fn handshake(
+    client: Ssl,
+    server: Ssl,
+) -> Result<(SslStream<MemoryStream>, SslStream<MemoryStream>), HandshakeError<MemoryStream>> {
+    let (client_stream, server_stream) = pipe();
+    let mut client = Side::new(client.connect(client_stream))?;
+    let mut server = Side::new(server.accept(server_stream))?;
+
+    for _ in 0..MAX_HANDSHAKE_ROUNDS {
+        client = client.step()?;
+        server = server.step()?;
+
+        let done = match (&client, &server) {
+            (&Side::Done(_), &Side::Done(_)) => true,
+            _ => false,
+        };
+        if done {
+            return Ok((client.into_stream(), server.into_stream()));
+        }
+    }
+
+    // report whichever side is still waiting
+    match client {
+        Side::Mid(mid) => Err(HandshakeError::WouldBlock(mid)),
+        Side::Done(_) => match server {
+            Side::Mid(mid) => Err(HandshakeError::WouldBlock(mid)),
+            Side::Done(_) => unreachable!(),
+        },
+    }
+}
+
+enum Side {
+    Mid(MidHandshakeSslStream<MemoryStream>),
+    Done(SslStream<MemoryStream>),
+}
+
+impl Side {
+    fn new(
+        r: Result<SslStream<MemoryStream>, HandshakeError<MemoryStream>>,
+    ) -> Result<Side, HandshakeError<MemoryStream>> {
+        match r {
+            Ok(stream) => Ok(Side::Done(stream)),
+            Err(HandshakeError::WouldBlock(mid)) => Ok(Side::Mid(mid)),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn step(self) -> Result<Side, HandshakeError<MemoryStream>> {
+        match self {
+            Side::Mid(mid) => Side::new(mid.handshake()),
+            done => Ok(done),
+        }
+    }
+
+    fn into_stream(self) -> SslStream<MemoryStream> {
+        match self {
+            Side::Done(stream) => stream,
+            Side::Mid(_) => unreachable!(),
+        }
+    }
+}
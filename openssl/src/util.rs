@@ -1,3 +1,5 @@
+#[cfg(ossl110)]
+use ffi;
 use libc::{c_char, c_int, c_void};
 use std::any::Any;
 use std::panic::{self, AssertUnwindSafe};
@@ -65,3 +67,31 @@ where
         }
     }
 }
+
+/// Prime generation progress callback, passed to `BN_generate_prime_ex`.
+///
+/// The callback argument of the `BN_GENCB` is expected to be a pointer to a `CallbackState`.
+#[cfg(ossl110)]
+pub unsafe extern "C" fn invoke_bn_gencb<F>(p: c_int, n: c_int, gencb: *mut ffi::BN_GENCB) -> c_int
+where
+    F: FnMut(i32, i32) -> bool,
+{
+    let callback = &mut *(ffi::BN_GENCB_get_arg(gencb) as *mut CallbackState<F>);
+    if callback.panic.is_some() {
+        return 0;
+    }
+
+    let result = {
+        let cb = callback.cb.as_mut().unwrap();
+        panic::catch_unwind(AssertUnwindSafe(|| cb(p as i32, n as i32)))
+    };
+
+    match result {
+        Ok(true) => 1,
+        Ok(false) => 0,
+        Err(err) => {
+            callback.panic = Some(err);
+            0
+        }
+    }
+}
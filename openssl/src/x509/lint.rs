@@ -0,0 +1,324 @@
+//! Certificate linting.
+//!
+//! The checks performed here flag certificates which are likely to be rejected by clients or which
+//! suggest problems in the issuance process. They do not verify the certificate's signature or
+//! chain.
+//!
+//! # Examples
+//!
+//! ```
+//! use openssl::x509::{self, X509Ref};
+//! use openssl::x509::lint::LintProfile;
+//!
+//! fn audit(cert: &X509Ref) {
+//!     for finding in x509::lint(cert, &LintProfile::tls_server()).unwrap() {
+//!         println!("{}", finding);
+//!     }
+//! }
+//! ```
+use ffi;
+use foreign_types::ForeignTypeRef;
+use libc::c_int;
+use std::collections::HashSet;
+use std::fmt;
+
+use asn1::Asn1ObjectRef;
+use error::ErrorStack;
+use nid::Nid;
+use x509::X509Ref;
+
+/// A set of checks to perform on a certificate.
+#[derive(Debug, Clone)]
+pub struct LintProfile {
+    min_serial_bits: u32,
+    require_subject_alt_name: bool,
+    max_validity_days: Option<u32>,
+    check_signature_algorithm: bool,
+    check_critical_extensions: bool,
+}
+
+impl LintProfile {
+    /// Returns a profile performing the checks which apply to all certificates.
+    ///
+    /// Serial numbers must be at least 64 bits long, weak signature algorithms are flagged, and
+    /// extensions are checked for correct criticality. No requirements are placed on the
+    /// subject alternative name or the validity period.
+    pub fn new() -> LintProfile {
+        LintProfile {
+            min_serial_bits: 64,
+            require_subject_alt_name: false,
+            max_validity_days: None,
+            check_signature_algorithm: true,
+            check_critical_extensions: true,
+        }
+    }
+
+    /// Returns a profile appropriate for publicly trusted TLS server certificates.
+    ///
+    /// In addition to the checks performed by [`new`], a subject alternative name is required and
+    /// the validity period may not exceed 825 days.
+    ///
+    /// [`new`]: #method.new
+    pub fn tls_server() -> LintProfile {
+        LintProfile {
+            require_subject_alt_name: true,
+            max_validity_days: Some(825),
+            ..LintProfile::new()
+        }
+    }
+
+    /// Sets the minimum number of bits in the certificate's serial number.
+    ///
+    /// A value of 0 disables the length check, though negative serial numbers are still flagged.
+    pub fn set_min_serial_bits(&mut self, bits: u32) -> &mut LintProfile {
+        self.min_serial_bits = bits;
+        self
+    }
+
+    /// Configures whether the certificate must contain a subject alternative name extension.
+    pub fn set_require_subject_alt_name(&mut self, require: bool) -> &mut LintProfile {
+        self.require_subject_alt_name = require;
+        self
+    }
+
+    /// Sets the maximum length of the certificate's validity period in days.
+    ///
+    /// The check requires OpenSSL 1.0.2 or newer. On older versions, linting with a maximum set
+    /// produces an unsupported finding rather than silently passing.
+    pub fn set_max_validity_days(&mut self, days: Option<u32>) -> &mut LintProfile {
+        self.max_validity_days = days;
+        self
+    }
+
+    /// Configures whether signatures using weak digests are flagged.
+    ///
+    /// The digests considered weak are those of [`X509Ref::uses_weak_signature`].
+    ///
+    /// [`X509Ref::uses_weak_signature`]: ../struct.X509Ref.html#method.uses_weak_signature
+    pub fn set_check_signature_algorithm(&mut self, check: bool) -> &mut LintProfile {
+        self.check_signature_algorithm = check;
+        self
+    }
+
+    /// Configures whether extensions are checked for correct criticality and duplicates.
+    pub fn set_check_critical_extensions(&mut self, check: bool) -> &mut LintProfile {
+        self.check_critical_extensions = check;
+        self
+    }
+}
+
+/// The check which produced a `LintFinding`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LintCheck {
+    /// The serial number is negative or too short.
+    SerialNumber,
+    /// The subject alternative name extension is missing.
+    SubjectAltName,
+    /// The certificate is signed with a weak algorithm.
+    SignatureAlgorithm,
+    /// The validity period is too long or inverted.
+    ValidityPeriod,
+    /// An extension has incorrect criticality or is duplicated.
+    CriticalExtension,
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+/// A problem found in a certificate, or a check which could not be performed.
+#[derive(Debug, Clone)]
+pub struct LintFinding {
+    check: LintCheck,
+    message: String,
+    unsupported: bool,
+}
+
+impl LintFinding {
+    fn new(check: LintCheck, message: String) -> LintFinding {
+        LintFinding {
+            check,
+            message,
+            unsupported: false,
+        }
+    }
+
+    #[cfg(not(any(ossl102, ossl110)))]
+    fn unsupported(check: LintCheck, message: String) -> LintFinding {
+        LintFinding {
+            check,
+            message,
+            unsupported: true,
+        }
+    }
+
+    /// Returns the check which produced this finding.
+    pub fn check(&self) -> LintCheck {
+        self.check
+    }
+
+    /// Determines if the check could not be performed with the linked version of OpenSSL.
+    ///
+    /// Such a finding does not indicate a problem with the certificate, but the certificate has
+    /// not been shown to pass the check either.
+    pub fn is_unsupported(&self) -> bool {
+        self.unsupported
+    }
+
+    /// Returns a description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.message)
+    }
+}
+
+/// Checks a certificate against a profile, returning the problems found.
+///
+/// An empty list indicates that the certificate passed all of the checks enabled in `profile`.
+/// Checks which cannot be performed with the linked version of OpenSSL are reported as findings
+/// for which `is_unsupported` returns `true`.
+pub fn lint(cert: &X509Ref, profile: &LintProfile) -> Result<Vec<LintFinding>, ErrorStack> {
+    let mut findings = vec![];
+
+    check_serial_number(cert, profile, &mut findings)?;
+
+    if profile.require_subject_alt_name && cert.subject_alt_names().map_or(true, |n| n.len() == 0)
+    {
+        findings.push(LintFinding::new(
+            LintCheck::SubjectAltName,
+            "the subject alternative name extension is missing or empty".to_string(),
+        ));
+    }
+
+    if profile.check_signature_algorithm && cert.uses_weak_signature() {
+        findings.push(LintFinding::new(
+            LintCheck::SignatureAlgorithm,
+            format!(
+                "the certificate is signed with the weak algorithm {}",
+                cert.signature_algorithm().object()
+            ),
+        ));
+    }
+
+    check_validity_period(cert, profile, &mut findings)?;
+
+    if profile.check_critical_extensions {
+        check_extensions(cert, &mut findings);
+    }
+
+    Ok(findings)
+}
+
+fn check_serial_number(
+    cert: &X509Ref,
+    profile: &LintProfile,
+    findings: &mut Vec<LintFinding>,
+) -> Result<(), ErrorStack> {
+    let serial = cert.serial_number().to_bn()?;
+
+    if serial.is_negative() {
+        findings.push(LintFinding::new(
+            LintCheck::SerialNumber,
+            "the serial number is negative".to_string(),
+        ));
+    }
+
+    let bits = serial.num_bits() as u32;
+    if bits < profile.min_serial_bits {
+        findings.push(LintFinding::new(
+            LintCheck::SerialNumber,
+            format!(
+                "the serial number is {} bits long, but at least {} are required",
+                bits, profile.min_serial_bits
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(any(ossl102, ossl110))]
+fn check_validity_period(
+    cert: &X509Ref,
+    profile: &LintProfile,
+    findings: &mut Vec<LintFinding>,
+) -> Result<(), ErrorStack> {
+    let diff = cert.not_before().diff(cert.not_after())?;
+
+    if diff.days < 0 || diff.secs < 0 {
+        findings.push(LintFinding::new(
+            LintCheck::ValidityPeriod,
+            "the certificate expires before it becomes valid".to_string(),
+        ));
+    } else if let Some(max) = profile.max_validity_days {
+        let days = diff.days as u32 + if diff.secs > 0 { 1 } else { 0 };
+        if days > max {
+            findings.push(LintFinding::new(
+                LintCheck::ValidityPeriod,
+                format!(
+                    "the validity period is {} days long, but at most {} are allowed",
+                    days, max
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// ASN1_TIME_diff is not available
+#[cfg(not(any(ossl102, ossl110)))]
+fn check_validity_period(
+    _: &X509Ref,
+    profile: &LintProfile,
+    findings: &mut Vec<LintFinding>,
+) -> Result<(), ErrorStack> {
+    if profile.max_validity_days.is_some() {
+        findings.push(LintFinding::unsupported(
+            LintCheck::ValidityPeriod,
+            "the validity period cannot be checked with this version of OpenSSL".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn check_extensions(cert: &X509Ref, findings: &mut Vec<LintFinding>) {
+    let mut seen = HashSet::new();
+
+    unsafe {
+        let count = ffi::X509_get_ext_count(cert.as_ptr());
+        for i in 0..count {
+            let ext = ffi::X509_get_ext(cert.as_ptr(), i as c_int);
+            let object = Asn1ObjectRef::from_ptr(ffi::X509_EXTENSION_get_object(ext));
+            let nid = object.nid();
+            let critical = ffi::X509_EXTENSION_get_critical(ext) > 0;
+
+            if nid != Nid::UNDEF && !seen.insert(nid) {
+                findings.push(LintFinding::new(
+                    LintCheck::CriticalExtension,
+                    format!("the {} extension appears more than once", object),
+                ));
+            }
+
+            if !critical {
+                continue;
+            }
+
+            if nid == Nid::SUBJECT_KEY_IDENTIFIER || nid == Nid::AUTHORITY_KEY_IDENTIFIER {
+                findings.push(LintFinding::new(
+                    LintCheck::CriticalExtension,
+                    format!("the {} extension must not be marked critical", object),
+                ));
+            } else if ffi::X509_supported_extension(ext) == 0 {
+                findings.push(LintFinding::new(
+                    LintCheck::CriticalExtension,
+                    format!("the critical extension {} is not recognized", object),
+                ));
+            }
+        }
+    }
+}
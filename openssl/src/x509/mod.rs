@@ -7,26 +7,32 @@
 //! Internet protocols, including SSL/TLS, which is the basis for HTTPS,
 //! the secure protocol for browsing the web.
 
-use libc::{c_int, c_long};
+use libc::{c_int, c_long, c_uint};
 use ffi;
 use foreign_types::{ForeignType, ForeignTypeRef};
+use std::cmp::Ordering;
 use std::error::Error;
 use std::ffi::{CStr, CString};
 use std::fmt;
 use std::marker::PhantomData;
 use std::mem;
+#[cfg(any(ossl102, ossl110))]
+use std::net::IpAddr;
 use std::path::Path;
 use std::ptr;
 use std::slice;
 use std::str;
+#[cfg(any(ossl102, ossl110))]
+use std::time::Duration;
 
 use {cvt, cvt_n, cvt_p};
-use asn1::{Asn1BitStringRef, Asn1IntegerRef, Asn1ObjectRef, Asn1StringRef, Asn1TimeRef};
+use asn1::{Asn1BitStringRef, Asn1IntegerRef, Asn1Object, Asn1ObjectRef, Asn1StringRef,
+           Asn1TimeRef};
 use bio::MemBioSlice;
 use conf::ConfRef;
 use error::ErrorStack;
 use ex_data::Index;
-use hash::MessageDigest;
+use hash::{DigestBytes, MessageDigest};
 use nid::Nid;
 use pkey::{HasPrivate, HasPublic, PKey, PKeyRef, Public};
 use stack::{Stack, StackRef, Stackable};
@@ -42,13 +48,24 @@ use ffi::{ASN1_STRING_get0_data as ASN1_STRING_data,
 
 #[cfg(any(ossl102, ossl110))]
 pub mod verify;
+#[cfg(any(ossl102, ossl110))]
+use self::verify::{X509CheckFlags, X509VerifyParamRef};
 
 pub mod extension;
+pub mod lint;
+pub mod req_policy;
+#[cfg(any(ossl102, ossl110))]
+pub mod stapling;
 pub mod store;
+#[cfg(any(ossl102, ossl110))]
+pub mod verify_cache;
 
 #[cfg(test)]
 mod tests;
 
+pub use self::lint::lint;
+use self::req_policy::{ReqPolicy, ReqViolation};
+
 foreign_type_and_impl_send_sync! {
     type CType = ffi::X509_STORE_CTX;
     fn drop = ffi::X509_STORE_CTX_free;
@@ -97,6 +114,18 @@ impl X509StoreContextRef {
         }
     }
 
+    /// Returns a mutable reference to the X509 verification configuration.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    ///
+    /// This corresponds to [`X509_STORE_CTX_get0_param`].
+    ///
+    /// [`X509_STORE_CTX_get0_param`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_STORE_CTX_get0_param.html
+    #[cfg(any(ossl102, ossl110))]
+    pub fn param_mut(&mut self) -> &mut X509VerifyParamRef {
+        unsafe { X509VerifyParamRef::from_ptr_mut(ffi::X509_STORE_CTX_get0_param(self.as_ptr())) }
+    }
+
     /// Returns the error code of the context.
     ///
     /// This corresponds to [`X509_STORE_CTX_get_error`].
@@ -405,6 +434,17 @@ impl X509Ref {
         }
     }
 
+    /// Returns the numerical value of the version field of the certificate.
+    ///
+    /// Note that the version is zero-indexed; a version 3 certificate will return `2`.
+    ///
+    /// This corresponds to [`X509_get_version`].
+    ///
+    /// [`X509_get_version`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_get_version.html
+    pub fn version(&self) -> i32 {
+        unsafe { compat::X509_get_version(self.as_ptr()) as i32 }
+    }
+
     /// Returns this certificate's subject alternative name entries, if they exist.
     ///
     /// This corresponds to [`X509_get_ext_d2i`] called with `NID_subject_alt_name`.
@@ -447,6 +487,112 @@ impl X509Ref {
         }
     }
 
+    /// Returns the contents of this certificate's basic constraints extension, if it exists.
+    ///
+    /// This corresponds to [`X509_get_ext_d2i`] called with `NID_basic_constraints`.
+    ///
+    /// [`X509_get_ext_d2i`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_get_ext_d2i.html
+    pub fn basic_constraints(&self) -> Option<X509BasicConstraints> {
+        unsafe {
+            let bc = ffi::X509_get_ext_d2i(
+                self.as_ptr(),
+                ffi::NID_basic_constraints,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            ) as *mut ffi::BASIC_CONSTRAINTS;
+            if bc.is_null() {
+                return None;
+            }
+
+            let ca = (*bc).ca != 0;
+            let pathlen = if (*bc).pathlen.is_null() {
+                None
+            } else {
+                Some(ffi::ASN1_INTEGER_get((*bc).pathlen) as u32)
+            };
+            ffi::BASIC_CONSTRAINTS_free(bc);
+
+            Some(X509BasicConstraints { ca, pathlen })
+        }
+    }
+
+    /// Returns the contents of this certificate's key usage extension, if it exists.
+    ///
+    /// This corresponds to [`X509_get_ext_d2i`] called with `NID_key_usage`.
+    ///
+    /// [`X509_get_ext_d2i`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_get_ext_d2i.html
+    pub fn key_usage(&self) -> Option<X509KeyUsage> {
+        unsafe {
+            let usage = ffi::X509_get_ext_d2i(
+                self.as_ptr(),
+                ffi::NID_key_usage,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            ) as *mut ffi::ASN1_BIT_STRING;
+            if usage.is_null() {
+                return None;
+            }
+
+            let ptr = ASN1_STRING_data(usage as *mut _);
+            let len = ffi::ASN1_STRING_length(usage as *mut _);
+            let data = slice::from_raw_parts(ptr as *const u8, len as usize);
+            let mut bits = 0;
+            if let Some(&b) = data.get(0) {
+                bits |= b as c_uint;
+            }
+            if let Some(&b) = data.get(1) {
+                bits |= (b as c_uint) << 8;
+            }
+            ffi::ASN1_BIT_STRING_free(usage);
+
+            Some(X509KeyUsage::from_bits_truncate(bits))
+        }
+    }
+
+    /// Returns the purposes listed in this certificate's extended key usage extension, if it
+    /// exists.
+    ///
+    /// This corresponds to [`X509_get_ext_d2i`] called with `NID_ext_key_usage`.
+    ///
+    /// [`X509_get_ext_d2i`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_get_ext_d2i.html
+    pub fn extended_key_usage(&self) -> Option<Stack<Asn1Object>> {
+        unsafe {
+            let stack = ffi::X509_get_ext_d2i(
+                self.as_ptr(),
+                ffi::NID_ext_key_usage,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+            if stack.is_null() {
+                None
+            } else {
+                Some(Stack::from_ptr(stack as *mut _))
+            }
+        }
+    }
+
+    /// Returns the entries of this certificate's authority information access extension, if it
+    /// exists.
+    ///
+    /// This corresponds to [`X509_get_ext_d2i`] called with `NID_info_access`.
+    ///
+    /// [`X509_get_ext_d2i`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_get_ext_d2i.html
+    pub fn authority_info_access(&self) -> Option<Stack<AccessDescription>> {
+        unsafe {
+            let stack = ffi::X509_get_ext_d2i(
+                self.as_ptr(),
+                ffi::NID_info_access,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+            if stack.is_null() {
+                None
+            } else {
+                Some(Stack::from_ptr(stack as *mut _))
+            }
+        }
+    }
+
     pub fn public_key(&self) -> Result<PKey<Public>, ErrorStack> {
         unsafe {
             let pkey = cvt_p(ffi::X509_get_pubkey(self.as_ptr()))?;
@@ -454,23 +600,53 @@ impl X509Ref {
         }
     }
 
-    /// Returns certificate fingerprint calculated using provided hash
-    pub fn fingerprint(&self, hash_type: MessageDigest) -> Result<Vec<u8>, ErrorStack> {
+    /// Returns the DER-encoded SubjectPublicKeyInfo structure of the certificate.
+    ///
+    /// Unlike serializing the key returned by `public_key`, this encodes the structure as it
+    /// appears in the certificate, including the exact algorithm parameters. This is the input
+    /// used for public key pinning, for example.
+    ///
+    /// This corresponds to [`X509_get_X509_PUBKEY`] and [`i2d_X509_PUBKEY`].
+    ///
+    /// [`X509_get_X509_PUBKEY`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_get_X509_PUBKEY.html
+    /// [`i2d_X509_PUBKEY`]: https://www.openssl.org/docs/man1.1.0/crypto/i2d_X509_PUBKEY.html
+    pub fn public_key_der(&self) -> Result<Vec<u8>, ErrorStack> {
+        unsafe { spki_to_der(compat::X509_get_X509_PUBKEY(self.as_ptr())) }
+    }
+
+    /// Returns a digest of the DER representation of the certificate.
+    ///
+    /// This is commonly used as the certificate's fingerprint.
+    ///
+    /// This corresponds to [`X509_digest`].
+    ///
+    /// [`X509_digest`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_digest.html
+    pub fn digest(&self, hash_type: MessageDigest) -> Result<DigestBytes, ErrorStack> {
         unsafe {
-            let evp = hash_type.as_ptr();
+            let mut digest = DigestBytes {
+                buf: [0; ffi::EVP_MAX_MD_SIZE as usize],
+                len: ffi::EVP_MAX_MD_SIZE as usize,
+            };
             let mut len = ffi::EVP_MAX_MD_SIZE;
-            let mut buf = vec![0u8; len as usize];
             cvt(ffi::X509_digest(
                 self.as_ptr(),
-                evp,
-                buf.as_mut_ptr() as *mut _,
+                hash_type.as_ptr(),
+                digest.buf.as_mut_ptr() as *mut _,
                 &mut len,
             ))?;
-            buf.truncate(len as usize);
-            Ok(buf)
+            digest.len = len as usize;
+
+            Ok(digest)
         }
     }
 
+    /// Returns certificate fingerprint calculated using provided hash
+    ///
+    /// This is equivalent to `digest`, but allocates the result.
+    pub fn fingerprint(&self, hash_type: MessageDigest) -> Result<Vec<u8>, ErrorStack> {
+        self.digest(hash_type).map(|digest| digest.to_vec())
+    }
+
     /// Returns the certificate's Not After validity period.
     pub fn not_after(&self) -> &Asn1TimeRef {
         unsafe {
@@ -489,6 +665,45 @@ impl X509Ref {
         }
     }
 
+    /// Returns the signed number of seconds from `now` until the certificate's Not After time.
+    ///
+    /// The value is negative if the certificate expired before `now`.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    #[cfg(any(ossl102, ossl110))]
+    pub fn not_after_secs_from(&self, now: &Asn1TimeRef) -> Result<i64, ErrorStack> {
+        now.diff(self.not_after()).map(|diff| diff.total_secs())
+    }
+
+    /// Returns the time remaining from `now` until the certificate's Not After time.
+    ///
+    /// Returns `None` if the certificate expired before `now`.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    #[cfg(any(ossl102, ossl110))]
+    pub fn not_after_duration_from(
+        &self,
+        now: &Asn1TimeRef,
+    ) -> Result<Option<Duration>, ErrorStack> {
+        self.not_after_secs_from(now).map(|secs| {
+            if secs < 0 {
+                None
+            } else {
+                Some(Duration::from_secs(secs as u64))
+            }
+        })
+    }
+
+    /// Returns the signed number of seconds from `now` until the certificate's Not Before time.
+    ///
+    /// The value is negative if the certificate became valid before `now`.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    #[cfg(any(ossl102, ossl110))]
+    pub fn not_before_secs_from(&self, now: &Asn1TimeRef) -> Result<i64, ErrorStack> {
+        now.diff(self.not_before()).map(|diff| diff.total_secs())
+    }
+
     /// Returns the certificate's signature
     pub fn signature(&self) -> &Asn1BitStringRef {
         unsafe {
@@ -499,6 +714,53 @@ impl X509Ref {
         }
     }
 
+    /// Returns the certificate's issuer unique identifier, if present.
+    ///
+    /// This corresponds to [`X509_get0_uids`].
+    ///
+    /// [`X509_get0_uids`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_get0_uids.html
+    pub fn issuer_unique_id(&self) -> Option<&Asn1BitStringRef> {
+        unsafe {
+            let mut uid = ptr::null();
+            compat::X509_get0_uids(self.as_ptr(), &mut uid, ptr::null_mut());
+            if uid.is_null() {
+                None
+            } else {
+                Some(Asn1BitStringRef::from_ptr(uid as *mut _))
+            }
+        }
+    }
+
+    /// Returns the certificate's subject unique identifier, if present.
+    ///
+    /// This corresponds to [`X509_get0_uids`].
+    ///
+    /// [`X509_get0_uids`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_get0_uids.html
+    pub fn subject_unique_id(&self) -> Option<&Asn1BitStringRef> {
+        unsafe {
+            let mut uid = ptr::null();
+            compat::X509_get0_uids(self.as_ptr(), ptr::null_mut(), &mut uid);
+            if uid.is_null() {
+                None
+            } else {
+                Some(Asn1BitStringRef::from_ptr(uid as *mut _))
+            }
+        }
+    }
+
+    /// Returns the DER encoding of the certificate's `TBSCertificate`.
+    ///
+    /// This is the portion of the certificate covered by its signature. The bytes are taken from
+    /// the certificate's encoding as parsed rather than re-encoded, so they are exactly what the
+    /// issuer signed.
+    pub fn tbs_der(&self) -> Result<Vec<u8>, ErrorStack> {
+        let der = self.to_der()?;
+        let (header, _) = der_header(&der).expect("BUG: invalid certificate encoding");
+        let (tbs_header, tbs_len) =
+            der_header(&der[header..]).expect("BUG: invalid certificate encoding");
+        Ok(der[header..header + tbs_header + tbs_len].to_vec())
+    }
+
     /// Returns the certificate's signature algorithm.
     pub fn signature_algorithm(&self) -> &X509AlgorithmRef {
         unsafe {
@@ -509,6 +771,102 @@ impl X509Ref {
         }
     }
 
+    /// Returns the `Nid` of the digest used in the certificate's signature.
+    ///
+    /// Returns `None` if the signature algorithm is not recognized or does not use a separate
+    /// digest, as is the case for Ed25519.
+    ///
+    /// This corresponds to [`OBJ_find_sigid_algs`].
+    ///
+    /// [`OBJ_find_sigid_algs`]: https://www.openssl.org/docs/man1.1.0/crypto/OBJ_find_sigid_algs.html
+    pub fn signature_digest_nid(&self) -> Option<Nid> {
+        let sig_nid = self.signature_algorithm().object().nid();
+        unsafe {
+            let mut digest_nid = 0;
+            let r = ffi::OBJ_find_sigid_algs(sig_nid.as_raw(), &mut digest_nid, ptr::null_mut());
+            if r == 0 || digest_nid == ffi::NID_undef {
+                None
+            } else {
+                Some(Nid::from_raw(digest_nid))
+            }
+        }
+    }
+
+    /// Determines if the certificate is signed with a digest considered weak.
+    ///
+    /// MD2, MD4, MD5, SHA-0 and SHA-1 signatures are treated as weak. This is a policy check
+    /// only - OpenSSL itself may still accept such signatures during verification.
+    pub fn uses_weak_signature(&self) -> bool {
+        match self.signature_digest_nid() {
+            Some(Nid::MD2) | Some(Nid::MD4) | Some(Nid::MD5) | Some(Nid::SHA) | Some(Nid::SHA1) => {
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks if the certificate matches a DNS hostname.
+    ///
+    /// The subject alternative names are checked, as well as the subject's common name if no DNS
+    /// names are present and `X509CheckFlags::NEVER_CHECK_SUBJECT` is not set.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    ///
+    /// This corresponds to [`X509_check_host`].
+    ///
+    /// [`X509_check_host`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_check_host.html
+    #[cfg(any(ossl102, ossl110))]
+    pub fn check_host(&self, host: &str, flags: X509CheckFlags) -> Result<bool, ErrorStack> {
+        unsafe {
+            cvt_n(ffi::X509_check_host(
+                self.as_ptr(),
+                host.as_ptr() as *const _,
+                host.len(),
+                flags.bits(),
+                ptr::null_mut(),
+            )).map(|n| n != 0)
+        }
+    }
+
+    /// Checks if the certificate matches an email address.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    ///
+    /// This corresponds to [`X509_check_email`].
+    ///
+    /// [`X509_check_email`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_check_host.html
+    #[cfg(any(ossl102, ossl110))]
+    pub fn check_email(&self, email: &str, flags: X509CheckFlags) -> Result<bool, ErrorStack> {
+        unsafe {
+            cvt_n(ffi::X509_check_email(
+                self.as_ptr(),
+                email.as_ptr() as *const _,
+                email.len(),
+                flags.bits(),
+            )).map(|n| n != 0)
+        }
+    }
+
+    /// Checks if the certificate matches an IPv4 or IPv6 address.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    ///
+    /// This corresponds to [`X509_check_ip`].
+    ///
+    /// [`X509_check_ip`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_check_host.html
+    #[cfg(any(ossl102, ossl110))]
+    pub fn check_ip(&self, ip: IpAddr, flags: X509CheckFlags) -> Result<bool, ErrorStack> {
+        unsafe {
+            let (buf, len) = verify::ip_octets(ip);
+            cvt_n(ffi::X509_check_ip(
+                self.as_ptr(),
+                buf.as_ptr(),
+                len,
+                flags.bits(),
+            )).map(|n| n != 0)
+        }
+    }
+
     /// Returns the list of OCSP responder URLs specified in the certificate's Authority Information
     /// Access field.
     pub fn ocsp_responders(&self) -> Result<Stack<OpensslString>, ErrorStack> {
@@ -523,6 +881,21 @@ impl X509Ref {
         }
     }
 
+    /// Checks that the certificate was signed by the private key corresponding to `key`.
+    ///
+    /// Returns `true` if the signature is valid. This only checks the signature; the certificate
+    /// chain is not built and the validity period is not checked.
+    ///
+    /// This corresponds to [`X509_verify`].
+    ///
+    /// [`X509_verify`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_verify.html
+    pub fn verify<T>(&self, key: &PKeyRef<T>) -> Result<bool, ErrorStack>
+    where
+        T: HasPublic,
+    {
+        unsafe { cvt_n(ffi::X509_verify(self.as_ptr(), key.as_ptr())).map(|n| n != 0) }
+    }
+
     /// Returns this certificate's serial number.
     ///
     /// This corresponds to [`X509_get_serialNumber`].
@@ -557,6 +930,16 @@ impl X509Ref {
         to_der,
         ffi::i2d_X509
     }
+
+    to_text! {
+        /// Converts the certificate to human readable text.
+        ///
+        /// This corresponds to [`X509_print`].
+        ///
+        /// [`X509_print`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_print.html
+        to_text,
+        ffi::X509_print
+    }
 }
 
 impl ToOwned for X509Ref {
@@ -600,6 +983,29 @@ impl X509 {
         ffi::d2i_X509
     }
 
+    from_pem_strict! {
+        /// Like `from_pem`, but rejects input larger than `max_len` bytes or containing anything
+        /// other than whitespace around the PEM block.
+        ///
+        /// See the [`parse`] module for details.
+        ///
+        /// [`parse`]: ../parse/index.html
+        from_pem_strict,
+        X509,
+        ffi::PEM_read_bio_X509
+    }
+
+    from_der_strict! {
+        /// Like `from_der`, but rejects input larger than `max_len` bytes or with trailing data.
+        ///
+        /// See the [`parse`] module for details.
+        ///
+        /// [`parse`]: ../parse/index.html
+        from_der_strict,
+        X509,
+        ffi::d2i_X509
+    }
+
     /// Deserializes a list of PEM-formatted certificates.
     pub fn stack_from_pem(pem: &[u8]) -> Result<Vec<X509>, ErrorStack> {
         unsafe {
@@ -804,6 +1210,17 @@ impl X509Name {
         let file = CString::new(file.as_ref().as_os_str().to_str().unwrap()).unwrap();
         unsafe { cvt_p(ffi::SSL_load_client_CA_file(file.as_ptr())).map(|p| Stack::from_ptr(p)) }
     }
+
+    from_der! {
+        /// Deserializes a DER-encoded X509 name structure.
+        ///
+        /// This corresponds to [`d2i_X509_NAME`].
+        ///
+        /// [`d2i_X509_NAME`]: https://www.openssl.org/docs/man1.1.0/crypto/d2i_X509_NAME.html
+        from_der,
+        X509Name,
+        ffi::d2i_X509_NAME
+    }
 }
 
 impl Stackable for X509Name {
@@ -811,20 +1228,78 @@ impl Stackable for X509Name {
 }
 
 impl X509NameRef {
+    to_der! {
+        /// Serializes the name into a DER-encoded X509 name structure.
+        ///
+        /// This corresponds to [`i2d_X509_NAME`].
+        ///
+        /// [`i2d_X509_NAME`]: https://www.openssl.org/docs/man1.1.0/crypto/i2d_X509_NAME.html
+        to_der,
+        ffi::i2d_X509_NAME
+    }
+
+    to_text! {
+        /// Converts the name to human readable text, such as `C=AU, CN=foobar.com`.
+        ///
+        /// There is no PEM encoding of a bare name, so unlike the other X509 types this has no
+        /// `to_pem` counterpart.
+        ///
+        /// This corresponds to [`X509_NAME_print`].
+        ///
+        /// [`X509_NAME_print`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_NAME_print_ex.html
+        to_text,
+        ffi::X509_NAME_print,
+        0
+    }
+
     /// Returns the name entries by the nid.
     pub fn entries_by_nid<'a>(&'a self, nid: Nid) -> X509NameEntries<'a> {
         X509NameEntries {
             name: self,
-            nid: nid,
+            nid: Some(nid),
+            loc: -1,
+        }
+    }
+
+    /// Returns an iterator over all of the name entries, in order.
+    pub fn entries<'a>(&'a self) -> X509NameEntries<'a> {
+        X509NameEntries {
+            name: self,
+            nid: None,
             loc: -1,
         }
     }
+
+    /// Compares the canonical encodings of two names.
+    ///
+    /// Unlike a comparison of the DER encodings, this ignores differences in string types, ASCII
+    /// case, and leading, trailing, and repeated whitespace within each value. It is appropriate
+    /// for checking whether two certificates refer to the same entity, as OpenSSL does when
+    /// building chains. Non-ASCII characters are compared exactly, without Unicode case folding
+    /// or normalization. The order of the entries is significant.
+    ///
+    /// This corresponds to [`X509_NAME_cmp`].
+    ///
+    /// [`X509_NAME_cmp`]: https://www.openssl.org/docs/man1.1.1/man3/X509_NAME_cmp.html
+    pub fn canonical_cmp(&self, other: &X509NameRef) -> Result<Ordering, ErrorStack> {
+        unsafe {
+            let r = ffi::X509_NAME_cmp(self.as_ptr(), other.as_ptr());
+            // -2 indicates that a name could not be encoded, but may also be a valid difference
+            if r == -2 {
+                let errors = ErrorStack::get();
+                if !errors.errors().is_empty() {
+                    return Err(errors);
+                }
+            }
+            Ok(r.cmp(&0))
+        }
+    }
 }
 
 /// A type to destructure and examine an `X509Name`.
 pub struct X509NameEntries<'a> {
     name: &'a X509NameRef,
-    nid: Nid,
+    nid: Option<Nid>,
     loc: c_int,
 }
 
@@ -833,11 +1308,20 @@ impl<'a> Iterator for X509NameEntries<'a> {
 
     fn next(&mut self) -> Option<&'a X509NameEntryRef> {
         unsafe {
-            self.loc =
-                ffi::X509_NAME_get_index_by_NID(self.name.as_ptr(), self.nid.as_raw(), self.loc);
-
-            if self.loc == -1 {
-                return None;
+            match self.nid {
+                Some(nid) => {
+                    self.loc =
+                        ffi::X509_NAME_get_index_by_NID(self.name.as_ptr(), nid.as_raw(), self.loc);
+                    if self.loc == -1 {
+                        return None;
+                    }
+                }
+                None => {
+                    self.loc += 1;
+                    if self.loc >= ffi::X509_NAME_entry_count(self.name.as_ptr()) {
+                        return None;
+                    }
+                }
             }
 
             let entry = ffi::X509_NAME_get_entry(self.name.as_ptr(), self.loc);
@@ -870,6 +1354,18 @@ impl X509NameEntryRef {
             Asn1StringRef::from_ptr(data)
         }
     }
+
+    /// Returns the field name of an `X509NameEntry`.
+    ///
+    /// This corresponds to [`X509_NAME_ENTRY_get_object`].
+    ///
+    /// [`X509_NAME_ENTRY_get_object`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_NAME_ENTRY_get_object.html
+    pub fn object(&self) -> &Asn1ObjectRef {
+        unsafe {
+            let object = ffi::X509_NAME_ENTRY_get_object(self.as_ptr());
+            Asn1ObjectRef::from_ptr(object)
+        }
+    }
 }
 
 /// A builder used to construct an `X509Req`.
@@ -1023,6 +1519,29 @@ impl X509Req {
         X509Req,
         ffi::d2i_X509_REQ
     }
+
+    from_pem_strict! {
+        /// Like `from_pem`, but rejects input larger than `max_len` bytes or containing anything
+        /// other than whitespace around the PEM block.
+        ///
+        /// See the [`parse`] module for details.
+        ///
+        /// [`parse`]: ../parse/index.html
+        from_pem_strict,
+        X509Req,
+        ffi::PEM_read_bio_X509_REQ
+    }
+
+    from_der_strict! {
+        /// Like `from_der`, but rejects input larger than `max_len` bytes or with trailing data.
+        ///
+        /// See the [`parse`] module for details.
+        ///
+        /// [`parse`]: ../parse/index.html
+        from_der_strict,
+        X509Req,
+        ffi::d2i_X509_REQ
+    }
 }
 
 impl X509ReqRef {
@@ -1048,6 +1567,16 @@ impl X509ReqRef {
         ffi::i2d_X509_REQ
     }
 
+    to_text! {
+        /// Converts the certificate request to human readable text.
+        ///
+        /// This corresponds to [`X509_REQ_print`].
+        ///
+        /// [`X509_REQ_print`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_REQ_print.html
+        to_text,
+        ffi::X509_REQ_print
+    }
+
     /// Returns the numerical value of the version field of the certificate request.
     ///
     /// This corresponds to [`X509_REQ_get_version`]
@@ -1082,6 +1611,34 @@ impl X509ReqRef {
         }
     }
 
+    /// Returns the DER-encoded SubjectPublicKeyInfo structure of the certificate request.
+    ///
+    /// Unlike serializing the key returned by `public_key`, this encodes the structure as it
+    /// appears in the request, including the exact algorithm parameters.
+    ///
+    /// This corresponds to [`X509_REQ_get_X509_PUBKEY`] and [`i2d_X509_PUBKEY`].
+    ///
+    /// [`X509_REQ_get_X509_PUBKEY`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_REQ_get_X509_PUBKEY.html
+    /// [`i2d_X509_PUBKEY`]: https://www.openssl.org/docs/man1.1.0/crypto/i2d_X509_PUBKEY.html
+    pub fn public_key_der(&self) -> Result<Vec<u8>, ErrorStack> {
+        unsafe { spki_to_der(compat::X509_REQ_get_X509_PUBKEY(self.as_ptr())) }
+    }
+
+    /// Checks that the certificate request was signed by the private key corresponding to `key`.
+    ///
+    /// Returns `true` if the signature is valid. For a self-signed request, `key` should be the
+    /// request's own public key.
+    ///
+    /// This corresponds to [`X509_REQ_verify`].
+    ///
+    /// [`X509_REQ_verify`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_REQ_verify.html
+    pub fn verify<T>(&self, key: &PKeyRef<T>) -> Result<bool, ErrorStack>
+    where
+        T: HasPublic,
+    {
+        unsafe { cvt_n(ffi::X509_REQ_verify(self.as_ptr(), key.as_ptr())).map(|n| n != 0) }
+    }
+
     /// Returns the extensions of the certificate request.
     ///
     /// This corresponds to [`X509_REQ_get_extensions"]
@@ -1091,6 +1648,18 @@ impl X509ReqRef {
             Ok(Stack::from_ptr(extensions))
         }
     }
+
+    /// Checks the request against a policy, returning the violations found.
+    ///
+    /// The signature, public key, subject, and requested extensions are all checked. An empty
+    /// list indicates that the request meets every requirement of `policy`.
+    ///
+    /// See the [`req_policy`] module for more details.
+    ///
+    /// [`req_policy`]: req_policy/index.html
+    pub fn validate(&self, policy: &ReqPolicy) -> Result<Vec<ReqViolation>, ErrorStack> {
+        req_policy::validate(self, policy)
+    }
 }
 
 /// The result of peer certificate verification.
@@ -1153,6 +1722,63 @@ impl X509VerifyResult {
     /// Application verification failure.
     pub const APPLICATION_VERIFICATION: X509VerifyResult =
         X509VerifyResult(ffi::X509_V_ERR_APPLICATION_VERIFICATION);
+    /// The issuer certificate could not be found.
+    pub const UNABLE_TO_GET_ISSUER_CERT: X509VerifyResult =
+        X509VerifyResult(ffi::X509_V_ERR_UNABLE_TO_GET_ISSUER_CERT);
+    /// The signature of a certificate is invalid.
+    pub const CERT_SIGNATURE_FAILURE: X509VerifyResult =
+        X509VerifyResult(ffi::X509_V_ERR_CERT_SIGNATURE_FAILURE);
+    /// A certificate is not yet valid.
+    pub const CERT_NOT_YET_VALID: X509VerifyResult =
+        X509VerifyResult(ffi::X509_V_ERR_CERT_NOT_YET_VALID);
+    /// A certificate has expired.
+    pub const CERT_HAS_EXPIRED: X509VerifyResult =
+        X509VerifyResult(ffi::X509_V_ERR_CERT_HAS_EXPIRED);
+    /// The end entity certificate is self-signed and not trusted.
+    pub const DEPTH_ZERO_SELF_SIGNED_CERT: X509VerifyResult =
+        X509VerifyResult(ffi::X509_V_ERR_DEPTH_ZERO_SELF_SIGNED_CERT);
+    /// The chain contains a self-signed certificate which is not trusted.
+    pub const SELF_SIGNED_CERT_IN_CHAIN: X509VerifyResult =
+        X509VerifyResult(ffi::X509_V_ERR_SELF_SIGNED_CERT_IN_CHAIN);
+    /// The issuer of a certificate in the chain could not be found locally.
+    pub const UNABLE_TO_GET_ISSUER_CERT_LOCALLY: X509VerifyResult =
+        X509VerifyResult(ffi::X509_V_ERR_UNABLE_TO_GET_ISSUER_CERT_LOCALLY);
+    /// The chain contains only the end entity certificate, and its issuer is not trusted.
+    pub const UNABLE_TO_VERIFY_LEAF_SIGNATURE: X509VerifyResult =
+        X509VerifyResult(ffi::X509_V_ERR_UNABLE_TO_VERIFY_LEAF_SIGNATURE);
+    /// The chain is longer than the maximum verification depth.
+    pub const CERT_CHAIN_TOO_LONG: X509VerifyResult =
+        X509VerifyResult(ffi::X509_V_ERR_CERT_CHAIN_TOO_LONG);
+    /// A certificate has been revoked.
+    pub const CERT_REVOKED: X509VerifyResult = X509VerifyResult(ffi::X509_V_ERR_CERT_REVOKED);
+    /// A certificate used as a CA is not valid for that purpose.
+    pub const INVALID_CA: X509VerifyResult = X509VerifyResult(ffi::X509_V_ERR_INVALID_CA);
+    /// The basic constraints path length of a CA certificate was exceeded.
+    pub const PATH_LENGTH_EXCEEDED: X509VerifyResult =
+        X509VerifyResult(ffi::X509_V_ERR_PATH_LENGTH_EXCEEDED);
+    /// The end entity certificate is not valid for the requested purpose.
+    pub const INVALID_PURPOSE: X509VerifyResult = X509VerifyResult(ffi::X509_V_ERR_INVALID_PURPOSE);
+    /// The root CA is not trusted for the requested purpose.
+    pub const CERT_UNTRUSTED: X509VerifyResult = X509VerifyResult(ffi::X509_V_ERR_CERT_UNTRUSTED);
+    /// The root CA is marked to reject the requested purpose.
+    pub const CERT_REJECTED: X509VerifyResult = X509VerifyResult(ffi::X509_V_ERR_CERT_REJECTED);
+    /// The certificate does not match the expected hostname.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    #[cfg(any(ossl102, ossl110))]
+    pub const HOSTNAME_MISMATCH: X509VerifyResult =
+        X509VerifyResult(ffi::X509_V_ERR_HOSTNAME_MISMATCH);
+    /// The certificate does not match the expected email address.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    #[cfg(any(ossl102, ossl110))]
+    pub const EMAIL_MISMATCH: X509VerifyResult = X509VerifyResult(ffi::X509_V_ERR_EMAIL_MISMATCH);
+    /// The certificate does not match the expected IP address.
+    ///
+    /// Requires OpenSSL 1.0.2 or newer.
+    #[cfg(any(ossl102, ossl110))]
+    pub const IP_ADDRESS_MISMATCH: X509VerifyResult =
+        X509VerifyResult(ffi::X509_V_ERR_IP_ADDRESS_MISMATCH);
 }
 
 foreign_type_and_impl_send_sync! {
@@ -1217,6 +1843,67 @@ impl Stackable for GeneralName {
     type StackType = ffi::stack_st_GENERAL_NAME;
 }
 
+/// The contents of a certificate's basic constraints extension.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct X509BasicConstraints {
+    ca: bool,
+    pathlen: Option<u32>,
+}
+
+impl X509BasicConstraints {
+    /// Returns `true` if the certificate is a CA.
+    pub fn ca(&self) -> bool {
+        self.ca
+    }
+
+    /// Returns the maximum number of intermediate CAs which may follow this certificate in a
+    /// chain, if limited.
+    pub fn pathlen(&self) -> Option<u32> {
+        self.pathlen
+    }
+}
+
+bitflags! {
+    /// The usages permitted by a certificate's key usage extension.
+    pub struct X509KeyUsage: c_uint {
+        const DIGITAL_SIGNATURE = ffi::X509v3_KU_DIGITAL_SIGNATURE;
+        const NON_REPUDIATION = ffi::X509v3_KU_NON_REPUDIATION;
+        const KEY_ENCIPHERMENT = ffi::X509v3_KU_KEY_ENCIPHERMENT;
+        const DATA_ENCIPHERMENT = ffi::X509v3_KU_DATA_ENCIPHERMENT;
+        const KEY_AGREEMENT = ffi::X509v3_KU_KEY_AGREEMENT;
+        const KEY_CERT_SIGN = ffi::X509v3_KU_KEY_CERT_SIGN;
+        const CRL_SIGN = ffi::X509v3_KU_CRL_SIGN;
+        const ENCIPHER_ONLY = ffi::X509v3_KU_ENCIPHER_ONLY;
+        const DECIPHER_ONLY = ffi::X509v3_KU_DECIPHER_ONLY;
+    }
+}
+
+foreign_type_and_impl_send_sync! {
+    type CType = ffi::ACCESS_DESCRIPTION;
+    fn drop = ffi::ACCESS_DESCRIPTION_free;
+
+    /// An entry of a certificate's authority information access extension.
+    pub struct AccessDescription;
+    /// Reference to `AccessDescription`.
+    pub struct AccessDescriptionRef;
+}
+
+impl AccessDescriptionRef {
+    /// Returns the access method, such as `Nid::AD_OCSP` or `Nid::AD_CA_ISSUERS`.
+    pub fn method(&self) -> &Asn1ObjectRef {
+        unsafe { Asn1ObjectRef::from_ptr((*self.as_ptr()).method) }
+    }
+
+    /// Returns the location at which the resource may be accessed.
+    pub fn location(&self) -> &GeneralNameRef {
+        unsafe { GeneralNameRef::from_ptr((*self.as_ptr()).location) }
+    }
+}
+
+impl Stackable for AccessDescription {
+    type StackType = ffi::stack_st_ACCESS_DESCRIPTION;
+}
+
 foreign_type_and_impl_send_sync! {
     type CType = ffi::X509_ALGOR;
     fn drop = ffi::X509_ALGOR_free;
@@ -1239,15 +1926,48 @@ impl X509AlgorithmRef {
     }
 }
 
+unsafe fn spki_to_der(key: *mut ffi::X509_PUBKEY) -> Result<Vec<u8>, ErrorStack> {
+    assert!(!key.is_null());
+    let len = cvt(ffi::i2d_X509_PUBKEY(key, ptr::null_mut()))?;
+    let mut buf = vec![0; len as usize];
+    cvt(ffi::i2d_X509_PUBKEY(key, &mut buf.as_mut_ptr()))?;
+    Ok(buf)
+}
+
+// Returns the lengths of the header and contents of the DER element at the start of `der`.
+fn der_header(der: &[u8]) -> Option<(usize, usize)> {
+    let first = match der.get(1) {
+        Some(&first) => first as usize,
+        None => return None,
+    };
+    if first < 0x80 {
+        return Some((2, first));
+    }
+
+    let n = first & 0x7f;
+    if n == 0 || n > 4 || der.len() < 2 + n {
+        return None;
+    }
+    let len = der[2..2 + n].iter().fold(0, |len, &b| (len << 8) | b as usize);
+    if der.len() < 2 + n + len {
+        return None;
+    }
+    Some((2 + n, len))
+}
+
 #[cfg(ossl110)]
 mod compat {
     pub use ffi::X509_getm_notAfter as X509_get_notAfter;
     pub use ffi::X509_getm_notBefore as X509_get_notBefore;
     pub use ffi::X509_up_ref;
+    pub use ffi::X509_get_version;
     pub use ffi::X509_REQ_get_version;
     pub use ffi::X509_REQ_get_subject_name;
     pub use ffi::X509_get0_signature;
+    pub use ffi::X509_get0_uids;
     pub use ffi::X509_ALGOR_get0;
+    pub use ffi::X509_get_X509_PUBKEY;
+    pub use ffi::X509_REQ_get_X509_PUBKEY;
 }
 
 #[cfg(ossl10x)]
@@ -1274,6 +1994,10 @@ mod compat {
         );
     }
 
+    pub unsafe fn X509_get_version(x: *mut ffi::X509) -> ::libc::c_long {
+        ::ffi::ASN1_INTEGER_get((*(*x).cert_info).version)
+    }
+
     pub unsafe fn X509_REQ_get_version(x: *mut ffi::X509_REQ) -> ::libc::c_long {
         ::ffi::ASN1_INTEGER_get((*(*x).req_info).version)
     }
@@ -1282,6 +2006,14 @@ mod compat {
         (*(*x).req_info).subject
     }
 
+    pub unsafe fn X509_get_X509_PUBKEY(x: *const ffi::X509) -> *mut ffi::X509_PUBKEY {
+        (*(*x).cert_info).key
+    }
+
+    pub unsafe fn X509_REQ_get_X509_PUBKEY(x: *mut ffi::X509_REQ) -> *mut ffi::X509_PUBKEY {
+        (*(*x).req_info).pubkey
+    }
+
     pub unsafe fn X509_get0_signature(
         psig: *mut *const ffi::ASN1_BIT_STRING,
         palg: *mut *const ffi::X509_ALGOR,
@@ -1295,6 +2027,19 @@ mod compat {
         }
     }
 
+    pub unsafe fn X509_get0_uids(
+        x: *const ffi::X509,
+        piuid: *mut *const ffi::ASN1_BIT_STRING,
+        psuid: *mut *const ffi::ASN1_BIT_STRING,
+    ) {
+        if !piuid.is_null() {
+            *piuid = (*(*x).cert_info).issuerUID;
+        }
+        if !psuid.is_null() {
+            *psuid = (*(*x).cert_info).subjectUID;
+        }
+    }
+
     pub unsafe fn X509_ALGOR_get0(
         paobj: *mut *const ffi::ASN1_OBJECT,
         pptype: *mut c_int,
@@ -0,0 +1,337 @@
+//! Certificate signing request validation.
+//!
+//! Before issuing a certificate, a CA should check that a request is well formed and only asks
+//! for what the CA is willing to certify. [`X509ReqRef::validate`] checks a request against a
+//! `ReqPolicy`, reporting every violation found rather than stopping at the first one.
+//!
+//! # Examples
+//!
+//! ```
+//! use openssl::nid::Nid;
+//! use openssl::x509::X509ReqRef;
+//! use openssl::x509::req_policy::ReqPolicy;
+//!
+//! fn accept(req: &X509ReqRef) -> bool {
+//!     let mut policy = ReqPolicy::new();
+//!     policy
+//!         .add_allowed_dns_suffix("example.com")
+//!         .forbid_subject_attribute(Nid::ORGANIZATIONNAME);
+//!
+//!     let violations = req.validate(&policy).unwrap();
+//!     for violation in &violations {
+//!         println!("{}", violation);
+//!     }
+//!     violations.is_empty()
+//! }
+//! ```
+//!
+//! [`X509ReqRef::validate`]: ../struct.X509ReqRef.html#method.validate
+use ffi;
+use foreign_types::{ForeignType, ForeignTypeRef};
+#[allow(deprecated, unused_imports)]
+use std::ascii::AsciiExt;
+use std::fmt;
+
+use asn1::Asn1ObjectRef;
+use error::ErrorStack;
+use nid::Nid;
+use pkey::{Id, PKeyRef, Public};
+use stack::Stack;
+use x509::{GeneralName, X509ReqRef};
+
+/// The requirements a certificate signing request must meet.
+#[derive(Debug, Clone)]
+pub struct ReqPolicy {
+    min_rsa_bits: Option<u32>,
+    allowed_curves: Vec<Nid>,
+    forbidden_subject_attributes: Vec<Nid>,
+    allowed_extensions: Vec<Nid>,
+    require_subject_alt_name: bool,
+    dns_suffixes: Vec<String>,
+}
+
+impl ReqPolicy {
+    /// Returns a default policy.
+    ///
+    /// RSA keys of at least 2048 bits and EC keys on the P-256, P-384, and P-521 curves are
+    /// accepted. The subject alternative name, key usage, and extended key usage extensions may
+    /// be requested. No subject attributes are forbidden, and any subject alternative names are
+    /// accepted.
+    pub fn new() -> ReqPolicy {
+        ReqPolicy {
+            min_rsa_bits: Some(2048),
+            allowed_curves: vec![Nid::X9_62_PRIME256V1, Nid::SECP384R1, Nid::SECP521R1],
+            forbidden_subject_attributes: vec![],
+            allowed_extensions: vec![Nid::SUBJECT_ALT_NAME, Nid::KEY_USAGE, Nid::EXT_KEY_USAGE],
+            require_subject_alt_name: false,
+            dns_suffixes: vec![],
+        }
+    }
+
+    /// Sets the minimum size of RSA keys in bits.
+    ///
+    /// `None` rejects RSA keys entirely.
+    pub fn set_min_rsa_bits(&mut self, bits: Option<u32>) -> &mut ReqPolicy {
+        self.min_rsa_bits = bits;
+        self
+    }
+
+    /// Sets the curves EC keys may use.
+    ///
+    /// An empty list rejects EC keys entirely. EC keys with explicit curve parameters are always
+    /// rejected.
+    pub fn set_allowed_curves(&mut self, curves: &[Nid]) -> &mut ReqPolicy {
+        self.allowed_curves = curves.to_vec();
+        self
+    }
+
+    /// Rejects requests whose subject contains an attribute of the given type.
+    pub fn forbid_subject_attribute(&mut self, attribute: Nid) -> &mut ReqPolicy {
+        self.forbidden_subject_attributes.push(attribute);
+        self
+    }
+
+    /// Allows requests to contain an extension of the given type.
+    pub fn allow_extension(&mut self, extension: Nid) -> &mut ReqPolicy {
+        self.allowed_extensions.push(extension);
+        self
+    }
+
+    /// Configures whether the request must contain a non-empty subject alternative name.
+    pub fn set_require_subject_alt_name(&mut self, require: bool) -> &mut ReqPolicy {
+        self.require_subject_alt_name = require;
+        self
+    }
+
+    /// Allows DNS names equal to or under `suffix` in the subject alternative name.
+    ///
+    /// Once a suffix has been added, only DNS names under an allowed suffix are accepted, and
+    /// subject alternative names of other types are rejected.
+    pub fn add_allowed_dns_suffix(&mut self, suffix: &str) -> &mut ReqPolicy {
+        self.dns_suffixes
+            .push(suffix.trim_matches('.').to_ascii_lowercase());
+        self
+    }
+
+    fn dns_name_allowed(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.dns_suffixes.is_empty() || self.dns_suffixes.iter().any(|suffix| {
+            name == *suffix
+                || (name.ends_with(&**suffix) && name[..name.len() - suffix.len()].ends_with('.'))
+        })
+    }
+}
+
+/// The check which produced a `ReqViolation`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ReqCheck {
+    /// The signature does not match the request's public key.
+    Signature,
+    /// The public key is of a disallowed type or size.
+    PublicKey,
+    /// The subject contains a forbidden attribute.
+    SubjectAttribute,
+    /// A disallowed extension was requested.
+    Extension,
+    /// The subject alternative name is missing, malformed, or contains a disallowed name.
+    SubjectAltName,
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+/// A way in which a certificate signing request fails to meet a `ReqPolicy`.
+#[derive(Debug, Clone)]
+pub struct ReqViolation {
+    check: ReqCheck,
+    message: String,
+}
+
+impl ReqViolation {
+    fn new(check: ReqCheck, message: String) -> ReqViolation {
+        ReqViolation { check, message }
+    }
+
+    /// Returns the check which produced this violation.
+    pub fn check(&self) -> ReqCheck {
+        self.check
+    }
+
+    /// Returns a description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ReqViolation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.message)
+    }
+}
+
+pub(crate) fn validate(
+    req: &X509ReqRef,
+    policy: &ReqPolicy,
+) -> Result<Vec<ReqViolation>, ErrorStack> {
+    let mut violations = vec![];
+
+    let key = req.public_key()?;
+    // OpenSSL reports some malformed signatures as errors rather than mismatches
+    if !req.verify(&key).unwrap_or(false) {
+        violations.push(ReqViolation::new(
+            ReqCheck::Signature,
+            "the request's signature does not match its public key".to_string(),
+        ));
+    }
+
+    check_public_key(&key, policy, &mut violations)?;
+
+    for entry in req.subject_name().entries() {
+        if policy
+            .forbidden_subject_attributes
+            .contains(&entry.object().nid())
+        {
+            violations.push(ReqViolation::new(
+                ReqCheck::SubjectAttribute,
+                format!(
+                    "the subject contains the forbidden attribute {}",
+                    entry.object()
+                ),
+            ));
+        }
+    }
+
+    let subject_alt_names = check_extensions(req, policy, &mut violations)?;
+    check_subject_alt_names(subject_alt_names, policy, &mut violations);
+
+    Ok(violations)
+}
+
+fn check_public_key(
+    key: &PKeyRef<Public>,
+    policy: &ReqPolicy,
+    violations: &mut Vec<ReqViolation>,
+) -> Result<(), ErrorStack> {
+    let id = key.id();
+
+    if id == Id::RSA {
+        let bits = key.bits();
+        match policy.min_rsa_bits {
+            Some(min) if bits >= min => {}
+            Some(min) => violations.push(ReqViolation::new(
+                ReqCheck::PublicKey,
+                format!(
+                    "the RSA key is {} bits long, but at least {} are required",
+                    bits, min
+                ),
+            )),
+            None => violations.push(ReqViolation::new(
+                ReqCheck::PublicKey,
+                "RSA keys are not allowed".to_string(),
+            )),
+        }
+    } else if id == Id::EC {
+        let key = key.ec_key()?;
+        match key.group().curve_name() {
+            Some(curve) if policy.allowed_curves.contains(&curve) => {}
+            Some(curve) => violations.push(ReqViolation::new(
+                ReqCheck::PublicKey,
+                format!(
+                    "the EC key uses the curve {}, which is not allowed",
+                    curve.short_name().unwrap_or("unknown")
+                ),
+            )),
+            None => violations.push(ReqViolation::new(
+                ReqCheck::PublicKey,
+                "the EC key uses explicit curve parameters".to_string(),
+            )),
+        }
+    } else {
+        violations.push(ReqViolation::new(
+            ReqCheck::PublicKey,
+            "the key type is not allowed".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn check_extensions(
+    req: &X509ReqRef,
+    policy: &ReqPolicy,
+    violations: &mut Vec<ReqViolation>,
+) -> Result<Option<Stack<GeneralName>>, ErrorStack> {
+    let extensions = match req.extensions() {
+        Ok(extensions) => extensions,
+        // OpenSSL returns null without an error if the request has no extensions
+        Err(ref e) if e.errors().is_empty() => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let mut subject_alt_names = None;
+    for extension in &extensions {
+        unsafe {
+            let object = Asn1ObjectRef::from_ptr(ffi::X509_EXTENSION_get_object(
+                extension.as_ptr(),
+            ));
+            let nid = object.nid();
+
+            if !policy.allowed_extensions.contains(&nid) {
+                violations.push(ReqViolation::new(
+                    ReqCheck::Extension,
+                    format!("the {} extension is not allowed", object),
+                ));
+            }
+
+            if nid == Nid::SUBJECT_ALT_NAME {
+                let names = ffi::X509V3_EXT_d2i(extension.as_ptr());
+                if names.is_null() {
+                    ErrorStack::get();
+                    violations.push(ReqViolation::new(
+                        ReqCheck::SubjectAltName,
+                        "the subject alternative name extension is malformed".to_string(),
+                    ));
+                } else {
+                    subject_alt_names = Some(Stack::from_ptr(names as *mut _));
+                }
+            }
+        }
+    }
+
+    Ok(subject_alt_names)
+}
+
+fn check_subject_alt_names(
+    names: Option<Stack<GeneralName>>,
+    policy: &ReqPolicy,
+    violations: &mut Vec<ReqViolation>,
+) {
+    let names = match names {
+        Some(ref names) if names.len() > 0 => names,
+        _ => {
+            if policy.require_subject_alt_name {
+                violations.push(ReqViolation::new(
+                    ReqCheck::SubjectAltName,
+                    "the subject alternative name extension is missing or empty".to_string(),
+                ));
+            }
+            return;
+        }
+    };
+
+    for name in names {
+        match name.dnsname() {
+            Some(dns) => if !policy.dns_name_allowed(dns) {
+                violations.push(ReqViolation::new(
+                    ReqCheck::SubjectAltName,
+                    format!("the DNS name {} is not allowed", dns),
+                ));
+            },
+            None => if !policy.dns_suffixes.is_empty() {
+                violations.push(ReqViolation::new(
+                    ReqCheck::SubjectAltName,
+                    "only DNS names are allowed in the subject alternative name".to_string(),
+                ));
+            },
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//! OCSP stapling for servers.
+//!
+//! An [`OcspStapler`] fetches OCSP responses for a server's certificate, verifies them, and
+//! caches the most recent one. Once installed on an `SslContextBuilder`, the cached response is
+//! stapled to every handshake in which the client requests it.
+//!
+//! Responses are refreshed halfway through their validity period, so that a response is still
+//! available if the responder is temporarily unreachable. The stapler does not fetch responses
+//! on its own; [`refresh_if_needed`] should be called periodically, for example from a
+//! background thread.
+//!
+//! Requires OpenSSL 1.0.2 or newer.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use openssl::ssl::{SslAcceptor, SslMethod};
+//! use openssl::stack::Stack;
+//! use openssl::x509::X509;
+//! use openssl::x509::store::X509StoreBuilder;
+//! use openssl::x509::stapling::OcspStapler;
+//! use std::error::Error;
+//! use std::sync::Arc;
+//! use std::thread;
+//!
+//! fn post_to_responder(request: &[u8]) -> Result<Vec<u8>, Box<Error + Send + Sync>> {
+//!     // send the request to the URL in the certificate's authority information access extension
+//! #   unimplemented!()
+//! }
+//!
+//! # fn main() {
+//! # let (cert, issuer): (X509, X509) = unimplemented!();
+//! let mut chain = Stack::new().unwrap();
+//! chain.push(issuer).unwrap();
+//! let mut store = X509StoreBuilder::new().unwrap();
+//! store.set_default_paths().unwrap();
+//!
+//! let stapler = OcspStapler::new(&cert, &chain, store.build(), post_to_responder).unwrap();
+//! let stapler = Arc::new(stapler);
+//!
+//! let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+//! OcspStapler::install(&stapler, &mut acceptor).unwrap();
+//!
+//! thread::spawn(move || loop {
+//!     if let Err(e) = stapler.refresh_if_needed() {
+//!         println!("failed to refresh OCSP response: {}", e);
+//!     }
+//!     thread::sleep(stapler.time_until_refresh());
+//! });
+//! # }
+//! ```
+//!
+//! [`OcspStapler`]: struct.OcspStapler.html
+//! [`refresh_if_needed`]: struct.OcspStapler.html#method.refresh_if_needed
+use foreign_types::ForeignTypeRef;
+use std::cmp;
+use std::error;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use asn1::{Asn1GeneralizedTimeRef, Asn1Time, Asn1TimeRef};
+use error::ErrorStack;
+use hash::MessageDigest;
+use ocsp::{OcspCertId, OcspFlag, OcspRequest, OcspResponse, OcspResponseStatus};
+use ssl::SslContextBuilder;
+use stack::{Stack, StackRef};
+use x509::{X509, X509Ref, X509VerifyResult};
+use x509::store::X509Store;
+
+// the clock skew tolerated when checking the validity period of a response
+const MAX_CLOCK_SKEW: u32 = 5 * 60;
+
+/// The callback used to send OCSP requests to the responder.
+type Fetch = Fn(&[u8]) -> Result<Vec<u8>, Box<error::Error + Send + Sync>> + Send + Sync;
+
+/// Fetches, verifies, and caches OCSP responses for a certificate.
+///
+/// The stapler is safe to share between threads.
+pub struct OcspStapler {
+    cert: X509,
+    issuer: X509,
+    chain: Vec<X509>,
+    store: X509Store,
+    fetch: Box<Fetch>,
+    retry_interval: Duration,
+    state: Mutex<State>,
+}
+
+struct State {
+    response: Option<CachedResponse>,
+    next_refresh: Instant,
+}
+
+struct CachedResponse {
+    der: Vec<u8>,
+    expires: Instant,
+}
+
+impl OcspStapler {
+    /// Creates a stapler for `cert`.
+    ///
+    /// `chain` must contain the certificate's issuer, and is used along with `store` to verify
+    /// the responder's signature. `fetch` is called with a DER-encoded OCSP request, and must
+    /// return the DER-encoded response from the certificate's OCSP responder.
+    pub fn new<F>(
+        cert: &X509Ref,
+        chain: &StackRef<X509>,
+        store: X509Store,
+        fetch: F,
+    ) -> Result<OcspStapler, ErrorStack>
+    where
+        F: Fn(&[u8]) -> Result<Vec<u8>, Box<error::Error + Send + Sync>> + 'static + Send + Sync,
+    {
+        let issuer = match chain.iter().find(|c| c.issued(cert) == X509VerifyResult::OK) {
+            Some(issuer) => issuer.to_owned(),
+            None => {
+                return Err(ErrorStack::from_data(
+                    "the chain does not contain the certificate's issuer",
+                ))
+            }
+        };
+
+        Ok(OcspStapler {
+            cert: cert.to_owned(),
+            issuer,
+            chain: chain.iter().map(|c| c.to_owned()).collect(),
+            store,
+            fetch: Box::new(fetch),
+            retry_interval: Duration::from_secs(60),
+            state: Mutex::new(State {
+                response: None,
+                next_refresh: Instant::now(),
+            }),
+        })
+    }
+
+    /// Sets the time to wait before fetching a response again after a failure.
+    ///
+    /// Defaults to one minute.
+    pub fn set_retry_interval(&mut self, interval: Duration) {
+        self.retry_interval = interval;
+    }
+
+    /// Installs the stapler on a server's context.
+    ///
+    /// This replaces any status callback set on the context. The cached response is stapled
+    /// when the client requests it, and nothing is stapled if no unexpired response is cached.
+    ///
+    /// This uses [`SslContextBuilder::set_status_callback`].
+    ///
+    /// [`SslContextBuilder::set_status_callback`]: ../../ssl/struct.SslContextBuilder.html#method.set_status_callback
+    pub fn install(
+        stapler: &Arc<OcspStapler>,
+        ctx: &mut SslContextBuilder,
+    ) -> Result<(), ErrorStack> {
+        let stapler = stapler.clone();
+        ctx.set_status_callback(move |ssl| match stapler.response() {
+            Some(response) => ssl.set_ocsp_status(&response).map(|_| true),
+            None => Ok(false),
+        })
+    }
+
+    /// Returns the cached DER-encoded response, if it has not expired.
+    pub fn response(&self) -> Option<Vec<u8>> {
+        let state = self.state.lock().unwrap();
+        match state.response {
+            Some(ref response) if response.expires > Instant::now() => Some(response.der.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the time remaining until the response should be refreshed.
+    pub fn time_until_refresh(&self) -> Duration {
+        let next_refresh = self.state.lock().unwrap().next_refresh;
+        let now = Instant::now();
+        if next_refresh > now {
+            next_refresh - now
+        } else {
+            Duration::from_secs(0)
+        }
+    }
+
+    /// Refreshes the response if it is due to be refreshed.
+    ///
+    /// Returns `true` if a new response was fetched.
+    pub fn refresh_if_needed(&self) -> Result<bool, StapleError> {
+        if self.time_until_refresh() > Duration::from_secs(0) {
+            return Ok(false);
+        }
+        self.refresh().map(|_| true)
+    }
+
+    /// Fetches and verifies a new response, replacing the cached one.
+    ///
+    /// If this fails, the previously cached response continues to be used until it expires, and
+    /// the next refresh is scheduled after the retry interval.
+    pub fn refresh(&self) -> Result<(), StapleError> {
+        let result = self.fetch_response();
+
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        match result {
+            Ok((der, lifetime, refresh)) => {
+                state.response = Some(CachedResponse {
+                    der,
+                    expires: now + lifetime,
+                });
+                state.next_refresh = now + refresh;
+                Ok(())
+            }
+            Err(e) => {
+                state.next_refresh = now + self.retry_interval;
+                Err(e)
+            }
+        }
+    }
+
+    // Returns a new response, along with its lifetime and the time until it should be refreshed.
+    fn fetch_response(&self) -> Result<(Vec<u8>, Duration, Duration), StapleError> {
+        let mut request = OcspRequest::new()?;
+        request.add_id(self.cert_id()?)?;
+        let der = (self.fetch)(&request.to_der()?).map_err(StapleError::Fetch)?;
+
+        let response = OcspResponse::from_der(&der)?;
+        if response.status() != OcspResponseStatus::SUCCESSFUL {
+            return Err(StapleError::Response(response.status()));
+        }
+
+        let basic = response.basic()?;
+        let mut chain = Stack::new()?;
+        for cert in &self.chain {
+            chain.push(cert.clone())?;
+        }
+        basic.verify(&chain, &self.store, OcspFlag::empty())?;
+
+        let id = self.cert_id()?;
+        let status = match basic.find_status(&id) {
+            Some(status) => status,
+            None => return Err(StapleError::MissingStatus),
+        };
+        status.check_validity(MAX_CLOCK_SKEW, None)?;
+
+        // responses without a next update time may be replaced at any time
+        let next_update = match status.next_update {
+            Some(next_update) => as_time(next_update),
+            None => return Ok((der, self.retry_interval, self.retry_interval)),
+        };
+
+        let now = Asn1Time::now()?;
+        let this_update = as_time(status.this_update);
+        let remaining = now.diff(next_update)?.total_secs();
+        let period = this_update.diff(next_update)?.total_secs();
+
+        let lifetime = Duration::from_secs(cmp::max(remaining, 0) as u64);
+        let refresh = Duration::from_secs(cmp::max(remaining - period / 2, 0) as u64);
+        let refresh = cmp::min(cmp::max(refresh, self.retry_interval), lifetime);
+        Ok((der, lifetime, refresh))
+    }
+
+    fn cert_id(&self) -> Result<OcspCertId, ErrorStack> {
+        OcspCertId::from_cert(MessageDigest::sha1(), &self.cert, &self.issuer)
+    }
+}
+
+// ASN1_TIME_diff accepts both UTCTime and GeneralizedTime values
+fn as_time(time: &Asn1GeneralizedTimeRef) -> &Asn1TimeRef {
+    unsafe { Asn1TimeRef::from_ptr(time.as_ptr() as *mut _) }
+}
+
+/// An error refreshing the response cached by an `OcspStapler`.
+#[derive(Debug)]
+pub enum StapleError {
+    /// The fetch callback failed.
+    Fetch(Box<error::Error + Send + Sync>),
+    /// The responder did not return a successful response.
+    Response(OcspResponseStatus),
+    /// The response does not contain the status of the certificate.
+    MissingStatus,
+    /// The response could not be parsed or verified, or its validity period has ended.
+    Ssl(ErrorStack),
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl From<ErrorStack> for StapleError {
+    fn from(e: ErrorStack) -> StapleError {
+        StapleError::Ssl(e)
+    }
+}
+
+impl fmt::Display for StapleError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StapleError::Fetch(ref e) => write!(fmt, "failed to fetch the OCSP response: {}", e),
+            StapleError::Response(status) => write!(
+                fmt,
+                "the OCSP responder returned the unsuccessful status {}",
+                status.as_raw()
+            ),
+            StapleError::MissingStatus => {
+                fmt.write_str("the OCSP response does not contain the certificate's status")
+            }
+            StapleError::Ssl(ref e) => write!(fmt, "invalid OCSP response: {}", e),
+            StapleError::__Nonexhaustive => unreachable!(),
+        }
+    }
+}
+
+impl error::Error for StapleError {
+    fn description(&self) -> &str {
+        "an error refreshing an OCSP response"
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            StapleError::Fetch(ref e) => Some(&**e),
+            StapleError::Ssl(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
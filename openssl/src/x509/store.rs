@@ -42,11 +42,15 @@
 
 use ffi;
 use foreign_types::ForeignTypeRef;
+use std::fs::File;
+use std::io::{self, Read};
 use std::mem;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 use {cvt, cvt_p};
 use error::ErrorStack;
-use x509::X509;
+use x509::{X509, X509Ref};
 
 foreign_type! {
     type CType = ffi::X509_STORE;
@@ -95,9 +99,104 @@ impl X509StoreBuilderRef {
     pub fn set_default_paths(&mut self) -> Result<(), ErrorStack> {
         unsafe { cvt(ffi::X509_STORE_set_default_paths(self.as_ptr())).map(|_| ()) }
     }
+
+    /// Adds the certificates in a Windows system certificate store.
+    ///
+    /// `name` is the name of the system store, typically `"ROOT"` for the trusted root
+    /// certificates. This allows peers to be verified against the trust configured in the
+    /// operating system rather than a bundled set of certificates. Certificates which cannot be
+    /// parsed or are already present are skipped, and the number of certificates added is
+    /// returned.
+    ///
+    /// To use the store for TLS connections, call this on `SslContextBuilder::cert_store_mut`.
+    ///
+    /// Requires the `windows-system-store` feature, and is only available on Windows.
+    ///
+    /// This corresponds to [`CertOpenSystemStoreW`].
+    ///
+    /// [`CertOpenSystemStoreW`]: https://docs.microsoft.com/en-us/windows/desktop/api/wincrypt/nf-wincrypt-certopensystemstorew
+    #[cfg(all(windows, feature = "windows-system-store"))]
+    pub fn add_windows_system_store(&mut self, name: &str) -> io::Result<usize> {
+        let certs = windows::system_store_certs(name)?;
+
+        let mut added = 0;
+        for der in certs {
+            let cert = match X509::from_der(&der) {
+                Ok(cert) => cert,
+                Err(_) => continue,
+            };
+            if self.add_cert(cert).is_ok() {
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
 }
 
-foreign_type! {
+#[cfg(all(windows, feature = "windows-system-store"))]
+#[allow(bad_style)]
+mod windows {
+    use libc::c_void;
+    use std::io;
+    use std::ptr;
+    use std::slice;
+
+    type HCERTSTORE = *mut c_void;
+
+    const X509_ASN_ENCODING: u32 = 0x1;
+
+    #[repr(C)]
+    struct CERT_CONTEXT {
+        dwCertEncodingType: u32,
+        pbCertEncoded: *mut u8,
+        cbCertEncoded: u32,
+        pCertInfo: *mut c_void,
+        hCertStore: HCERTSTORE,
+    }
+
+    #[link(name = "crypt32")]
+    extern "system" {
+        fn CertOpenSystemStoreW(hProv: usize, szSubsystemProtocol: *const u16) -> HCERTSTORE;
+        fn CertEnumCertificatesInStore(
+            hCertStore: HCERTSTORE,
+            pPrevCertContext: *const CERT_CONTEXT,
+        ) -> *const CERT_CONTEXT;
+        fn CertCloseStore(hCertStore: HCERTSTORE, dwFlags: u32) -> i32;
+    }
+
+    /// Returns the DER encoding of each X.509 certificate in the named system store.
+    pub fn system_store_certs(name: &str) -> io::Result<Vec<Vec<u8>>> {
+        let name = name.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+
+        unsafe {
+            let store = CertOpenSystemStoreW(0, name.as_ptr());
+            if store.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut certs = vec![];
+            let mut cert = ptr::null();
+            loop {
+                // passing the previous context frees it
+                cert = CertEnumCertificatesInStore(store, cert);
+                if cert.is_null() {
+                    break;
+                }
+                if (*cert).dwCertEncodingType & X509_ASN_ENCODING == 0 {
+                    continue;
+                }
+                let der =
+                    slice::from_raw_parts((*cert).pbCertEncoded, (*cert).cbCertEncoded as usize);
+                certs.push(der.to_vec());
+            }
+
+            CertCloseStore(store, 0);
+            Ok(certs)
+        }
+    }
+}
+
+foreign_type_and_impl_send_sync! {
     type CType = ffi::X509_STORE;
     fn drop = ffi::X509_STORE_free;
 
@@ -106,3 +205,115 @@ foreign_type! {
     /// Reference to an `X509Store`.
     pub struct X509StoreRef;
 }
+
+/// A set of trusted certificates which can be modified while in use.
+///
+/// Every modification builds a new `X509Store`, so snapshots returned by [`store`] are never
+/// changed after the fact and connections in the middle of certificate verification are not
+/// disturbed. New connections pick up the changes by installing the current snapshot, for example
+/// with `SslRef::set_verify_cert_store`, so long-running servers can update their trust anchors
+/// without recreating their `SslContext`s.
+///
+/// The epoch is incremented with each modification, and can be used to detect that a cached
+/// snapshot is out of date.
+///
+/// [`store`]: #method.store
+pub struct ReloadableStore(Mutex<ReloadableState>);
+
+struct ReloadableState {
+    certs: Vec<X509>,
+    store: Arc<X509Store>,
+    epoch: u64,
+}
+
+impl ReloadableStore {
+    /// Creates a new store containing the specified certificates.
+    pub fn new(certs: Vec<X509>) -> Result<ReloadableStore, ErrorStack> {
+        let store = build_store(&certs)?;
+        Ok(ReloadableStore(Mutex::new(ReloadableState {
+            certs: certs,
+            store: Arc::new(store),
+            epoch: 0,
+        })))
+    }
+
+    /// Returns a snapshot of the current contents of the store.
+    pub fn store(&self) -> Arc<X509Store> {
+        self.0.lock().unwrap().store.clone()
+    }
+
+    /// Returns the number of modifications which have been made to the store.
+    pub fn epoch(&self) -> u64 {
+        self.0.lock().unwrap().epoch
+    }
+
+    /// Adds a certificate to the store.
+    ///
+    /// Adding a certificate which is already present has no effect.
+    pub fn add_cert(&self, cert: X509) -> Result<(), ErrorStack> {
+        let der = cert.to_der()?;
+        let mut state = self.0.lock().unwrap();
+        for existing in &state.certs {
+            if existing.to_der()? == der {
+                return Ok(());
+            }
+        }
+
+        let mut certs = state.certs.clone();
+        certs.push(cert);
+        state.replace(certs)
+    }
+
+    /// Removes a certificate from the store.
+    ///
+    /// Returns `false` if the certificate was not present.
+    pub fn remove_cert(&self, cert: &X509Ref) -> Result<bool, ErrorStack> {
+        let der = cert.to_der()?;
+        let mut state = self.0.lock().unwrap();
+        let mut certs = Vec::with_capacity(state.certs.len());
+        for existing in &state.certs {
+            if existing.to_der()? != der {
+                certs.push(existing.clone());
+            }
+        }
+
+        if certs.len() == state.certs.len() {
+            return Ok(false);
+        }
+        state.replace(certs).map(|_| true)
+    }
+
+    /// Replaces the contents of the store with the certificates in a sequence of PEM blocks.
+    pub fn reload_from_pem(&self, pem: &[u8]) -> Result<(), ErrorStack> {
+        let certs = X509::stack_from_pem(pem)?;
+        self.0.lock().unwrap().replace(certs)
+    }
+
+    /// Replaces the contents of the store with the certificates in a PEM file.
+    ///
+    /// The store is unchanged if the file cannot be read or parsed.
+    pub fn reload_from<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut pem = vec![];
+        File::open(path)?.read_to_end(&mut pem)?;
+        self.reload_from_pem(&pem)?;
+        Ok(())
+    }
+}
+
+impl ReloadableState {
+    fn replace(&mut self, certs: Vec<X509>) -> Result<(), ErrorStack> {
+        let store = build_store(&certs)?;
+        self.certs = certs;
+        self.store = Arc::new(store);
+        self.epoch += 1;
+        Ok(())
+    }
+}
+
+fn build_store(certs: &[X509]) -> Result<X509Store, ErrorStack> {
+    let mut builder = X509StoreBuilder::new()?;
+    for cert in certs {
+        builder.add_cert(cert.clone())?;
+    }
+    Ok(builder.build())
+}
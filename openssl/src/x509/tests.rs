@@ -1,16 +1,23 @@
 use hex::{self, FromHex};
+use std::cmp::Ordering;
+#[cfg(any(ossl102, ossl110))]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use asn1::Asn1Time;
+use asn1::{Asn1Integer, Asn1Time};
 use bn::{BigNum, MsbOption};
-use hash::MessageDigest;
+use hash::{hash, MessageDigest};
 use nid::Nid;
 use pkey::{PKey, Private};
 use rsa::Rsa;
+use sign::Verifier;
 use stack::Stack;
-use x509::{X509, X509Name, X509Req, X509StoreContext, X509VerifyResult};
+use x509::{self, X509, X509Extension, X509KeyUsage, X509Name, X509Req, X509StoreContext,
+           X509VerifyResult};
 use x509::extension::{AuthorityKeyIdentifier, BasicConstraints, ExtendedKeyUsage, KeyUsage,
                       SubjectAlternativeName, SubjectKeyIdentifier};
-use x509::store::X509StoreBuilder;
+use x509::lint::{LintCheck, LintProfile};
+use x509::req_policy::{ReqCheck, ReqPolicy};
+use x509::store::{ReloadableStore, X509StoreBuilder};
 
 fn pkey() -> PKey<Private> {
     let rsa = Rsa::generate(2048).unwrap();
@@ -29,6 +36,131 @@ fn test_cert_loading() {
     assert_eq!(fingerprint, hash_vec);
 }
 
+#[test]
+fn test_cert_digest() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+
+    let digest = cert.digest(MessageDigest::sha1()).unwrap();
+    assert_eq!(
+        hex::encode(digest),
+        "59172d9313e84459bcff27f967e79e6e9217e584"
+    );
+
+    let der = cert.to_der().unwrap();
+    let digest = cert.digest(MessageDigest::sha256()).unwrap();
+    assert_eq!(&*digest, &*hash(MessageDigest::sha256(), &der).unwrap());
+}
+
+#[test]
+fn test_signature_digest() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    assert_eq!(cert.signature_digest_nid(), Some(Nid::SHA256));
+    assert!(!cert.uses_weak_signature());
+
+    let pkey = pkey();
+    let mut builder = X509::builder().unwrap();
+    builder.set_pubkey(&pkey).unwrap();
+    builder.sign(&pkey, MessageDigest::sha1()).unwrap();
+    let cert = builder.build();
+    assert_eq!(cert.signature_digest_nid(), Some(Nid::SHA1));
+    assert!(cert.uses_weak_signature());
+}
+
+#[test]
+#[cfg(all(windows, feature = "windows-system-store"))]
+fn test_windows_system_store() {
+    let mut builder = X509StoreBuilder::new().unwrap();
+    assert!(builder.add_windows_system_store("ROOT").unwrap() > 0);
+}
+
+#[test]
+fn test_tbs_der() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    let ca = include_bytes!("../../test/root-ca.pem");
+    let ca = X509::from_pem(ca).unwrap();
+
+    let tbs = cert.tbs_der().unwrap();
+    let der = cert.to_der().unwrap();
+    assert_eq!(tbs[0], 0x30);
+    assert!(der.windows(tbs.len()).any(|w| w == &tbs[..]));
+
+    let digest = MessageDigest::from_nid(cert.signature_digest_nid().unwrap()).unwrap();
+    let key = ca.public_key().unwrap();
+    let mut verifier = Verifier::new(digest, &key).unwrap();
+    verifier.update(&tbs).unwrap();
+    assert!(verifier.verify(cert.signature().as_slice()).unwrap());
+}
+
+#[test]
+fn test_unique_ids() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    assert!(cert.issuer_unique_id().is_none());
+    assert!(cert.subject_unique_id().is_none());
+}
+
+#[test]
+#[cfg(any(ossl102, ossl110))]
+fn test_cert_validity_durations() {
+    let pkey = pkey();
+    let mut builder = X509::builder().unwrap();
+    builder.set_pubkey(&pkey).unwrap();
+    let not_before = Asn1Time::from_unix(1_000_000_000).unwrap();
+    builder.set_not_before(&not_before).unwrap();
+    let not_after = Asn1Time::from_unix(1_000_086_410).unwrap();
+    builder.set_not_after(&not_after).unwrap();
+    builder.sign(&pkey, MessageDigest::sha256()).unwrap();
+    let cert = builder.build();
+
+    let now = Asn1Time::from_unix(1_000_000_000).unwrap();
+    assert_eq!(cert.not_after_secs_from(&now).unwrap(), 86_410);
+    assert_eq!(
+        cert.not_after_duration_from(&now).unwrap(),
+        Some(Duration::from_secs(86_410))
+    );
+    assert_eq!(cert.not_before_secs_from(&now).unwrap(), 0);
+
+    let later = Asn1Time::from_unix(1_000_100_000).unwrap();
+    assert_eq!(cert.not_after_secs_from(&later).unwrap(), -13_590);
+    assert_eq!(cert.not_after_duration_from(&later).unwrap(), None);
+}
+
+#[test]
+fn test_asn1_time_from_str() {
+    let utc = Asn1Time::from_str("990101000000Z").unwrap();
+    assert_eq!(utc.to_string(), "Jan  1 00:00:00 1999 GMT");
+
+    let generalized = Asn1Time::from_str("20990101000000Z").unwrap();
+    assert_eq!(generalized.to_string(), "Jan  1 00:00:00 2099 GMT");
+
+    assert!(Asn1Time::from_str("foo").is_err());
+}
+
+#[test]
+#[cfg(any(ossl102, ossl110))]
+fn test_asn1_time_compare() {
+    let a = Asn1Time::from_unix(915_148_800).unwrap();
+    let b = Asn1Time::from_str("990101000000Z").unwrap();
+    let c = Asn1Time::from_unix(1_000_000_000).unwrap();
+    assert!(a == b);
+    assert!(a < c);
+    assert!(c > b);
+
+    assert!(c == UNIX_EPOCH + Duration::from_secs(1_000_000_000));
+    assert!(c < SystemTime::now());
+    assert!(Asn1Time::days_from_now(1).unwrap() > SystemTime::now());
+
+    let now = SystemTime::now();
+    let diff = Asn1Time::from_system_time(now)
+        .unwrap()
+        .diff(&Asn1Time::now().unwrap())
+        .unwrap();
+    assert!(diff.total_secs().abs() <= 1);
+}
+
 #[test]
 fn test_cert_issue_validity() {
     let cert = include_bytes!("../../test/cert.pem");
@@ -40,6 +172,38 @@ fn test_cert_issue_validity() {
     assert_eq!(not_after, "Aug 12 17:00:03 2026 GMT");
 }
 
+#[test]
+fn test_verify() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    let ca = include_bytes!("../../test/root-ca.pem");
+    let ca = X509::from_pem(ca).unwrap();
+    let key = include_bytes!("../../test/key.pem");
+    let key = PKey::private_key_from_pem(key).unwrap();
+
+    assert!(cert.verify(&ca.public_key().unwrap()).unwrap());
+    assert!(!cert.verify(&key).unwrap());
+    assert!(ca.verify(&ca.public_key().unwrap()).unwrap());
+    assert!(key.public_eq(&cert.public_key().unwrap()));
+    assert!(!key.public_eq(&ca.public_key().unwrap()));
+}
+
+#[test]
+fn test_public_key_der() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+
+    let spki = cert.public_key_der().unwrap();
+    assert_eq!(spki, cert.public_key().unwrap().public_key_to_der().unwrap());
+
+    // the SubjectPublicKeyInfo appears verbatim in the certificate's encoding
+    let der = cert.to_der().unwrap();
+    assert!(der.windows(spki.len()).any(|w| w == &spki[..]));
+
+    let key = PKey::public_key_from_der(&spki).unwrap();
+    assert!(key.public_eq(&cert.public_key().unwrap()));
+}
+
 #[test]
 fn test_save_der() {
     let cert = include_bytes!("../../test/cert.pem");
@@ -49,6 +213,76 @@ fn test_save_der() {
     assert!(!der.is_empty());
 }
 
+#[test]
+fn test_to_text() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+
+    let text = String::from_utf8(cert.to_text().unwrap()).unwrap();
+    assert!(text.contains("Subject: "));
+    assert!(text.contains("CN=foobar.com") || text.contains("CN = foobar.com"));
+}
+
+#[test]
+fn test_name_der_round_trip() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+
+    let der = cert.subject_name().to_der().unwrap();
+    let name = X509Name::from_der(&der).unwrap();
+    assert_eq!(name.to_der().unwrap(), der);
+    let cn = name.entries_by_nid(Nid::COMMONNAME).next().unwrap();
+    assert_eq!(cn.data().as_slice(), b"foobar.com");
+
+    let text = String::from_utf8(name.to_text().unwrap()).unwrap();
+    assert!(text.contains("CN=foobar.com"));
+}
+
+#[test]
+fn test_name_entries() {
+    let mut name = X509Name::builder().unwrap();
+    name.append_entry_by_text("C", "US").unwrap();
+    name.append_entry_by_nid(Nid::ORGANIZATIONNAME, "Example")
+        .unwrap();
+    name.append_entry_by_nid(Nid::COMMONNAME, "foobar.com")
+        .unwrap();
+    let name = name.build();
+
+    let entries = name.entries()
+        .map(|e| (e.object().nid(), e.data().as_slice().to_vec()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        vec![
+            (Nid::COUNTRYNAME, b"US".to_vec()),
+            (Nid::ORGANIZATIONNAME, b"Example".to_vec()),
+            (Nid::COMMONNAME, b"foobar.com".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn test_name_canonical_cmp() {
+    // CN=Foo  Bar as a PrintableString
+    let der = Vec::from_hex("30133111300f06035504031308466f6f2020426172").unwrap();
+    let printable = X509Name::from_der(&der).unwrap();
+
+    let mut name = X509Name::builder().unwrap();
+    name.append_entry_by_nid(Nid::COMMONNAME, " foo bar ").unwrap();
+    let utf8 = name.build();
+    assert_ne!(printable.to_der().unwrap(), utf8.to_der().unwrap());
+    assert_eq!(printable.canonical_cmp(&utf8).unwrap(), Ordering::Equal);
+
+    let mut name = X509Name::builder().unwrap();
+    name.append_entry_by_nid(Nid::COMMONNAME, "foo baz").unwrap();
+    let other = name.build();
+    assert_ne!(printable.canonical_cmp(&other).unwrap(), Ordering::Equal);
+    assert_eq!(
+        printable.canonical_cmp(&other).unwrap(),
+        other.canonical_cmp(&printable).unwrap().reverse()
+    );
+}
+
 #[test]
 fn test_subject_read_cn() {
     let cert = include_bytes!("../../test/cert.pem");
@@ -104,6 +338,27 @@ fn test_subject_alt_name() {
     assert_eq!(Some("http://www.example.com"), subject_alt_names[4].uri());
 }
 
+#[test]
+#[cfg(any(ossl102, ossl110))]
+fn test_check_host_ip_email() {
+    use std::net::IpAddr;
+    use x509::verify::X509CheckFlags;
+
+    let cert = include_bytes!("../../test/alt_name_cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+
+    assert!(cert.check_host("example.com", X509CheckFlags::empty()).unwrap());
+    assert!(!cert.check_host("foobar.com", X509CheckFlags::empty()).unwrap());
+    assert!(cert.check_ip("127.0.0.1".parse::<IpAddr>().unwrap(), X509CheckFlags::empty())
+        .unwrap());
+    assert!(cert.check_ip("::1".parse::<IpAddr>().unwrap(), X509CheckFlags::empty())
+        .unwrap());
+    assert!(!cert.check_ip("127.0.0.2".parse::<IpAddr>().unwrap(), X509CheckFlags::empty())
+        .unwrap());
+    assert!(cert.check_email("test@example.com", X509CheckFlags::empty()).unwrap());
+    assert!(!cert.check_email("foo@example.com", X509CheckFlags::empty()).unwrap());
+}
+
 #[test]
 fn test_subject_alt_name_iter() {
     let cert = include_bytes!("../../test/alt_name_cert.pem");
@@ -190,12 +445,40 @@ fn x509_builder() {
         .build(&builder.x509v3_context(None, None))
         .unwrap();
     builder.append_extension(subject_alternative_name).unwrap();
+    let authority_info_access = X509Extension::new_nid(
+        None,
+        None,
+        Nid::INFO_ACCESS,
+        "OCSP;URI:http://ocsp.example.com",
+    ).unwrap();
+    builder.append_extension(authority_info_access).unwrap();
 
     builder.sign(&pkey, MessageDigest::sha256()).unwrap();
 
     let x509 = builder.build();
 
+    let basic_constraints = x509.basic_constraints().unwrap();
+    assert!(basic_constraints.ca());
+    assert_eq!(None, basic_constraints.pathlen());
+    assert_eq!(
+        Some(X509KeyUsage::DIGITAL_SIGNATURE | X509KeyUsage::KEY_ENCIPHERMENT),
+        x509.key_usage()
+    );
+    let ext_key_usage = x509.extended_key_usage().unwrap();
+    assert_eq!(3, ext_key_usage.len());
+    assert_eq!(Nid::SERVER_AUTH, ext_key_usage[0].nid());
+    assert_eq!(Nid::CLIENT_AUTH, ext_key_usage[1].nid());
+    assert_eq!("2.999.1", ext_key_usage[2].to_string());
+    let authority_info_access = x509.authority_info_access().unwrap();
+    assert_eq!(1, authority_info_access.len());
+    assert_eq!(Nid::AD_OCSP, authority_info_access[0].method().nid());
+    assert_eq!(
+        Some("http://ocsp.example.com"),
+        authority_info_access[0].location().uri()
+    );
+
     assert!(pkey.public_eq(&x509.public_key().unwrap()));
+    assert_eq!(2, x509.version());
 
     let cn = x509.subject_name()
         .entries_by_nid(Nid::COMMONNAME)
@@ -205,6 +488,24 @@ fn x509_builder() {
     assert_eq!(serial, x509.serial_number().to_bn().unwrap());
 }
 
+#[test]
+fn test_serial_number_bn_conversions() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+
+    let serial = cert.serial_number();
+    let bn = serial.to_bn().unwrap();
+    let round_trip = Asn1Integer::from_bn(&bn).unwrap();
+    assert!(*serial == *round_trip);
+    assert!(serial.to_owned().unwrap() == round_trip);
+
+    let mut next = bn.to_owned().unwrap();
+    next.add_word(1).unwrap();
+    let next = next.to_asn1_integer().unwrap();
+    assert!(*serial < *next);
+    assert!(next > round_trip);
+}
+
 #[test]
 fn x509_req_builder() {
     let pkey = pkey();
@@ -238,6 +539,13 @@ fn x509_req_builder() {
     let req = builder.build();
     assert!(req.public_key().unwrap().public_eq(&pkey));
     assert_eq!(req.extensions().unwrap().len(), extensions.len());
+    assert!(req.verify(&req.public_key().unwrap()).unwrap());
+    assert!(!req.to_text().unwrap().is_empty());
+
+    let other = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    assert!(!req.verify(&other).unwrap());
+
+    assert_eq!(req.public_key_der().unwrap(), pkey.public_key_to_der().unwrap());
 }
 
 #[test]
@@ -294,6 +602,91 @@ fn clone_x509() {
     cert.clone();
 }
 
+#[test]
+fn test_reloadable_store() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    let ca = include_bytes!("../../test/root-ca.pem");
+    let ca = X509::from_pem(ca).unwrap();
+    let chain = Stack::new().unwrap();
+
+    let verify = |store: &ReloadableStore| {
+        let mut context = X509StoreContext::new().unwrap();
+        context
+            .init(&store.store(), &cert, &chain, |c| c.verify_cert())
+            .unwrap()
+    };
+
+    let store = ReloadableStore::new(vec![]).unwrap();
+    assert_eq!(store.epoch(), 0);
+    assert!(!verify(&store));
+
+    let snapshot = store.store();
+    store.add_cert(ca.clone()).unwrap();
+    assert_eq!(store.epoch(), 1);
+    assert!(verify(&store));
+    store.add_cert(ca.clone()).unwrap();
+    assert_eq!(store.epoch(), 1);
+
+    let mut context = X509StoreContext::new().unwrap();
+    assert!(!context
+        .init(&snapshot, &cert, &chain, |c| c.verify_cert())
+        .unwrap());
+
+    assert!(store.remove_cert(&ca).unwrap());
+    assert!(!store.remove_cert(&ca).unwrap());
+    assert_eq!(store.epoch(), 2);
+    assert!(!verify(&store));
+
+    store
+        .reload_from_pem(include_bytes!("../../test/root-ca.pem"))
+        .unwrap();
+    assert_eq!(store.epoch(), 3);
+    assert!(verify(&store));
+}
+
+#[test]
+#[cfg(any(ossl102, ossl110))]
+fn test_verify_cache() {
+    use x509::verify_cache::VerifyCache;
+
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    let ca = include_bytes!("../../test/root-ca.pem");
+    let ca = X509::from_pem(ca).unwrap();
+    let chain = Stack::new().unwrap();
+
+    let trusted = ReloadableStore::new(vec![ca.clone()]).unwrap();
+    let empty = X509StoreBuilder::new().unwrap().build();
+    let cache = VerifyCache::new(1, Duration::from_secs(60));
+
+    let result = cache.verify(&trusted.store(), 0, &cert, &chain).unwrap();
+    assert_eq!(result, X509VerifyResult::OK);
+    assert_eq!(cache.len(), 1);
+
+    // the result is served from the cache while the epoch is unchanged
+    let result = cache.verify(&empty, 0, &cert, &chain).unwrap();
+    assert_eq!(result, X509VerifyResult::OK);
+
+    let result = cache.verify(&empty, 1, &cert, &chain).unwrap();
+    assert_ne!(result, X509VerifyResult::OK);
+    assert_eq!(cache.len(), 1);
+
+    // results for older epochs are not cached
+    let result = cache.verify(&trusted.store(), 0, &cert, &chain).unwrap();
+    assert_eq!(result, X509VerifyResult::OK);
+    let result = cache.verify(&empty, 1, &cert, &chain).unwrap();
+    assert_ne!(result, X509VerifyResult::OK);
+
+    // the oldest result is evicted once the cache is full
+    let result = cache.verify(&empty, 1, &ca, &chain).unwrap();
+    assert_ne!(result, X509VerifyResult::OK);
+    assert_eq!(cache.len(), 1);
+
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
 #[test]
 fn test_verify_cert() {
     let cert = include_bytes!("../../test/cert.pem");
@@ -319,6 +712,36 @@ fn test_verify_cert() {
     );
 }
 
+#[test]
+#[cfg(any(ossl102, ossl110))]
+fn test_verify_cert_param_host() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    let ca = include_bytes!("../../test/root-ca.pem");
+    let ca = X509::from_pem(ca).unwrap();
+    let chain = Stack::new().unwrap();
+
+    let mut store_bldr = X509StoreBuilder::new().unwrap();
+    store_bldr.add_cert(ca).unwrap();
+    let store = store_bldr.build();
+
+    let mut context = X509StoreContext::new().unwrap();
+    assert!(
+        context
+            .init(&store, &cert, &chain, |c| {
+                c.param_mut().set_host("foobar.com")?;
+                c.verify_cert()
+            })
+            .unwrap()
+    );
+    assert!(!context
+        .init(&store, &cert, &chain, |c| {
+            c.param_mut().set_host("example.com")?;
+            c.verify_cert()
+        })
+        .unwrap());
+}
+
 #[test]
 fn test_verify_fails() {
     let cert = include_bytes!("../../test/cert.pem");
@@ -336,3 +759,298 @@ fn test_verify_fails() {
         .init(&store, &cert, &chain, |c| c.verify_cert())
         .unwrap());
 }
+
+#[test]
+fn test_verify_error_details() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    let chain = Stack::new().unwrap();
+    let store = X509StoreBuilder::new().unwrap().build();
+
+    let mut context = X509StoreContext::new().unwrap();
+    context
+        .init(&store, &cert, &chain, |c| {
+            assert!(!c.verify_cert()?);
+            assert_eq!(c.error(), X509VerifyResult::UNABLE_TO_GET_ISSUER_CERT_LOCALLY);
+            assert_eq!(c.error_depth(), 0);
+            assert_eq!(
+                c.current_cert().unwrap().to_der().unwrap(),
+                cert.to_der().unwrap()
+            );
+            assert!(!c.error().error_string().is_empty());
+
+            c.set_error(X509VerifyResult::CERT_REJECTED);
+            assert_eq!(c.error(), X509VerifyResult::CERT_REJECTED);
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn lint_tls_server() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+
+    let findings = x509::lint(&cert, &LintProfile::tls_server()).unwrap();
+    let checks = findings.iter().map(|f| f.check()).collect::<Vec<_>>();
+    assert_eq!(
+        checks,
+        [LintCheck::SubjectAltName, LintCheck::ValidityPeriod]
+    );
+    assert!(!findings[0].is_unsupported());
+    // the validity period is too long, but can only be measured on 1.0.2 and newer
+    #[cfg(any(ossl102, ossl110))]
+    assert!(!findings[1].is_unsupported());
+    #[cfg(not(any(ossl102, ossl110)))]
+    assert!(findings[1].is_unsupported());
+
+    assert!(x509::lint(&cert, &LintProfile::new()).unwrap().is_empty());
+}
+
+#[test]
+fn lint_serial_number() {
+    let cert = include_bytes!("../../test/alt_name_cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+
+    let findings = x509::lint(&cert, &LintProfile::new()).unwrap();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].check(), LintCheck::SerialNumber);
+
+    let mut profile = LintProfile::new();
+    profile.set_min_serial_bits(0);
+    assert!(x509::lint(&cert, &profile).unwrap().is_empty());
+}
+
+#[test]
+fn lint_critical_extension() {
+    let pkey = pkey();
+
+    let mut name = X509Name::builder().unwrap();
+    name.append_entry_by_nid(Nid::COMMONNAME, "foobar.com")
+        .unwrap();
+    let name = name.build();
+
+    let mut builder = X509::builder().unwrap();
+    builder.set_version(2).unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder.set_issuer_name(&name).unwrap();
+    builder
+        .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    builder
+        .set_not_after(&Asn1Time::days_from_now(365).unwrap())
+        .unwrap();
+    builder.set_pubkey(&pkey).unwrap();
+
+    let mut serial = BigNum::new().unwrap();
+    serial.rand(128, MsbOption::ONE, false).unwrap();
+    builder
+        .set_serial_number(&serial.to_asn1_integer().unwrap())
+        .unwrap();
+
+    let subject_key_identifier = SubjectKeyIdentifier::new()
+        .critical()
+        .build(&builder.x509v3_context(None, None))
+        .unwrap();
+    builder.append_extension(subject_key_identifier).unwrap();
+    builder.sign(&pkey, MessageDigest::sha256()).unwrap();
+    let cert = builder.build();
+
+    let findings = x509::lint(&cert, &LintProfile::new()).unwrap();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].check(), LintCheck::CriticalExtension);
+}
+
+fn policy_req(key: &PKey<Private>, dns: &[&str], ca: bool) -> X509Req {
+    let mut name = X509Name::builder().unwrap();
+    name.append_entry_by_nid(Nid::COMMONNAME, "foobar.com")
+        .unwrap();
+    name.append_entry_by_nid(Nid::ORGANIZATIONNAME, "Foobar Inc")
+        .unwrap();
+    let name = name.build();
+
+    let mut builder = X509Req::builder().unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder.set_pubkey(key).unwrap();
+
+    let mut extensions = Stack::new().unwrap();
+    if !dns.is_empty() {
+        let mut san = SubjectAlternativeName::new();
+        for name in dns {
+            san.dns(name);
+        }
+        let san = san.build(&builder.x509v3_context(None)).unwrap();
+        extensions.push(san).unwrap();
+    }
+    if ca {
+        extensions
+            .push(BasicConstraints::new().critical().ca().build().unwrap())
+            .unwrap();
+    }
+    if extensions.len() > 0 {
+        builder.add_extensions(&extensions).unwrap();
+    }
+
+    builder.sign(key, MessageDigest::sha256()).unwrap();
+    builder.build()
+}
+
+#[test]
+fn req_validate() {
+    let key = pkey();
+    let req = policy_req(&key, &["foobar.com", "www.foobar.com"], false);
+
+    let mut policy = ReqPolicy::new();
+    assert!(req.validate(&policy).unwrap().is_empty());
+
+    policy
+        .set_require_subject_alt_name(true)
+        .add_allowed_dns_suffix("foobar.com");
+    assert!(req.validate(&policy).unwrap().is_empty());
+
+    policy.forbid_subject_attribute(Nid::ORGANIZATIONNAME);
+    let violations = req.validate(&policy).unwrap();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].check(), ReqCheck::SubjectAttribute);
+
+    let mut policy = ReqPolicy::new();
+    policy.set_min_rsa_bits(Some(4096));
+    let violations = req.validate(&policy).unwrap();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].check(), ReqCheck::PublicKey);
+}
+
+#[test]
+fn req_validate_violations() {
+    let key = pkey();
+    let req = policy_req(&key, &["foobar.com", "evil.com", "notfoobar.com"], true);
+
+    let mut policy = ReqPolicy::new();
+    policy.add_allowed_dns_suffix("foobar.com");
+    let violations = req.validate(&policy).unwrap();
+    let checks = violations.iter().map(|v| v.check()).collect::<Vec<_>>();
+    assert_eq!(
+        checks,
+        [
+            ReqCheck::Extension,
+            ReqCheck::SubjectAltName,
+            ReqCheck::SubjectAltName,
+        ]
+    );
+    assert!(violations[1].message().contains("evil.com"));
+
+    policy.allow_extension(Nid::BASIC_CONSTRAINTS);
+    assert_eq!(req.validate(&policy).unwrap().len(), 2);
+
+    let req = policy_req(&key, &[], false);
+    let mut policy = ReqPolicy::new();
+    assert!(req.validate(&policy).unwrap().is_empty());
+    policy.set_require_subject_alt_name(true);
+    let violations = req.validate(&policy).unwrap();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].check(), ReqCheck::SubjectAltName);
+}
+
+#[test]
+#[cfg(any(ossl102, ossl110))]
+fn test_ocsp_stapler() {
+    use ocsp::{OcspRequest, OcspResponse, OcspResponseStatus};
+    use ssl::{SslAcceptor, SslMethod};
+    use std::sync::Arc;
+    use x509::stapling::{OcspStapler, StapleError};
+
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    let ca = include_bytes!("../../test/root-ca.pem");
+    let ca = X509::from_pem(ca).unwrap();
+    let mut chain = Stack::new().unwrap();
+    chain.push(ca.clone()).unwrap();
+    let mut store = X509StoreBuilder::new().unwrap();
+    store.add_cert(ca.clone()).unwrap();
+
+    // the responder is unavailable
+    let stapler = OcspStapler::new(&cert, &chain, store.build(), |request| {
+        OcspRequest::from_der(request).unwrap();
+        let response = OcspResponse::create(OcspResponseStatus::TRY_LATER, None).unwrap();
+        Ok(response.to_der().unwrap())
+    }).unwrap();
+    let stapler = Arc::new(stapler);
+
+    match stapler.refresh_if_needed() {
+        Err(StapleError::Response(status)) => assert_eq!(status, OcspResponseStatus::TRY_LATER),
+        r => panic!("unexpected result {:?}", r),
+    }
+    assert!(stapler.response().is_none());
+    assert!(stapler.time_until_refresh() > Duration::from_secs(0));
+    assert!(!stapler.refresh_if_needed().unwrap());
+
+    let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    OcspStapler::install(&stapler, &mut acceptor).unwrap();
+
+    let store = X509StoreBuilder::new().unwrap().build();
+    let stapler =
+        OcspStapler::new(&cert, &chain, store, |_| Err("the responder is unreachable".into()))
+            .unwrap();
+    match stapler.refresh() {
+        Err(StapleError::Fetch(_)) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+
+    // the chain must contain the certificate's issuer
+    let store = X509StoreBuilder::new().unwrap().build();
+    let empty = Stack::new().unwrap();
+    match OcspStapler::new(&cert, &empty, store, |_| Ok(vec![])) {
+        Err(e) => assert_eq!(e.errors().len(), 1),
+        Ok(_) => panic!("a stapler was created without an issuer"),
+    }
+}
+
+#[test]
+#[cfg(any(ossl102, ossl110))]
+fn test_ocsp_stapler_verified_response() {
+    use ocsp::{OcspCertId, OcspCertStatus, OcspResponse};
+    use x509::stapling::OcspStapler;
+
+    let cert = X509::from_pem(include_bytes!("../../test/ocsp-cert.pem")).unwrap();
+    let ca = X509::from_pem(include_bytes!("../../test/ocsp-ca.pem")).unwrap();
+    let mut chain = Stack::new().unwrap();
+    chain.push(ca.clone()).unwrap();
+    let id = OcspCertId::from_cert(MessageDigest::sha1(), &cert, &ca).unwrap();
+
+    // the responses are signed by the issuer and valid until 2126, or have no next update time
+    let with_next = include_bytes!("../../test/ocsp-good.der");
+    let without_next = include_bytes!("../../test/ocsp-good-no-next-update.der");
+
+    let response = OcspResponse::from_der(with_next).unwrap();
+    let basic = response.basic().unwrap();
+    let status = basic.find_status(&id).unwrap();
+    assert_eq!(status.status, OcspCertStatus::GOOD);
+    assert!(status.next_update.is_some());
+    status.check_validity(0, None).unwrap();
+
+    let response = OcspResponse::from_der(without_next).unwrap();
+    let basic = response.basic().unwrap();
+    let status = basic.find_status(&id).unwrap();
+    assert_eq!(status.status, OcspCertStatus::GOOD);
+    assert!(status.next_update.is_none());
+    status.check_validity(0, None).unwrap();
+
+    let mut store = X509StoreBuilder::new().unwrap();
+    store.add_cert(ca.clone()).unwrap();
+    let stapler = OcspStapler::new(&cert, &chain, store.build(), move |_| Ok(with_next.to_vec()))
+        .unwrap();
+    stapler.refresh().unwrap();
+    assert_eq!(stapler.response().unwrap(), &with_next[..]);
+    assert!(stapler.time_until_refresh() > Duration::from_secs(365 * 24 * 60 * 60));
+
+    // without a next update time the response is refreshed after the retry interval
+    let mut store = X509StoreBuilder::new().unwrap();
+    store.add_cert(ca.clone()).unwrap();
+    let mut stapler =
+        OcspStapler::new(&cert, &chain, store.build(), move |_| Ok(without_next.to_vec()))
+            .unwrap();
+    stapler.set_retry_interval(Duration::from_secs(30));
+    stapler.refresh().unwrap();
+    assert_eq!(stapler.response().unwrap(), &without_next[..]);
+    assert!(stapler.time_until_refresh() <= Duration::from_secs(30));
+}
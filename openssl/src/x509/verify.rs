@@ -67,17 +67,7 @@ impl X509VerifyParamRef {
     /// [`X509_VERIFY_PARAM_set1_ip`]: https://www.openssl.org/docs/man1.1.0/crypto/X509_VERIFY_PARAM_set1_ip.html
     pub fn set_ip(&mut self, ip: IpAddr) -> Result<(), ErrorStack> {
         unsafe {
-            let mut buf = [0; 16];
-            let len = match ip {
-                IpAddr::V4(addr) => {
-                    buf[..4].copy_from_slice(&addr.octets());
-                    4
-                }
-                IpAddr::V6(addr) => {
-                    buf.copy_from_slice(&addr.octets());
-                    16
-                }
-            };
+            let (buf, len) = ip_octets(ip);
             cvt(ffi::X509_VERIFY_PARAM_set1_ip(
                 self.as_ptr(),
                 buf.as_ptr() as *const _,
@@ -86,3 +76,19 @@ impl X509VerifyParamRef {
         }
     }
 }
+
+/// Returns the network-order octets of an address along with the number of them which are used.
+pub(crate) fn ip_octets(ip: IpAddr) -> ([u8; 16], usize) {
+    let mut buf = [0; 16];
+    let len = match ip {
+        IpAddr::V4(addr) => {
+            buf[..4].copy_from_slice(&addr.octets());
+            4
+        }
+        IpAddr::V6(addr) => {
+            buf.copy_from_slice(&addr.octets());
+            16
+        }
+    };
+    (buf, len)
+}
@@ -0,0 +1,209 @@
+//! Caching of certificate chain verification results.
+//!
+//! Verifying a certificate chain involves several signature checks. Services which repeatedly
+//! verify the same certificates outside of a TLS handshake, such as client certificates presented
+//! with each request, can use a [`VerifyCache`] to skip verification of chains which have been
+//! seen recently.
+//!
+//! Results are keyed by the SHA-256 fingerprints of the certificate and its untrusted chain, along
+//! with an epoch identifying the contents of the trust store. A successful result expires along
+//! with the first certificate in the verified chain to expire, and no later than the cache's
+//! maximum age.
+//! Failures are retained for the maximum age.
+//!
+//! Requires OpenSSL 1.0.2 or newer.
+//!
+//! # Examples
+//!
+//! ```
+//! use openssl::stack::Stack;
+//! use openssl::x509::{X509Ref, X509VerifyResult};
+//! use openssl::x509::store::ReloadableStore;
+//! use openssl::x509::verify_cache::VerifyCache;
+//!
+//! fn is_trusted(cache: &VerifyCache, store: &ReloadableStore, cert: &X509Ref) -> bool {
+//!     let chain = Stack::new().unwrap();
+//!     // the epoch is read first, so a concurrent reload cannot be cached under the old epoch
+//!     let epoch = store.epoch();
+//!     let result = cache.verify(&store.store(), epoch, cert, &chain).unwrap();
+//!     result == X509VerifyResult::OK
+//! }
+//! ```
+//!
+//! [`VerifyCache`]: struct.VerifyCache.html
+use std::cmp;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use asn1::Asn1Time;
+use error::ErrorStack;
+use hash::{Hasher, MessageDigest};
+use stack::StackRef;
+use x509::{X509, X509Ref, X509StoreContext, X509VerifyResult};
+use x509::store::X509StoreRef;
+
+/// A cache of certificate chain verification results.
+///
+/// The cache verifies chains against the store's default parameters, and is safe to share
+/// between threads.
+pub struct VerifyCache {
+    capacity: usize,
+    max_age: Duration,
+    state: Mutex<CacheState>,
+}
+
+struct CacheState {
+    epoch: u64,
+    entries: HashMap<Vec<u8>, Entry>,
+}
+
+struct Entry {
+    result: X509VerifyResult,
+    expires: Instant,
+}
+
+impl VerifyCache {
+    /// Creates a cache holding at most `capacity` results, each for no longer than `max_age`.
+    pub fn new(capacity: usize, max_age: Duration) -> VerifyCache {
+        VerifyCache {
+            capacity,
+            max_age,
+            state: Mutex::new(CacheState {
+                epoch: 0,
+                entries: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Verifies `cert` against `store`, using `chain` as untrusted intermediates.
+    ///
+    /// A cached result is returned if `cert` and `chain` were verified recently with the same
+    /// `epoch`. The epoch must change whenever the contents of the store do, and a cache must
+    /// only be used with a single store. When used with a `ReloadableStore`, read its epoch
+    /// before taking a snapshot of the store.
+    ///
+    /// Results for an epoch older than the newest one seen are not cached. Seeing a newer epoch
+    /// discards all cached results.
+    pub fn verify(
+        &self,
+        store: &X509StoreRef,
+        epoch: u64,
+        cert: &X509Ref,
+        chain: &StackRef<X509>,
+    ) -> Result<X509VerifyResult, ErrorStack> {
+        let key = cache_key(cert, chain)?;
+        let now = Instant::now();
+        {
+            let mut state = self.state.lock().unwrap();
+            if epoch > state.epoch {
+                state.epoch = epoch;
+                state.entries.clear();
+            }
+            if epoch == state.epoch {
+                if let Some(entry) = state.entries.get(&key) {
+                    if entry.expires > now {
+                        return Ok(entry.result);
+                    }
+                }
+            }
+        }
+
+        let max_age = self.max_age;
+        let mut ctx = X509StoreContext::new()?;
+        let (result, lifetime) = ctx.init(store, cert, chain, |c| {
+            c.verify_cert()?;
+            let result = c.error();
+            let lifetime = if result == X509VerifyResult::OK {
+                match c.chain() {
+                    Some(chain) => cmp::min(max_age, remaining_validity(chain)?),
+                    None => Duration::from_secs(0),
+                }
+            } else {
+                max_age
+            };
+            Ok((result, lifetime))
+        })?;
+
+        if lifetime > Duration::from_secs(0) {
+            let mut state = self.state.lock().unwrap();
+            if epoch == state.epoch {
+                state.insert(
+                    key,
+                    Entry {
+                        result,
+                        expires: now + lifetime,
+                    },
+                    self.capacity,
+                );
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the number of cached results, including expired ones which have not been evicted.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Determines if no results are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Discards all cached results.
+    pub fn clear(&self) {
+        self.state.lock().unwrap().entries.clear();
+    }
+}
+
+impl CacheState {
+    fn insert(&mut self, key: Vec<u8>, entry: Entry, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() >= capacity && !self.entries.contains_key(&key) {
+            let now = Instant::now();
+            self.entries.retain(|_, e| e.expires > now);
+        }
+
+        if self.entries.len() >= capacity && !self.entries.contains_key(&key) {
+            // evict the result closest to expiring
+            let oldest = self.entries
+                .iter()
+                .min_by_key(|&(_, e)| e.expires)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.entries.insert(key, entry);
+    }
+}
+
+fn cache_key(cert: &X509Ref, chain: &StackRef<X509>) -> Result<Vec<u8>, ErrorStack> {
+    let mut hasher = Hasher::new(MessageDigest::sha256())?;
+    hasher.update(&cert.digest(MessageDigest::sha256())?)?;
+    for cert in chain {
+        hasher.update(&cert.digest(MessageDigest::sha256())?)?;
+    }
+    Ok(hasher.finish()?.to_vec())
+}
+
+// Returns the time remaining until the first certificate in the chain to expire does so.
+fn remaining_validity(chain: &StackRef<X509>) -> Result<Duration, ErrorStack> {
+    let now = Asn1Time::now()?;
+    let mut remaining = None;
+    for cert in chain {
+        let secs = cert.not_after_secs_from(&now)?;
+        remaining = Some(match remaining {
+            Some(r) => cmp::min(r, secs),
+            None => secs,
+        });
+    }
+
+    Ok(Duration::from_secs(cmp::max(remaining.unwrap_or(0), 0) as u64))
+}